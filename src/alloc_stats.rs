@@ -0,0 +1,105 @@
+//! src/alloc_stats.rs
+
+/*******************************************************************************
+ *                            ALLOC STATS MODULE
+ *-------------------------------------------------------------------------------
+ * Allocation counting for performance work, behind the `dev-metrics` feature.
+ * `CountingAllocator` wraps the system allocator and counts every allocation
+ * and the bytes requested, in process-wide atomic counters. A binary opts in
+ * by installing it:
+ *
+ *     #[global_allocator]
+ *     static ALLOCATOR: rdp::CountingAllocator = rdp::CountingAllocator;
+ *
+ * Counters are shared by all threads, so a measurement taken while other
+ * threads allocate includes their allocations too.
+ ******************************************************************************/
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, counting what it hands out. A reallocation counts
+/// as one allocation of its new size.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CountingAllocator;
+
+fn record(bytes: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(bytes as u64, Ordering::Relaxed);
+}
+
+// SAFETY: every method forwards to `System` with the caller's arguments
+// unchanged, so it upholds the same contract; counting has no effect on the
+// memory returned.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// Allocations counted by `CountingAllocator`, since the counters were last
+/// reset or between two snapshots.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct AllocStats {
+    pub allocations: u64,
+    pub bytes: u64,
+}
+
+impl AllocStats {
+    /// The counters as they are now. Always zero unless `CountingAllocator`
+    /// is the global allocator.
+    pub fn snapshot() -> Self {
+        Self {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            bytes: BYTES.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Sets the counters back to zero.
+    pub fn reset() {
+        ALLOCATIONS.store(0, Ordering::Relaxed);
+        BYTES.store(0, Ordering::Relaxed);
+    }
+
+    /// What was counted between `earlier` and this snapshot.
+    pub fn since(self, earlier: Self) -> Self {
+        Self {
+            allocations: self.allocations.saturating_sub(earlier.allocations),
+            bytes: self.bytes.saturating_sub(earlier.bytes),
+        }
+    }
+
+    /// Runs `f` and returns its result with the allocations it made.
+    pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Self) {
+        let before = Self::snapshot();
+        let result = f();
+        (result, Self::snapshot().since(before))
+    }
+}
+
+impl fmt::Display for AllocStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} allocations, {} bytes", self.allocations, self.bytes)
+    }
+}
@@ -8,6 +8,8 @@
  * as the foundation for further processing (e.g., interpretation or codegen).
  ********************************************************************************/
 
+use std::{error, fmt};
+
 /// A complete program is just a single `Expression`. By wrapping it in `Program`,
 /// we have a clear entry point for the entire AST.
 #[derive(Debug, PartialEq, Clone)]
@@ -26,6 +28,10 @@ pub struct Program {
 pub enum Expression {
     /// A `let` binding (e.g., `let x = ... in ...`).
     LetExpr {
+        /// Whether the binding was written `let rec`. Tools here treat the
+        /// name as in scope in its value and in the body, whether or not it
+        /// is written `let rec`; this records the intent.
+        recursive: bool,
         /// The name bound by this `let`.
         identifier: String,
         /// Optional type annotation (e.g., `x: Int`).
@@ -36,6 +42,21 @@ pub enum Expression {
         body: Box<Expression>,
     },
 
+    /// A `let` of several bindings joined by `and`
+    /// (e.g., `let x = ... and y = ... in ...`). A group's bindings stay side
+    /// by side in source order, none nested in another's scope.
+    LetAnd {
+        /// Whether the bindings were written `let rec`. Tools here treat
+        /// every name of the group as in scope in every value and in the
+        /// body, whether or not it is written `let rec`; this records the
+        /// intent.
+        recursive: bool,
+        /// The bindings, at least two.
+        bindings: Vec<LetBinding>,
+        /// The body in which the bindings are valid (after `in`).
+        body: Box<Expression>,
+    },
+
     /// An `if` expression with a condition, `then` branch, and `else` branch.
     IfExpr {
         /// The Boolean condition.
@@ -94,6 +115,24 @@ pub enum Expression {
         right: Box<Expression>,
     },
 
+    /// A list operation like `x :: xs` or `xs ++ ys`.
+    List {
+        /// Left-hand operand, the element for `::`.
+        left: Box<Expression>,
+        /// List operator (`::`, `++`).
+        operator: ListOperator,
+        /// Right-hand operand.
+        right: Box<Expression>,
+    },
+
+    /// A prefix operation like `!done`.
+    Unary {
+        /// Prefix operators (`!` and `-`).
+        operator: UnaryOperator,
+        /// The operand.
+        operand: Box<Expression>,
+    },
+
     /// A function or operator application, e.g., `f x y` or `func arg`.
     Application(Vec<Expression>),
 
@@ -104,10 +143,81 @@ pub enum Expression {
     FunctionComposition(FunctionComposition),
 }
 
+impl Expression {
+    /// Visits this expression and every expression nested inside it, parents
+    /// before children and left to right.
+    pub fn walk<'a>(&'a self, visit: &mut impl FnMut(&'a Expression)) {
+        visit(self);
+        match self {
+            Expression::LetExpr { value, body, .. } => {
+                value.walk(visit);
+                body.walk(visit);
+            }
+            Expression::LetAnd { bindings, body, .. } => {
+                for binding in bindings {
+                    binding.value.walk(visit);
+                }
+                body.walk(visit);
+            }
+            Expression::IfExpr {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                condition.walk(visit);
+                then_branch.walk(visit);
+                else_branch.walk(visit);
+            }
+            Expression::Lambda { body, .. } => body.walk(visit),
+            Expression::PatternMatch { expression, arms } => {
+                expression.walk(visit);
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        guard.walk(visit);
+                    }
+                    arm.expression.walk(visit);
+                }
+            }
+            Expression::Comparison { left, right, .. } | Expression::Logic { left, right, .. } => {
+                left.walk(visit);
+                if let Some(right) = right {
+                    right.walk(visit);
+                }
+            }
+            Expression::Arithmetic { left, right, .. } | Expression::List { left, right, .. } => {
+                left.walk(visit);
+                right.walk(visit);
+            }
+            Expression::Unary { operand, .. } => operand.walk(visit),
+            Expression::Application(expressions)
+            | Expression::Term(Term::Tuple(expressions) | Term::List(expressions)) => {
+                for expression in expressions {
+                    expression.walk(visit);
+                }
+            }
+            Expression::Term(Term::GroupedExpression(expression))
+            | Expression::Term(Term::MemberAccess { expression, .. }) => expression.walk(visit),
+            Expression::Term(_) => {}
+            Expression::FunctionComposition(FunctionComposition { f, g }) => {
+                f.walk(visit);
+                g.walk(visit);
+            }
+        }
+    }
+}
+
+/// One binding of a [`Expression::LetAnd`], `x: Int = value`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LetBinding {
+    pub identifier: String,
+    pub type_annotation: Option<TypeAnnotation>,
+    pub value: Box<Expression>,
+}
+
 /********************************************************************************
  *                                 TERM ENUM
  *-------------------------------------------------------------------------------*
- * Terminal forms in the AST: plain identifiers, numbers, grouped expressions,
+ * Terminal forms in the AST: plain identifiers, literals, grouped expressions,
  * or member accesses (for expressions in parentheses with a dot).
  ********************************************************************************/
 #[derive(Debug, PartialEq, Clone)]
@@ -118,6 +228,12 @@ pub enum Term {
     /// A numeric literal (floats or ints).
     Number(f64),
 
+    /// A string literal, e.g. `"hi"`.
+    String(String),
+
+    /// A boolean literal, `true` or `false`.
+    Boolean(bool),
+
     /// A grouped expression, e.g. `(expr)`.
     GroupedExpression(Box<Expression>),
 
@@ -126,6 +242,12 @@ pub enum Term {
         expression: Box<Expression>,
         member: String,
     },
+
+    /// A tuple of two or more expressions, e.g. `(a, b)`.
+    Tuple(Vec<Expression>),
+
+    /// A list literal, e.g. `[1, 2, 3]` or `[]`.
+    List(Vec<Expression>),
 }
 
 /********************************************************************************
@@ -139,6 +261,9 @@ pub enum Term {
 #[derive(Debug, PartialEq, Clone)]
 pub struct MatchArm {
     pub pattern: Pattern,
+    /// The `when` condition that must also hold for the arm to be taken. It
+    /// sees the pattern's variables.
+    pub guard: Option<Box<Expression>>,
     pub expression: Box<Expression>,
 }
 
@@ -152,10 +277,107 @@ pub enum Pattern {
     /// A numeric pattern (e.g., `42`).
     Number(f64),
 
+    /// A boolean pattern, `true` or `false`.
+    Boolean(bool),
+
     /// A grouped pattern `(pat)`.
     Grouped(Box<Pattern>),
+
+    /// A cons pattern `head :: tail`, matching a non-empty list.
+    Cons(Box<Pattern>, Box<Pattern>),
 }
 
+impl TryFrom<&Expression> for Pattern {
+    type Error = PatternConversionError;
+
+    /// Converts the pattern-shaped fragment of an expression: identifiers,
+    /// numbers, booleans, grouping, and cons.
+    fn try_from(expression: &Expression) -> Result<Self, Self::Error> {
+        match expression {
+            Expression::Term(Term::Identifier(name)) => Ok(Pattern::Identifier(name.clone())),
+            Expression::Term(Term::Number(value)) => Ok(Pattern::Number(*value)),
+            Expression::Term(Term::Boolean(value)) => Ok(Pattern::Boolean(*value)),
+            Expression::Term(Term::GroupedExpression(inner)) => Ok(Pattern::Grouped(Box::new(
+                Pattern::try_from(inner.as_ref())?,
+            ))),
+            Expression::List {
+                left,
+                operator: ListOperator::Cons,
+                right,
+            } => Ok(Pattern::Cons(
+                Box::new(Pattern::try_from(left.as_ref())?),
+                Box::new(Pattern::try_from(right.as_ref())?),
+            )),
+            _ => Err(PatternConversionError {
+                expression: expression.clone(),
+            }),
+        }
+    }
+}
+
+impl From<&Pattern> for Expression {
+    fn from(pattern: &Pattern) -> Self {
+        match pattern {
+            Pattern::Identifier(name) => Expression::Term(Term::Identifier(name.clone())),
+            Pattern::Number(value) => Expression::Term(Term::Number(*value)),
+            Pattern::Boolean(value) => Expression::Term(Term::Boolean(*value)),
+            Pattern::Grouped(inner) => Expression::Term(Term::GroupedExpression(Box::new(
+                Expression::from(inner.as_ref()),
+            ))),
+            Pattern::Cons(head, tail) => Expression::List {
+                left: Box::new(Expression::from(head.as_ref())),
+                operator: ListOperator::Cons,
+                right: Box::new(Expression::from(tail.as_ref())),
+            },
+        }
+    }
+}
+
+/// Returned when an expression has no pattern counterpart. Holds the
+/// outermost sub-expression that prevented the conversion.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PatternConversionError {
+    pub expression: Expression,
+}
+
+impl PatternConversionError {
+    /// Names the kind of the offending sub-expression, e.g. `application`.
+    pub fn kind(&self) -> &'static str {
+        match &self.expression {
+            Expression::LetExpr { .. } | Expression::LetAnd { .. } => "let expression",
+            Expression::IfExpr { .. } => "if expression",
+            Expression::Lambda { .. } => "lambda",
+            Expression::PatternMatch { .. } => "match expression",
+            Expression::Comparison { .. } => "comparison",
+            Expression::Logic { .. } => "logic expression",
+            Expression::Arithmetic { .. } => "arithmetic expression",
+            Expression::List { .. } => "list expression",
+            Expression::Unary { .. } => "negation",
+            Expression::Application(_) => "application",
+            Expression::Term(Term::MemberAccess { .. }) => "member access",
+            Expression::Term(Term::String(_)) => "string literal",
+            Expression::Term(Term::Tuple(_)) => "tuple",
+            Expression::Term(Term::List(_)) => "list literal",
+            Expression::FunctionComposition(_) => "function composition",
+            Expression::Term(_) => "term",
+        }
+    }
+}
+
+impl fmt::Display for PatternConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = self.kind();
+        let article = if kind.starts_with(['a', 'e', 'i', 'o', 'u']) {
+            "An"
+        } else {
+            "A"
+        };
+        write!(f, "{} {} is not allowed in a pattern.", article, kind)
+    }
+}
+
+impl error::Error for PatternConversionError {}
+
 /********************************************************************************
  *                             TYPE ANNOTATIONS
  *-------------------------------------------------------------------------------*
@@ -171,23 +393,40 @@ pub enum TypeAnnotation {
     String,
     /// Floating-point type.
     Float,
+    /// A user-defined type, named with a capital letter, such as `List`.
+    Named(String),
+    /// A type variable, named with a lowercase letter, such as `a`.
+    Var(String),
     /// A function type `(T1 -> T2)`.
     Function(Box<TypeAnnotation>, Box<TypeAnnotation>),
+    /// A list type `[T]`.
+    List(Box<TypeAnnotation>),
+    /// A tuple type `(T1, T2, ...)`, with at least two elements.
+    Tuple(Vec<TypeAnnotation>),
+}
+
+impl fmt::Display for TypeAnnotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&crate::print_type(self))
+    }
 }
 
 /********************************************************************************
  *                              OPERATORS
  *-------------------------------------------------------------------------------*
- * Comparisons, logic, arithmetic, and function composition are each captured
- * in their own small enums or structs.
+ * Comparisons, logic, arithmetic, lists, and function composition are each
+ * captured in their own small enums or structs.
  ********************************************************************************/
 
-/// Comparison operators (`==`, `<`, `>`).
+/// Comparison operators (`==`, `!=`, `<`, `>`, `<=`, `>=`).
 #[derive(Debug, PartialEq, Clone)]
 pub enum ComparisonOperator {
     Equal,
+    NotEqual,
     LessThan,
     GreaterThan,
+    LessEqual,
+    GreaterEqual,
 }
 
 /// Logical operators (`&&`, `||`).
@@ -197,13 +436,35 @@ pub enum LogicOperator {
     Or,
 }
 
-/// Arithmetic operators (`+`, `-`, `*`, `/`).
+/// Arithmetic operators (`+`, `-`, `*`, `/`, `%`, `^`).
 #[derive(Debug, PartialEq, Clone)]
 pub enum ArithmeticOperator {
     Add,
     Subtract,
     Multiply,
     Divide,
+    /// The remainder of a truncating division, with the sign of the dividend.
+    Modulo,
+    /// Raising to a power.
+    Power,
+}
+
+/// List operators (`::`, `++`).
+#[derive(Debug, PartialEq, Clone)]
+pub enum ListOperator {
+    /// Prepending an element to a list.
+    Cons,
+    /// Joining two lists, or two strings.
+    Concat,
+}
+
+/// Prefix operators (`!` and `-`).
+#[derive(Debug, PartialEq, Clone)]
+pub enum UnaryOperator {
+    /// Boolean negation.
+    Not,
+    /// Arithmetic negation.
+    Negate,
 }
 
 /// Represents a function composition operator, typically `.`.
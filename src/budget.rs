@@ -0,0 +1,135 @@
+//! src/budget.rs
+
+/*******************************************************************************
+ *                               BUDGET MODULE
+ *-------------------------------------------------------------------------------
+ * Complexity limits for programs produced by generators, so a pathological
+ * output is rejected before it reaches anything that evaluates it. A
+ * `Budget` caps, each optionally:
+ *
+ *   - `depth`        nesting depth of expressions, the root being 1;
+ *   - `arms`         arms of a single `match`;
+ *   - `application`  terms in a single application, the function included;
+ *   - `lets`         `let`s nested inside one another;
+ *   - `nodes`        expressions in the whole program.
+ *
+ * Every violation is an `over-budget` error naming the measured and allowed
+ * values and the path to the offending expression, as `--search` prints it.
+ ******************************************************************************/
+
+use std::{cmp::Reverse, str::FromStr};
+
+use crate::{AstIndex, Diagnostic, Expression, NodeId, Program};
+
+/// Code of every budget violation.
+pub const OVER_BUDGET: &str = "over-budget";
+
+/// Limits on the shape of a program. `None` means unlimited.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Budget {
+    pub max_depth: Option<usize>,
+    pub max_match_arms: Option<usize>,
+    pub max_application_length: Option<usize>,
+    pub max_let_depth: Option<usize>,
+    pub max_nodes: Option<usize>,
+}
+
+/// Checks `program` against `budget`, reporting every limit it exceeds.
+///
+/// # Errors
+///
+/// Returns one `over-budget` error per violation: one for each of depth,
+/// `let` depth, and node count at the deepest or outermost place it is
+/// exceeded, and one for each `match` and application that is too long.
+pub fn enforce_budget(program: &Program, budget: &Budget) -> Result<(), Vec<Diagnostic>> {
+    let index = program.build_index();
+    let mut violations = Vec::new();
+    let mut report = |what: &str, measured: usize, limit: Option<usize>, at: NodeId| {
+        if let Some(limit) = limit.filter(|limit| measured > *limit) {
+            violations.push(Diagnostic::error(
+                OVER_BUDGET,
+                format!(
+                    "{} is {}, over the budget of {}, at {}",
+                    what,
+                    measured,
+                    limit,
+                    index.display_path(at)
+                ),
+            ));
+        }
+    };
+
+    report("node count", index.len(), budget.max_nodes, index.root());
+
+    // Point at the first of the deepest nodes.
+    let depths = (0..index.len()).map(|id| (index.path_to_root(id).len(), id));
+    if let Some((depth, id)) = depths.min_by_key(|&(depth, id)| (Reverse(depth), id)) {
+        report("expression depth", depth, budget.max_depth, id);
+    }
+    let lets = (0..index.len()).map(|id| (let_depth(&index, id), id));
+    if let Some((depth, id)) = lets.min_by_key(|&(depth, id)| (Reverse(depth), id)) {
+        report("let depth", depth, budget.max_let_depth, id);
+    }
+
+    for id in 0..index.len() {
+        match index.node(id) {
+            Expression::PatternMatch { arms, .. } => {
+                report("match arm count", arms.len(), budget.max_match_arms, id)
+            }
+            Expression::Application(items) => report(
+                "application length",
+                items.len(),
+                budget.max_application_length,
+                id,
+            ),
+            _ => {}
+        }
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+/// Number of `let`s enclosing `id`, itself included.
+fn let_depth(index: &AstIndex, id: NodeId) -> usize {
+    index
+        .path_to_root(id)
+        .into_iter()
+        .filter(|&node| matches!(index.node(node), Expression::LetExpr { .. }))
+        .count()
+}
+
+impl FromStr for Budget {
+    type Err = String;
+
+    /// Parses comma-separated limits such as `depth=32,nodes=5000`.
+    fn from_str(limits: &str) -> Result<Self, Self::Err> {
+        let mut budget = Budget::default();
+        for limit in limits.split(',') {
+            let (name, value) = limit
+                .split_once('=')
+                .ok_or_else(|| format!("expected NAME=N, found '{}'", limit))?;
+            let value: usize = value
+                .parse()
+                .map_err(|_| format!("invalid limit '{}' for '{}'", value, name))?;
+            let slot = match name {
+                "depth" => &mut budget.max_depth,
+                "arms" => &mut budget.max_match_arms,
+                "application" => &mut budget.max_application_length,
+                "lets" => &mut budget.max_let_depth,
+                "nodes" => &mut budget.max_nodes,
+                _ => {
+                    return Err(format!(
+                    "unknown limit '{}'; expected one of: depth, arms, application, lets, nodes",
+                    name
+                ))
+                }
+            };
+            *slot = Some(value);
+        }
+        Ok(budget)
+    }
+}
@@ -0,0 +1,280 @@
+//! src/callgraph.rs
+
+/*******************************************************************************
+ *                             CALL GRAPH MODULE
+ *-------------------------------------------------------------------------------
+ * Which `let`-bound functions call which, for finding one's way around larger
+ * programs. Every `let` is a node, named by the bindings whose values it sits
+ * in: `let inner` inside the value of `let outer` is `outer.inner`. A name
+ * bound more than once at the same place gets a `#2`, `#3`, ... suffix from
+ * its second binding on.
+ *
+ * A binding calls another when its value applies it: the other's name heads
+ * an application, or is an operand of `.`. Names are resolved by scope, so a
+ * call reaches the innermost binding of that name, and a top-level
+ * declaration may call one declared below it. Calls through lambda
+ * parameters and pattern variables are not recorded, and calls to names
+ * bound nowhere, such as builtins, reach an external node for the name.
+ * Calls made by the program's body, outside every binding, have no caller
+ * and add no edge.
+ ******************************************************************************/
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{
+    declarations::cycles, index::child_expressions, template::binders_for, template::ungrouped,
+    Expression, FunctionComposition, Program, Term,
+};
+
+/// A binding, or a name called but bound nowhere.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CallNode {
+    /// The qualified name, such as `outer.inner`, or the name itself for an
+    /// external node.
+    pub name: String,
+    /// Whether the name is bound nowhere in the program.
+    pub external: bool,
+}
+
+/// The calls between a program's `let` bindings.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct CallGraph {
+    nodes: Vec<CallNode>,
+    /// (caller, callee) pairs of indices into `nodes`.
+    edges: HashSet<(usize, usize)>,
+}
+
+/// Builds the call graph of the `let` bindings of `program`.
+pub fn call_graph(program: &Program) -> CallGraph {
+    let mut builder = Builder::default();
+    let (declarations, body) = program.declarations();
+
+    // Top-level declarations exist up front, so earlier ones can call later.
+    let ids: Vec<usize> = declarations
+        .iter()
+        .map(|declaration| builder.binding(None, declaration.identifier))
+        .collect();
+    for (declaration, &id) in declarations.iter().zip(&ids) {
+        builder.declared.entry(declaration.identifier).or_insert(id);
+    }
+    for (declaration, &id) in declarations.iter().zip(&ids) {
+        builder
+            .scope
+            .push((declaration.identifier.to_string(), Some(id)));
+        builder.walk(declaration.value, Some(id));
+    }
+    builder.walk(body, None);
+    builder.graph
+}
+
+impl CallGraph {
+    /// Every node, bindings and external names alike, in the order they were
+    /// found.
+    pub fn nodes(&self) -> &[CallNode] {
+        &self.nodes
+    }
+
+    /// Every call, as (caller, callee) names, sorted by name.
+    pub fn edges(&self) -> Vec<(&str, &str)> {
+        let mut edges: Vec<(&str, &str)> = self
+            .edges
+            .iter()
+            .map(|&(from, to)| (self.name(from), self.name(to)))
+            .collect();
+        edges.sort_unstable();
+        edges
+    }
+
+    /// The bindings no other binding calls: the program's entry points.
+    pub fn roots(&self) -> Vec<&str> {
+        let called: BTreeSet<usize> = self
+            .edges
+            .iter()
+            .filter(|(from, to)| from != to)
+            .map(|&(_, to)| to)
+            .collect();
+        self.bindings()
+            .filter(|id| !called.contains(id))
+            .map(|id| self.name(id))
+            .collect()
+    }
+
+    /// The bindings that can't be reached by calls starting from the
+    /// bindings named in `names`, in node order.
+    pub fn unreachable_from(&self, names: &[&str]) -> Vec<&str> {
+        let uses = self.uses();
+        let mut reached = vec![false; self.nodes.len()];
+        let mut stack: Vec<usize> = (0..self.nodes.len())
+            .filter(|&id| names.contains(&self.name(id)))
+            .collect();
+        while let Some(id) = stack.pop() {
+            if !std::mem::replace(&mut reached[id], true) {
+                stack.extend(&uses[id]);
+            }
+        }
+        self.bindings()
+            .filter(|&id| !reached[id])
+            .map(|id| self.name(id))
+            .collect()
+    }
+
+    /// The groups of bindings that call each other, directly or through
+    /// others, including a binding that calls itself. Each group is in node
+    /// order, and the groups are in order of their first member.
+    pub fn cycles(&self) -> Vec<Vec<&str>> {
+        cycles(&self.uses())
+            .into_iter()
+            .map(|cycle| cycle.into_iter().map(|id| self.name(id)).collect())
+            .collect()
+    }
+
+    /// Renders the graph in Graphviz DOT format, with external nodes dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph calls {\n");
+        for node in &self.nodes {
+            if node.external {
+                dot.push_str(&format!("  \"{}\" [style=dashed];\n", node.name));
+            } else {
+                dot.push_str(&format!("  \"{}\";\n", node.name));
+            }
+        }
+        for (from, to) in self.edges() {
+            dot.push_str(&format!("  \"{}\" -> \"{}\";\n", from, to));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn name(&self, id: usize) -> &str {
+        &self.nodes[id].name
+    }
+
+    /// The ids of the binding nodes.
+    fn bindings(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.nodes.len()).filter(|&id| !self.nodes[id].external)
+    }
+
+    /// The callees of each node.
+    fn uses(&self) -> Vec<BTreeSet<usize>> {
+        let mut uses = vec![BTreeSet::new(); self.nodes.len()];
+        for &(from, to) in &self.edges {
+            uses[from].insert(to);
+        }
+        uses
+    }
+}
+
+/// Collects the call graph while walking the program.
+#[derive(Default)]
+struct Builder<'a> {
+    graph: CallGraph,
+    /// Names bound around the current expression, innermost last, with
+    /// their node if they are `let`-bound.
+    scope: Vec<(String, Option<usize>)>,
+    /// The first top-level declaration of each name.
+    declared: HashMap<&'a str, usize>,
+    /// The node of each external name.
+    externals: HashMap<&'a str, usize>,
+    /// How many bindings each qualified name has had.
+    seen: HashMap<String, usize>,
+}
+
+impl<'a> Builder<'a> {
+    /// Adds a node for the binding of `identifier` inside the value of
+    /// `parent`, or at the top when there is none.
+    fn binding(&mut self, parent: Option<usize>, identifier: &str) -> usize {
+        let qualified = match parent {
+            Some(parent) => format!("{}.{}", self.graph.nodes[parent].name, identifier),
+            None => identifier.to_string(),
+        };
+        let count = self.seen.entry(qualified.clone()).or_insert(0);
+        *count += 1;
+        let name = match *count {
+            1 => qualified,
+            n => format!("{}#{}", qualified, n),
+        };
+        self.graph.nodes.push(CallNode {
+            name,
+            external: false,
+        });
+        self.graph.nodes.len() - 1
+    }
+
+    /// Records the calls in `expression`, made by `caller`.
+    fn walk(&mut self, expression: &'a Expression, caller: Option<usize>) {
+        if let Expression::LetExpr {
+            identifier,
+            value,
+            body,
+            ..
+        } = expression
+        {
+            let id = self.binding(caller, identifier);
+            self.scope.push((identifier.clone(), Some(id)));
+            self.walk(value, Some(id));
+            self.walk(body, caller);
+            self.scope.pop();
+            return;
+        }
+        if let Expression::LetAnd { bindings, body, .. } = expression {
+            let depth = self.scope.len();
+            let ids: Vec<usize> = bindings
+                .iter()
+                .map(|binding| self.binding(caller, &binding.identifier))
+                .collect();
+            for (binding, &id) in bindings.iter().zip(&ids) {
+                self.scope.push((binding.identifier.clone(), Some(id)));
+            }
+            for (binding, &id) in bindings.iter().zip(&ids) {
+                self.walk(&binding.value, Some(id));
+            }
+            self.walk(body, caller);
+            self.scope.truncate(depth);
+            return;
+        }
+
+        match expression {
+            Expression::Application(items) => self.call(&items[0], caller),
+            Expression::FunctionComposition(FunctionComposition { f, g }) => {
+                self.call(f, caller);
+                self.call(g, caller);
+            }
+            _ => {}
+        }
+        for (step, child) in child_expressions(expression) {
+            let depth = self.scope.len();
+            self.scope.extend(
+                binders_for(expression, step)
+                    .into_iter()
+                    .map(|name| (name, None)),
+            );
+            self.walk(child, caller);
+            self.scope.truncate(depth);
+        }
+    }
+
+    /// Records a call of `callee` by `caller`, if `callee` is a name that
+    /// isn't a parameter.
+    fn call(&mut self, callee: &'a Expression, caller: Option<usize>) {
+        let Expression::Term(Term::Identifier(name)) = ungrouped(callee) else {
+            return;
+        };
+        let target = match self.scope.iter().rev().find(|(bound, _)| bound == name) {
+            Some((_, Some(id))) => *id,
+            Some((_, None)) => return,
+            None => match self.declared.get(name.as_str()) {
+                Some(&id) => id,
+                None => *self.externals.entry(name).or_insert_with(|| {
+                    self.graph.nodes.push(CallNode {
+                        name: name.clone(),
+                        external: true,
+                    });
+                    self.graph.nodes.len() - 1
+                }),
+            },
+        };
+        if let Some(caller) = caller {
+            self.graph.edges.insert((caller, target));
+        }
+    }
+}
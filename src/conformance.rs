@@ -0,0 +1,495 @@
+//! src/conformance.rs
+
+/*******************************************************************************
+ *                            CONFORMANCE MODULE
+ *-------------------------------------------------------------------------------
+ * Runs the language conformance suite stored under `conformance/`. Every case
+ * is a directory holding an `input.pfl` program alongside the outcome that any
+ * implementation of the language is expected to produce for it:
+ *
+ *   - `tokens.txt` : the token stream, one token per line with its position
+ *   - `ast.txt`    : the resulting `Program` as an s-expression
+ *   - `error.txt`  : the phase that failed, the error code, its span, and the
+ *                    message
+ *
+ * Every format is defined here rather than derived from `Debug`, so renaming
+ * a field or a variant in the crate leaves the fixtures alone.
+ *
+ * A missing expectation file means that artifact must *not* be produced, e.g.
+ * a case without `ast.txt` is expected to fail before an AST exists. The exact
+ * manifest format is documented in `conformance/README.md`, so other
+ * implementations can consume the same files.
+ ******************************************************************************/
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    Expression, FunctionComposition, LetBinding, Lexer, LineIndex, MatchArm, ParseError, Parser,
+    Pattern, Program, SpannedToken, Term, Token, TypeAnnotation, UnaryOperator,
+};
+
+/// Location of the conformance suite bundled with this crate.
+pub const SUITE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/conformance");
+
+/// File holding the program under test.
+pub const INPUT_FILE: &str = "input.pfl";
+
+/// File holding the expected token stream.
+pub const TOKENS_FILE: &str = "tokens.txt";
+
+/// File holding the expected AST.
+pub const AST_FILE: &str = "ast.txt";
+
+/// File holding the expected error.
+pub const ERROR_FILE: &str = "error.txt";
+
+/*******************************************************************************
+ *                                 OUTCOME
+ *-------------------------------------------------------------------------------
+ * The textual artifacts produced by running a single program through the
+ * lexer and parser. These are exactly what the expectation files contain.
+ ******************************************************************************/
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Outcome {
+    /// The token stream, if lexing succeeded.
+    pub tokens: Option<String>,
+    /// The AST, if parsing succeeded.
+    pub ast: Option<String>,
+    /// The rendered error, if either phase failed.
+    pub error: Option<String>,
+}
+
+impl Outcome {
+    /// Lexes and parses `input`, rendering every artifact in the suite's format.
+    pub fn of(input: &str) -> Self {
+        let mut outcome = Outcome::default();
+        let index = LineIndex::new(input);
+
+        let tokens = match Lexer::new(input).tokenize_spanned() {
+            Ok(tokens) => tokens,
+            Err(err) => {
+                outcome.error = Some(render_error("lex", &err, &index));
+                return outcome;
+            }
+        };
+        outcome.tokens = Some(render_tokens(&tokens));
+
+        match Parser::from_spanned(tokens).parse_program() {
+            Ok(program) => outcome.ast = Some(render_ast(&program)),
+            Err(err) => outcome.error = Some(render_error("parse", &err, &index)),
+        }
+        outcome
+    }
+
+    /// Pairs each expectation file name with the artifact it should contain.
+    fn artifacts(&self) -> [(&'static str, &Option<String>); 3] {
+        [
+            (TOKENS_FILE, &self.tokens),
+            (AST_FILE, &self.ast),
+            (ERROR_FILE, &self.error),
+        ]
+    }
+}
+
+/// Renders a token stream in the suite's textual format: one token per
+/// line, as its `line:column`, its kind, and its source text.
+pub fn render_tokens(tokens: &[SpannedToken]) -> String {
+    tokens
+        .iter()
+        .map(
+            |SpannedToken {
+                 token, position, ..
+             }| match token {
+                Token::Eof => format!("{} eof", position),
+                _ => format!("{} {} {}", position, token_kind(token), token),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The kind a token is listed under in `tokens.txt`.
+fn token_kind(token: &Token) -> &'static str {
+    match token {
+        Token::Let
+        | Token::In
+        | Token::If
+        | Token::Then
+        | Token::Else
+        | Token::Match
+        | Token::With => "keyword",
+        Token::True | Token::False => "boolean",
+        Token::Identifier(_) => "identifier",
+        Token::Number(_) => "number",
+        Token::String(_) => "string",
+        Token::Whitespace(_) | Token::Comment(_) => "trivia",
+        _ => "symbol",
+    }
+}
+
+/// Renders an error in the suite's textual format: the phase that failed,
+/// the error code, the half-open span it covers as `line:column-line:column`
+/// if it has one, and the message.
+pub fn render_error(phase: &str, err: &ParseError, index: &LineIndex) -> String {
+    let at = |offset| {
+        let (line, column) = index.offset_to_line_col(offset);
+        format!("{}:{}", line, column)
+    };
+    match err.span(index) {
+        Some(span) => format!(
+            "{} {} {}-{}: {}",
+            phase,
+            err.code(),
+            at(span.start),
+            at(span.end),
+            err
+        ),
+        None => format!("{} {}: {}", phase, err.code(), err),
+    }
+}
+
+/*******************************************************************************
+ *                               S-EXPRESSIONS
+ *-------------------------------------------------------------------------------
+ * `ast.txt` holds the program as an s-expression. Names, numbers, Booleans,
+ * and quoted strings are atoms; every other node is a list headed by its
+ * form, e.g. `(let x 1 (+ x 2))`. A list that does not fit in 80 columns is
+ * broken after its head, with each element on its own line two spaces in.
+ ******************************************************************************/
+
+/// Lines of `ast.txt` are kept within this many columns where they can be.
+const AST_WIDTH: usize = 80;
+
+enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>),
+}
+
+impl Sexp {
+    fn atom(text: impl Into<String>) -> Self {
+        Sexp::Atom(text.into())
+    }
+
+    /// A list headed by the form `head`.
+    fn form(head: &str, elements: impl IntoIterator<Item = Sexp>) -> Self {
+        Sexp::List(std::iter::once(Sexp::atom(head)).chain(elements).collect())
+    }
+
+    fn flat(&self) -> String {
+        match self {
+            Sexp::Atom(text) => text.clone(),
+            Sexp::List(elements) => {
+                let elements: Vec<String> = elements.iter().map(Sexp::flat).collect();
+                format!("({})", elements.join(" "))
+            }
+        }
+    }
+
+    fn write(&self, out: &mut String, indent: usize) {
+        let flat = self.flat();
+        match self {
+            Sexp::List(elements) if indent + flat.chars().count() > AST_WIDTH => {
+                out.push('(');
+                out.push_str(&elements[0].flat());
+                for element in &elements[1..] {
+                    out.push('\n');
+                    out.push_str(&" ".repeat(indent + 2));
+                    element.write(out, indent + 2);
+                }
+                out.push(')');
+            }
+            _ => out.push_str(&flat),
+        }
+    }
+}
+
+/// Renders a program in the suite's s-expression format.
+pub fn render_ast(program: &Program) -> String {
+    let mut out = String::new();
+    expression(&program.expression).write(&mut out, 0);
+    out
+}
+
+fn expression(expression: &Expression) -> Sexp {
+    // Comparisons and logic have an optional right operand.
+    let binary = |symbol: &str, left: &Expression, right: Option<&Expression>| {
+        Sexp::form(
+            symbol,
+            std::iter::once(self::expression(left)).chain(right.map(self::expression)),
+        )
+    };
+    match expression {
+        Expression::LetExpr {
+            recursive,
+            identifier,
+            type_annotation,
+            value,
+            body,
+        } => Sexp::form(
+            if *recursive { "let-rec" } else { "let" },
+            std::iter::once(Sexp::atom(identifier))
+                .chain(annotation(type_annotation))
+                .chain([self::expression(value), self::expression(body)]),
+        ),
+        Expression::LetAnd {
+            recursive,
+            bindings,
+            body,
+        } => Sexp::form(
+            if *recursive { "let-rec-and" } else { "let-and" },
+            bindings.iter().map(binding).chain([self::expression(body)]),
+        ),
+        Expression::IfExpr {
+            condition,
+            then_branch,
+            else_branch,
+        } => Sexp::form(
+            "if",
+            [condition, then_branch, else_branch].map(|branch| self::expression(branch)),
+        ),
+        Expression::Lambda {
+            parameter,
+            type_annotation,
+            body,
+        } => Sexp::form(
+            "lambda",
+            std::iter::once(Sexp::atom(parameter))
+                .chain(annotation(type_annotation))
+                .chain([self::expression(body)]),
+        ),
+        Expression::PatternMatch { expression, arms } => Sexp::form(
+            "match",
+            std::iter::once(self::expression(expression)).chain(arms.iter().map(arm)),
+        ),
+        Expression::Comparison {
+            left,
+            operator,
+            right,
+        } => binary(operator.symbol(), left, right.as_deref()),
+        Expression::Logic {
+            left,
+            operator,
+            right,
+        } => binary(operator.symbol(), left, right.as_deref()),
+        Expression::Arithmetic {
+            left,
+            operator,
+            right,
+        } => binary(operator.symbol(), left, Some(right)),
+        Expression::List {
+            left,
+            operator,
+            right,
+        } => binary(operator.symbol(), left, Some(right)),
+        Expression::Unary { operator, operand } => Sexp::form(
+            match operator {
+                UnaryOperator::Not => "not",
+                UnaryOperator::Negate => "negate",
+            },
+            [self::expression(operand)],
+        ),
+        Expression::Application(items) => Sexp::form("apply", items.iter().map(self::expression)),
+        Expression::Term(term) => self::term(term),
+        Expression::FunctionComposition(FunctionComposition { f, g }) => {
+            Sexp::form(".", [self::expression(f), self::expression(g)])
+        }
+    }
+}
+
+fn term(term: &Term) -> Sexp {
+    match term {
+        Term::Identifier(name) => Sexp::atom(name),
+        Term::Number(value) => Sexp::atom(value.to_string()),
+        Term::String(text) => Sexp::atom(Token::String(text.clone()).to_string()),
+        Term::Boolean(value) => Sexp::atom(value.to_string()),
+        Term::GroupedExpression(inner) => Sexp::form("group", [expression(inner)]),
+        Term::MemberAccess { expression, member } => {
+            Sexp::form("member", [self::expression(expression), Sexp::atom(member)])
+        }
+        Term::Tuple(elements) => Sexp::form("tuple", elements.iter().map(expression)),
+        Term::List(elements) => Sexp::form("list", elements.iter().map(expression)),
+    }
+}
+
+fn binding(binding: &LetBinding) -> Sexp {
+    Sexp::List(
+        std::iter::once(Sexp::atom(&binding.identifier))
+            .chain(annotation(&binding.type_annotation))
+            .chain([expression(&binding.value)])
+            .collect(),
+    )
+}
+
+fn arm(arm: &MatchArm) -> Sexp {
+    let guard = arm
+        .guard
+        .as_deref()
+        .map(|guard| Sexp::form("when", [expression(guard)]));
+    Sexp::form(
+        "arm",
+        std::iter::once(pattern(&arm.pattern))
+            .chain(guard)
+            .chain([expression(&arm.expression)]),
+    )
+}
+
+fn pattern(pattern: &Pattern) -> Sexp {
+    match pattern {
+        Pattern::Identifier(name) => Sexp::atom(name),
+        Pattern::Number(value) => Sexp::atom(value.to_string()),
+        Pattern::Boolean(value) => Sexp::atom(value.to_string()),
+        Pattern::Grouped(inner) => Sexp::form("group", [self::pattern(inner)]),
+        Pattern::Cons(head, tail) => Sexp::form("::", [self::pattern(head), self::pattern(tail)]),
+    }
+}
+
+/// The `(: type)` element of an annotated binder, if it is annotated.
+fn annotation(annotation: &Option<TypeAnnotation>) -> Option<Sexp> {
+    annotation
+        .as_ref()
+        .map(|annotation| Sexp::form(":", [type_annotation(annotation)]))
+}
+
+fn type_annotation(annotation: &TypeAnnotation) -> Sexp {
+    match annotation {
+        TypeAnnotation::Int => Sexp::atom("Int"),
+        TypeAnnotation::Bool => Sexp::atom("Bool"),
+        TypeAnnotation::String => Sexp::atom("String"),
+        TypeAnnotation::Float => Sexp::atom("Float"),
+        TypeAnnotation::Named(name) | TypeAnnotation::Var(name) => Sexp::atom(name),
+        TypeAnnotation::Function(from, to) => {
+            Sexp::form("->", [type_annotation(from), type_annotation(to)])
+        }
+        TypeAnnotation::List(element) => Sexp::form("list", [type_annotation(element)]),
+        TypeAnnotation::Tuple(elements) => {
+            Sexp::form("tuple", elements.iter().map(type_annotation))
+        }
+    }
+}
+
+/*******************************************************************************
+ *                                 REPORT
+ *-------------------------------------------------------------------------------
+ * Aggregated results of a suite run. Each failure names the case, the
+ * expectation file that disagreed, and both sides of the comparison.
+ ******************************************************************************/
+
+/// A single expectation that did not hold.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Failure {
+    /// The case directory name, e.g. `001-let-basic`.
+    pub case: String,
+    /// The expectation file that disagreed, e.g. `ast.txt`.
+    pub file: &'static str,
+    /// The expected artifact, or `None` if it should not have been produced.
+    pub expected: Option<String>,
+    /// The produced artifact, or `None` if it was not produced.
+    pub actual: Option<String>,
+}
+
+/// Summary of a suite run.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Report {
+    /// Number of cases whose expectations all held.
+    pub passed: usize,
+    /// Every expectation that did not hold.
+    pub failures: Vec<Failure>,
+}
+
+impl Report {
+    /// Returns `true` if every case passed.
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/*******************************************************************************
+ *                                 RUNNER
+ ******************************************************************************/
+
+/// Runs the bundled suite against this crate.
+pub fn run_all() -> io::Result<Report> {
+    run_dir(Path::new(SUITE_DIR))
+}
+
+/// Runs every case found under `dir` against this crate.
+pub fn run_dir(dir: &Path) -> io::Result<Report> {
+    let mut report = Report::default();
+
+    for case in case_dirs(dir)? {
+        let outcome = Outcome::of(&fs::read_to_string(case.join(INPUT_FILE))?);
+        let name = case_name(&case);
+        let mut passed = true;
+
+        for (file, actual) in outcome.artifacts() {
+            let expected = read_expectation(&case.join(file))?;
+            if expected != *actual {
+                passed = false;
+                report.failures.push(Failure {
+                    case: name.clone(),
+                    file,
+                    expected,
+                    actual: actual.clone(),
+                });
+            }
+        }
+
+        if passed {
+            report.passed += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// Rewrites the expectation files of every case under `dir` from the current
+/// implementation. Used after an intentional change in behavior.
+pub fn bless_dir(dir: &Path) -> io::Result<()> {
+    for case in case_dirs(dir)? {
+        let outcome = Outcome::of(&fs::read_to_string(case.join(INPUT_FILE))?);
+
+        for (file, artifact) in outcome.artifacts() {
+            let path = case.join(file);
+            match artifact {
+                Some(text) => fs::write(path, format!("{}\n", text))?,
+                None if path.exists() => fs::remove_file(path)?,
+                None => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/*******************************************************************************
+ *                                 HELPERS
+ ******************************************************************************/
+
+/// Lists the case directories under `dir`, sorted by name.
+fn case_dirs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut cases = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.join(INPUT_FILE).is_file() {
+            cases.push(path);
+        }
+    }
+    cases.sort();
+    Ok(cases)
+}
+
+/// Reads an expectation file, treating a missing file as "not produced".
+fn read_expectation(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text.trim_end_matches('\n').to_string())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn case_name(case: &Path) -> String {
+    case.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
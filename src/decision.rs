@@ -0,0 +1,141 @@
+//! src/decision.rs
+
+/*******************************************************************************
+ *                              DECISION MODULE
+ *-------------------------------------------------------------------------------
+ * Compiles the arms of a `match` into a decision tree, so selecting an arm
+ * costs a few comparisons instead of one test per arm. Whatever evaluates
+ * matches should select arms through it; selection must always agree with
+ * trying the arms in order and taking the first that matches.
+ *
+ * Patterns have no sub-patterns yet, so the tree is a single switch on the
+ * scrutinee: number literals, sorted for binary search, then a default arm.
+ * Only the first arm for each literal is kept, and nothing after the first
+ * irrefutable arm, since those arms can never be selected. Scrutinees are
+ * numbers, which match no Boolean or cons pattern, so those arms are left out
+ * too.
+ *
+ * A guard is only decided at run time, so the tree stops at the first guarded
+ * arm a number can match. A value that no arm before it selects is
+ * undecided: from that arm on, arms must be tried in order.
+ ******************************************************************************/
+
+use std::cmp::Ordering;
+
+use crate::{MatchArm, Pattern};
+
+/// A compiled `match`: which arm a scrutinee selects.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DecisionTree {
+    /// Literal arms as (literal, arm index), sorted by literal.
+    cases: Vec<(f64, usize)>,
+    /// The first irrefutable arm, taken when no literal matches.
+    default: Option<usize>,
+    /// The first guarded arm a number can match, where the tree stops.
+    guarded: Option<usize>,
+    /// How many arms the `match` has.
+    arms: usize,
+}
+
+/// The arm a value selects, and how many comparisons selecting it took.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Selection {
+    /// The selected arm, or `None` if no arm matches. When `undecided`, the
+    /// guarded arm where trying arms in order has to resume.
+    pub arm: Option<usize>,
+    pub comparisons: usize,
+    /// Whether the value reached a guarded arm, so the tree can't tell which
+    /// arm it selects.
+    pub undecided: bool,
+}
+
+/// Compiles `arms` into a decision tree.
+pub fn compile_match(arms: &[MatchArm]) -> DecisionTree {
+    let mut cases: Vec<(f64, usize)> = Vec::new();
+    let (mut default, mut guarded) = (None, None);
+    for (index, arm) in arms.iter().enumerate() {
+        match test(&arm.pattern) {
+            Test::Never => {}
+            Test::Number(_) | Test::Any if arm.guard.is_some() => {
+                guarded = Some(index);
+                break;
+            }
+            Test::Number(value) => cases.push((value, index)),
+            Test::Any => {
+                default = Some(index);
+                break;
+            }
+        }
+    }
+    // A stable sort keeps repeated literals in arm order; the first wins.
+    cases.sort_by(|a, b| a.0.total_cmp(&b.0));
+    cases.dedup_by(|later, first| later.0 == first.0);
+    DecisionTree {
+        cases,
+        default,
+        guarded,
+        arms: arms.len(),
+    }
+}
+
+impl DecisionTree {
+    /// Selects the arm for a scrutinee equal to `value`.
+    pub fn select(&self, value: f64) -> Selection {
+        let (mut low, mut high) = (0, self.cases.len());
+        let mut comparisons = 0;
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let (literal, arm) = self.cases[middle];
+            comparisons += 1;
+            match value.partial_cmp(&literal) {
+                Some(Ordering::Equal) => {
+                    return Selection {
+                        arm: Some(arm),
+                        comparisons,
+                        undecided: false,
+                    }
+                }
+                Some(Ordering::Less) => high = middle,
+                Some(Ordering::Greater) => low = middle + 1,
+                // NaN equals no literal.
+                None => break,
+            }
+        }
+        Selection {
+            arm: self.default.or(self.guarded),
+            comparisons,
+            undecided: self.guarded.is_some(),
+        }
+    }
+
+    /// The arms that can be selected, in source order. Every other arm is
+    /// unreachable. Any arm from the first guarded one on may be selected.
+    pub fn reachable_arms(&self) -> Vec<usize> {
+        let mut arms: Vec<usize> = self.cases.iter().map(|&(_, arm)| arm).collect();
+        arms.extend(self.default);
+        if let Some(guarded) = self.guarded {
+            arms.extend(guarded..self.arms);
+        }
+        arms.sort_unstable();
+        arms
+    }
+}
+
+/// What a pattern checks of the scrutinee.
+enum Test {
+    /// Equality with a number.
+    Number(f64),
+    /// A Boolean or list shape, which no number passes.
+    Never,
+    /// Nothing: every value matches.
+    Any,
+}
+
+fn test(pattern: &Pattern) -> Test {
+    match pattern {
+        Pattern::Number(value) => Test::Number(*value),
+        Pattern::Boolean(_) | Pattern::Cons(..) => Test::Never,
+        Pattern::Identifier(_) => Test::Any,
+        Pattern::Grouped(inner) => test(inner),
+    }
+}
@@ -0,0 +1,182 @@
+//! src/declarations.rs
+
+/*******************************************************************************
+ *                            DECLARATIONS MODULE
+ *-------------------------------------------------------------------------------
+ * Dependency order for a program's top-level declarations: the chain of
+ * `let`s the program starts with, `let a = ... in let b = ... in body`.
+ *
+ * Within that chain a declaration may use one defined below it, so source
+ * order need not be evaluation order. `Program::topo_sorted` reorders the
+ * chain so every declaration comes after the ones it uses, keeping source
+ * order wherever dependencies allow, and reports declarations that depend on
+ * each other in a cycle. A declaration using itself is fine: `let` is
+ * recursive.
+ *
+ * Forward references make a name's declaration ambiguous if it is declared
+ * twice, so duplicate top-level names are reported too.
+ ******************************************************************************/
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{free_variables, Diagnostic, Expression, Program, TypeAnnotation};
+
+/// One top-level `let`, borrowed from its program.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Declaration<'a> {
+    pub recursive: bool,
+    pub identifier: &'a str,
+    pub type_annotation: &'a Option<TypeAnnotation>,
+    pub value: &'a Expression,
+}
+
+impl Program {
+    /// The `let`s the program starts with, outermost first, and the
+    /// expression they scope over.
+    pub fn declarations(&self) -> (Vec<Declaration<'_>>, &Expression) {
+        let mut declarations = Vec::new();
+        let mut body = &self.expression;
+        while let Expression::LetExpr {
+            recursive,
+            identifier,
+            type_annotation,
+            value,
+            body: inner,
+        } = body
+        {
+            declarations.push(Declaration {
+                recursive: *recursive,
+                identifier,
+                type_annotation,
+                value,
+            });
+            body = inner;
+        }
+        (declarations, body)
+    }
+
+    /// The program with its top-level declarations reordered so each comes
+    /// after the declarations it uses. Independent declarations keep their
+    /// source order, so an already ordered program comes back unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `duplicate-declaration` error for each name declared more
+    /// than once, or otherwise a `declaration-cycle` error for each group of
+    /// declarations that use each other.
+    pub fn topo_sorted(&self) -> Result<Program, Vec<Diagnostic>> {
+        let (declarations, body) = self.declarations();
+        let names: Vec<&str> = declarations
+            .iter()
+            .map(|declaration| declaration.identifier)
+            .collect();
+
+        let mut positions = HashMap::new();
+        let mut errors = Vec::new();
+        for (i, name) in names.iter().enumerate() {
+            if positions.insert(*name, i).is_some() {
+                errors.push(Diagnostic::error(
+                    "duplicate-declaration",
+                    format!("'{}' is declared more than once at the top level", name),
+                ));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        // `uses[i]`: the other declarations that declaration `i` refers to.
+        let uses: Vec<BTreeSet<usize>> = declarations
+            .iter()
+            .enumerate()
+            .map(|(i, declaration)| {
+                free_variables(declaration.value)
+                    .iter()
+                    .filter_map(|name| positions.get(name.as_str()).copied())
+                    .filter(|&j| j != i)
+                    .collect()
+            })
+            .collect();
+
+        let cycles = cycles(&uses);
+        if !cycles.is_empty() {
+            return Err(cycles
+                .iter()
+                .map(|cycle| {
+                    let members: Vec<String> =
+                        cycle.iter().map(|&i| format!("'{}'", names[i])).collect();
+                    Diagnostic::error(
+                        "declaration-cycle",
+                        format!(
+                            "top-level declarations depend on each other in a cycle: {}",
+                            members.join(", ")
+                        ),
+                    )
+                })
+                .collect());
+        }
+
+        // Repeatedly take the first declaration in source order whose
+        // dependencies are all placed.
+        let mut placed = vec![false; declarations.len()];
+        let mut order = Vec::with_capacity(declarations.len());
+        while order.len() < declarations.len() {
+            let next = (0..declarations.len())
+                .find(|&i| !placed[i] && uses[i].iter().all(|&j| placed[j]))
+                .expect("acyclic declarations always have one ready");
+            placed[next] = true;
+            order.push(next);
+        }
+
+        let expression = order.iter().rev().fold(body.clone(), |body, &i| {
+            let declaration = declarations[i];
+            Expression::LetExpr {
+                recursive: declaration.recursive,
+                identifier: declaration.identifier.to_string(),
+                type_annotation: declaration.type_annotation.clone(),
+                value: Box::new(declaration.value.clone()),
+                body: Box::new(body),
+            }
+        });
+        Ok(Program { expression })
+    }
+}
+
+/// The strongly connected components of the graph `uses` that contain a
+/// cycle, each sorted, in order of their first member. A node is in a
+/// component of its own only if it uses itself.
+pub(crate) fn cycles(uses: &[BTreeSet<usize>]) -> Vec<Vec<usize>> {
+    // Members of a cycle are exactly the nodes that reach each other.
+    let reach: Vec<BTreeSet<usize>> = (0..uses.len())
+        .map(|start| {
+            let mut seen = BTreeSet::new();
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                for &next in &uses[node] {
+                    if seen.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+            seen
+        })
+        .collect();
+
+    let mut assigned = vec![false; uses.len()];
+    let mut cycles = Vec::new();
+    for i in 0..uses.len() {
+        if assigned[i] || !reach[i].contains(&i) {
+            continue;
+        }
+        let cycle: Vec<usize> = reach[i]
+            .iter()
+            .copied()
+            .filter(|&j| reach[j].contains(&i))
+            .collect();
+        for &j in &cycle {
+            assigned[j] = true;
+        }
+        cycles.push(cycle);
+    }
+    cycles
+}
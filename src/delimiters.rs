@@ -0,0 +1,114 @@
+//! src/delimiters.rs
+
+/*******************************************************************************
+ *                             DELIMITERS MODULE
+ *-------------------------------------------------------------------------------
+ * Finds the partner of a delimiter, for an editor's "jump to matching". It
+ * works from the token stream rather than the AST, so it keeps working on
+ * code that doesn't parse, or even lex: the tokens come from
+ * `tokenize_lossy`, which lexes around whatever it can't.
+ *
+ * Parentheses pair up with a stack of their own. Keywords pair up in one scan
+ * with a stack of the constructs they open: `let` with `in`, `match` with
+ * `with`, and `if` with `then` and `else`, which lead to each other in turn,
+ * so repeated jumps cycle through the construct. A `with` that doesn't close
+ * a `match` starts a binding of a match arm, and is no delimiter.
+ *
+ * Nothing is guessed. A delimiter whose partner is missing has none; once a
+ * closing keyword doesn't close the innermost open construct, or a `)`
+ * closes over a construct still open, no keyword after it is paired either.
+ ******************************************************************************/
+
+use crate::{Lexer, LossyToken, Span, Token};
+
+/// A keyword construct opened but not closed yet, by the index of its first
+/// token.
+enum Open {
+    Let(usize),
+    Match(usize),
+    /// An `if`, and its `then` once seen.
+    If(usize, Option<usize>),
+}
+
+/// The span of the partner of the delimiter at `offset` in `src`: the other
+/// parenthesis of a `(` or `)`, or the next keyword of the `let`, `if`, or
+/// `match` construct whose keyword it is. Returns `None` if `offset` is not on
+/// a delimiter, or the delimiter is unbalanced.
+pub fn matching_delimiter(src: &str, offset: usize) -> Option<Span> {
+    let tokens = lossy_tokens(src);
+    let at = tokens
+        .iter()
+        .position(|(_, span)| span.start <= offset && offset < span.end)?;
+    partners(&tokens)
+        .into_iter()
+        .find_map(|(from, to)| (from == at).then_some(tokens[to].1))
+}
+
+/// The tokens of `src` that lex, with their spans.
+pub(crate) fn lossy_tokens(src: &str) -> Vec<(Token, Span)> {
+    Lexer::new(src)
+        .tokenize_lossy()
+        .into_iter()
+        .filter_map(|piece| match piece {
+            LossyToken::Token { token, span } => Some((token, span)),
+            LossyToken::Trivia { .. } | LossyToken::Error { .. } => None,
+        })
+        .collect()
+}
+
+/// Every delimiter of `tokens` with a partner, as pairs of token indices
+/// from the delimiter to its partner.
+pub(crate) fn partners(tokens: &[(Token, Span)]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    // Each open `(`, with how many constructs were open before it.
+    let mut parens: Vec<(usize, usize)> = Vec::new();
+    let mut open: Vec<Open> = Vec::new();
+    let mut broken = false;
+
+    for (index, (token, _)) in tokens.iter().enumerate() {
+        // Constructs opened outside the innermost `(` can't close inside it.
+        let floor = parens.last().map_or(0, |&(_, depth)| depth);
+        let closable = open.len() > floor;
+        match token {
+            Token::LeftParen => parens.push((index, open.len())),
+            Token::RightParen => {
+                if let Some((left, depth)) = parens.pop() {
+                    pairs.extend([(left, index), (index, left)]);
+                    broken |= open.len() > depth;
+                    open.truncate(depth);
+                }
+            }
+            _ if broken => {}
+            Token::Let => open.push(Open::Let(index)),
+            Token::Match => open.push(Open::Match(index)),
+            Token::If => open.push(Open::If(index, None)),
+            Token::In => match open.last().filter(|_| closable) {
+                Some(&Open::Let(start)) => {
+                    open.pop();
+                    pairs.extend([(start, index), (index, start)]);
+                }
+                _ => broken = true,
+            },
+            Token::With => {
+                if let Some(&Open::Match(start)) = open.last().filter(|_| closable) {
+                    open.pop();
+                    pairs.extend([(start, index), (index, start)]);
+                }
+            }
+            Token::Then => match open.last_mut().filter(|_| closable) {
+                Some(Open::If(_, then @ None)) => *then = Some(index),
+                _ => broken = true,
+            },
+            Token::Else => match open.last().filter(|_| closable) {
+                Some(&Open::If(start, Some(then))) => {
+                    open.pop();
+                    pairs.extend([(start, then), (then, index), (index, start)]);
+                }
+                _ => broken = true,
+            },
+            _ => {}
+        }
+    }
+
+    pairs
+}
@@ -0,0 +1,158 @@
+//! src/diagnostic.rs
+
+/*******************************************************************************
+ *                             DIAGNOSTIC MODULE
+ *-------------------------------------------------------------------------------
+ * Defines `Diagnostic`, the report produced by checks that run on a parsed
+ * `Program` (lints). Unlike `ParseError`, a warning does not stop processing:
+ * the program is well-formed, but likely not what was intended. A
+ * `ParseError` converts into an error-level diagnostic, so tools can report
+ * both through one channel.
+ *
+ * A `DiagnosticsConfig` sets, per diagnostic code, whether warnings are
+ * reported as warnings, promoted to errors, or dropped.
+ ******************************************************************************/
+
+use std::{collections::BTreeMap, fmt};
+
+use crate::{Fix, Location, ParseError};
+
+/// How seriously a diagnostic should be taken.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Severity {
+    /// Worth fixing, but the program is still accepted.
+    Warning,
+    /// The program is rejected.
+    Error,
+}
+
+/// A single finding about a program.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    /// How serious the finding is.
+    pub severity: Severity,
+    /// Stable, kebab-case name of the check that produced it, e.g.
+    /// `non-exhaustive-match`.
+    pub code: &'static str,
+    /// Human-readable explanation.
+    pub message: String,
+    /// Located edits that resolve the finding, as alternatives in order of
+    /// preference, for tools that apply fixes.
+    pub fixes: Vec<Fix>,
+    /// Where the finding is, if known.
+    pub location: Option<Location>,
+}
+
+impl Diagnostic {
+    /// Creates a warning produced by the check named `code`.
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            code,
+            message: message.into(),
+            fixes: Vec::new(),
+            location: None,
+        }
+    }
+
+    /// Creates an error produced by the check named `code`.
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            ..Self::warning(code, message)
+        }
+    }
+
+    /// Writes the rendering shown by `Display` into `out` without allocating.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error only if writing to `out` fails.
+    pub fn write_rendered(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        write!(out, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+
+    /// Attaches a fix, after any already attached.
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fixes.push(fix);
+        self
+    }
+}
+
+/// What to do with the warnings of one diagnostic code.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Level {
+    /// Drop them.
+    Allow,
+    /// Report them as warnings.
+    #[default]
+    Warn,
+    /// Report them as errors, rejecting the program.
+    Deny,
+}
+
+/// Levels for warnings: one for every code, with overrides for specific
+/// codes. Errors are always reported as errors.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DiagnosticsConfig {
+    pub default_level: Level,
+    pub overrides: BTreeMap<String, Level>,
+}
+
+impl DiagnosticsConfig {
+    /// The level for warnings with `code`.
+    pub fn level(&self, code: &str) -> Level {
+        self.overrides
+            .get(code)
+            .copied()
+            .unwrap_or(self.default_level)
+    }
+
+    /// Applies the configured levels to `diagnostics`: allowed warnings are
+    /// dropped and denied ones become errors.
+    pub fn apply(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        diagnostics
+            .into_iter()
+            .filter_map(|mut diagnostic| {
+                if diagnostic.severity == Severity::Warning {
+                    match self.level(diagnostic.code) {
+                        Level::Allow => return None,
+                        Level::Warn => {}
+                        Level::Deny => diagnostic.severity = Severity::Error,
+                    }
+                }
+                Some(diagnostic)
+            })
+            .collect()
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    /// Reports a lexing or parsing failure as an error diagnostic, keyed by the
+    /// error's stable code.
+    fn from(err: &ParseError) -> Self {
+        Self {
+            severity: Severity::Error,
+            code: err.code(),
+            message: err.to_string(),
+            fixes: Vec::new(),
+            location: None,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// Renders as `warning[code]: message`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_rendered(f)
+    }
+}
@@ -0,0 +1,347 @@
+//! src/diff.rs
+
+/*******************************************************************************
+ *                                DIFF MODULE
+ *-------------------------------------------------------------------------------
+ * Semantic diffs between two versions of a program, for code review. A
+ * program's top-level bindings are the chain of `let`s it opens with; what
+ * follows them is its body.
+ *
+ * Bindings are paired by name, occurrence by occurrence. A binding left over
+ * in the old version whose value is alpha-equivalent to one left over in the
+ * new version was renamed; the rest were removed or added. Paired values, and
+ * the bodies, are then compared with each pair's names standing for the same
+ * binder, so renaming a binding does not also modify every use of it.
+ *
+ * A modification is reported at the smallest expression holding the whole
+ * difference. Versions that differ only in whitespace and comments have the
+ * same AST and no changes; versions that differ only in parentheses or in the
+ * names of inner binders have a single formatting-only change.
+ ******************************************************************************/
+
+use std::fmt;
+
+use crate::{
+    alpha_equivalent, index::child_expressions, print_expression, print_type, template::ungrouped,
+    AstIndex, Expression, Program, Term, TypeAnnotation,
+};
+
+/// One difference between two versions of a program.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Change {
+    /// A top-level binding only the new version has.
+    Added { name: String },
+
+    /// A top-level binding only the old version has.
+    Removed { name: String },
+
+    /// A top-level binding whose name changed but whose value did not.
+    Renamed { from: String, to: String },
+
+    /// An expression that changed: its path in the new version's `AstIndex`,
+    /// and both versions of it, printed.
+    Modified {
+        path: String,
+        old: String,
+        new: String,
+    },
+
+    /// The versions differ only in parentheses or the names of inner binders.
+    FormattingOnly,
+}
+
+impl Change {
+    /// Whether the change leaves the program's meaning and names alone.
+    pub fn is_formatting_only(&self) -> bool {
+        matches!(self, Change::FormattingOnly)
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added { name } => write!(f, "added `{}`", name),
+            Change::Removed { name } => write!(f, "removed `{}`", name),
+            Change::Renamed { from, to } => write!(f, "renamed `{}` to `{}`", from, to),
+            Change::Modified { path, old, new } => {
+                write!(f, "modified {}: `{}` -> `{}`", path, old, new)
+            }
+            Change::FormattingOnly => write!(f, "formatting only"),
+        }
+    }
+}
+
+/// Lists the changes from `old` to `new`: bindings removed, renamed, or
+/// modified in the old version's order, then bindings added, then the body.
+/// Empty when the two are the same tree.
+pub fn semantic_diff(old: &Program, new: &Program) -> Vec<Change> {
+    if old == new {
+        return Vec::new();
+    }
+    let (old_bindings, old_body) = spine(&old.expression);
+    let (new_bindings, new_body) = spine(&new.expression);
+
+    // `pairs[i]` is the new binding that old binding `i` became, if any.
+    let mut pairs: Vec<Option<usize>> = vec![None; old_bindings.len()];
+    let mut taken = vec![false; new_bindings.len()];
+    for (i, binding) in old_bindings.iter().enumerate() {
+        pairs[i] =
+            (0..new_bindings.len()).find(|&j| !taken[j] && new_bindings[j].name == binding.name);
+        if let Some(j) = pairs[i] {
+            taken[j] = true;
+        }
+    }
+    for i in 0..old_bindings.len() {
+        if pairs[i].is_some() {
+            continue;
+        }
+        pairs[i] = (0..new_bindings.len()).find(|&j| {
+            let mut candidate = pairs.clone();
+            candidate[i] = Some(j);
+            !taken[j]
+                && old_bindings[i].type_annotation == new_bindings[j].type_annotation
+                && equivalent(
+                    &binders(&candidate, &old_bindings, &new_bindings),
+                    old_bindings[i].value,
+                    new_bindings[j].value,
+                )
+        });
+        if let Some(j) = pairs[i] {
+            taken[j] = true;
+        }
+    }
+
+    let binders = binders(&pairs, &old_bindings, &new_bindings);
+    let index = new.build_index();
+    let mut changes = Vec::new();
+    for (binding, pair) in old_bindings.iter().zip(&pairs) {
+        let Some(other) = pair.map(|j| &new_bindings[j]) else {
+            changes.push(Change::Removed {
+                name: binding.name.to_string(),
+            });
+            continue;
+        };
+        if binding.name != other.name {
+            changes.push(Change::Renamed {
+                from: binding.name.to_string(),
+                to: other.name.to_string(),
+            });
+            continue;
+        }
+        if binding.recursive != other.recursive || binding.type_annotation != other.type_annotation
+        {
+            changes.push(Change::Modified {
+                path: path(&index, other.node),
+                old: binding.header(),
+                new: other.header(),
+            });
+        }
+        if !equivalent(&binders, binding.value, other.value) {
+            changes.push(modified(&index, binding.value, other.value));
+        }
+    }
+    for (binding, _) in new_bindings.iter().zip(&taken).filter(|(_, &taken)| !taken) {
+        changes.push(Change::Added {
+            name: binding.name.to_string(),
+        });
+    }
+    if !equivalent(&binders, old_body, new_body) {
+        changes.push(modified(&index, old_body, new_body));
+    }
+
+    if changes.is_empty() {
+        changes.push(Change::FormattingOnly);
+    }
+    changes
+}
+
+/// A top-level `let` binding.
+struct Binding<'a> {
+    /// The `let` expression itself.
+    node: &'a Expression,
+    recursive: bool,
+    name: &'a str,
+    type_annotation: &'a Option<TypeAnnotation>,
+    value: &'a Expression,
+}
+
+impl Binding<'_> {
+    /// The binding's name and type, as written after `let`.
+    fn header(&self) -> String {
+        let rec = if self.recursive { "rec " } else { "" };
+        match self.type_annotation {
+            Some(annotation) => format!("{}{}: {}", rec, self.name, print_type(annotation)),
+            None => format!("{}{}", rec, self.name),
+        }
+    }
+}
+
+/// Splits `expression` into the `let`s it opens with, outermost first, and
+/// the body after them.
+fn spine(mut expression: &Expression) -> (Vec<Binding<'_>>, &Expression) {
+    let mut bindings = Vec::new();
+    while let node @ Expression::LetExpr {
+        recursive,
+        identifier,
+        type_annotation,
+        value,
+        body,
+    } = ungrouped(expression)
+    {
+        bindings.push(Binding {
+            node,
+            recursive: *recursive,
+            name: identifier,
+            type_annotation,
+            value,
+        });
+        expression = body;
+    }
+    (bindings, expression)
+}
+
+/// The names of each paired binding, old then new, in the old version's
+/// order.
+fn binders<'a>(
+    pairs: &[Option<usize>],
+    old: &[Binding<'a>],
+    new: &[Binding<'a>],
+) -> Vec<(&'a str, &'a str)> {
+    pairs
+        .iter()
+        .zip(old)
+        .filter_map(|(pair, binding)| pair.map(|j| (binding.name, new[j].name)))
+        .collect()
+}
+
+/// Whether `old` and `new` are alpha-equivalent when each pair in `binders`
+/// names the same binder around them. Since `let` is recursive, a binding's
+/// own value is inside its binder too.
+fn equivalent(binders: &[(&str, &str)], old: &Expression, new: &Expression) -> bool {
+    let close = |expression: &Expression, names: Vec<&str>| {
+        names
+            .into_iter()
+            .rev()
+            .fold(expression.clone(), |body, name| Expression::Lambda {
+                parameter: name.to_string(),
+                type_annotation: None,
+                body: Box::new(body),
+            })
+    };
+    alpha_equivalent(
+        &close(old, binders.iter().map(|(name, _)| *name).collect()),
+        &close(new, binders.iter().map(|(_, name)| *name).collect()),
+    )
+}
+
+/// Reports the smallest difference between `old` and `new`, which are known
+/// to differ; `index` indexes the new version.
+fn modified(index: &AstIndex, old: &Expression, new: &Expression) -> Change {
+    let (old, new) = smallest_difference(old, new);
+    Change::Modified {
+        path: path(index, new),
+        old: print_expression(old),
+        new: print_expression(new),
+    }
+}
+
+/// The path to `node` in `index`.
+fn path(index: &AstIndex, node: &Expression) -> String {
+    index
+        .id_of(node)
+        .map_or_else(|| "root".to_string(), |id| index.display_path(id))
+}
+
+/// Descends from `old` and `new` for as long as they differ in exactly one
+/// sub-expression and are otherwise alike, returning where they stop.
+fn smallest_difference<'a, 'b>(
+    mut old: &'a Expression,
+    mut new: &'b Expression,
+) -> (&'a Expression, &'b Expression) {
+    loop {
+        (old, new) = (ungrouped(old), ungrouped(new));
+        let old_children = child_expressions(old);
+        let new_children = child_expressions(new);
+        if !same_head(old, new) || old_children.len() != new_children.len() {
+            return (old, new);
+        }
+        let mut differing = old_children
+            .into_iter()
+            .zip(new_children)
+            .filter(|((_, a), (_, b))| !alpha_equivalent(a, b));
+        match (differing.next(), differing.next()) {
+            (Some(((_, a), (_, b))), None) => (old, new) = (a, b),
+            _ => return (old, new),
+        }
+    }
+}
+
+/// Whether `old` and `new` are the same kind of expression with the same
+/// contents, apart from their sub-expressions.
+fn same_head(old: &Expression, new: &Expression) -> bool {
+    match (old, new) {
+        (
+            Expression::LetExpr {
+                recursive: a_recursive,
+                identifier: a,
+                type_annotation: a_type,
+                ..
+            },
+            Expression::LetExpr {
+                recursive: b_recursive,
+                identifier: b,
+                type_annotation: b_type,
+                ..
+            },
+        ) => a_recursive == b_recursive && a == b && a_type == b_type,
+        (
+            Expression::LetAnd {
+                recursive: a_recursive,
+                bindings: a,
+                ..
+            },
+            Expression::LetAnd {
+                recursive: b_recursive,
+                bindings: b,
+                ..
+            },
+        ) => {
+            a_recursive == b_recursive
+                && a.len() == b.len()
+                && a.iter().zip(b).all(|(a, b)| {
+                    a.identifier == b.identifier && a.type_annotation == b.type_annotation
+                })
+        }
+        (
+            Expression::Lambda {
+                parameter: a,
+                type_annotation: a_type,
+                ..
+            },
+            Expression::Lambda {
+                parameter: b,
+                type_annotation: b_type,
+                ..
+            },
+        ) => a == b && a_type == b_type,
+        (Expression::IfExpr { .. }, Expression::IfExpr { .. }) => true,
+        (Expression::PatternMatch { arms: a, .. }, Expression::PatternMatch { arms: b, .. }) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|(a, b)| a.pattern == b.pattern && a.guard.is_some() == b.guard.is_some())
+        }
+        (Expression::Application(a), Expression::Application(b)) => a.len() == b.len(),
+        (
+            Expression::Term(Term::MemberAccess { member: a, .. }),
+            Expression::Term(Term::MemberAccess { member: b, .. }),
+        ) => a == b,
+        (Expression::Term(Term::Tuple(a)), Expression::Term(Term::Tuple(b)))
+        | (Expression::Term(Term::List(a)), Expression::Term(Term::List(b))) => a.len() == b.len(),
+        (Expression::Term(_), Expression::Term(_)) => false,
+        _ => match (old.as_binary(), new.as_binary()) {
+            (Some((a, ..)), Some((b, ..))) => a == b,
+            _ => false,
+        },
+    }
+}
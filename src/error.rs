@@ -11,6 +11,12 @@
 
 use std::{error, fmt};
 
+use crate::{LineIndex, Position, Span};
+
+/// How many characters of the offending line an excerpt shows at most; longer
+/// lines are cut to a window around the caret.
+const EXCERPT_WIDTH: usize = 80;
+
 /// Enumerates all parse errors that may appear when tokenizing or parsing.
 ///
 /// Each variant holds enough context for downstream systems to identify
@@ -19,19 +25,23 @@ use std::{error, fmt};
 pub enum ParseError {
     /// Signifies that the parser encountered a token other than what
     /// was expected. Contains details on what was expected, what was found,
-    /// and a short message describing the context.
+    /// a short message describing the context, and where the token starts
+    /// when the tokens came with positions.
     UnexpectedToken {
         expected: String,
         found: String,
         message: String,
+        position: Option<Position>,
     },
 
     /// Indicates an abrupt end of input before a complete construct could
     /// be parsed.
     UnexpectedEOF,
 
-    /// Raised when a numeric literal doesn’t parse cleanly (e.g., `12.3.4`).
-    InvalidNumberFormat(String),
+    /// Raised when a numeric literal doesn’t parse cleanly (e.g., `1__0`).
+    /// Carries the literal as written and the offset (in characters) where it
+    /// starts.
+    InvalidNumberFormat { lexeme: String, offset: usize },
 
     /// Raised when an identifier doesn’t conform to the language’s naming rules.
     InvalidIdentifier(String),
@@ -44,16 +54,202 @@ pub enum ParseError {
 
     /// A catch-all for errors that don’t fit other variants.
     Other(String),
+
+    /// Raised when an identifier is longer than the lexer's configured limit.
+    /// Carries the offset (in characters) where the identifier starts.
+    IdentifierTooLong { offset: usize, limit: usize },
+
+    /// Raised when a line is longer than the lexer's configured limit.
+    /// Carries the 1-based line number.
+    LineTooLong { line: usize, limit: usize },
+
+    /// Raised when expressions, patterns, or types nest deeper than the
+    /// parser's limit, or operator chains hold more operators than theirs.
+    NestingTooDeep { limit: usize },
+
+    /// Raised when a number is written with a decimal comma, as in `1,5`.
+    /// Carries the whole run and the offset (in characters) where it starts.
+    CommaDecimal { lexeme: String, offset: usize },
+
+    /// Raised when a string literal contains an escape sequence that isn't
+    /// recognized, such as `\q`. Carries the escape as written and the offset
+    /// (in characters) of its backslash.
+    InvalidEscape { escape: String, offset: usize },
+
+    /// Raised when a comparison follows another at the same level, as in
+    /// `a < b < c`. Carries both operators and where the second starts when
+    /// the tokens came with positions.
+    ChainedComparison {
+        first: String,
+        second: String,
+        position: Option<Position>,
+    },
 }
 
-impl fmt::Display for ParseError {
-    /// Renders an error variant into a user-friendly string.
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl ParseError {
+    /// Every code returned by [`ParseError::code`], in order. Codes are stable:
+    /// new variants get new codes, and retired codes are never reused.
+    pub const CODES: [&'static str; 13] = [
+        "E0001", "E0002", "E0003", "E0004", "E0005", "E0006", "E0007", "E0008", "E0009", "E0010",
+        "E0011", "E0012", "E0013",
+    ];
+
+    /// Returns the stable code identifying this kind of error, e.g. `E0001`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedToken { .. } => "E0001",
+            ParseError::UnexpectedEOF => "E0002",
+            ParseError::InvalidNumberFormat { .. } => "E0003",
+            ParseError::InvalidIdentifier(_) => "E0004",
+            ParseError::UnterminatedString => "E0005",
+            ParseError::MissingPatternMatchArm => "E0006",
+            ParseError::Other(_) => "E0007",
+            ParseError::IdentifierTooLong { .. } => "E0008",
+            ParseError::LineTooLong { .. } => "E0009",
+            ParseError::NestingTooDeep { .. } => "E0010",
+            ParseError::CommaDecimal { .. } => "E0011",
+            ParseError::InvalidEscape { .. } => "E0012",
+            ParseError::ChainedComparison { .. } => "E0013",
+        }
+    }
+
+    /// Writes the full human rendering of the error into `out`: a
+    /// `error[E0001]: message` header and, for errors that carry a position,
+    /// the location and the offending line of `source` with a caret under the
+    /// column. Nothing is allocated along the way; `index` must be built from
+    /// `source` and can be shared by every error of the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error only if writing to `out` fails.
+    pub fn write_rendered(
+        &self,
+        source: &str,
+        index: &LineIndex,
+        out: &mut impl fmt::Write,
+    ) -> fmt::Result {
+        write!(out, "error[{}]: ", self.code())?;
+        self.write_message(out)?;
+        out.write_char('\n')?;
+        self.write_excerpt(source, index, out)
+    }
+
+    /// Writes the part of [`ParseError::write_rendered`] after its header:
+    /// the location and the offending line of `source` with a caret under the
+    /// column, or nothing for errors without a position. A line longer than 80
+    /// characters is cut to a window around the column, and each cut end is
+    /// marked with `…`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error only if writing to `out` fails.
+    pub fn write_excerpt(
+        &self,
+        source: &str,
+        index: &LineIndex,
+        out: &mut impl fmt::Write,
+    ) -> fmt::Result {
+        let Some((line, column)) = self.line_col(index) else {
+            return Ok(());
+        };
+        let text = index.line_text(source, line).unwrap_or_default();
+        let gutter = digits(line);
+        writeln!(
+            out,
+            "{:>gutter$}--> {}:{}",
+            "",
+            line,
+            column,
+            gutter = gutter
+        )?;
+        writeln!(out, "{:>gutter$} |", "", gutter = gutter)?;
+        let window = Window::around(text, column);
+        write!(out, "{} | ", line)?;
+        if window.cut_start {
+            out.write_char('…')?;
+        }
+        out.write_str(window.text)?;
+        if window.cut_end {
+            out.write_char('…')?;
+        }
+        out.write_char('\n')?;
+        writeln!(
+            out,
+            "{:>gutter$} | {:>caret$}",
+            "",
+            "^",
+            gutter = gutter,
+            caret = window.caret
+        )
+    }
+
+    /// The characters the error points at, if it carries a position. `index`
+    /// must be built from the text that failed.
+    pub fn span(&self, index: &LineIndex) -> Option<Span> {
+        match self {
+            // The identifier up to the first character past the limit.
+            ParseError::IdentifierTooLong { offset, limit } => Some(Span {
+                start: *offset,
+                end: offset + limit + 1,
+            }),
+            // The number, as far as it was read; with its decimal comma.
+            ParseError::InvalidNumberFormat { lexeme, offset }
+            | ParseError::CommaDecimal { lexeme, offset } => Some(Span {
+                start: *offset,
+                end: offset + lexeme.chars().count(),
+            }),
+            // The escape sequence, from its backslash.
+            ParseError::InvalidEscape { escape, offset } => Some(Span {
+                start: *offset,
+                end: offset + escape.chars().count(),
+            }),
+            // The offending token's first character.
+            ParseError::UnexpectedToken {
+                position: Some(position),
+                ..
+            }
+            | ParseError::ChainedComparison {
+                position: Some(position),
+                ..
+            } => {
+                let start = index.line_col_to_offset(position.line, position.column)?;
+                Some(Span {
+                    start,
+                    end: start + 1,
+                })
+            }
+            // The first character past the limit.
+            ParseError::LineTooLong { line, limit } => {
+                let start = index.line_col_to_offset(*line, limit + 1)?;
+                Some(Span {
+                    start,
+                    end: start + 1,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The 1-based line and column the error points at, if it carries a
+    /// position: where its span starts, except that an identifier too long
+    /// points at its first character past the limit.
+    pub fn line_col(&self, index: &LineIndex) -> Option<(usize, usize)> {
+        let span = self.span(index)?;
+        let offset = match self {
+            ParseError::IdentifierTooLong { .. } => span.end - 1,
+            _ => span.start,
+        };
+        Some(index.offset_to_line_col(offset))
+    }
+
+    /// Writes the one-line message shown by `Display`.
+    fn write_message(&self, f: &mut impl fmt::Write) -> fmt::Result {
         match self {
             ParseError::UnexpectedToken {
                 expected,
                 found,
                 message,
+                ..
             } => {
                 write!(
                     f,
@@ -62,8 +258,8 @@ impl fmt::Display for ParseError {
                 )
             }
             ParseError::UnexpectedEOF => write!(f, "Unexpected end of file."),
-            ParseError::InvalidNumberFormat(num) => {
-                write!(f, "Invalid number format: '{}'.", num)
+            ParseError::InvalidNumberFormat { lexeme, .. } => {
+                write!(f, "Invalid number format: '{}'.", lexeme)
             }
             ParseError::InvalidIdentifier(id) => {
                 write!(f, "Invalid identifier: '{}'.", id)
@@ -73,8 +269,83 @@ impl fmt::Display for ParseError {
                 write!(f, "Pattern match expression missing arms.")
             }
             ParseError::Other(msg) => write!(f, "Error: {}", msg),
+            ParseError::IdentifierTooLong { offset, limit } => write!(
+                f,
+                "Identifier starting at offset {} exceeds the maximum length of {} characters.",
+                offset, limit
+            ),
+            ParseError::LineTooLong { line, limit } => write!(
+                f,
+                "Line {} exceeds the maximum length of {} characters.",
+                line, limit
+            ),
+            ParseError::NestingTooDeep { limit } => write!(
+                f,
+                "Expression nests deeper than the maximum of {} levels.",
+                limit
+            ),
+            ParseError::CommaDecimal { lexeme, .. } => write!(
+                f,
+                "Comma is not a decimal separator; use '{}' instead of '{}'.",
+                lexeme.replace(',', "."),
+                lexeme
+            ),
+            ParseError::InvalidEscape { escape, .. } => {
+                write!(f, "Invalid escape sequence '{}' in string literal.", escape)
+            }
+            ParseError::ChainedComparison { first, second, .. } => write!(
+                f,
+                "Comparison operators cannot be chained: '{}' follows '{}'; use parentheses or '&&'.",
+                second, first
+            ),
         }
     }
 }
 
+impl fmt::Display for ParseError {
+    /// Renders an error variant into a user-friendly string.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_message(f)
+    }
+}
+
 impl error::Error for ParseError {}
+
+/// The part of a line an excerpt shows.
+struct Window<'a> {
+    text: &'a str,
+    /// Whether characters before `text` were cut, shown as a leading `…`.
+    cut_start: bool,
+    /// Whether characters after `text` were cut, shown as a trailing `…`.
+    cut_end: bool,
+    /// The 1-based column of the caret in the shown line, `…` included.
+    caret: usize,
+}
+
+impl<'a> Window<'a> {
+    /// The at most [`EXCERPT_WIDTH`] characters of `line` around the 1-based
+    /// `column`, centered on it unless that would run past an end.
+    fn around(line: &'a str, column: usize) -> Self {
+        let length = line.chars().count();
+        let start = column
+            .saturating_sub(1 + EXCERPT_WIDTH / 2)
+            .min(length.saturating_sub(EXCERPT_WIDTH));
+        let end = (start + EXCERPT_WIDTH).min(length);
+        let offset = |chars: usize| {
+            line.char_indices()
+                .nth(chars)
+                .map_or(line.len(), |(offset, _)| offset)
+        };
+        Window {
+            text: &line[offset(start)..offset(end)],
+            cut_start: start > 0,
+            cut_end: end < length,
+            caret: column - start + usize::from(start > 0),
+        }
+    }
+}
+
+/// Number of decimal digits in `n`.
+fn digits(n: usize) -> usize {
+    n.checked_ilog10().map_or(1, |log| log as usize + 1)
+}
@@ -0,0 +1,112 @@
+//! src/features.rs
+
+/*******************************************************************************
+ *                              FEATURES MODULE
+ *-------------------------------------------------------------------------------
+ * Lets embedders ask, at runtime, which language and tooling capabilities the
+ * linked version of this crate supports, before exposing options to their own
+ * users. The list is maintained by hand as features land.
+ ******************************************************************************/
+
+use std::fmt;
+
+/// A capability that a given version of this crate may or may not support.
+///
+/// New variants are added as the language grows, so matches on this enum
+/// need a wildcard arm.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum Feature {
+    /// `_` separators in number literals, e.g. `1_000_000`.
+    DigitSeparators,
+    /// A leading `#!` line is ignored, so scripts can be executable.
+    Shebang,
+    /// Configurable identifier and line length limits in the lexer.
+    LexerLimits,
+    /// Lint checks over parsed programs, e.g. match exhaustiveness.
+    Lints,
+    /// Diagnostics exported as SARIF logs.
+    Sarif,
+    /// Token, operator, and construct usage statistics over many programs.
+    Stats,
+    /// Searching programs for expressions matching a `$name` template.
+    StructuralSearch,
+    /// String literals, e.g. `"hello"`.
+    Strings,
+    /// `true` and `false` as literals rather than identifiers.
+    Booleans,
+    /// Tuple expressions, e.g. `(a, b)`.
+    Tuples,
+    /// List literals, e.g. `[1, 2, 3]`.
+    Lists,
+}
+
+/// Every feature this version of the crate knows about.
+const ALL: [Feature; 11] = [
+    Feature::DigitSeparators,
+    Feature::Shebang,
+    Feature::LexerLimits,
+    Feature::Lints,
+    Feature::Sarif,
+    Feature::Stats,
+    Feature::StructuralSearch,
+    Feature::Strings,
+    Feature::Booleans,
+    Feature::Tuples,
+    Feature::Lists,
+];
+
+/// The features this version of the crate supports.
+const SUPPORTED: [Feature; 11] = [
+    Feature::DigitSeparators,
+    Feature::Shebang,
+    Feature::LexerLimits,
+    Feature::Lints,
+    Feature::Sarif,
+    Feature::Stats,
+    Feature::StructuralSearch,
+    Feature::Strings,
+    Feature::Booleans,
+    Feature::Tuples,
+    Feature::Lists,
+];
+
+impl Feature {
+    /// Every feature known to this version, whether supported or not.
+    pub fn all() -> &'static [Feature] {
+        &ALL
+    }
+
+    /// A stable, kebab-case name for the feature, e.g. `digit-separators`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::DigitSeparators => "digit-separators",
+            Feature::Shebang => "shebang",
+            Feature::LexerLimits => "lexer-limits",
+            Feature::Lints => "lints",
+            Feature::Sarif => "sarif",
+            Feature::Stats => "stats",
+            Feature::StructuralSearch => "structural-search",
+            Feature::Strings => "strings",
+            Feature::Booleans => "booleans",
+            Feature::Tuples => "tuples",
+            Feature::Lists => "lists",
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Returns the features supported by this version of the crate.
+pub fn features() -> &'static [Feature] {
+    &SUPPORTED
+}
+
+/// Returns `true` if this version of the crate supports `feature`.
+pub fn supports(feature: Feature) -> bool {
+    features().contains(&feature)
+}
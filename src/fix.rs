@@ -0,0 +1,133 @@
+//! src/fix.rs
+
+/*******************************************************************************
+ *                                FIX MODULE
+ *-------------------------------------------------------------------------------
+ * Applies the fixes attached to diagnostics to the source they were found in.
+ * Only fixes marked `MachineApplicable` are applied, and only if none of their
+ * edits overlap an edit already accepted; diagnostics are taken in order, so
+ * the first of two conflicting fixes wins and the other is skipped.
+ *
+ * Applying one fix can make or break another, so `fix_until_stable` applies a
+ * round of fixes, diagnoses the result again, and repeats until no fix is
+ * left or a round limit is reached.
+ ******************************************************************************/
+
+use crate::{Diagnostic, TextEdit};
+
+/// Rounds `fix_until_stable` runs by default before giving up.
+pub const MAX_FIX_ROUNDS: usize = 10;
+
+/// How sure a fix is to be what was intended.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Applicability {
+    /// Safe to apply without review.
+    MachineApplicable,
+    /// Likely right, but should be reviewed; never applied automatically.
+    MaybeIncorrect,
+}
+
+/// Edits to the source that together resolve a diagnostic.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Fix {
+    pub applicability: Applicability,
+    pub edits: Vec<TextEdit>,
+}
+
+/// The result of applying fixes.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FixOutcome {
+    /// The source with the fixes applied.
+    pub source: String,
+    /// Number of fixes applied.
+    pub applied: usize,
+    /// Diagnostics whose fixes were skipped because they overlap a fix that
+    /// was applied. For `fix_until_stable`, those of the last round.
+    pub skipped: Vec<Diagnostic>,
+    /// Whether no machine-applicable fix is left unapplied.
+    pub converged: bool,
+}
+
+/// Applies the first machine-applicable fix of each of `diagnostics` to
+/// `source`, skipping those that overlap one applied earlier.
+pub fn apply_fixes(source: &str, diagnostics: &[Diagnostic]) -> FixOutcome {
+    let mut accepted: Vec<&TextEdit> = Vec::new();
+    let mut applied = 0;
+    let mut skipped = Vec::new();
+    for diagnostic in diagnostics {
+        let Some(fix) = diagnostic
+            .fixes
+            .iter()
+            .find(|fix| fix.applicability == Applicability::MachineApplicable)
+        else {
+            continue;
+        };
+        let conflicts = fix.edits.iter().enumerate().any(|(index, edit)| {
+            fix.edits[..index]
+                .iter()
+                .chain(accepted.iter().copied())
+                .any(|other| overlap(edit, other))
+        });
+        if conflicts {
+            skipped.push(diagnostic.clone());
+        } else {
+            accepted.extend(&fix.edits);
+            applied += 1;
+        }
+    }
+
+    let accepted: Vec<TextEdit> = accepted.into_iter().cloned().collect();
+    FixOutcome {
+        source: apply_edits(source, &accepted),
+        applied,
+        converged: skipped.is_empty(),
+        skipped,
+    }
+}
+
+/// Applies `edits`, which must not overlap, to `source`.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> String {
+    // Apply from the end, so earlier offsets stay valid.
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key(|edit| edit.range.start);
+    edits
+        .iter()
+        .rev()
+        .fold(source.to_string(), |source, edit| edit.apply(&source))
+}
+
+/// Applies fixes to `source` in rounds, diagnosing it with `diagnose` before
+/// each, until a round finds nothing to apply or `max_rounds` rounds ran.
+pub fn fix_until_stable(
+    source: &str,
+    max_rounds: usize,
+    diagnose: impl Fn(&str) -> Vec<Diagnostic>,
+) -> FixOutcome {
+    let mut outcome = FixOutcome {
+        source: source.to_string(),
+        applied: 0,
+        skipped: Vec::new(),
+        converged: false,
+    };
+    for _ in 0..max_rounds {
+        let round = apply_fixes(&outcome.source, &diagnose(&outcome.source));
+        if round.applied == 0 {
+            outcome.converged = true;
+            return outcome;
+        }
+        outcome.source = round.source;
+        outcome.applied += round.applied;
+        outcome.skipped = round.skipped;
+    }
+    outcome.converged = !diagnose(&outcome.source)
+        .iter()
+        .flat_map(|diagnostic| &diagnostic.fixes)
+        .any(|fix| fix.applicability == Applicability::MachineApplicable);
+    outcome
+}
+
+/// Whether two edits touch the same text. Two insertions at the same offset
+/// overlap, since either order could be meant.
+fn overlap(a: &TextEdit, b: &TextEdit) -> bool {
+    (a.range.start < b.range.end && b.range.start < a.range.end) || a.range.start == b.range.start
+}
@@ -0,0 +1,192 @@
+//! src/folding.rs
+
+/*******************************************************************************
+ *                               FOLDING MODULE
+ *-------------------------------------------------------------------------------
+ * Computes the folding ranges an editor offers: one for every construct that
+ * spans several lines, from the spans the parser records for each node. A
+ * `let` folds its value and its body, a lambda its body, a match each of its
+ * arms, from the arm's `|` to the end of its body, and parentheses what they
+ * enclose.
+ *
+ * Ranges are given in lines, so two constructs side by side can share one:
+ * a value ending on the line its body starts on. The earlier one then stops
+ * at the line before, which keeps every pair of ranges either nested or
+ * apart. A construct left on a single line that way has no range.
+ *
+ * The parser stops at the first error, so code that doesn't parse only folds
+ * what a scan of its tokens recovers: the parentheses that pair up.
+ ******************************************************************************/
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::{
+    delimiters::{lossy_tokens, partners},
+    Expression, Lexer, LineIndex, MatchArm, Parser, Program, Span, Term, Token,
+};
+
+/// The construct a folding range covers.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FoldKind {
+    /// The value bound by a `let`.
+    LetValue,
+    /// The body after a `let`'s `in`.
+    LetBody,
+    /// The body of a lambda.
+    LambdaBody,
+    /// A match arm, from its `|` to the end of its body.
+    MatchArm,
+    /// What a pair of parentheses encloses, parentheses included.
+    Grouped,
+}
+
+/// Lines an editor can fold away, 1-based and inclusive.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FoldRange {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub kind: FoldKind,
+}
+
+/// The folding ranges of `src`, outermost first for ranges that start on the
+/// same line, and ordered by their first line otherwise.
+pub fn folding_ranges(src: &str) -> Vec<FoldRange> {
+    let folds = match Lexer::new(src).tokenize_spanned() {
+        Ok(tokens) => {
+            let starts: Vec<(Token, Span)> = tokens
+                .iter()
+                .map(|token| (token.token.clone(), token.span))
+                .collect();
+            match Parser::from_spanned(tokens).parse_program_spanned() {
+                Ok((program, spans)) => node_folds(&program, &spans, &starts),
+                Err(_) => paren_folds(src),
+            }
+        }
+        Err(_) => paren_folds(src),
+    };
+    by_line(src, folds)
+}
+
+/// The constructs of `program` that can fold, by the characters they cover.
+fn node_folds(
+    program: &Program,
+    spans: &[Option<Span>],
+    tokens: &[(Token, Span)],
+) -> Vec<(Span, FoldKind)> {
+    let index = program.build_index();
+    let span = |expression: &Expression| index.id_of(expression).and_then(|id| spans[id]);
+    let mut folds = Vec::new();
+
+    for id in 0..index.len() {
+        match index.node(id) {
+            Expression::LetExpr { value, body, .. } => {
+                folds.extend(span(value).map(|span| (span, FoldKind::LetValue)));
+                folds.extend(span(body).map(|span| (span, FoldKind::LetBody)));
+            }
+            Expression::LetAnd { bindings, body, .. } => {
+                for binding in bindings {
+                    folds.extend(span(&binding.value).map(|span| (span, FoldKind::LetValue)));
+                }
+                folds.extend(span(body).map(|span| (span, FoldKind::LetBody)));
+            }
+            Expression::Lambda { body, .. } => {
+                folds.extend(span(body).map(|span| (span, FoldKind::LambdaBody)));
+            }
+            Expression::PatternMatch { expression, arms } => {
+                folds.extend(arm_spans(expression, arms, &span, tokens))
+            }
+            grouped @ Expression::Term(Term::GroupedExpression(_)) => {
+                folds.extend(span(grouped).map(|span| (span, FoldKind::Grouped)));
+            }
+            _ => {}
+        }
+    }
+
+    folds
+}
+
+/// The span of each arm of a match on `scrutinee`: from the first token after
+/// whatever precedes the arm, its `|`, to the end of its body.
+fn arm_spans(
+    scrutinee: &Expression,
+    arms: &[MatchArm],
+    span: &impl Fn(&Expression) -> Option<Span>,
+    tokens: &[(Token, Span)],
+) -> Vec<(Span, FoldKind)> {
+    let mut folds = Vec::new();
+    let Some(mut previous) = end(scrutinee, span) else {
+        return folds;
+    };
+    for (arm, position) in arms.iter().zip(0..) {
+        let next = tokens.partition_point(|(_, span)| span.start < previous);
+        let start = tokens[next..]
+            .iter()
+            .find(|(token, _)| !(position == 0 && *token == Token::With))
+            .map(|(_, span)| span.start);
+        let (Some(start), Some(end)) = (start, end(&arm.expression, span)) else {
+            return folds;
+        };
+        folds.push((Span { start, end }, FoldKind::MatchArm));
+        previous = end;
+    }
+    folds
+}
+
+/// Where `expression` ends. An arm's `with` bindings are sugar for `let`s
+/// without a span of their own, so those end where their body does.
+fn end(expression: &Expression, span: &impl Fn(&Expression) -> Option<Span>) -> Option<usize> {
+    match (span(expression), expression) {
+        (Some(span), _) => Some(span.end),
+        (None, Expression::LetExpr { body, .. }) => end(body, span),
+        (None, _) => None,
+    }
+}
+
+/// The parentheses of `src` that pair up, for code that doesn't parse.
+fn paren_folds(src: &str) -> Vec<(Span, FoldKind)> {
+    let tokens = lossy_tokens(src);
+    partners(&tokens)
+        .into_iter()
+        .filter(|&(open, close)| tokens[open].0 == Token::LeftParen && open < close)
+        .map(|(open, close)| {
+            let span = Span {
+                start: tokens[open].1.start,
+                end: tokens[close].1.end,
+            };
+            (span, FoldKind::Grouped)
+        })
+        .collect()
+}
+
+/// Turns `folds` into line ranges: a fold ending on the line where another
+/// that it doesn't contain starts ends a line earlier; those left on one
+/// line, and all but the outermost of those on the same lines, are dropped.
+fn by_line(src: &str, mut folds: Vec<(Span, FoldKind)>) -> Vec<FoldRange> {
+    let lines = LineIndex::new(src);
+    let line = |offset: usize| lines.offset_to_line_col(offset).0;
+    folds.sort_by_key(|&(span, _)| (span.start, Reverse(span.end)));
+
+    // The last character any fold starts at, by line.
+    let last_start: HashMap<usize, usize> = folds
+        .iter()
+        .map(|&(span, _)| (line(span.start), span.start))
+        .collect();
+    let mut ranges: Vec<FoldRange> = folds
+        .iter()
+        .map(|&(span, kind)| {
+            let (start_line, end_line) = (line(span.start), line(span.end - 1));
+            let next = last_start
+                .get(&end_line)
+                .is_some_and(|&start| start >= span.end);
+            FoldRange {
+                start_line,
+                end_line: if next { end_line - 1 } else { end_line },
+                kind,
+            }
+        })
+        .filter(|range| range.start_line < range.end_line)
+        .collect();
+    ranges.dedup_by_key(|range| (range.start_line, range.end_line));
+    ranges
+}
@@ -0,0 +1,238 @@
+//! src/index.rs
+
+/*******************************************************************************
+ *                               INDEX MODULE
+ *-------------------------------------------------------------------------------
+ * Navigation over a parsed program without re-walking or cloning it. One
+ * pre-order walk assigns every expression a `NodeId` (its position in that
+ * walk, so ids agree with `Expression::walk`) and records borrowed references,
+ * parent links, and the field each child hangs from. Queries are then O(1)
+ * or O(depth).
+ *
+ * Only expressions are indexed; patterns and type annotations are reached
+ * through the expression holding them.
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use crate::{Expression, FunctionComposition, Program, Term};
+
+/// Identifies an expression within one `AstIndex`: its position in a
+/// pre-order walk of the program.
+pub type NodeId = usize;
+
+/// One step from a node to one of its children: the field holding the child
+/// and, for list fields (`arms`, `items`), its position in the list. An arm's
+/// guard is at `guards` with the arm's position.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Step {
+    pub field: &'static str,
+    pub index: Option<usize>,
+}
+
+/// Parent links and child lists for every expression in a program.
+#[derive(Debug)]
+pub struct AstIndex<'a> {
+    nodes: Vec<&'a Expression>,
+    parents: Vec<Option<(NodeId, Step)>>,
+    children: Vec<Vec<NodeId>>,
+    ids: HashMap<*const Expression, NodeId>,
+}
+
+impl Program {
+    /// Indexes every expression of the program in one walk.
+    pub fn build_index(&self) -> AstIndex<'_> {
+        let mut index = AstIndex {
+            nodes: Vec::new(),
+            parents: Vec::new(),
+            children: Vec::new(),
+            ids: HashMap::new(),
+        };
+        index.add(&self.expression, None);
+        index
+    }
+}
+
+impl<'a> AstIndex<'a> {
+    /// The id of the program's root expression.
+    pub fn root(&self) -> NodeId {
+        0
+    }
+
+    /// Number of indexed expressions.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Always `false`: a program has at least its root expression.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The expression with the given id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` does not belong to this index.
+    pub fn node(&self, id: NodeId) -> &'a Expression {
+        self.nodes[id]
+    }
+
+    /// The id of `node`, if it is part of the indexed program. Looks the node
+    /// up by address, so it must be borrowed from the same program.
+    pub fn id_of(&self, node: &Expression) -> Option<NodeId> {
+        self.ids.get(&(node as *const Expression)).copied()
+    }
+
+    /// The parent of `id`, or `None` for the root.
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.parents[id].map(|(parent, _)| parent)
+    }
+
+    /// The step from the parent of `id` to `id`, or `None` for the root.
+    pub fn step(&self, id: NodeId) -> Option<Step> {
+        self.parents[id].map(|(_, step)| step)
+    }
+
+    /// The children of `id`, left to right.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.children[id]
+    }
+
+    /// `id` followed by each of its ancestors, ending with the root.
+    pub fn path_to_root(&self, id: NodeId) -> Vec<NodeId> {
+        let mut path = vec![id];
+        let mut current = id;
+        while let Some(parent) = self.parent(current) {
+            path.push(parent);
+            current = parent;
+        }
+        path
+    }
+
+    /// The steps leading from the root down to `id`.
+    pub fn path(&self, id: NodeId) -> Vec<Step> {
+        let mut steps: Vec<Step> = self
+            .path_to_root(id)
+            .into_iter()
+            .filter_map(|node| self.step(node))
+            .collect();
+        steps.reverse();
+        steps
+    }
+
+    /// The path to `id` as text, e.g. `body.items[1]`, or `root` for the root.
+    pub fn display_path(&self, id: NodeId) -> String {
+        let steps: Vec<String> = self
+            .path(id)
+            .iter()
+            .map(|step| match step.index {
+                Some(i) => format!("{}[{}]", step.field, i),
+                None => step.field.to_string(),
+            })
+            .collect();
+        if steps.is_empty() {
+            "root".to_string()
+        } else {
+            steps.join(".")
+        }
+    }
+
+    /// Follows `steps` down from the root, returning the node reached, if any.
+    pub fn node_at_path(&self, steps: &[Step]) -> Option<NodeId> {
+        steps.iter().try_fold(self.root(), |node, step| {
+            self.children(node)
+                .iter()
+                .copied()
+                .find(|child| self.step(*child) == Some(*step))
+        })
+    }
+
+    fn add(&mut self, node: &'a Expression, parent: Option<(NodeId, Step)>) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(node);
+        self.parents.push(parent);
+        self.children.push(Vec::new());
+        self.ids.insert(node as *const Expression, id);
+
+        for (step, child) in child_expressions(node) {
+            let child_id = self.add(child, Some((id, step)));
+            self.children[id].push(child_id);
+        }
+        id
+    }
+}
+
+/// Lists the direct sub-expressions of `node` with the step leading to each,
+/// in the same order as `Expression::walk` visits them.
+pub(crate) fn child_expressions(node: &Expression) -> Vec<(Step, &Expression)> {
+    let field = |field| Step { field, index: None };
+    let item = |field, index| Step {
+        field,
+        index: Some(index),
+    };
+
+    match node {
+        Expression::LetExpr { value, body, .. } => {
+            vec![(field("value"), &**value), (field("body"), &**body)]
+        }
+        Expression::LetAnd { bindings, body, .. } => {
+            let mut children: Vec<_> = bindings
+                .iter()
+                .enumerate()
+                .map(|(i, binding)| (item("bindings", i), &*binding.value))
+                .collect();
+            children.push((field("body"), &**body));
+            children
+        }
+        Expression::IfExpr {
+            condition,
+            then_branch,
+            else_branch,
+        } => vec![
+            (field("condition"), &**condition),
+            (field("then_branch"), &**then_branch),
+            (field("else_branch"), &**else_branch),
+        ],
+        Expression::Lambda { body, .. } => vec![(field("body"), &**body)],
+        Expression::PatternMatch { expression, arms } => {
+            let mut children = vec![(field("expression"), &**expression)];
+            for (i, arm) in arms.iter().enumerate() {
+                if let Some(guard) = &arm.guard {
+                    children.push((item("guards", i), &**guard));
+                }
+                children.push((item("arms", i), &*arm.expression));
+            }
+            children
+        }
+        Expression::Comparison { left, right, .. } | Expression::Logic { left, right, .. } => {
+            let mut children = vec![(field("left"), &**left)];
+            if let Some(right) = right {
+                children.push((field("right"), &**right));
+            }
+            children
+        }
+        Expression::Arithmetic { left, right, .. } | Expression::List { left, right, .. } => {
+            vec![(field("left"), &**left), (field("right"), &**right)]
+        }
+        Expression::Unary { operand, .. } => vec![(field("operand"), &**operand)],
+        Expression::Application(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, expression)| (item("items", i), expression))
+            .collect(),
+        Expression::Term(Term::Tuple(elements) | Term::List(elements)) => elements
+            .iter()
+            .enumerate()
+            .map(|(i, expression)| (item("elements", i), expression))
+            .collect(),
+        Expression::Term(Term::GroupedExpression(expression))
+        | Expression::Term(Term::MemberAccess { expression, .. }) => {
+            vec![(field("expression"), &**expression)]
+        }
+        Expression::Term(_) => Vec::new(),
+        Expression::FunctionComposition(FunctionComposition { f, g }) => {
+            vec![(field("f"), &**f), (field("g"), &**g)]
+        }
+    }
+}
@@ -9,66 +9,392 @@
  * these tokens for syntax analysis.
  ********************************************************************************/
 
-use crate::{ParseError, Token};
+use crate::{LossyToken, ParseError, Position, Span, SpannedToken, Token};
+
+/*-----------------------------------------------------------------------------
+ *                              LEXER OPTIONS
+ *-----------------------------------------------------------------------------
+ * Limits protecting tools from runaway input, such as a generated file with a
+ * multi-megabyte line. Every limit is off by default.
+ *---------------------------------------------------------------------------*/
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LexerOptions {
+    /// Longest identifier accepted, in characters.
+    pub max_identifier_length: Option<usize>,
+
+    /// Longest line accepted, in characters, not counting the line break.
+    pub max_line_length: Option<usize>,
+}
 
 /*-----------------------------------------------------------------------------
  *                              LEXER STRUCT
  *-----------------------------------------------------------------------------
- * The `Lexer` holds the input as a vector of characters (`input`) and a cursor
- * index (`current`). Methods on the `Lexer` advance through the input, producing
- * tokens until exhaustion or error.
+ * The `Lexer` borrows the input (`input`) and keeps a byte cursor into it
+ * (`current`), always on a character boundary. Methods on the `Lexer` advance
+ * through the input, producing tokens until exhaustion or error. Offsets
+ * reported in spans and errors count characters, so the cursor is tracked in
+ * characters as well (`offset`).
  *---------------------------------------------------------------------------*/
-pub struct Lexer {
-    /// The entire input, split into characters.
-    input: Vec<char>,
+pub struct Lexer<'a> {
+    /// The entire input.
+    input: &'a str,
 
-    /// Current position in `input`.
+    /// Current byte index in `input`.
     current: usize,
+
+    /// Current position in `input`, in characters.
+    offset: usize,
+
+    /// The `#!` line that opened the input, if any.
+    shebang: Option<String>,
+
+    /// Limits enforced while lexing.
+    options: LexerOptions,
+
+    /// Current line number, starting at 1.
+    line: usize,
+
+    /// Position in `input` where the current line starts, in characters.
+    line_start: usize,
+
+    /// Where the token being lexed starts.
+    token_start: Position,
+
+    /// Where the token being lexed starts, in characters.
+    token_offset: usize,
+
+    /// The first line found to exceed `max_line_length`, not yet reported.
+    overlong_line: Option<usize>,
+
+    /// Whether `$name` metavariables are accepted, as in search templates.
+    template: bool,
+
+    /// How many `(` and `[` are open, for telling a decimal comma from a
+    /// separator.
+    depth: usize,
+
+    /// Whether `Eof` or an error has been produced, ending the token stream.
+    finished: bool,
 }
 
-impl Lexer {
+impl<'a> Lexer<'a> {
     //--------------------------------------------------------------------------
     // CONSTRUCTOR
     //--------------------------------------------------------------------------
 
-    /// Creates a new `Lexer` from a &str. Internally stores the string’s characters.
-    pub fn new(input: &str) -> Self {
-        Self {
-            input: input.chars().collect(),
+    /// Creates a new `Lexer` from a &str, which it borrows rather than copies.
+    ///
+    /// A `#!` line at the very start of the input (as in an executable script)
+    /// is set aside rather than tokenized; see [`Lexer::shebang`].
+    pub fn new(input: &'a str) -> Self {
+        Self::with_options(input, LexerOptions::default())
+    }
+
+    /// Creates a new `Lexer` that enforces the limits in `options`.
+    pub fn with_options(input: &'a str, options: LexerOptions) -> Self {
+        let mut lexer = Self {
+            input,
             current: 0,
+            offset: 0,
+            shebang: None,
+            options,
+            line: 1,
+            line_start: 0,
+            token_start: Position { line: 1, column: 1 },
+            token_offset: 0,
+            overlong_line: None,
+            template: false,
+            depth: 0,
+            finished: false,
+        };
+        lexer.skip_shebang();
+        lexer
+    }
+
+    /// Creates a `Lexer` for a search template, which additionally accepts
+    /// `$name` metavariables. They lex as identifiers whose name keeps the
+    /// `$`, so they can never collide with an identifier of a program.
+    pub fn template(input: &'a str) -> Self {
+        Self {
+            template: true,
+            ..Self::new(input)
         }
     }
 
+    /// Starts the lexer over on `input`, keeping its options and whether it
+    /// accepts metavariables, so one lexer can serve many small inputs.
+    pub fn reset(&mut self, input: &'a str) {
+        *self = Self {
+            template: self.template,
+            ..Self::with_options(input, self.options)
+        };
+    }
+
     //--------------------------------------------------------------------------
     // PUBLIC API
     //--------------------------------------------------------------------------
 
+    /// Converts `input` into tokens with a default lexer, in one call.
+    ///
+    /// # Errors
+    /// Returns the first `ParseError` of [`Lexer::tokenize`].
+    pub fn tokenize_str(input: &str) -> Result<Vec<Token>, ParseError> {
+        Lexer::new(input).tokenize()
+    }
+
     /// Converts the entire input into a vector of `Token`s.
     ///
     /// This processes each chunk of text until we reach the end, returning
     /// `Ok(Vec<Token>)` on success, or `Err(ParseError)` if tokenization fails
-    /// due to malformed input.
+    /// due to malformed input. To consume tokens one at a time instead, use
+    /// the lexer as an [`Iterator`].
     pub fn tokenize(&mut self) -> Result<Vec<Token>, ParseError> {
+        let tokens = self.by_ref().collect::<Result<Vec<Token>, ParseError>>()?;
+        if cfg!(debug_assertions) {
+            self.check_invariants(&tokens);
+        }
+        Ok(tokens)
+    }
+
+    /// Like [`Lexer::tokenize`], but pairs every token with the line and
+    /// column where it starts. `Eof` is placed just past the last character.
+    pub fn tokenize_spanned(&mut self) -> Result<Vec<SpannedToken>, ParseError> {
+        let tokens = std::iter::from_fn(|| self.next_spanned())
+            .collect::<Result<Vec<SpannedToken>, ParseError>>()?;
+
+        if cfg!(debug_assertions) {
+            let tokens: Vec<Token> = tokens.iter().map(|spanned| spanned.token.clone()).collect();
+            self.check_invariants(&tokens);
+        }
+        Ok(tokens)
+    }
+
+    /// Like [`Lexer::tokenize`], but records each error and carries on
+    /// instead of stopping at the first, so a file with several typos reports
+    /// them all at once. A character that doesn't lex is skipped; a malformed
+    /// literal is dropped whole. Returns the tokens that did lex, ending with
+    /// `Eof`, and the errors in input order. Without errors, the tokens are
+    /// exactly those of `tokenize`.
+    pub fn tokenize_recovering(&mut self) -> (Vec<Token>, Vec<ParseError>) {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            let start = self.offset;
+            match self.next_token() {
+                Ok(token) => {
+                    if let Err(err) = self.check_line_length() {
+                        errors.push(err);
+                    }
+                    let done = token == Token::Eof;
+                    tokens.push(token);
+                    if done {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    if matches!(err, ParseError::InvalidEscape { .. }) {
+                        self.skip_string();
+                    }
+                    errors.push(err);
+                    if self.offset == start {
+                        self.advance();
+                    }
+                }
+            }
+        }
+
+        if cfg!(debug_assertions) {
+            self.check_invariants(&tokens);
+        }
+        (tokens, errors)
+    }
+
+    /// Like [`Lexer::tokenize`], but keeps whitespace and comments as
+    /// `Whitespace` and `Comment` tokens between the others, for tools such as
+    /// formatters that must not lose them. A run of whitespace is one token,
+    /// each comment is another, and a `#!` line that opens the input is a
+    /// `Comment`. Trivia tokens hold their text exactly, so the source can be
+    /// rebuilt from them and the other tokens; [`Parser::new`] drops them.
+    ///
+    /// [`Parser::new`]: crate::Parser::new
+    pub fn tokenize_with_trivia(&mut self) -> Result<Vec<Token>, ParseError> {
         let mut tokens = Vec::new();
+        if let Some(shebang) = &self.shebang {
+            tokens.push(Token::Comment(shebang.clone()));
+        }
+
+        loop {
+            let start = self.current;
+            if self.at_comment() {
+                self.skip_comment();
+                tokens.push(Token::Comment(self.input[start..self.current].to_string()));
+                continue;
+            }
+            while self.peek().is_some_and(|c| c.is_whitespace()) {
+                self.advance();
+            }
+            if self.current > start {
+                tokens.push(Token::Whitespace(
+                    self.input[start..self.current].to_string(),
+                ));
+                continue;
+            }
 
-        // Keep producing tokens until we exhaust the input.
-        while !self.is_at_end() {
             let token = self.next_token()?;
+            self.check_line_length()?;
+            let done = token == Token::Eof;
             tokens.push(token);
+            if done {
+                break;
+            }
         }
 
-        // Append EOF marker.
-        tokens.push(Token::Eof);
+        if cfg!(debug_assertions) {
+            self.check_invariants(&tokens);
+        }
         Ok(tokens)
     }
 
+    /// Converts the entire input into pieces that never fail to lex, for tools
+    /// such as syntax highlighters that must handle broken code.
+    ///
+    /// Characters that don't lex become `Error` pieces, merged into maximal
+    /// runs, and lexing resumes after them; a string with a bad escape is
+    /// one error through its closing `"`. Whitespace, comments, and the `#!`
+    /// line become `Trivia`. The spans of the result cover the input with no
+    /// gaps or overlaps. `Eof` is not included, and the lexer's limits are not
+    /// enforced.
+    pub fn tokenize_lossy(&mut self) -> Vec<LossyToken> {
+        let options = std::mem::take(&mut self.options);
+        let mut pieces = Vec::new();
+
+        if self.offset > 0 {
+            pieces.push(LossyToken::Trivia {
+                span: Span {
+                    start: 0,
+                    end: self.offset,
+                },
+            });
+        }
+
+        while !self.is_at_end() {
+            let start = self.offset;
+            if self.peek().is_some_and(|c| c.is_whitespace()) || self.at_comment() {
+                self.skip_whitespace();
+                let span = Span {
+                    start,
+                    end: self.offset,
+                };
+                pieces.push(LossyToken::Trivia { span });
+                continue;
+            }
+
+            match self.next_token() {
+                Ok(token) => pieces.push(LossyToken::Token {
+                    token,
+                    span: Span {
+                        start,
+                        end: self.offset,
+                    },
+                }),
+                Err(err) => {
+                    if matches!(err, ParseError::InvalidEscape { .. }) {
+                        self.skip_string();
+                    }
+                    if self.offset == start {
+                        self.advance();
+                    }
+                    match pieces.last_mut() {
+                        Some(LossyToken::Error { span }) if span.end == start => {
+                            span.end = self.offset;
+                        }
+                        _ => pieces.push(LossyToken::Error {
+                            span: Span {
+                                start,
+                                end: self.offset,
+                            },
+                        }),
+                    }
+                }
+            }
+        }
+
+        self.options = options;
+        pieces
+    }
+
+    /// Panics, in debug builds only, if `tokens` isn't a complete stream for
+    /// this lexer's input: the input must be fully consumed, and the stream
+    /// must end with exactly one `Eof`. Called at the end of
+    /// [`Lexer::tokenize`]; a violation is a bug in the lexer.
+    pub(crate) fn check_invariants(&self, tokens: &[Token]) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let eofs = tokens.iter().filter(|token| **token == Token::Eof).count();
+        let violation = if !self.is_at_end() {
+            Some("input not fully consumed")
+        } else if tokens.last() != Some(&Token::Eof) {
+            Some("token stream does not end with Eof")
+        } else if eofs != 1 {
+            Some("token stream has more than one Eof")
+        } else {
+            None
+        };
+
+        if let Some(violation) = violation {
+            panic!(
+                "lexer invariant violated: {}\n  cursor: {} of {} characters\n  tokens: {:?}",
+                violation,
+                self.offset,
+                self.input.chars().count(),
+                tokens
+            );
+        }
+    }
+
+    /// Returns the `#!` line that opened the input, without its line break.
+    pub fn shebang(&self) -> Option<&str> {
+        self.shebang.as_deref()
+    }
+
     //--------------------------------------------------------------------------
     // NEXT TOKEN
     //--------------------------------------------------------------------------
 
+    /// Produces the next token of the stream with its position, or `None`
+    /// once `Eof` or an error has been produced.
+    fn next_spanned(&mut self) -> Option<Result<SpannedToken, ParseError>> {
+        if self.finished {
+            return None;
+        }
+
+        let result = self.next_token().and_then(|token| {
+            self.check_line_length()?;
+            Ok(token)
+        });
+        self.finished = !matches!(result, Ok(ref token) if *token != Token::Eof);
+        Some(result.map(|token| SpannedToken {
+            token,
+            position: self.token_start,
+            span: Span {
+                start: self.token_offset,
+                end: self.offset,
+            },
+        }))
+    }
+
     /// Fetches the next meaningful token, skipping any whitespace encountered.
     fn next_token(&mut self) -> Result<Token, ParseError> {
         self.skip_whitespace();
+        self.token_start = Position {
+            line: self.line,
+            column: self.offset - self.line_start + 1,
+        };
+        self.token_offset = self.offset;
 
         // If we’re at end, return EOF token.
         if self.is_at_end() {
@@ -79,38 +405,64 @@ impl Lexer {
         let c = self.advance();
 
         match c {
-            // Check for keyword starts: e.g. 'l' -> "let", 'm' -> "match".
-            'l' if self.peek_keyword("et") => self.consume_keyword("et", Token::Let),
-            'i' if self.peek_keyword("f") => self.consume_keyword("f", Token::If),
-            't' if self.peek_keyword("hen") => self.consume_keyword("hen", Token::Then),
-            'e' if self.peek_keyword("lse") => self.consume_keyword("lse", Token::Else),
-            'm' if self.peek_keyword("atch") => self.consume_keyword("atch", Token::Match),
-            'w' if self.peek_keyword("ith") => self.consume_keyword("ith", Token::With),
-
             // Single-char or small multi-char operators.
             '\\' => Ok(Token::Lambda),
             '=' if self.match_char('=') => Ok(Token::Equal),
+            '!' if self.match_char('=') => Ok(Token::NotEqual),
+            '!' => Ok(Token::Bang),
+            '<' if self.match_char('=') => Ok(Token::LessEqual),
             '<' => Ok(Token::LessThan),
+            '>' if self.match_char('=') => Ok(Token::GreaterEqual),
             '>' => Ok(Token::GreaterThan),
             '&' if self.match_char('&') => Ok(Token::And),
             '|' if self.match_char('|') => Ok(Token::Or),
+            '|' if self.match_char('>') => Ok(Token::PipeForward),
+            '+' if self.match_char('+') => Ok(Token::PlusPlus),
             '+' => Ok(Token::Plus),
             '-' if self.match_char('>') => Ok(Token::Arrow),
             '-' => Ok(Token::Minus),
             '*' => Ok(Token::Star),
             '/' => Ok(Token::Slash),
+            '%' => Ok(Token::Percent),
+            '^' => Ok(Token::Caret),
             '.' => Ok(Token::Dot),
             '|' => Ok(Token::Pipe),
-            '(' => Ok(Token::LeftParen),
-            ')' => Ok(Token::RightParen),
+            '(' => {
+                self.depth += 1;
+                Ok(Token::LeftParen)
+            }
+            ')' => {
+                self.depth = self.depth.saturating_sub(1);
+                Ok(Token::RightParen)
+            }
+            '[' => {
+                self.depth += 1;
+                Ok(Token::LeftBracket)
+            }
+            ']' => {
+                self.depth = self.depth.saturating_sub(1);
+                Ok(Token::RightBracket)
+            }
+            ',' => Ok(Token::Comma),
+            ':' if self.match_char(':') => Ok(Token::Cons),
             ':' => Ok(Token::Colon),
+            ';' if self.match_char(';') => Ok(Token::DoubleSemicolon),
+            ';' => Ok(Token::Semicolon),
             '=' => Ok(Token::Assign),
 
             // If the character is numeric, parse a number literal.
             ch if ch.is_ascii_digit() => self.number(ch),
 
-            // If the character is alphabetic, parse an identifier (or potential keyword).
-            ch if ch.is_ascii_alphabetic() => self.identifier(ch),
+            '"' => self.string(),
+
+            // If the character is a letter, parse an identifier or keyword. The
+            // whole word is read first, so `letter` is an identifier, not `let`.
+            ch if is_identifier_start(ch) => self.identifier(ch),
+
+            // Metavariables, in template mode only.
+            '$' if self.template && self.peek().is_some_and(is_identifier_start) => {
+                self.identifier('$')
+            }
 
             // Underscore is recognized as a wildcard pattern.
             '_' => Ok(Token::Wildcard),
@@ -120,6 +472,7 @@ impl Lexer {
                 expected: "valid token".to_string(),
                 found: c.to_string(),
                 message: "Unexpected character".to_string(),
+                position: Some(self.token_start),
             }),
         }
     }
@@ -130,31 +483,160 @@ impl Lexer {
 
     /// Parses a numeric literal (integer or floating-point).
     ///
+    /// Digits may be grouped with `_` separators (`1_000_000`), which are
+    /// dropped from the value. A separator must sit between two digits, so
+    /// `1_`, `1__0`, and `1._5` are rejected with the offending lexeme.
+    ///
+    /// An exponent may follow, as in `1e10`, `2.5e-3`, or `6.02E23`. It needs
+    /// at least one digit, so `1e` and `1e+` are rejected the same way, as
+    /// are literals too large for an `f64`, such as `1e999`.
+    ///
+    /// Outside parentheses, a comma with digits directly on both sides, as in
+    /// `1,5`, is taken for a decimal comma and rejected with a hint. Spaced
+    /// commas and commas inside parentheses or brackets lex as `Comma`.
+    ///
     /// # Arguments
     /// * `start` - the initial digit we encountered.
     fn number(&mut self, start: char) -> Result<Token, ParseError> {
-        let mut value = start.to_string();
+        let mut lexeme = start.to_string();
+        let offset = self.offset - 1;
 
         // Accumulate any additional digits.
-        while self.peek().map_or(false, |c| c.is_ascii_digit()) {
-            value.push(self.advance());
-        }
+        self.digits(&mut lexeme);
 
         // If the next character is '.', collect decimal digits.
         if self.peek() == Some('.') {
-            value.push(self.advance());
+            lexeme.push(self.advance());
 
             // Gather any digits after the decimal point.
-            while self.peek().map_or(false, |c| c.is_ascii_digit()) {
-                value.push(self.advance());
+            self.digits(&mut lexeme);
+        }
+
+        // An exponent: `e` or `E`, an optional sign, and at least one digit,
+        // with no identifier attached (`1e5e`).
+        let mut bad_exponent = false;
+        if matches!(self.peek(), Some('e' | 'E')) {
+            lexeme.push(self.advance());
+            if matches!(self.peek(), Some('+' | '-')) {
+                lexeme.push(self.advance());
+            }
+            bad_exponent = !self.peek().is_some_and(|c| c.is_ascii_digit());
+            self.digits(&mut lexeme);
+            bad_exponent |= self.peek().is_some_and(is_identifier_continue);
+        }
+
+        if bad_exponent
+            || !lexeme
+                .split(['.', 'e', 'E', '+', '-'])
+                .all(|part| !part.starts_with('_') && !part.ends_with('_') && !part.contains("__"))
+        {
+            // Report a directly attached identifier (`1_x`) as part of the number.
+            while self.peek().is_some_and(is_identifier_continue) {
+                lexeme.push(self.advance());
             }
+            return Err(ParseError::InvalidNumberFormat { lexeme, offset });
         }
 
-        // Convert to a floating-point value, or raise an error if invalid.
-        value
-            .parse::<f64>()
-            .map(Token::Number)
-            .map_err(|_| ParseError::InvalidNumberFormat(value))
+        // Convert to a floating-point value, or raise an error if invalid or
+        // too large to represent.
+        let value = match lexeme.replace('_', "").parse::<f64>() {
+            Ok(value) if value.is_finite() => value,
+            _ => return Err(ParseError::InvalidNumberFormat { lexeme, offset }),
+        };
+
+        if self.depth == 0
+            && self.peek() == Some(',')
+            && self.peek_next().is_some_and(|c| c.is_ascii_digit())
+        {
+            lexeme.push(self.advance());
+            self.digits(&mut lexeme);
+            return Err(ParseError::CommaDecimal { lexeme, offset });
+        }
+        Ok(Token::Number(value))
+    }
+
+    /// Appends a run of digits and `_` separators to `lexeme`.
+    fn digits(&mut self, lexeme: &mut String) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '_') {
+            lexeme.push(self.advance());
+        }
+    }
+
+    //--------------------------------------------------------------------------
+    // STRING LITERALS
+    //--------------------------------------------------------------------------
+
+    /// Parses a string literal, after its opening `"`. Everything up to the
+    /// closing `"` is taken as is, line breaks included, except for escape
+    /// sequences, which are decoded.
+    fn string(&mut self) -> Result<Token, ParseError> {
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.advance();
+                    return Ok(Token::String(text));
+                }
+                Some('\\') => text.push(self.escape()?),
+                Some(_) => text.push(self.advance()),
+                None => return Err(ParseError::UnterminatedString),
+            }
+        }
+    }
+
+    /// Skips the rest of a string literal through its closing `"`, stepping
+    /// over escapes, so that lexing can resume after a bad escape inside it
+    /// instead of taking the closing `"` for an opening one.
+    fn skip_string(&mut self) {
+        while let Some(c) = self.peek() {
+            self.advance();
+            match c {
+                '"' => return,
+                '\\' if !self.is_at_end() => {
+                    self.advance();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Decodes the escape sequence starting at the next character, a `\`:
+    /// `\"`, `\\`, `\n`, `\t`, `\r`, or `\u{XXXX}` with one to six hex digits
+    /// naming a Unicode scalar value. Anything else is an `InvalidEscape`.
+    fn escape(&mut self) -> Result<char, ParseError> {
+        let offset = self.offset;
+        let mut escape = self.advance().to_string();
+        let Some(c) = self.peek() else {
+            return Err(ParseError::UnterminatedString);
+        };
+        escape.push(self.advance());
+        match c {
+            '"' => return Ok('"'),
+            '\\' => return Ok('\\'),
+            'n' => return Ok('\n'),
+            't' => return Ok('\t'),
+            'r' => return Ok('\r'),
+            'u' if self.match_char('{') => {
+                escape.push('{');
+                let mut digits = String::new();
+                while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                    digits.push(self.advance());
+                }
+                escape.push_str(&digits);
+                if self.match_char('}') {
+                    escape.push('}');
+                    let decoded = u32::from_str_radix(&digits, 16)
+                        .ok()
+                        .filter(|_| digits.len() <= 6)
+                        .and_then(char::from_u32);
+                    if let Some(decoded) = decoded {
+                        return Ok(decoded);
+                    }
+                }
+            }
+            _ => {}
+        }
+        Err(ParseError::InvalidEscape { escape, offset })
     }
 
     //--------------------------------------------------------------------------
@@ -166,15 +648,26 @@ impl Lexer {
     /// # Arguments
     /// * `start` - the initial alphabetic character.
     fn identifier(&mut self, start: char) -> Result<Token, ParseError> {
-        let mut text = start.to_string();
-
-        // Accumulate subsequent alphanumeric chars.
-        while self.peek().map_or(false, |c| c.is_ascii_alphanumeric()) {
-            text.push(self.advance());
+        let begin = self.current - start.len_utf8();
+        let offset = self.offset - 1;
+
+        // Accumulate subsequent letters, digits, underscores, and primes.
+        // Limits count characters, not bytes.
+        while self.peek().is_some_and(is_identifier_continue) {
+            // Stop before materializing an identifier over the limit.
+            if let Some(limit) = self
+                .options
+                .max_identifier_length
+                .filter(|limit| self.offset - offset >= *limit)
+            {
+                return Err(ParseError::IdentifierTooLong { offset, limit });
+            }
+            self.advance();
         }
 
         // Check if it’s one of our known keywords (like "in"). Otherwise, an identifier.
-        match text.as_str() {
+        let text = &self.input[begin..self.current];
+        match text {
             "let" => Ok(Token::Let),
             "in" => Ok(Token::In),
             "if" => Ok(Token::If),
@@ -182,42 +675,54 @@ impl Lexer {
             "else" => Ok(Token::Else),
             "match" => Ok(Token::Match),
             "with" => Ok(Token::With),
-            _ => Ok(Token::Identifier(text)),
+            "true" => Ok(Token::True),
+            "false" => Ok(Token::False),
+            _ => Ok(Token::Identifier(text.to_string())),
         }
     }
 
     //--------------------------------------------------------------------------
-    // WHITESPACE SKIPPING
+    // WHITESPACE AND COMMENT SKIPPING
     //--------------------------------------------------------------------------
 
-    /// Discards any leading whitespace before identifying a token.
+    /// Discards any leading whitespace and comments before identifying a
+    /// token.
     fn skip_whitespace(&mut self) {
-        while self.peek().map_or(false, |c| c.is_whitespace()) {
-            self.advance();
+        loop {
+            if self.peek().is_some_and(|c| c.is_whitespace()) {
+                self.advance();
+            } else if self.at_comment() {
+                self.skip_comment();
+            } else {
+                break;
+            }
         }
     }
 
-    //--------------------------------------------------------------------------
-    // STRING VIEW & KEYWORD CHECK
-    //--------------------------------------------------------------------------
+    /// Whether a `--` comment starts here. A single `-` is Minus and `->` is
+    /// Arrow; only two dashes in a row open a comment.
+    fn at_comment(&self) -> bool {
+        self.rest().starts_with("--")
+    }
 
-    /// Verifies if the upcoming slice of input matches a given `keyword`.
-    /// (Used for multi-char checks like "et", "hen", etc.)
-    fn peek_keyword(&self, keyword: &str) -> bool {
-        self.input[self.current..]
-            .iter()
-            .take(keyword.len())
-            .collect::<String>()
-            == keyword
+    /// Consumes a `--` comment up to, but not including, the end of its line.
+    fn skip_comment(&mut self) {
+        while self.peek().is_some_and(|c| c != '\n') {
+            self.advance();
+        }
     }
 
-    /// Consumes a keyword from the input if it matches, returning the `token`.
-    fn consume_keyword(&mut self, keyword: &str, token: Token) -> Result<Token, ParseError> {
-        // We already confirmed the match; just advance past the keyword.
-        for _ in 0..keyword.len() {
+    /// Consumes a `#!` line if it opens the input. Anywhere else, `#` is an
+    /// unexpected character.
+    fn skip_shebang(&mut self) {
+        if !self.input.starts_with("#!") {
+            return;
+        }
+
+        while self.peek().is_some_and(|c| c != '\n') {
             self.advance();
         }
-        Ok(token)
+        self.shebang = Some(self.input[..self.current].to_string());
     }
 
     //--------------------------------------------------------------------------
@@ -226,11 +731,32 @@ impl Lexer {
 
     /// Consumes and returns the next character in `input`.
     fn advance(&mut self) -> char {
-        let ch = self.input[self.current];
-        self.current += 1;
+        let ch = self.peek().expect("advanced past the end of the input");
+        self.current += ch.len_utf8();
+        self.offset += 1;
+
+        if ch == '\n' {
+            self.line += 1;
+            self.line_start = self.offset;
+        } else if self.overlong_line.is_none()
+            && self
+                .options
+                .max_line_length
+                .is_some_and(|limit| self.offset - self.line_start > limit)
+        {
+            self.overlong_line = Some(self.line);
+        }
         ch
     }
 
+    /// Reports the first line seen so far that exceeds `max_line_length`.
+    fn check_line_length(&mut self) -> Result<(), ParseError> {
+        match (self.overlong_line.take(), self.options.max_line_length) {
+            (Some(line), Some(limit)) => Err(ParseError::LineTooLong { line, limit }),
+            _ => Ok(()),
+        }
+    }
+
     /// If the next character matches `expected`, consume it. Otherwise, return false.
     fn match_char(&mut self, expected: char) -> bool {
         if self.peek() == Some(expected) {
@@ -243,7 +769,17 @@ impl Lexer {
 
     /// Provides the next character without consuming it, if available.
     fn peek(&self) -> Option<char> {
-        self.input.get(self.current).copied()
+        self.rest().chars().next()
+    }
+
+    /// Provides the character after the next one without consuming anything.
+    fn peek_next(&self) -> Option<char> {
+        self.rest().chars().nth(1)
+    }
+
+    /// The input not consumed yet.
+    fn rest(&self) -> &'a str {
+        &self.input[self.current..]
     }
 
     /// Checks whether we have reached or passed the end of the input.
@@ -251,3 +787,63 @@ impl Lexer {
         self.current >= self.input.len()
     }
 }
+
+/*-----------------------------------------------------------------------------
+ *                              TOKEN STREAM
+ *-----------------------------------------------------------------------------
+ * A `Lexer` is an iterator over its tokens, for consuming large inputs lazily.
+ * It yields `Eof` last, or stops after the first error, and then keeps
+ * returning `None`.
+ *---------------------------------------------------------------------------*/
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned()
+            .map(|result| result.map(|spanned| spanned.token))
+    }
+}
+
+impl std::iter::FusedIterator for Lexer<'_> {}
+
+/// Whether `c` can start an identifier: any Unicode letter, as in `λ` or
+/// `café`. Keywords are all ASCII, so they are unaffected.
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic()
+}
+
+/// Whether `c` can continue an identifier after its first letter: letters,
+/// digits, underscores, and primes, as in `my_list'` or `x₁`.
+fn is_identifier_continue(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '\''
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::*;
+
+    /// Tests that a token stream with a duplicate `Eof` trips the invariant
+    /// check.
+    #[test]
+    #[should_panic(expected = "lexer invariant violated")]
+    fn test_invariant_duplicate_eof() {
+        // Arrange
+        let mut lexer = Lexer::new("x");
+        let mut tokens = lexer.tokenize().unwrap();
+        tokens.push(Token::Eof);
+
+        // Act
+        lexer.check_invariants(&tokens);
+    }
+
+    /// Tests that unconsumed input trips the invariant check.
+    #[test]
+    #[should_panic(expected = "lexer invariant violated")]
+    fn test_invariant_unconsumed_input() {
+        // Arrange
+        let lexer = Lexer::new("x");
+
+        // Act
+        lexer.check_invariants(&[Token::Eof]);
+    }
+}
@@ -4,18 +4,74 @@
 // Modules
 //-------------------------------------------------------------------------
 
+#[cfg(feature = "dev-metrics")]
+mod alloc_stats;
 mod ast;
+mod budget;
+mod callgraph;
+pub mod conformance;
+mod decision;
+mod declarations;
+mod delimiters;
+mod diagnostic;
+mod diff;
 mod error;
+mod features;
+mod fix;
+mod folding;
+mod index;
+mod json;
 mod lexer;
+mod line_index;
+mod lint;
+mod literate;
+mod operators;
 mod parser;
+mod printer;
+pub mod repl;
+mod sarif;
+mod slice;
+mod source;
+mod specialize;
+mod stats;
+mod suggest;
+mod template;
+pub mod testrand;
 mod tokens;
+mod validate;
 
 //-------------------------------------------------------------------------
 // Exports
 //-------------------------------------------------------------------------
 
+#[cfg(feature = "dev-metrics")]
+pub use alloc_stats::*;
 pub use ast::*;
+pub use budget::*;
+pub use callgraph::*;
+pub use decision::*;
+pub use declarations::*;
+pub use delimiters::*;
+pub use diagnostic::*;
+pub use diff::*;
 pub use error::*;
+pub use features::*;
+pub use fix::*;
+pub use folding::*;
+pub use index::*;
 pub use lexer::*;
+pub use line_index::*;
+pub use lint::*;
+pub use literate::*;
+pub use operators::*;
 pub use parser::*;
+pub use printer::*;
+pub use sarif::*;
+pub use slice::*;
+pub use source::*;
+pub use specialize::*;
+pub use stats::*;
+pub use suggest::*;
+pub use template::*;
 pub use tokens::*;
+pub use validate::*;
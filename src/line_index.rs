@@ -0,0 +1,222 @@
+//! src/line_index.rs
+
+/*******************************************************************************
+ *                             LINE INDEX MODULE
+ *-------------------------------------------------------------------------------
+ * Maps character offsets in a source text to 1-based line and column numbers
+ * and back. Line starts are found once, in one pass over the text, so a file
+ * with many errors pays for the scan once instead of once per error. Anything
+ * that reports or accepts positions should go through it, so every feature
+ * agrees on where lines break.
+ *
+ * Offsets count characters, not bytes, matching the offsets the lexer reports.
+ * Columns count characters too, except in the `_utf16` variants, which count
+ * UTF-16 code units as the Language Server Protocol does: a character outside
+ * the Basic Multilingual Plane, such as most emoji, takes two.
+ *
+ * After an edit, `apply_edit` updates the index in place. It scans only the
+ * replaced text and the inserted text, and shifts the positions after them.
+ ******************************************************************************/
+
+use std::ops::Range;
+
+/// Start positions of every line of one source text.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LineIndex {
+    /// For each line, its first character as `(char offset, byte offset)`.
+    starts: Vec<(usize, usize)>,
+    /// Char offsets of the characters that take two UTF-16 code units, sorted.
+    wide: Vec<usize>,
+    /// Length of the text in characters.
+    chars: usize,
+    /// Length of the text in bytes.
+    bytes: usize,
+}
+
+/// A replacement of the characters in `range` by `replacement`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TextEdit {
+    /// Char offsets of the replaced text.
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    /// Applies the edit to `source`. Offsets past the end are clamped to it.
+    pub fn apply(&self, source: &str) -> String {
+        let byte = |offset: usize| {
+            source
+                .char_indices()
+                .nth(offset)
+                .map_or(source.len(), |(byte, _)| byte)
+        };
+        let (start, end) = (byte(self.range.start), byte(self.range.end));
+        let end = end.max(start);
+        format!("{}{}{}", &source[..start], self.replacement, &source[end..])
+    }
+}
+
+impl LineIndex {
+    /// Indexes the lines of `source`. Lines are separated by `\n`; a `\r`
+    /// before it is treated as part of the break.
+    pub fn new(source: &str) -> Self {
+        let mut index = Self {
+            starts: vec![(0, 0)],
+            wide: Vec::new(),
+            chars: 0,
+            bytes: source.len(),
+        };
+        index.chars = index.scan(source, 0, 0);
+        index
+    }
+
+    /// Records the line starts and wide characters of `text`, which begins at
+    /// `(char_base, byte_base)`. Returns the length of `text` in characters.
+    fn scan(&mut self, text: &str, char_base: usize, byte_base: usize) -> usize {
+        let mut chars = 0;
+        for (byte, c) in text.char_indices() {
+            if c.len_utf16() == 2 {
+                self.wide.push(char_base + chars);
+            }
+            chars += 1;
+            if c == '\n' {
+                self.starts.push((char_base + chars, byte_base + byte + 1));
+            }
+        }
+        chars
+    }
+
+    /// Number of lines. Text ending in a line break has an empty last line.
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// The 1-based line and column of the character at `offset`. Offsets past
+    /// the end of the text are clamped to the end.
+    pub fn offset_to_line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.chars);
+        let line = self.starts.partition_point(|(start, _)| *start <= offset);
+        (line, offset - self.starts[line - 1].0 + 1)
+    }
+
+    /// Like `offset_to_line_col`, but the column counts UTF-16 code units.
+    pub fn offset_to_line_col_utf16(&self, offset: usize) -> (usize, usize) {
+        let (line, column) = self.offset_to_line_col(offset);
+        let start = self.starts[line - 1].0;
+        (
+            line,
+            column + self.wide_between(start..start + column - 1).len(),
+        )
+    }
+
+    /// The char offset of the 1-based `line` and `column`, or `None` if there
+    /// is no such position. The column just past the last character of a
+    /// line, where its line break is, is a position.
+    pub fn line_col_to_offset(&self, line: usize, column: usize) -> Option<usize> {
+        let span = self.line_span(line)?;
+        let offset = span.start + column.checked_sub(1)?;
+        (offset <= span.end).then_some(offset)
+    }
+
+    /// Like `line_col_to_offset`, but `column` counts UTF-16 code units.
+    /// A column between the two halves of a character is not a position.
+    pub fn line_col_utf16_to_offset(&self, line: usize, column: usize) -> Option<usize> {
+        let span = self.line_span(line)?;
+        let mut units = column.checked_sub(1)?;
+        let mut offset = span.start;
+        for &wide in self.wide_between(span.clone()) {
+            if units <= wide - offset {
+                break;
+            }
+            units -= wide - offset;
+            if units == 1 {
+                return None;
+            }
+            units -= 2;
+            offset = wide + 1;
+        }
+        let offset = offset + units;
+        (offset <= span.end).then_some(offset)
+    }
+
+    /// The text of the 1-based `line` in `source`, without its line break, or
+    /// `None` if there is no such line. `source` must be the indexed text.
+    pub fn line_text<'a>(&self, source: &'a str, line: usize) -> Option<&'a str> {
+        let (_, start) = *self.starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .starts
+            .get(line)
+            .map_or(self.bytes, |(_, next)| next - 1);
+        let text = source.get(start..end)?;
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    /// Updates the index for `edit` applied to `source`, the indexed text as
+    /// it was before the edit. Afterwards the index equals one built from
+    /// `edit.apply(source)`.
+    pub fn apply_edit(&mut self, source: &str, edit: &TextEdit) {
+        let start = edit.range.start.min(self.chars);
+        let end = edit.range.end.clamp(start, self.chars);
+
+        // Find the replaced bytes by scanning from the start of their line.
+        let (line_start, line_byte) = self.starts[self.offset_to_line_col(start).0 - 1];
+        let mut bytes = source[line_byte..]
+            .char_indices()
+            .map(|(byte, _)| line_byte + byte)
+            .chain([self.bytes])
+            .skip(start - line_start);
+        let start_byte = bytes.next().unwrap_or(self.bytes);
+        let end_byte = match end - start {
+            0 => start_byte,
+            replaced => bytes.nth(replaced - 1).unwrap_or(self.bytes),
+        };
+
+        let inserted_chars = edit.replacement.chars().count();
+        let char_shift = |offset: usize| offset - (end - start) + inserted_chars;
+        let byte_shift = |byte: usize| byte - (end_byte - start_byte) + edit.replacement.len();
+
+        // Drop what was inside the replaced text and shift what followed it.
+        let first_after = self.starts.partition_point(|(offset, _)| *offset <= end);
+        let mut after: Vec<(usize, usize)> = self
+            .starts
+            .split_off(first_after)
+            .into_iter()
+            .map(|(offset, byte)| (char_shift(offset), byte_shift(byte)))
+            .collect();
+        self.starts
+            .truncate(self.starts.partition_point(|(offset, _)| *offset <= start));
+        let first_wide_after = self.wide.partition_point(|offset| *offset < end);
+        let mut wide_after: Vec<usize> = self
+            .wide
+            .split_off(first_wide_after)
+            .into_iter()
+            .map(char_shift)
+            .collect();
+        self.wide
+            .truncate(self.wide.partition_point(|offset| *offset < start));
+
+        self.scan(&edit.replacement, start, start_byte);
+        self.starts.append(&mut after);
+        self.wide.append(&mut wide_after);
+        self.chars = char_shift(self.chars);
+        self.bytes = byte_shift(self.bytes);
+    }
+
+    /// The char offsets of the 1-based `line`, from its first character to
+    /// its line break, or to the end of the text on the last line.
+    fn line_span(&self, line: usize) -> Option<Range<usize>> {
+        let (start, _) = *self.starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .starts
+            .get(line)
+            .map_or(self.chars, |(next, _)| next - 1);
+        Some(start..end)
+    }
+
+    /// The wide characters with char offsets in `range`.
+    fn wide_between(&self, range: Range<usize>) -> &[usize] {
+        let from = self.wide.partition_point(|offset| *offset < range.start);
+        let to = self.wide.partition_point(|offset| *offset < range.end);
+        &self.wide[from..to]
+    }
+}
@@ -0,0 +1,431 @@
+//! src/lint.rs
+
+/*******************************************************************************
+ *                                LINT MODULE
+ *-------------------------------------------------------------------------------
+ * Checks that run over a successfully parsed `Program` and report likely
+ * mistakes as `Diagnostic`s. `lint` runs every default check; each check is
+ * also exposed on its own. Opt-in checks, such as `suggest_parens`, only run
+ * when called directly.
+ ******************************************************************************/
+
+use crate::{
+    index::child_expressions, print_expression, print_pattern, Applicability, Diagnostic,
+    Expression, Fix, Pattern, Program, Span, Term, TextEdit,
+};
+
+/// The code of every lint, default or opt-in.
+pub const LINT_CODES: [&str; 3] = ["non-exhaustive-match", "suggest-parens", "over-application"];
+
+/// Runs every lint over `program`, returning their diagnostics in order.
+pub fn lint(program: &Program) -> Vec<Diagnostic> {
+    check_exhaustiveness(program)
+}
+
+//------------------------------------------------------------------------------
+// EXHAUSTIVENESS
+//------------------------------------------------------------------------------
+
+/// Reports every `match` whose arms do not cover all possible values.
+///
+/// Numbers form an infinite domain, so a match is only exhaustive if one of its
+/// arms is irrefutable: an identifier or `_`, possibly parenthesized. Arms
+/// for both `true` and `false` also cover every Boolean, so a match whose
+/// arms are all Booleans is reported with the one it misses. A guarded arm
+/// covers nothing, since its guard may fail.
+pub fn check_exhaustiveness(program: &Program) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    program.expression.walk(&mut |expression| {
+        if let Expression::PatternMatch { arms, .. } = expression {
+            let unguarded: Vec<&Pattern> = arms
+                .iter()
+                .filter(|arm| arm.guard.is_none())
+                .map(|arm| &arm.pattern)
+                .collect();
+            if !unguarded.iter().any(|pattern| is_irrefutable(pattern))
+                && ![true, false].iter().all(|value| {
+                    unguarded
+                        .iter()
+                        .any(|pattern| boolean(pattern) == Some(*value))
+                })
+            {
+                let covered: Vec<String> = unguarded
+                    .iter()
+                    .map(|pattern| format!("'{}'", print_pattern(pattern)))
+                    .collect();
+                let booleans: Option<Vec<bool>> =
+                    unguarded.iter().map(|pattern| boolean(pattern)).collect();
+                let message = if covered.is_empty() {
+                    "match is not exhaustive: every arm has a guard; add an identifier or '_' \
+                     arm without one"
+                        .to_string()
+                } else if let Some(booleans) = booleans {
+                    // The arms cover only one Boolean, or this match would
+                    // be exhaustive.
+                    let missing = !booleans[0];
+                    format!(
+                        "match is not exhaustive: missing '{}'; add a '{}' arm",
+                        missing, missing
+                    )
+                } else {
+                    format!(
+                        "match is not exhaustive: only {} covered; add an identifier or '_' arm",
+                        covered.join(", ")
+                    )
+                };
+                diagnostics.push(Diagnostic::warning("non-exhaustive-match", message));
+            }
+        }
+    });
+
+    diagnostics
+}
+
+/// Whether `pattern` matches every value.
+fn is_irrefutable(pattern: &Pattern) -> bool {
+    match pattern {
+        Pattern::Identifier(_) => true,
+        Pattern::Number(_) | Pattern::Boolean(_) | Pattern::Cons(..) => false,
+        Pattern::Grouped(inner) => is_irrefutable(inner),
+    }
+}
+
+/// The Boolean `pattern` matches, if it is a Boolean literal.
+fn boolean(pattern: &Pattern) -> Option<bool> {
+    match pattern {
+        Pattern::Boolean(value) => Some(*value),
+        Pattern::Grouped(inner) => boolean(inner),
+        Pattern::Identifier(_) | Pattern::Number(_) | Pattern::Cons(..) => None,
+    }
+}
+
+//------------------------------------------------------------------------------
+// PARENTHESIZATION
+//------------------------------------------------------------------------------
+
+/// A parent/child pairing of operators that parses fine but is easily read
+/// with the wrong grouping when written without parentheses.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Confusion {
+    /// An application as an arithmetic operand: `f x + y` is `(f x) + y`.
+    ApplicationInArithmetic,
+    /// `&&` and `||` mixed: `a || b && c` is `a || (b && c)`.
+    MixedLogic,
+    /// A logic operand of a comparison: `a < b && c` is `a < (b && c)`.
+    LogicInComparison,
+}
+
+/// The confusions `suggest_parens` reports.
+pub const DEFAULT_CONFUSIONS: [Confusion; 3] = [
+    Confusion::ApplicationInArithmetic,
+    Confusion::MixedLogic,
+    Confusion::LogicInComparison,
+];
+
+/// Reports expressions matching any of the `DEFAULT_CONFUSIONS`. Opt-in: not
+/// part of `lint`.
+pub fn suggest_parens(program: &Program, spans: &[Option<Span>]) -> Vec<Diagnostic> {
+    suggest_parens_with(program, spans, &DEFAULT_CONFUSIONS)
+}
+
+/// Reports every expression where an operand without parentheses matches one
+/// of `confusions`. `spans` holds the source span of each node, by `NodeId`,
+/// as `Parser::parse_program_spanned` returns them; when the confusing
+/// operand has one, the diagnostic carries a fix wrapping it in parentheses.
+pub fn suggest_parens_with(
+    program: &Program,
+    spans: &[Option<Span>],
+    confusions: &[Confusion],
+) -> Vec<Diagnostic> {
+    let index = program.build_index();
+    let mut diagnostics = Vec::new();
+
+    for id in 0..index.len() {
+        let expression = index.node(id);
+        let Some((_, left, right)) = expression.as_binary() else {
+            continue;
+        };
+        let found = confusions.iter().find_map(|confusion| {
+            [left, right].into_iter().find_map(|child| {
+                confusion
+                    .reason(expression, child)
+                    .map(|reason| (child, reason))
+            })
+        });
+        let Some((child, reason)) = found else {
+            continue;
+        };
+        let mut diagnostic = Diagnostic::warning(
+            "suggest-parens",
+            format!(
+                "{}; make the grouping explicit: `{}`",
+                reason,
+                print_expression(expression)
+            ),
+        );
+        let span = index
+            .id_of(child)
+            .and_then(|child| spans.get(child)?.as_ref());
+        if let Some(span) = span {
+            diagnostic = diagnostic.with_fix(Fix {
+                applicability: Applicability::MachineApplicable,
+                edits: vec![
+                    TextEdit {
+                        range: span.start..span.start,
+                        replacement: "(".to_string(),
+                    },
+                    TextEdit {
+                        range: span.end..span.end,
+                        replacement: ")".to_string(),
+                    },
+                ],
+            });
+        }
+        diagnostics.push(diagnostic);
+    }
+
+    diagnostics
+}
+
+impl Confusion {
+    /// Explains why `child`, an operand of `parent`, is confusing, or returns
+    /// `None` if this confusion doesn't apply.
+    fn reason(self, parent: &Expression, child: &Expression) -> Option<String> {
+        match (self, parent, child) {
+            (
+                Confusion::ApplicationInArithmetic,
+                Expression::Arithmetic { operator, .. },
+                Expression::Application(_),
+            ) => Some(format!(
+                "function application binds tighter than '{}'",
+                operator.symbol()
+            )),
+            (
+                Confusion::MixedLogic,
+                Expression::Logic { operator, .. },
+                Expression::Logic {
+                    operator: inner, ..
+                },
+            ) if operator != inner => Some(format!(
+                "'{}' and '{}' are mixed without parentheses",
+                inner.symbol(),
+                operator.symbol()
+            )),
+            (
+                Confusion::LogicInComparison,
+                Expression::Comparison { operator, .. },
+                Expression::Logic {
+                    operator: inner, ..
+                },
+            ) => Some(format!(
+                "'{}' binds tighter than '{}'",
+                inner.symbol(),
+                operator.symbol()
+            )),
+            _ => None,
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// ARITY
+//------------------------------------------------------------------------------
+
+/// Reports applications of a `let`-bound lambda chain to more arguments than
+/// it has parameters, e.g. `add 1 2 3` for `let add = \x -> \y -> x + y`.
+/// Opt-in: not part of `lint`.
+///
+/// Conservative: a binding used anywhere except at the head of an application
+/// (passed as an argument, composed, or returned) may be called in ways this
+/// check can't see, so its applications are not reported.
+pub fn check_arity(program: &Program) -> Vec<Diagnostic> {
+    let mut check = ArityCheck::default();
+    check.visit(&program.expression);
+
+    check
+        .calls
+        .iter()
+        .filter_map(|&(binding, arguments)| {
+            let binding = &check.bindings[binding];
+            (!binding.escapes && arguments > binding.arity).then(|| {
+                Diagnostic::warning(
+                    "over-application",
+                    format!(
+                        "'{}' takes {} argument{} but is applied to {}",
+                        binding.name,
+                        binding.arity,
+                        if binding.arity == 1 { "" } else { "s" },
+                        arguments
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// A `let` binding whose value is a lambda chain.
+struct ArityBinding<'a> {
+    name: &'a str,
+    arity: usize,
+    /// Whether the binding is used other than by being applied.
+    escapes: bool,
+}
+
+#[derive(Default)]
+struct ArityCheck<'a> {
+    /// Names in scope, innermost last, with the binding each refers to if it
+    /// has a known arity.
+    scope: Vec<(&'a str, Option<usize>)>,
+    bindings: Vec<ArityBinding<'a>>,
+    /// Applications of known-arity bindings, as (binding, argument count).
+    calls: Vec<(usize, usize)>,
+}
+
+impl<'a> ArityCheck<'a> {
+    fn visit(&mut self, expression: &'a Expression) {
+        match expression {
+            Expression::Application(_) => {
+                let (head, arguments) = flatten_application(expression);
+                match self.resolve(head) {
+                    Some(binding) => self.calls.push((binding, arguments.len())),
+                    None => self.visit(head),
+                }
+                for argument in arguments {
+                    self.visit(argument);
+                }
+            }
+            Expression::Term(Term::Identifier(_)) => {
+                if let Some(binding) = self.resolve(expression) {
+                    self.bindings[binding].escapes = true;
+                }
+            }
+            Expression::LetExpr {
+                identifier,
+                value,
+                body,
+                ..
+            } => {
+                let arity = lambda_arity(value);
+                let binding = (arity > 0).then(|| {
+                    self.bindings.push(ArityBinding {
+                        name: identifier,
+                        arity,
+                        escapes: false,
+                    });
+                    self.bindings.len() - 1
+                });
+                // `let` is recursive: the name is in scope in its own value.
+                self.scoped(vec![(identifier.as_str(), binding)], |check| {
+                    check.visit(value);
+                    check.visit(body);
+                });
+            }
+            Expression::LetAnd { bindings, body, .. } => {
+                let names = bindings
+                    .iter()
+                    .map(|binding| {
+                        let arity = lambda_arity(&binding.value);
+                        let id = (arity > 0).then(|| {
+                            self.bindings.push(ArityBinding {
+                                name: &binding.identifier,
+                                arity,
+                                escapes: false,
+                            });
+                            self.bindings.len() - 1
+                        });
+                        (binding.identifier.as_str(), id)
+                    })
+                    .collect();
+                self.scoped(names, |check| {
+                    for binding in bindings {
+                        check.visit(&binding.value);
+                    }
+                    check.visit(body);
+                });
+            }
+            Expression::Lambda {
+                parameter, body, ..
+            } => self.scoped(vec![(parameter.as_str(), None)], |check| check.visit(body)),
+            Expression::PatternMatch { expression, arms } => {
+                self.visit(expression);
+                for arm in arms {
+                    let mut names = Vec::new();
+                    pattern_names(&arm.pattern, &mut names);
+                    let names = names.into_iter().map(|name| (name, None)).collect();
+                    self.scoped(names, |check| {
+                        if let Some(guard) = &arm.guard {
+                            check.visit(guard);
+                        }
+                        check.visit(&arm.expression);
+                    });
+                }
+            }
+            _ => {
+                for (_, child) in child_expressions(expression) {
+                    self.visit(child);
+                }
+            }
+        }
+    }
+
+    /// The known-arity binding `expression` names, if it is such a name.
+    fn resolve(&self, expression: &Expression) -> Option<usize> {
+        match ungrouped(expression) {
+            Expression::Term(Term::Identifier(name)) => self
+                .scope
+                .iter()
+                .rev()
+                .find(|(bound, _)| bound == name)
+                .and_then(|(_, binding)| *binding),
+            _ => None,
+        }
+    }
+
+    fn scoped(&mut self, names: Vec<(&'a str, Option<usize>)>, visit: impl FnOnce(&mut Self)) {
+        let depth = self.scope.len();
+        self.scope.extend(names);
+        visit(self);
+        self.scope.truncate(depth);
+    }
+}
+
+/// Splits an application into its head and every argument, looking through
+/// parenthesized partial applications: `(f a) b` is `f` applied to `a b`.
+fn flatten_application(expression: &Expression) -> (&Expression, Vec<&Expression>) {
+    match ungrouped(expression) {
+        Expression::Application(items) => {
+            let (head, mut arguments) = flatten_application(&items[0]);
+            arguments.extend(&items[1..]);
+            (head, arguments)
+        }
+        _ => (expression, Vec::new()),
+    }
+}
+
+/// The number of parameters of a lambda chain, `\x -> \y -> ...`.
+fn lambda_arity(expression: &Expression) -> usize {
+    match ungrouped(expression) {
+        Expression::Lambda { body, .. } => 1 + lambda_arity(body),
+        _ => 0,
+    }
+}
+
+fn pattern_names<'a>(pattern: &'a Pattern, names: &mut Vec<&'a str>) {
+    match pattern {
+        Pattern::Identifier(name) if name != "_" => names.push(name),
+        Pattern::Grouped(inner) => pattern_names(inner, names),
+        Pattern::Cons(head, tail) => {
+            pattern_names(head, names);
+            pattern_names(tail, names);
+        }
+        _ => {}
+    }
+}
+
+fn ungrouped(mut expression: &Expression) -> &Expression {
+    while let Expression::Term(Term::GroupedExpression(inner)) = expression {
+        expression = inner;
+    }
+    expression
+}
@@ -0,0 +1,206 @@
+//! src/literate.rs
+
+/*******************************************************************************
+ *                              LITERATE MODULE
+ *-------------------------------------------------------------------------------
+ * Checks the programs embedded in Markdown documentation, so examples in the
+ * docs don't silently stop parsing. Every fenced code block whose info string
+ * starts with `pfl` is a program:
+ *
+ *     ```pfl                       parsed and linted like a `.pfl` file
+ *     ```pfl ignore                skipped
+ *     ```pfl expect-error=E0001    must fail with that error code
+ *
+ * Attributes follow the language, separated by spaces or commas; unknown
+ * ones are ignored. Blocks in other languages are skipped, along with any
+ * `pfl` fences inside them.
+ *
+ * Diagnostics are located in the Markdown file itself: at the exact position
+ * when the error carries one, and otherwise at the block it came from.
+ ******************************************************************************/
+
+use crate::{
+    validate::parse_and_lint, Diagnostic, LineIndex, Location, Severity, SourceFile, Span,
+    ValidateConfig,
+};
+
+/// Code reported when a block marked `expect-error` does not fail as expected.
+pub const MISSING_EXPECTED_ERROR: &str = "missing-expected-error";
+
+/// A `pfl` code block found in a Markdown document.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CodeBlock {
+    /// The block's text, between its fences.
+    pub source: String,
+    /// The characters `source` occupies in the document.
+    pub span_in_markdown: Span,
+    /// The words of the info string after `pfl`, such as `ignore`.
+    pub attrs: Vec<String>,
+}
+
+impl CodeBlock {
+    /// Whether the block is marked `ignore`.
+    pub fn is_ignored(&self) -> bool {
+        self.attrs.iter().any(|attr| attr == "ignore")
+    }
+
+    /// The code from an `expect-error=CODE` attribute, if there is one.
+    pub fn expected_error(&self) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find_map(|attr| attr.strip_prefix("expect-error="))
+    }
+}
+
+/// What `check_docs` found in a document.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DocsReport {
+    /// Blocks that were parsed, whether or not they passed.
+    pub checked: usize,
+    /// Blocks marked `ignore`.
+    pub ignored: usize,
+    /// Findings in the checked blocks, in document order, located in the
+    /// document.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl DocsReport {
+    /// Whether any block failed.
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+/// Finds every `pfl` code block in `markdown`, in document order. A block
+/// whose closing fence is missing runs to the end of the document.
+pub fn extract_blocks(markdown: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    // The open fence's length, and the block being collected if it is `pfl`.
+    let mut open: Option<(usize, Option<CodeBlock>)> = None;
+    let mut offset = 0;
+
+    for line in markdown.split_inclusive('\n') {
+        let length = line.chars().count();
+        let fence = fence(line);
+        match (&mut open, fence) {
+            (None, Some((width, info))) => {
+                let mut words = info
+                    .split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|word| !word.is_empty());
+                let block = (words.next() == Some("pfl")).then(|| CodeBlock {
+                    source: String::new(),
+                    span_in_markdown: Span {
+                        start: offset + length,
+                        end: offset + length,
+                    },
+                    attrs: words.map(str::to_string).collect(),
+                });
+                open = Some((width, block));
+            }
+            (None, None) => {}
+            (Some((open_width, block)), Some((width, ""))) if width >= *open_width => {
+                blocks.extend(block.take());
+                open = None;
+            }
+            (Some((_, block)), _) => {
+                if let Some(block) = block {
+                    block.source.push_str(line);
+                    block.span_in_markdown.end += length;
+                }
+            }
+        }
+        offset += length;
+    }
+
+    if let Some((_, block)) = open {
+        blocks.extend(block);
+    }
+    blocks
+}
+
+/// The length of the backtick fence that `line` starts with, and the info
+/// string after it, if `line` is a fence.
+fn fence(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim();
+    let width = line.chars().take_while(|&c| c == '`').count();
+    (width >= 3).then(|| (width, line[width..].trim()))
+}
+
+/// Parses and lints every `pfl` block in `file`, a Markdown document, as
+/// `config` says.
+pub fn check_docs(file: &SourceFile, config: &ValidateConfig) -> DocsReport {
+    let mut report = DocsReport {
+        checked: 0,
+        ignored: 0,
+        diagnostics: Vec::new(),
+    };
+
+    for block in extract_blocks(file.text()) {
+        if block.is_ignored() {
+            report.ignored += 1;
+            continue;
+        }
+        report.checked += 1;
+
+        let diagnostics = check_block(&block, config);
+        let located = |mut diagnostic: Diagnostic, span: Option<Span>| {
+            diagnostic.location = Some(Location {
+                file: file.id(),
+                span: Some(span.unwrap_or(block.span_in_markdown)),
+            });
+            diagnostic
+        };
+        match block.expected_error() {
+            Some(code)
+                if diagnostics
+                    .iter()
+                    .any(|(diagnostic, _)| diagnostic.code == code) => {}
+            Some(code) => {
+                let found = match diagnostics
+                    .iter()
+                    .find(|(diagnostic, _)| diagnostic.severity == Severity::Error)
+                {
+                    Some((diagnostic, _)) => format!("failed with {}", diagnostic.code),
+                    None => "passed".to_string(),
+                };
+                let diagnostic = Diagnostic::error(
+                    MISSING_EXPECTED_ERROR,
+                    format!("block was expected to fail with {}, but {}", code, found),
+                );
+                report.diagnostics.push(located(diagnostic, None));
+            }
+            None => report.diagnostics.extend(
+                diagnostics
+                    .into_iter()
+                    .map(|(diagnostic, span)| located(diagnostic, span)),
+            ),
+        }
+    }
+    report
+}
+
+/// Checks one block, returning each finding with its span in the document
+/// when it has one. Fixes are moved to edit the document.
+fn check_block(block: &CodeBlock, config: &ValidateConfig) -> Vec<(Diagnostic, Option<Span>)> {
+    let start = block.span_in_markdown.start;
+    match parse_and_lint(&block.source, config) {
+        Ok((_, diagnostics)) => diagnostics
+            .into_iter()
+            .map(|mut diagnostic| {
+                for edit in diagnostic.fixes.iter_mut().flat_map(|fix| &mut fix.edits) {
+                    edit.range = start + edit.range.start..start + edit.range.end;
+                }
+                (diagnostic, None)
+            })
+            .collect(),
+        Err(err) => {
+            let span = err.span(&LineIndex::new(&block.source)).map(|span| Span {
+                start: start + span.start,
+                end: start + span.end,
+            });
+            vec![(Diagnostic::from(&err), span)]
+        }
+    }
+}
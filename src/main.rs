@@ -6,63 +6,750 @@
  * This is the entry point for our language processing tool. It reads a `.pfl` file
  * or raw source code from the command line, tokenizes it with the `Lexer`, then
  * parses it with the `Parser` to produce an AST, which is printed for inspection.
+ * Lint findings about the AST are reported on stderr.
  ******************************************************************************/
 
 use std::env;
 use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::process;
 
-use rdp::{Lexer, Parser};
+use rdp::repl::{self, History};
+use rdp::{
+    apply_edits, call_graph, check_arity, check_docs, features, fix_until_stable, parse_template,
+    print_expression, print_styled, semantic_diff, slice, style_named, suggest_parens, validate,
+    AstIndex, Compact, Expanded, FileId, Level, Lexer, LexerOptions, LineIndex, NodeId, ParseError,
+    Parser, PrintStyle, Program, SarifLog, Severity, SourceMap, Span, Stats, ValidateConfig,
+    LINT_CODES, MAX_FIX_ROUNDS,
+};
+
+#[cfg(feature = "dev-metrics")]
+#[global_allocator]
+static ALLOCATOR: rdp::CountingAllocator = rdp::CountingAllocator;
 
 fn main() {
     // Collect command-line arguments
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    // Leading `--flag` arguments configure the run; the rest is the input.
+    //  - `--deny-warnings`: treat lint warnings as errors.
+    //  - `--allow=CODE`, `--warn=CODE`, `--deny=CODE`: set one lint's level.
+    //  - `--suggest-parens`: also suggest parentheses for ambiguous-looking code.
+    //  - `--strict-arity`: also flag functions applied to too many arguments.
+    //  - `--budget=NAME=N,...`: reject programs over complexity limits.
+    //  - `--diagnostics=sarif`: check one or more files and print a SARIF log.
+    //  - `--check-docs`: check the `pfl` code blocks of Markdown files.
+    //  - `--stats[=json]`: print usage statistics over files and directories.
+    //  - `--search=TEMPLATE`: print the expressions matching a template.
+    //  - `--replace=TEMPLATE`: with `--search`, print the file rewritten.
+    //  - `--fix`: apply the fixes of the lint findings to a file in place.
+    //  - `--slice=PATH`: print the expression at PATH and what it depends on.
+    //  - `--diff`: print the semantic changes between two versions of a file.
+    //  - `--callgraph[=dot]`: print which bindings call which.
+    //  - `--fmt`: print the program as formatted source instead of its AST.
+    //  - `--style=NAME`: lay out printed programs as `compact`, `expanded`,
+    //    or `teaching` source.
+    //  - `--perf-report`: print allocations per phase (`dev-metrics` builds).
+    //  - `--repl [--history-file=PATH]`: read and parse entries interactively.
+    //  - `--max-identifier-length=N`, `--max-line-length=N`: lexer limits.
+    //  - `--version [--verbose]`: print the version (and supported features).
+    let mut config = ValidateConfig::default();
+    let mut version = false;
+    let mut verbose = false;
+    let mut sarif = false;
+    let mut check_docs = false;
+    let mut stats = None;
+    let mut search = None;
+    let mut replace = None;
+    let mut fix = false;
+    let mut slice_at = None;
+    let mut diff = false;
+    let mut callgraph = None;
+    let mut fmt = false;
+    let mut style = None;
+    let mut perf_report = false;
+    let mut interactive = false;
+    let mut history_file = None;
+    while args.len() > 1 && args[1].starts_with("--") {
+        let flag = args.remove(1);
+        match flag.split_once('=') {
+            None if flag == "--deny-warnings" => config.diagnostics.default_level = Level::Deny,
+            Some(("--allow", code)) => set_level(&mut config, code, Level::Allow),
+            Some(("--warn", code)) => set_level(&mut config, code, Level::Warn),
+            Some(("--deny", code)) => set_level(&mut config, code, Level::Deny),
+            None if flag == "--suggest-parens" => config.checks.push(suggest_parens),
+            None if flag == "--strict-arity" => {
+                config.checks.push(|program, _| check_arity(program))
+            }
+            Some(("--budget", limits)) => {
+                config.budget = limits.parse().unwrap_or_else(|err| {
+                    eprintln!("Invalid budget: {}", err);
+                    process::exit(1);
+                })
+            }
+            None if flag == "--version" => version = true,
+            None if flag == "--verbose" => verbose = true,
+            Some(("--diagnostics", "sarif")) => sarif = true,
+            Some(("--diagnostics", "human")) => sarif = false,
+            None if flag == "--check-docs" => check_docs = true,
+            None if flag == "--stats" => stats = Some(false),
+            Some(("--stats", "json")) => stats = Some(true),
+            Some(("--search", template)) => search = Some(template.to_string()),
+            Some(("--replace", template)) => replace = Some(template.to_string()),
+            None if flag == "--fix" => fix = true,
+            Some(("--slice", path)) => slice_at = Some(path.to_string()),
+            None if flag == "--diff" => diff = true,
+            None if flag == "--callgraph" => callgraph = Some(false),
+            Some(("--callgraph", "dot")) => callgraph = Some(true),
+            None if flag == "--fmt" => fmt = true,
+            Some(("--style", name)) => match style_named(name) {
+                Some(named) => style = Some(named),
+                None => {
+                    eprintln!("Unknown style '{}'", name);
+                    process::exit(1);
+                }
+            },
+            None if flag == "--perf-report" => perf_report = true,
+            None if flag == "--repl" => interactive = true,
+            Some(("--history-file", path)) => history_file = Some(PathBuf::from(path)),
+            Some(("--max-identifier-length", n)) => {
+                config.options.max_identifier_length = Some(parse_limit(&flag, n))
+            }
+            Some(("--max-line-length", n)) => {
+                config.options.max_line_length = Some(parse_limit(&flag, n))
+            }
+            _ => {
+                eprintln!("Unknown option '{}'", flag);
+                process::exit(1);
+            }
+        }
+    }
+
+    if version {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        if verbose {
+            println!("features:");
+            for feature in features() {
+                println!("  {}", feature);
+            }
+        }
+        process::exit(0);
+    }
+    let options = config.options;
+    if interactive {
+        run_repl(history_file, &config);
+    }
 
     // We need at least 2 arguments: the program name and the input source (file or code).
     if args.len() < 2 {
         eprintln!("Usage:");
-        eprintln!("  {} <file.pfl>", args[0]);
-        eprintln!("  {} \"<source_code>\"", args[0]);
+        eprintln!("  {} [options] <file.pfl>", args[0]);
+        eprintln!("  {} [options] \"<source_code>\"", args[0]);
+        eprintln!("  {} [options] --diagnostics=sarif <file.pfl>...", args[0]);
+        eprintln!("  {} [options] --check-docs <file.md>...", args[0]);
+        eprintln!("  {} [options] --stats[=json] <dir|file.pfl>...", args[0]);
+        eprintln!(
+            "  {} [options] --search=TEMPLATE <dir|file.pfl>...",
+            args[0]
+        );
+        eprintln!("  {} [options] --fix <file.pfl>", args[0]);
+        eprintln!("  {} [options] --slice=PATH <file.pfl>", args[0]);
+        eprintln!("  {} [options] --diff <old.pfl> <new.pfl>", args[0]);
+        eprintln!("  {} [options] --callgraph[=dot] <file.pfl>", args[0]);
+        eprintln!("  {} [options] --repl [--history-file=PATH]", args[0]);
+        eprintln!("  {} --version [--verbose]", args[0]);
+        eprintln!("Options:");
+        eprintln!("  --deny-warnings            Treat lint warnings as errors");
+        eprintln!("  --allow=CODE, --warn=CODE, Silence a lint, report it as a warning,");
+        eprintln!("  --deny=CODE                or treat it as an error");
+        eprintln!("  --suggest-parens           Suggest parentheses for ambiguous-looking code");
+        eprintln!("  --strict-arity             Flag functions applied to too many arguments");
+        eprintln!("  --budget=NAME=N,...        Reject programs over limits: depth, arms,");
+        eprintln!("                             application, lets, nodes");
+        eprintln!("  --max-identifier-length=N  Reject identifiers longer than N");
+        eprintln!("  --max-line-length=N        Reject lines longer than N");
+        eprintln!("  --fmt                      Print the program as source instead of its AST");
+        eprintln!("  --style=NAME               Lay out printed programs: compact, expanded,");
+        eprintln!("                             teaching (default: expanded for --fmt,");
+        eprintln!("                             compact otherwise)");
+        eprintln!("  --perf-report              Print allocations per phase (dev-metrics builds)");
         process::exit(1);
     }
 
+    if sarif {
+        report_sarif(&args[1..], &config);
+    }
+    if check_docs {
+        report_docs(&args[1..], &config);
+    }
+    if let Some(json) = stats {
+        report_stats(&args[1..], options, json);
+    }
+    if let (Some(template), Some(replacement)) = (&search, &replace) {
+        report_rewrite(template, replacement, &args[1..], options);
+    }
+    if fix {
+        report_fix(&args[1..], &config);
+    }
+    if let Some(template) = search {
+        report_search(&template, &args[1..], options);
+    }
+    if let Some(at) = slice_at {
+        report_slice(
+            &at,
+            &args[1..],
+            options,
+            style.as_deref().unwrap_or(&Compact),
+        );
+    }
+    if diff {
+        report_diff(&args[1..], options);
+    }
+    if let Some(dot) = callgraph {
+        report_callgraph(&args[1..], options, dot);
+    }
+
     // Decide how to interpret the argument(s):
     //  - If there's exactly one argument beyond the program name and it ends in `.pfl`,
     //    read from that file.
     //  - Otherwise, treat all subsequent arguments as direct source code, joined by spaces.
-    let input = if args.len() == 2 && args[1].ends_with(".pfl") {
-        match fs::read_to_string(&args[1]) {
-            Ok(content) => content,
+    let file = (args.len() == 2 && args[1].ends_with(".pfl")).then(|| args[1].as_str());
+    let input = match file {
+        Some(path) => read_file(path, 1),
+        // Join arguments beyond index 1 with spaces for direct source code.
+        None => args[1..].join(" "),
+    };
+
+    if perf_report {
+        report_perf(&input, options, file);
+    }
+
+    // Tokenize the input with the `Lexer`, then parse the tokens into an AST
+    // (Program) with the `Parser`.
+    let (program, spans) = parse_spanned_or_exit(&input, options, file, 1);
+
+    // Report lint findings; any denied finding is fatal.
+    let diagnostics = config.lint(&program, &spans);
+    for diagnostic in &diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        process::exit(1);
+    }
+
+    // Print the program as formatted source, or the resulting AST in debug
+    // format.
+    if fmt {
+        let style = style.unwrap_or_else(|| Box::new(Expanded::default()));
+        println!("{}", print_styled(&program.expression, style.as_ref()));
+    } else {
+        println!("{:#?}", program);
+    }
+}
+
+/// Lexes and parses `input`, read from `file` if it came from one, with
+/// positions on the tokens. Exits with `status` after reporting an error.
+fn parse_or_exit(input: &str, options: LexerOptions, file: Option<&str>, status: i32) -> Program {
+    parse_spanned_or_exit(input, options, file, status).0
+}
+
+/// Like `parse_or_exit`, but also returns the span of each node.
+fn parse_spanned_or_exit(
+    input: &str,
+    options: LexerOptions,
+    file: Option<&str>,
+    status: i32,
+) -> (Program, Vec<Option<Span>>) {
+    let tokens = Lexer::with_options(input, options)
+        .tokenize_spanned()
+        .unwrap_or_else(|err| report_error("Lexing", &err, input, file, status));
+    Parser::from_spanned(tokens)
+        .parse_program_spanned()
+        .unwrap_or_else(|err| report_error("Parsing", &err, input, file, status))
+}
+
+/// Prints `err`, raised in the `phase` of reading `input`, at the
+/// `file:line:column` it points at, and exits with `status`. The file is left
+/// out for input given on the command line, and the line and column for
+/// errors without a position; errors with one are followed by the offending
+/// line with a caret under the column.
+fn report_error(phase: &str, err: &ParseError, input: &str, file: Option<&str>, status: i32) -> ! {
+    let index = LineIndex::new(input);
+    let position = err.line_col(&index);
+    let location = match (file, position) {
+        (Some(file), Some((line, column))) => format!(" at {}:{}:{}", file, line, column),
+        (None, Some((line, column))) => format!(" at {}:{}", line, column),
+        (Some(file), None) => format!(" in {}", file),
+        (None, None) => String::new(),
+    };
+    let mut excerpt = String::new();
+    err.write_excerpt(input, &index, &mut excerpt)
+        .expect("writing to a String cannot fail");
+    eprint!(
+        "{} Error [{}]{}: {}\n{}",
+        phase,
+        err.code(),
+        location,
+        err,
+        excerpt
+    );
+    process::exit(status);
+}
+
+/// Checks every file in `paths` and prints a single SARIF log covering all of
+/// them, instead of the AST. Exits non-zero if any file has an error, or any
+/// denied finding.
+fn report_sarif(paths: &[String], config: &ValidateConfig) -> ! {
+    let mut log = SarifLog::new();
+    let mut sources = SourceMap::new();
+    let mut failed = false;
+
+    for path in paths {
+        let file = load(&mut sources, Path::new(path));
+        let report = sources.get(file).validate(config);
+        failed |= report.has_errors();
+        log.add_file(Path::new(path), &report.diagnostics);
+    }
+
+    print!("{}", log.to_json());
+    process::exit(if failed { 1 } else { 0 });
+}
+
+/// Checks the `pfl` code blocks of every Markdown file in `paths`, printing
+/// each finding at its line in the document and a summary. Exits non-zero if
+/// any block failed.
+fn report_docs(paths: &[String], config: &ValidateConfig) -> ! {
+    let mut sources = SourceMap::new();
+    let mut failed = false;
+
+    for path in paths {
+        let id = load(&mut sources, Path::new(path));
+        let file = sources.get(id);
+        let report = check_docs(file, config);
+        for diagnostic in &report.diagnostics {
+            eprintln!("{}", sources.render(diagnostic));
+        }
+        eprintln!(
+            "{}: {} blocks checked, {} ignored",
+            file.name(),
+            report.checked,
+            report.ignored
+        );
+        failed |= report.has_errors();
+    }
+
+    process::exit(if failed { 1 } else { 0 });
+}
+
+/// Aggregates usage statistics over every `.pfl` file in `paths`, descending
+/// into directories, and prints them as tables or JSON. Files that fail to
+/// lex or parse are reported on stderr and left out of the totals.
+fn report_stats(paths: &[String], options: LexerOptions, json: bool) -> ! {
+    let mut files = Vec::new();
+    for path in paths {
+        if let Err(err) = collect_pfl_files(Path::new(path), &mut files) {
+            eprintln!("Error reading '{}': {}", path, err);
+            process::exit(1);
+        }
+    }
+
+    let mut stats = Stats::default();
+    let mut sources = SourceMap::new();
+    let mut failed = false;
+    for path in &files {
+        let id = load(&mut sources, path);
+        let file = sources.get(id);
+        let parsed = Lexer::with_options(file.text(), options)
+            .tokenize()
+            .and_then(|tokens| Ok((tokens.clone(), Parser::new(tokens).parse_program()?)));
+        match parsed {
+            Ok((tokens, program)) => {
+                stats.record_tokens(&tokens);
+                stats.record_program(&program);
+            }
             Err(err) => {
-                eprintln!("Error reading file '{}': {}", args[1], err);
-                process::exit(1);
+                eprintln!("{}", sources.render(&file.diagnose(&err)));
+                failed = true;
             }
         }
+    }
+
+    if json {
+        print!("{}", stats.to_json());
     } else {
-        // Join arguments beyond index 1 with spaces for direct source code.
-        args[1..].join(" ")
-    };
+        print!("{}", stats);
+    }
+    process::exit(if failed { 1 } else { 0 });
+}
 
-    // Create a lexer to tokenize the input.
-    let mut lexer = Lexer::new(&input);
-    let tokens = match lexer.tokenize() {
-        Ok(toks) => toks,
-        Err(err) => {
-            eprintln!("Lexing Error: {}", err);
+/// Prints every expression matching `template` in the `.pfl` files of
+/// `paths`, one per line as `file:path: expression`, where `path` leads from
+/// the root of the program to the match. Exits non-zero if nothing matched
+/// or a file failed to parse.
+fn report_search(template: &str, paths: &[String], options: LexerOptions) -> ! {
+    let template = parse_template(template).unwrap_or_else(|err| {
+        eprintln!("Template Error [{}]: {}", err.code(), err);
+        process::exit(1);
+    });
+
+    let mut files = Vec::new();
+    for path in paths {
+        if let Err(err) = collect_pfl_files(Path::new(path), &mut files) {
+            eprintln!("Error reading '{}': {}", path, err);
             process::exit(1);
         }
+    }
+
+    let mut found = false;
+    let mut failed = false;
+    let mut sources = SourceMap::new();
+    for path in &files {
+        let id = load(&mut sources, path);
+        let file = sources.get(id);
+        let program = match Lexer::with_options(file.text(), options)
+            .tokenize()
+            .and_then(|tokens| Parser::new(tokens).parse_program())
+        {
+            Ok(program) => program,
+            Err(err) => {
+                eprintln!("{}", sources.render(&file.diagnose(&err)));
+                failed = true;
+                continue;
+            }
+        };
+
+        let index = program.build_index();
+        for found_match in template.find_matches(&program) {
+            found = true;
+            println!(
+                "{}:{}: {}",
+                file.name(),
+                index.display_path(found_match.node),
+                print_expression(found_match.expression)
+            );
+        }
+    }
+    process::exit(if found && !failed { 0 } else { 1 });
+}
+
+/// Rewrites the single `.pfl` file in `paths`, replacing every match of
+/// `template` with `replacement`, and prints the result with the rest of the
+/// file as written. Matches that can't be rewritten safely are reported on
+/// stderr and left as they are.
+fn report_rewrite(template: &str, replacement: &str, paths: &[String], options: LexerOptions) -> ! {
+    let [path] = paths else {
+        eprintln!("--replace rewrites exactly one file");
+        process::exit(1);
     };
+    let (template, replacement) = match (parse_template(template), parse_template(replacement)) {
+        (Ok(template), Ok(replacement)) => (template, replacement),
+        (Err(err), _) | (_, Err(err)) => {
+            eprintln!("Template Error [{}]: {}", err.code(), err);
+            process::exit(1);
+        }
+    };
+
+    let input = read_file(path, 1);
+    let program = parse_or_exit(&input, options, Some(path), 1);
+
+    let rewrite = template.rewrite(&program, &replacement, &input);
+    for diagnostic in &rewrite.refused {
+        eprintln!("{}: {}", path, diagnostic);
+    }
+    print!("{}", apply_edits(&input, &rewrite.edits));
+    process::exit(0);
+}
 
-    // Create a parser to convert tokens into an AST (Program).
-    let mut parser = Parser::new(tokens);
-    let program = match parser.parse_program() {
-        Ok(prog) => prog,
-        Err(err) => {
-            eprintln!("Parsing Error: {}", err);
+/// Applies the machine-applicable fixes of the findings in the single `.pfl`
+/// file in `paths`, in rounds until none is left, and writes the file back if
+/// it still parses. Fixes skipped because they overlap another are reported
+/// on stderr; exits non-zero if any is left unapplied.
+fn report_fix(paths: &[String], config: &ValidateConfig) -> ! {
+    let [path] = paths else {
+        eprintln!("--fix rewrites exactly one file");
+        process::exit(1);
+    };
+    let input = read_file(path, 1);
+
+    let outcome = fix_until_stable(&input, MAX_FIX_ROUNDS, |source| {
+        validate(source, config).diagnostics
+    });
+    for diagnostic in &outcome.skipped {
+        eprintln!("{}: fix skipped: {}", path, diagnostic);
+    }
+    if outcome.applied > 0 {
+        if validate(&outcome.source, config).program.is_none() {
+            eprintln!("{}: the fixed program does not parse; not written", path);
             process::exit(1);
         }
+        fs::write(path, &outcome.source).unwrap_or_else(|err| {
+            eprintln!("Error writing file '{}': {}", path, err);
+            process::exit(1);
+        });
+    }
+    eprintln!("{}: applied {} fixes", path, outcome.applied);
+    process::exit(if outcome.converged { 0 } else { 1 });
+}
+
+/// Prints the slice of the single `.pfl` file in `paths` at `at`: the
+/// expression there and the bindings it depends on. `at` is a path in the
+/// form `--search` prints, such as `body.arms[1]`, or `root`.
+fn report_slice(at: &str, paths: &[String], options: LexerOptions, style: &dyn PrintStyle) -> ! {
+    let [path] = paths else {
+        eprintln!("--slice reads exactly one file");
+        process::exit(1);
+    };
+    let input = read_file(path, 1);
+    let program = parse_or_exit(&input, options, Some(path), 1);
+
+    let index = program.build_index();
+    let Some(target) = resolve_path(&index, at) else {
+        eprintln!("{}: no expression at '{}'", path, at);
+        process::exit(1);
+    };
+    println!(
+        "{}",
+        print_styled(&slice(&program, target).expression, style)
+    );
+    process::exit(0);
+}
+
+/// Prints the semantic changes between the two `.pfl` files in `paths`, old
+/// then new, one per line. Exits 0 when the changes are at most formatting,
+/// 1 when there are others, and 2 when a file can't be read or parsed.
+fn report_diff(paths: &[String], options: LexerOptions) -> ! {
+    let [old, new] = paths else {
+        eprintln!("--diff compares exactly two files");
+        process::exit(2);
     };
+    let parse = |path: &String| parse_or_exit(&read_file(path, 2), options, Some(path), 2);
 
-    // Print the resulting AST in debug format.
-    println!("{:#?}", program);
+    let changes = semantic_diff(&parse(old), &parse(new));
+    for change in &changes {
+        println!("{}", change);
+    }
+    let identical = changes.iter().all(|change| change.is_formatting_only());
+    process::exit(if identical { 0 } else { 1 });
+}
+
+/// Prints the call graph of the single `.pfl` file in `paths`: one
+/// `caller -> callee` line per call and one line per cycle, or with `dot`,
+/// the graph in Graphviz DOT format.
+fn report_callgraph(paths: &[String], options: LexerOptions, dot: bool) -> ! {
+    let [path] = paths else {
+        eprintln!("--callgraph reads exactly one file");
+        process::exit(1);
+    };
+    let input = read_file(path, 1);
+    let program = parse_or_exit(&input, options, Some(path), 1);
+
+    let graph = call_graph(&program);
+    if dot {
+        print!("{}", graph.to_dot());
+    } else {
+        for (caller, callee) in graph.edges() {
+            println!("{} -> {}", caller, callee);
+        }
+        for cycle in graph.cycles() {
+            println!("cycle: {}", cycle.join(", "));
+        }
+    }
+    process::exit(0);
+}
+
+/// Finds the node at a path such as `body.arms[1].left`, as printed by
+/// `--search`. `root` is the whole program.
+fn resolve_path(index: &AstIndex, path: &str) -> Option<NodeId> {
+    if path == "root" {
+        return Some(index.root());
+    }
+    path.split('.').try_fold(index.root(), |node, segment| {
+        let (field, position) = match segment.strip_suffix(']') {
+            Some(segment) => {
+                let (field, position) = segment.split_once('[')?;
+                (field, Some(position.parse().ok()?))
+            }
+            None => (segment, None),
+        };
+        index.children(node).iter().copied().find(|&child| {
+            index
+                .step(child)
+                .is_some_and(|step| step.field == field && step.index == position)
+        })
+    })
+}
+
+/// Lexes and parses `input`, read from `file` if given, printing the
+/// allocations each phase made instead of the AST.
+#[cfg(feature = "dev-metrics")]
+fn report_perf(input: &str, options: LexerOptions, file: Option<&str>) -> ! {
+    use rdp::AllocStats;
+
+    let (tokens, lex) =
+        AllocStats::measure(|| Lexer::with_options(input, options).tokenize_spanned());
+    let tokens = tokens.unwrap_or_else(|err| report_error("Lexing", &err, input, file, 1));
+    let (program, parse) = AllocStats::measure(|| Parser::from_spanned(tokens).parse_program());
+    if let Err(err) = program {
+        report_error("Parsing", &err, input, file, 1);
+    }
+
+    println!("{:<8}{:>12}{:>12}", "phase", "allocations", "bytes");
+    for (phase, stats) in [("lex", lex), ("parse", parse)] {
+        println!("{:<8}{:>12}{:>12}", phase, stats.allocations, stats.bytes);
+    }
+    process::exit(0);
+}
+
+#[cfg(not(feature = "dev-metrics"))]
+fn report_perf(_input: &str, _options: LexerOptions, _file: Option<&str>) -> ! {
+    eprintln!("--perf-report needs a build with `--features dev-metrics`");
+    process::exit(1);
+}
+
+/// Reads entries from stdin and prints the AST of each, or its error, until
+/// end of input. An entry continues over further lines while it is an
+/// incomplete program. On a terminal, entries are kept in the history file,
+/// and with the `line-editor` feature they can be edited and recalled.
+fn run_repl(history_file: Option<PathBuf>, config: &ValidateConfig) -> ! {
+    let terminal = io::stdin().is_terminal();
+    let history = match history_file.or_else(repl::default_history_path) {
+        Some(path) if terminal => History::open(&path).unwrap_or_else(|err| {
+            eprintln!("Error reading history '{}': {}", path.display(), err);
+            History::in_memory()
+        }),
+        _ => History::in_memory(),
+    };
+
+    #[cfg(all(feature = "line-editor", unix))]
+    if terminal {
+        use rdp::repl::editor::{Editor, Event};
+
+        let mut history = history;
+        let is_complete = |source: &str| !repl::is_incomplete(source);
+        let mut editor = Editor::new(history.entries().to_vec(), is_complete);
+        loop {
+            match repl::terminal::read_entry(&mut editor) {
+                Ok(Event::Submit(entry)) => {
+                    editor.remember(&entry);
+                    if let Err(err) = history.push(&entry) {
+                        eprintln!("Error writing history: {}", err);
+                    }
+                    evaluate_entry(&entry, config);
+                }
+                Ok(Event::Pending | Event::Cancelled) => {}
+                Ok(Event::Exit) => process::exit(0),
+                Err(err) => {
+                    eprintln!("Error reading input: {}", err);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+
+    let mut history = history;
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        let mut entry = String::new();
+        loop {
+            if terminal {
+                print!("{}", if entry.is_empty() { "> " } else { ". " });
+                let _ = io::stdout().flush();
+            }
+            match lines.next() {
+                Some(Ok(line)) => {
+                    if !entry.is_empty() {
+                        entry.push('\n');
+                    }
+                    entry.push_str(&line);
+                }
+                Some(Err(err)) => {
+                    eprintln!("Error reading input: {}", err);
+                    process::exit(1);
+                }
+                None if entry.trim().is_empty() => process::exit(0),
+                None => break,
+            }
+            if !repl::is_incomplete(&entry) {
+                break;
+            }
+        }
+        if terminal {
+            if let Err(err) = history.push(&entry) {
+                eprintln!("Error writing history: {}", err);
+            }
+        }
+        evaluate_entry(&entry, config);
+    }
+}
+
+/// Prints the REPL's reply to one entry.
+fn evaluate_entry(entry: &str, config: &ValidateConfig) {
+    let reply = repl::respond(entry, config);
+    for diagnostic in &reply.diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+    if let Some(output) = reply.output {
+        println!("{}", output);
+    }
+}
+
+/// Reads the file at `path`, exiting with `status` if it can't be read.
+fn read_file(path: &str, status: i32) -> String {
+    fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Error reading file '{}': {}", path, err);
+        process::exit(status);
+    })
+}
+
+/// Reads the file at `path` into `sources`, exiting if it can't be read.
+fn load(sources: &mut SourceMap, path: &Path) -> FileId {
+    sources.load(path).unwrap_or_else(|err| {
+        eprintln!("Error reading file '{}': {}", path.display(), err);
+        process::exit(1);
+    })
+}
+
+/// Adds `path` to `files` if it is a `.pfl` file, or every `.pfl` file below
+/// it if it is a directory, in name order.
+fn collect_pfl_files(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "pfl") {
+            collect_pfl_files(&entry, files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Sets the level of the lint named `code`, exiting if there is none.
+fn set_level(config: &mut ValidateConfig, code: &str, level: Level) {
+    if !LINT_CODES.contains(&code) {
+        eprintln!(
+            "Unknown lint '{}'; expected one of: {}",
+            code,
+            LINT_CODES.join(", ")
+        );
+        process::exit(1);
+    }
+    config.diagnostics.overrides.insert(code.to_string(), level);
+}
+
+/// Parses the value of a numeric option such as `--max-line-length=120`.
+fn parse_limit(flag: &str, value: &str) -> usize {
+    value.parse().unwrap_or_else(|_| {
+        eprintln!("Invalid value in '{}': expected a number", flag);
+        process::exit(1);
+    })
 }
@@ -0,0 +1,284 @@
+//! src/operators.rs
+
+/*******************************************************************************
+ *                             OPERATORS MODULE
+ *-------------------------------------------------------------------------------
+ * Metadata for the binary operators: surface text, precedence, and
+ * associativity. The parser drives its precedence climbing from this table,
+ * so tools that render or transform expressions can rely on it agreeing with
+ * what the parser actually does.
+ *
+ * Precedence levels, loosest first (higher binds tighter):
+ *
+ *   1. `.`                          composition, left-associative
+ *   2. `==` `!=` `<` `>` `<=` `>=`  comparison, non-associative
+ *   3. `||`                         logical or, left-associative
+ *   4. `&&`                         logical and, left-associative
+ *   5. `++`                         concatenation, left-associative
+ *   6. `+` `-`                      additive, left-associative
+ *   7. `*` `/` `%`                  multiplicative, left-associative
+ *   8. `^`                          power, right-associative
+ *   9. `::`                         cons, right-associative
+ *
+ * Prefix `!` and `-` bind tighter than any binary operator, `^` and `::`
+ * included, so `-2 ^ 2` is `(-2) ^ 2`; function application binds tighter
+ * still. A `-` is prefix only where an operand is
+ * expected, so `f -1` is still `f` minus 1.
+ ******************************************************************************/
+
+use crate::{
+    ArithmeticOperator, ComparisonOperator, CompositionOperator, Expression, FunctionComposition,
+    ListOperator, LogicOperator, ParseError, Token, UnaryOperator,
+};
+
+/// How a chain of operators with the same precedence groups.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Assoc {
+    /// `a op b op c` groups as `(a op b) op c`.
+    Left,
+    /// `a op b op c` groups as `a op (b op c)`.
+    Right,
+    /// `a op b op c` is not a single expression; the chain stops after `b`.
+    None,
+}
+
+/// Any binary operator of the language, unifying the per-kind operator enums.
+#[derive(Debug, PartialEq, Clone)]
+pub enum BinaryOperator {
+    Comparison(ComparisonOperator),
+    Logic(LogicOperator),
+    Arithmetic(ArithmeticOperator),
+    List(ListOperator),
+    Composition(CompositionOperator),
+}
+
+impl BinaryOperator {
+    /// Every binary operator, in the order of the table above.
+    pub fn all() -> [BinaryOperator; 17] {
+        [
+            BinaryOperator::Composition(CompositionOperator::Compose),
+            BinaryOperator::Comparison(ComparisonOperator::Equal),
+            BinaryOperator::Comparison(ComparisonOperator::NotEqual),
+            BinaryOperator::Comparison(ComparisonOperator::LessThan),
+            BinaryOperator::Comparison(ComparisonOperator::GreaterThan),
+            BinaryOperator::Comparison(ComparisonOperator::LessEqual),
+            BinaryOperator::Comparison(ComparisonOperator::GreaterEqual),
+            BinaryOperator::Logic(LogicOperator::And),
+            BinaryOperator::Logic(LogicOperator::Or),
+            BinaryOperator::List(ListOperator::Concat),
+            BinaryOperator::Arithmetic(ArithmeticOperator::Add),
+            BinaryOperator::Arithmetic(ArithmeticOperator::Subtract),
+            BinaryOperator::Arithmetic(ArithmeticOperator::Multiply),
+            BinaryOperator::Arithmetic(ArithmeticOperator::Divide),
+            BinaryOperator::Arithmetic(ArithmeticOperator::Modulo),
+            BinaryOperator::Arithmetic(ArithmeticOperator::Power),
+            BinaryOperator::List(ListOperator::Cons),
+        ]
+    }
+
+    /// The operator as written in source, e.g. `&&`.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Comparison(ComparisonOperator::Equal) => "==",
+            BinaryOperator::Comparison(ComparisonOperator::NotEqual) => "!=",
+            BinaryOperator::Comparison(ComparisonOperator::LessThan) => "<",
+            BinaryOperator::Comparison(ComparisonOperator::GreaterThan) => ">",
+            BinaryOperator::Comparison(ComparisonOperator::LessEqual) => "<=",
+            BinaryOperator::Comparison(ComparisonOperator::GreaterEqual) => ">=",
+            BinaryOperator::Logic(LogicOperator::And) => "&&",
+            BinaryOperator::Logic(LogicOperator::Or) => "||",
+            BinaryOperator::Arithmetic(ArithmeticOperator::Add) => "+",
+            BinaryOperator::Arithmetic(ArithmeticOperator::Subtract) => "-",
+            BinaryOperator::Arithmetic(ArithmeticOperator::Multiply) => "*",
+            BinaryOperator::Arithmetic(ArithmeticOperator::Divide) => "/",
+            BinaryOperator::Arithmetic(ArithmeticOperator::Modulo) => "%",
+            BinaryOperator::Arithmetic(ArithmeticOperator::Power) => "^",
+            BinaryOperator::List(ListOperator::Cons) => "::",
+            BinaryOperator::List(ListOperator::Concat) => "++",
+            BinaryOperator::Composition(CompositionOperator::Compose) => ".",
+        }
+    }
+
+    /// Binding strength; operators with a higher value bind tighter.
+    pub const fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Composition(_) => 1,
+            BinaryOperator::Comparison(_) => 2,
+            BinaryOperator::Logic(LogicOperator::Or) => 3,
+            BinaryOperator::Logic(LogicOperator::And) => 4,
+            BinaryOperator::List(ListOperator::Concat) => 5,
+            BinaryOperator::Arithmetic(ArithmeticOperator::Add | ArithmeticOperator::Subtract) => 6,
+            BinaryOperator::Arithmetic(
+                ArithmeticOperator::Multiply
+                | ArithmeticOperator::Divide
+                | ArithmeticOperator::Modulo,
+            ) => 7,
+            BinaryOperator::Arithmetic(ArithmeticOperator::Power) => 8,
+            BinaryOperator::List(ListOperator::Cons) => 9,
+        }
+    }
+
+    /// How a chain of operators at this precedence groups.
+    pub fn associativity(&self) -> Assoc {
+        match self {
+            BinaryOperator::Comparison(_) => Assoc::None,
+            BinaryOperator::Arithmetic(ArithmeticOperator::Power)
+            | BinaryOperator::List(ListOperator::Cons) => Assoc::Right,
+            BinaryOperator::Logic(_)
+            | BinaryOperator::Arithmetic(_)
+            | BinaryOperator::List(ListOperator::Concat)
+            | BinaryOperator::Composition(_) => Assoc::Left,
+        }
+    }
+
+    /// Builds the expression node for `left <op> right`.
+    pub fn node(self, left: Expression, right: Expression) -> Expression {
+        let (left, right) = (Box::new(left), Box::new(right));
+        match self {
+            BinaryOperator::Comparison(operator) => Expression::Comparison {
+                left,
+                operator,
+                right: Some(right),
+            },
+            BinaryOperator::Logic(operator) => Expression::Logic {
+                left,
+                operator,
+                right: Some(right),
+            },
+            BinaryOperator::Arithmetic(operator) => Expression::Arithmetic {
+                left,
+                operator,
+                right,
+            },
+            BinaryOperator::List(operator) => Expression::List {
+                left,
+                operator,
+                right,
+            },
+            BinaryOperator::Composition(CompositionOperator::Compose) => {
+                Expression::FunctionComposition(FunctionComposition { f: left, g: right })
+            }
+        }
+    }
+}
+
+impl TryFrom<&Token> for BinaryOperator {
+    type Error = ParseError;
+
+    fn try_from(token: &Token) -> Result<Self, Self::Error> {
+        match token {
+            Token::Equal => Ok(BinaryOperator::Comparison(ComparisonOperator::Equal)),
+            Token::NotEqual => Ok(BinaryOperator::Comparison(ComparisonOperator::NotEqual)),
+            Token::LessThan => Ok(BinaryOperator::Comparison(ComparisonOperator::LessThan)),
+            Token::GreaterThan => Ok(BinaryOperator::Comparison(ComparisonOperator::GreaterThan)),
+            Token::LessEqual => Ok(BinaryOperator::Comparison(ComparisonOperator::LessEqual)),
+            Token::GreaterEqual => Ok(BinaryOperator::Comparison(ComparisonOperator::GreaterEqual)),
+            Token::And => Ok(BinaryOperator::Logic(LogicOperator::And)),
+            Token::Or => Ok(BinaryOperator::Logic(LogicOperator::Or)),
+            Token::Plus => Ok(BinaryOperator::Arithmetic(ArithmeticOperator::Add)),
+            Token::Minus => Ok(BinaryOperator::Arithmetic(ArithmeticOperator::Subtract)),
+            Token::Star => Ok(BinaryOperator::Arithmetic(ArithmeticOperator::Multiply)),
+            Token::Slash => Ok(BinaryOperator::Arithmetic(ArithmeticOperator::Divide)),
+            Token::Percent => Ok(BinaryOperator::Arithmetic(ArithmeticOperator::Modulo)),
+            Token::Caret => Ok(BinaryOperator::Arithmetic(ArithmeticOperator::Power)),
+            Token::Cons => Ok(BinaryOperator::List(ListOperator::Cons)),
+            Token::PlusPlus => Ok(BinaryOperator::List(ListOperator::Concat)),
+            Token::Dot => Ok(BinaryOperator::Composition(CompositionOperator::Compose)),
+            _ => Err(ParseError::UnexpectedToken {
+                expected: "binary operator".to_string(),
+                found: token.to_string(),
+                message: "Expected a binary operator".to_string(),
+                position: None,
+            }),
+        }
+    }
+}
+
+impl Expression {
+    /// Splits a binary operation into its operator and operands. Returns `None`
+    /// for every other expression, including a comparison or logic node
+    /// without a right-hand side.
+    pub fn as_binary(&self) -> Option<(BinaryOperator, &Expression, &Expression)> {
+        match self {
+            Expression::Comparison {
+                left,
+                operator,
+                right: Some(right),
+            } => Some((BinaryOperator::Comparison(operator.clone()), left, right)),
+            Expression::Logic {
+                left,
+                operator,
+                right: Some(right),
+            } => Some((BinaryOperator::Logic(operator.clone()), left, right)),
+            Expression::Arithmetic {
+                left,
+                operator,
+                right,
+            } => Some((BinaryOperator::Arithmetic(operator.clone()), left, right)),
+            Expression::List {
+                left,
+                operator,
+                right,
+            } => Some((BinaryOperator::List(operator.clone()), left, right)),
+            Expression::FunctionComposition(FunctionComposition { f, g }) => Some((
+                BinaryOperator::Composition(CompositionOperator::Compose),
+                f,
+                g,
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl UnaryOperator {
+    /// The operator as written in source.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            UnaryOperator::Not => "!",
+            UnaryOperator::Negate => "-",
+        }
+    }
+}
+
+/*******************************************************************************
+ *                          PER-KIND OPERATOR METADATA
+ *-------------------------------------------------------------------------------
+ * Convenience accessors on each operator enum, delegating to the table above.
+ ******************************************************************************/
+
+macro_rules! delegate_metadata {
+    ($($operator:ident => $variant:ident),* $(,)?) => {
+        $(
+            impl $operator {
+                /// The operator as written in source.
+                pub fn symbol(&self) -> &'static str {
+                    BinaryOperator::$variant(self.clone()).symbol()
+                }
+
+                /// Binding strength; operators with a higher value bind tighter.
+                pub fn precedence(&self) -> u8 {
+                    BinaryOperator::$variant(self.clone()).precedence()
+                }
+
+                /// How a chain of operators at this precedence groups.
+                pub fn associativity(&self) -> Assoc {
+                    BinaryOperator::$variant(self.clone()).associativity()
+                }
+            }
+
+            impl From<$operator> for BinaryOperator {
+                fn from(operator: $operator) -> Self {
+                    BinaryOperator::$variant(operator)
+                }
+            }
+        )*
+    };
+}
+
+delegate_metadata! {
+    ComparisonOperator => Comparison,
+    LogicOperator => Logic,
+    ArithmeticOperator => Arithmetic,
+    ListOperator => List,
+    CompositionOperator => Composition,
+}
@@ -5,8 +5,8 @@
  *-------------------------------------------------------------------------------
  * This parser handles a functional language grammar, producing an AST for use
  * in interpretation or code generation. It follows a top-down approach,
- * mapping each EBNF rule to a dedicated function, and respects operator
- * precedence through the chaining of parse_* methods.
+ * mapping each EBNF rule to a dedicated function. Binary operators are parsed
+ * by precedence climbing over the table in `operators.rs`.
  *
  * Key grammar constructs:
  *   - Let, If, Lambda, and Match expressions
@@ -20,29 +20,130 @@
  ******************************************************************************/
 
 use crate::{
-    ArithmeticOperator, ComparisonOperator, Expression, FunctionComposition, LogicOperator,
-    MatchArm, ParseError, Pattern, Program, Term, Token, TypeAnnotation,
+    index::child_expressions, Assoc, BinaryOperator, CompositionOperator, Expression, LetBinding,
+    MatchArm, ParseError, Pattern, Position, Program, Span, SpannedToken, Term, Token,
+    TypeAnnotation, UnaryOperator,
 };
 
+/// Precedence of `.`, the loosest binary operator.
+const COMPOSITION_PRECEDENCE: u8 =
+    BinaryOperator::Composition(CompositionOperator::Compose).precedence();
+
+/// The tokens an expression was built from, `start..end`. An expression that
+/// is sugar, like the inner lambda of `\x y -> e`, has no source text of its
+/// own, only that of the construct it came from.
+#[derive(Debug, Clone, Copy)]
+struct Built {
+    start: usize,
+    end: usize,
+    sugar: bool,
+}
+
+/// A `with x = e` binding of a match arm, before it is desugared into a
+/// `let` around the arm's body.
+struct ArmBinding {
+    identifier: String,
+    type_annotation: Option<TypeAnnotation>,
+    value: Expression,
+}
+
 /*******************************************************************************
  *                              PARSER STRUCT
  *-------------------------------------------------------------------------------
  * `Parser` operates on a token list and a cursor indicating the current token
  * under consideration. The parser steps through the tokens, building the AST
  * if the stream conforms to the grammar, or returning a `ParseError` otherwise.
+ *
+ * Nesting is limited, so hostile input can't exhaust the stack of the parser
+ * or of anything that walks the tree afterwards. Every rule that can recurse
+ * counts one level. A chain like `1 + 2 + 3` nests the tree to the left
+ * without recursing, so its operators only count against a separate, larger
+ * budget for the chains enclosing a point.
+ *
+ * Every expression built is recorded with the tokens it came from, in the
+ * order the expressions are completed: children before their parent, left to
+ * right. That is a post-order walk of the finished tree, so
+ * `parse_program_spanned` can hand each `NodeId` its span. A pipeline, which
+ * puts its function before its argument in the tree, reorders the records
+ * to match.
  ******************************************************************************/
 pub struct Parser {
     tokens: Vec<Token>,
+    /// Where each token starts, if the tokens came with positions.
+    positions: Vec<Position>,
+    /// The characters each token covers, if the tokens came with positions.
+    spans: Vec<Span>,
+    /// The tokens of every expression built so far, in post-order.
+    built: Vec<Built>,
     current: usize,
+    depth: usize,
+    max_depth: usize,
+    /// Operators of the chains enclosing the current point.
+    chain: usize,
+    consumed: usize,
+    /// A contextual keyword that ends the expression being parsed instead of
+    /// being read as an argument, like `and` after a let binding's value.
+    stop_word: Option<&'static str>,
 }
 
 impl Parser {
     //--------------------------------------------------------------------------
     // CONSTRUCTOR
     //--------------------------------------------------------------------------
-    /// Creates a new parser given a list of tokens.
-    pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+    /// Creates a new parser given a list of tokens. Whitespace and comment
+    /// tokens, as kept by [`crate::Lexer::tokenize_with_trivia`], are dropped.
+    pub fn new(mut tokens: Vec<Token>) -> Self {
+        tokens.retain(|token| !token.is_trivia());
+        Self {
+            tokens,
+            positions: Vec::new(),
+            spans: Vec::new(),
+            built: Vec::new(),
+            current: 0,
+            depth: 0,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            chain: 0,
+            consumed: 0,
+            stop_word: None,
+        }
+    }
+
+    /// Creates a parser for tokens with positions, as produced by
+    /// [`crate::Lexer::tokenize_spanned`], so errors say where they are.
+    pub fn from_spanned(tokens: Vec<SpannedToken>) -> Self {
+        let (tokens, (positions, spans)) = tokens
+            .into_iter()
+            .filter(|spanned| !spanned.token.is_trivia())
+            .map(|spanned| (spanned.token, (spanned.position, spanned.span)))
+            .unzip();
+        Self {
+            positions,
+            spans,
+            ..Self::new(tokens)
+        }
+    }
+
+    /// How many tokens the parser has consumed so far, counting a token again
+    /// each time it is re-read after backtracking. Parsing does work in
+    /// proportion to this count, so it measures cost independently of the
+    /// machine.
+    pub fn tokens_consumed(&self) -> usize {
+        self.consumed
+    }
+
+    /// The nesting limit of a new parser.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+    /// How many more operators than nesting levels the chains enclosing a
+    /// point may hold, as in `1 + 2 + ... + 1000`.
+    pub const CHAIN_FACTOR: usize = 8;
+
+    /// Sets how deep expressions, patterns, and types may nest before parsing
+    /// fails with `ParseError::NestingTooDeep`. Operator chains may hold
+    /// [`Parser::CHAIN_FACTOR`] times as many operators.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
     }
 
     //--------------------------------------------------------------------------
@@ -50,13 +151,76 @@ impl Parser {
     //--------------------------------------------------------------------------
     ///
     /// Parses the entire token stream as a single `Program`. Our grammar defines
-    /// a program to be just one top-level expression.
+    /// a program to be just one top-level expression, optionally terminated
+    /// by `;;`.
     ///
     /// # Errors
-    /// Returns a `ParseError` if the tokens do not form a valid expression.
+    /// Returns a `ParseError` if the tokens do not form a valid expression, or
+    /// if tokens are left over after it.
     pub fn parse_program(&mut self) -> Result<Program, ParseError> {
         let expression = self.parse_expression()?;
-        Ok(Program { expression })
+        self.match_token(Token::DoubleSemicolon);
+
+        if let Some(token) = self.current_token().filter(|token| **token != Token::Eof) {
+            return Err(ParseError::UnexpectedToken {
+                expected: "end of input".to_string(),
+                found: token.to_string(),
+                message: "Unexpected token after the end of the program".to_string(),
+                position: self.position(),
+            });
+        }
+
+        let program = Program { expression };
+        self.check_invariants();
+        #[cfg(debug_assertions)]
+        program.check_invariants();
+        if cfg!(debug_assertions) {
+            assert_eq!(
+                size(&program.expression),
+                self.built.len(),
+                "parser invariant violated: expressions built and recorded differ"
+            );
+        }
+        Ok(program)
+    }
+
+    /// Like [`Parser::parse_program`], but also returns the characters each
+    /// expression covers, indexed by its `NodeId` in
+    /// [`Program::build_index`]. An expression that is sugar for a construct
+    /// of the source, like the inner lambda of `\x y -> e`, has no span of
+    /// its own; nor does any expression if the tokens came without positions.
+    /// The application written `x |> f` spans all of that.
+    ///
+    /// # Errors
+    /// Returns a `ParseError` under the same conditions as `parse_program`.
+    pub fn parse_program_spanned(&mut self) -> Result<(Program, Vec<Option<Span>>), ParseError> {
+        let program = self.parse_program()?;
+        let mut spans = Vec::with_capacity(self.built.len());
+        let mut built = self.built.iter();
+        self.assign_spans(&program.expression, &mut built, &mut spans);
+        Ok((program, spans))
+    }
+
+    /// Pushes the span of `expression` and of every expression under it onto
+    /// `spans`, in pre-order, taking their records from `built` in post-order.
+    fn assign_spans<'a>(
+        &self,
+        expression: &Expression,
+        built: &mut impl Iterator<Item = &'a Built>,
+        spans: &mut Vec<Option<Span>>,
+    ) {
+        let id = spans.len();
+        spans.push(None);
+        for (_, child) in child_expressions(expression) {
+            self.assign_spans(child, built, spans);
+        }
+        spans[id] = built.next().filter(|built| !built.sugar).and_then(|built| {
+            let (first, last) = (self.spans.get(built.start)?, self.spans.get(built.end - 1)?);
+            Some(Span {
+                start: first.start,
+                end: last.end,
+            })
+        });
     }
 
     //--------------------------------------------------------------------------
@@ -71,21 +235,20 @@ impl Parser {
     ///   * comparison (with composition attached)
     ///
     /// After parsing a comparison, it calls `parse_composition` to handle
-    /// function composition (.) at precedence level 6.
+    /// function composition (.) at precedence level 6, and `parse_pipeline`
+    /// for any `|>` after that.
     ///
     fn parse_expression(&mut self) -> Result<Expression, ParseError> {
-        match self.current_token() {
-            Some(Token::Let) => self.parse_let_expr(),
-            Some(Token::If) => self.parse_if_expr(),
-            Some(Token::Lambda) => self.parse_lambda(),
-            Some(Token::Match) => self.parse_pattern_match(),
+        self.nested(|parser| match parser.current_token() {
+            Some(Token::Let) => parser.parse_let_expr(),
+            Some(Token::If) => parser.parse_if_expr(),
+            Some(Token::Lambda) => parser.parse_lambda(),
+            Some(Token::Match) => parser.parse_pattern_match(),
             _ => {
-                // Compare first
-                let expr = self.parse_comparison()?;
-                // Then apply composition
-                self.parse_composition(expr)
+                let left = parser.parse_binary(COMPOSITION_PRECEDENCE)?;
+                parser.parse_pipeline(left)
             }
-        }
+        })
     }
 
     //--------------------------------------------------------------------------
@@ -97,14 +260,14 @@ impl Parser {
     /// member access rather than composition.
     ///
     fn parse_expression_no_composition(&mut self) -> Result<Expression, ParseError> {
-        match self.current_token() {
-            Some(Token::Let) => self.parse_let_expr(),
-            Some(Token::If) => self.parse_if_expr(),
-            Some(Token::Lambda) => self.parse_lambda(),
-            Some(Token::Match) => self.parse_pattern_match(),
+        self.nested(|parser| match parser.current_token() {
+            Some(Token::Let) => parser.parse_let_expr(),
+            Some(Token::If) => parser.parse_if_expr(),
+            Some(Token::Lambda) => parser.parse_lambda(),
+            Some(Token::Match) => parser.parse_pattern_match(),
             // stops at comparison
-            _ => self.parse_comparison(),
-        }
+            _ => parser.parse_binary(COMPOSITION_PRECEDENCE + 1),
+        })
     }
 
     //--------------------------------------------------------------------------
@@ -112,11 +275,64 @@ impl Parser {
     //--------------------------------------------------------------------------
     ///
     /// Grammar snippet:
-    ///   let_expr = "let" identifier [ ":" type_annotation ] "=" expression "in" expression
+    ///   let_expr    = "let" [ "rec" ] let_binding { "and" let_binding } "in" expression
+    ///   let_binding = identifier [ ":" type_annotation ] "=" expression
+    ///
+    /// `rec` is contextual: it is the keyword only when a name follows it, so
+    /// `let rec = 5 in rec` still binds `rec`. The keyword sets `recursive`
+    /// on the node. A single binding is a `LetExpr`; several joined by `and`
+    /// are one `LetAnd`, in source order. `and` is contextual too: it ends a
+    /// binding's value, but is an ordinary name anywhere else.
     ///
     fn parse_let_expr(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current;
         self.consume_token(Token::Let, "Expected 'let'")?;
+        let recursive = match self.peek_next_token() {
+            Some(Token::Identifier(_)) => self.eat_contextual("rec"),
+            Some(Token::Colon | Token::Assign) | None => false,
+            Some(token) if self.check_contextual("rec") => {
+                return Err(ParseError::UnexpectedToken {
+                    expected: "identifier".to_string(),
+                    found: token.to_string(),
+                    message: "Expected the name to bind after 'let rec'".to_string(),
+                    position: self.position_at(self.current + 1),
+                });
+            }
+            Some(_) => false,
+        };
+
+        let mut bindings = vec![self.parse_let_binding()?];
+        while self.eat_contextual("and") {
+            bindings.push(self.parse_let_binding()?);
+        }
+
+        self.consume_token(Token::In, "Expected 'in' in let expression")?;
+        let body = Box::new(self.parse_expression()?);
+        self.record(start);
 
+        if bindings.len() > 1 {
+            return Ok(Expression::LetAnd {
+                recursive,
+                bindings,
+                body,
+            });
+        }
+        let LetBinding {
+            identifier,
+            type_annotation,
+            value,
+        } = bindings.pop().unwrap();
+        Ok(Expression::LetExpr {
+            recursive,
+            identifier,
+            type_annotation,
+            value,
+            body,
+        })
+    }
+
+    /// Parses one `name [: type] = value` binding of a `let`.
+    fn parse_let_binding(&mut self) -> Result<LetBinding, ParseError> {
         let identifier = self.parse_identifier()?;
         let type_annotation = if self.match_token(Token::Colon) {
             Some(self.parse_type_annotation()?)
@@ -125,16 +341,11 @@ impl Parser {
         };
 
         self.consume_token(Token::Assign, "Expected '=' in let expression")?;
-        let value = self.parse_expression()?;
-
-        self.consume_token(Token::In, "Expected 'in' in let expression")?;
-        let body = self.parse_expression()?;
-
-        Ok(Expression::LetExpr {
+        let value = self.ending_at(Some("and"), Self::parse_expression)?;
+        Ok(LetBinding {
             identifier,
             type_annotation,
             value: Box::new(value),
-            body: Box::new(body),
         })
     }
 
@@ -145,6 +356,7 @@ impl Parser {
     /// if_expr = "if" expression "then" expression "else" expression
     ///
     fn parse_if_expr(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current;
         self.consume_token(Token::If, "Expected 'if'")?;
         let condition = self.parse_expression()?;
 
@@ -153,6 +365,7 @@ impl Parser {
 
         self.consume_token(Token::Else, "Expected 'else' after then branch")?;
         let else_branch = self.parse_expression()?;
+        self.record(start);
 
         Ok(Expression::IfExpr {
             condition: Box::new(condition),
@@ -165,37 +378,107 @@ impl Parser {
     // LAMBDA
     //--------------------------------------------------------------------------
     ///
-    /// lambda = "\" identifier [ ":" type_annotation ] "->" expression
+    /// lambda    = "\" identifier ":" type_annotation "->" expression
+    ///           | "\" parameter { parameter } "->" expression
+    /// parameter = identifier | "(" identifier [ ":" type_annotation ] ")"
+    ///
+    /// Several parameters are sugar for nested lambdas: `\x y -> e` produces
+    /// the same AST as `\x -> \y -> e`. A lone parameter may be annotated
+    /// bare, but among several an annotated one must be parenthesized, so
+    /// `\x: Int y -> e` is an error; `\(x: Int) y -> e` is meant.
     ///
     fn parse_lambda(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current;
         self.consume_token(Token::Lambda, "Expected '\\' for lambda")?;
-        let parameter = self.parse_identifier()?;
+        let outer_depth = self.depth;
+        let mut parameters = vec![self.parse_lambda_parameter()?];
+
+        if parameters[0].1.is_none() && self.match_token(Token::Colon) {
+            // The arrow after a bare annotation starts the body, so a
+            // function type here needs parentheses.
+            parameters[0].1 = Some(self.parse_type_operand()?);
+            if self.starts_lambda_parameter() {
+                return Err(self.bare_annotation_error());
+            }
+        }
+        while self.starts_lambda_parameter() {
+            self.enter()?;
+            parameters.push(self.parse_lambda_parameter()?);
+        }
+        if parameters.len() > 1 && self.current_token() == Some(&Token::Colon) {
+            return Err(self.bare_annotation_error());
+        }
+
+        self.consume_token(Token::Arrow, "Expected '->' in lambda")?;
+        let body = self.parse_expression()?;
+        self.depth = outer_depth;
+        for _ in 1..parameters.len() {
+            self.record_sugar(start);
+        }
+        self.record(start);
 
+        Ok(parameters
+            .into_iter()
+            .rev()
+            .fold(body, |body, (parameter, type_annotation)| {
+                Expression::Lambda {
+                    parameter,
+                    type_annotation,
+                    body: Box::new(body),
+                }
+            }))
+    }
+
+    /// Parses one parameter of a lambda, annotated only if parenthesized.
+    fn parse_lambda_parameter(&mut self) -> Result<(String, Option<TypeAnnotation>), ParseError> {
+        if !self.match_token(Token::LeftParen) {
+            return Ok((self.parse_identifier()?, None));
+        }
+        let parameter = self.parse_identifier()?;
         let type_annotation = if self.match_token(Token::Colon) {
             Some(self.parse_type_annotation()?)
         } else {
             None
         };
+        self.consume_token(Token::RightParen, "Expected ')' after lambda parameter")?;
+        Ok((parameter, type_annotation))
+    }
 
-        self.consume_token(Token::Arrow, "Expected '->' in lambda")?;
-        let body = self.parse_expression()?;
+    /// Whether another lambda parameter follows.
+    fn starts_lambda_parameter(&self) -> bool {
+        matches!(
+            self.current_token(),
+            Some(Token::Identifier(_) | Token::LeftParen)
+        )
+    }
 
-        Ok(Expression::Lambda {
-            parameter,
-            type_annotation,
-            body: Box::new(body),
-        })
+    /// The error for a bare annotation on a lambda with several parameters.
+    fn bare_annotation_error(&self) -> ParseError {
+        ParseError::UnexpectedToken {
+            expected: "->".to_string(),
+            found: self.current_token().unwrap_or(&Token::Eof).to_string(),
+            message: "Parenthesize annotated parameters of a lambda with several, \
+                      as in '\\(x: Int) y -> ...'"
+                .to_string(),
+            position: self.position(),
+        }
     }
 
     //--------------------------------------------------------------------------
     // PATTERN MATCH
     //--------------------------------------------------------------------------
     ///
-    /// pattern_match = "match" expression "with"
-    ///                 "|" pattern "->" expression
-    ///                 { "|" pattern "->" expression }
+    /// pattern_match = "match" expression "with" match_arm { match_arm }
+    /// match_arm     = "|" pattern [ guard ] { arm_binding } "->" expression
+    /// guard         = "when" expression
+    /// arm_binding   = "with" identifier [ ":" type_annotation ] "=" expression
+    ///
+    /// Arm bindings are sugar: `| p with x = e -> body` produces the same AST
+    /// as `| p -> let x = e in body`. A guard comes before them, since it only
+    /// sees the pattern's variables.
     ///
     fn parse_pattern_match(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current;
         self.consume_token(Token::Match, "Expected 'match'")?;
         let expression = self.parse_expression()?;
 
@@ -203,11 +486,30 @@ impl Parser {
         let mut arms = Vec::new();
 
         while self.match_token(Token::Pipe) {
-            let pattern = self.parse_pattern()?;
-            self.consume_token(Token::Arrow, "Expected '->' in match arm")?;
-            let arm_expression = self.parse_expression()?;
+            let (pattern, guard, bindings) = self.parse_arm_pattern()?;
+            // Each binding becomes a `let` around the body.
+            for _ in &bindings {
+                self.enter()?;
+            }
+            let body = self.parse_expression()?;
+            self.depth -= bindings.len();
+            for _ in &bindings {
+                self.record_sugar(start);
+            }
+            let arm_expression =
+                bindings
+                    .into_iter()
+                    .rev()
+                    .fold(body, |body, binding| Expression::LetExpr {
+                        recursive: false,
+                        identifier: binding.identifier,
+                        type_annotation: binding.type_annotation,
+                        value: Box::new(binding.value),
+                        body: Box::new(body),
+                    });
             arms.push(MatchArm {
                 pattern,
+                guard: guard.map(Box::new),
                 expression: Box::new(arm_expression),
             });
         }
@@ -215,111 +517,163 @@ impl Parser {
         if arms.is_empty() {
             return Err(ParseError::MissingPatternMatchArm);
         }
+        self.record(start);
 
-        Ok(Expression::PatternMatch {
+        let expression = Expression::PatternMatch {
             expression: Box::new(expression),
             arms,
-        })
+        };
+        self.check_invariants();
+        check_node(&expression);
+        Ok(expression)
     }
 
     //--------------------------------------------------------------------------
-    // COMPARISON
+    // BINARY OPERATORS
     //--------------------------------------------------------------------------
     ///
-    /// comparison = logic [ ( "==" | "<" | ">" ) logic ]
+    /// composition = comparison { "." comparison }
+    /// comparison  = logic [ ( "==" | "<" | ">" ) logic ]
+    /// logic       = arithmetic { ( "&&" | "||" ) arithmetic }
+    /// arithmetic  = product { ( "+" | "-" ) product }
+    /// product     = power { ( "*" | "/" | "%" ) power }
+    /// power       = unary [ "^" power ]
+    ///
+    /// Parsed by precedence climbing over the operator table in
+    /// `operators.rs`: only operators binding at least as tightly as
+    /// `min_precedence` are consumed. A non-associative operator closes its
+    /// level, so another at that level, as in `a < b < c`, is an error that
+    /// names both.
     ///
-    fn parse_comparison(&mut self) -> Result<Expression, ParseError> {
-        let left = self.parse_logic()?;
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expression, ParseError> {
+        let start = self.current;
+        let mut left = self.parse_unary()?;
+        let mut closed: Option<BinaryOperator> = None;
+        let outer_chain = self.chain;
 
-        if let Some(operator) = match self.current_token() {
-            Some(Token::Equal) => Some(ComparisonOperator::Equal),
-            Some(Token::LessThan) => Some(ComparisonOperator::LessThan),
-            Some(Token::GreaterThan) => Some(ComparisonOperator::GreaterThan),
-            _ => None,
-        } {
-            // consume operator
+        while let Some(operator) = self
+            .current_token()
+            .and_then(|token| BinaryOperator::try_from(token).ok())
+        {
+            let precedence = operator.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+            if let Some(first) = closed
+                .as_ref()
+                .filter(|first| first.precedence() == precedence)
+            {
+                return Err(ParseError::ChainedComparison {
+                    first: first.symbol().to_string(),
+                    second: operator.symbol().to_string(),
+                    position: self.position(),
+                });
+            }
             self.advance();
-            let right = self.parse_logic()?;
-            Ok(Expression::Comparison {
-                left: Box::new(left),
-                operator,
-                right: Some(Box::new(right)),
-            })
-        } else {
-            Ok(left)
+
+            let right = match operator.associativity() {
+                Assoc::Right => self.nested(|parser| parser.parse_binary(precedence))?,
+                Assoc::Left | Assoc::None => {
+                    self.extend_chain()?;
+                    self.parse_binary(precedence + 1)?
+                }
+            };
+            if operator.associativity() == Assoc::None {
+                closed = Some(operator.clone());
+            }
+            left = operator.node(left, right);
+            self.record(start);
         }
+        self.chain = outer_chain;
+        Ok(left)
     }
 
     //--------------------------------------------------------------------------
     // COMPOSITION
     //--------------------------------------------------------------------------
     ///
-    /// After comparison, we parse function composition (.) repeatedly, left-associative.
+    /// Continues `left` with any `. comparison` that follows, left-associative.
+    /// Used after `parse_expression_no_composition` inside parentheses, once
+    /// the dot turned out not to be a member access.
     ///
     fn parse_composition(&mut self, mut left: Expression) -> Result<Expression, ParseError> {
-        while let Some(Token::Dot) = self.current_token() {
-            self.advance();
-            let right = self.parse_comparison()?;
-            left = Expression::FunctionComposition(FunctionComposition {
-                f: Box::new(left),
-                g: Box::new(right),
-            });
+        let compose = BinaryOperator::Composition(CompositionOperator::Compose);
+        let start = self.last_start();
+        let outer_chain = self.chain;
+        while self.match_token(Token::Dot) {
+            self.extend_chain()?;
+            let right = self.parse_binary(COMPOSITION_PRECEDENCE + 1)?;
+            left = compose.clone().node(left, right);
+            self.record(start);
         }
+        self.chain = outer_chain;
         Ok(left)
     }
 
     //--------------------------------------------------------------------------
-    // LOGIC
+    // PIPELINE
     //--------------------------------------------------------------------------
     ///
-    /// logic = arithmetic [ ( "&&" | "||" ) arithmetic ]
+    /// pipeline = comparison { "|>" comparison }
     ///
-    fn parse_logic(&mut self) -> Result<Expression, ParseError> {
-        let mut left = self.parse_arithmetic()?;
-
-        while let Some(token) = self.current_token() {
-            let operator = match token {
-                Token::And => LogicOperator::And,
-                Token::Or => LogicOperator::Or,
-                _ => break,
-            };
-            self.advance();
-
-            let right = self.parse_arithmetic()?;
-            left = Expression::Logic {
-                left: Box::new(left),
-                operator,
-                right: Some(Box::new(right)),
+    /// Continues `left` with any `|> function` that follows, left-associative
+    /// and looser than every binary operator. A pipeline is sugar and leaves
+    /// no trace in the AST: `x |> f` is the application `f x`, and `x |> f a`
+    /// passes `x` as the last argument, `f a x`.
+    ///
+    fn parse_pipeline(&mut self, mut left: Expression) -> Result<Expression, ParseError> {
+        let start = self.last_start();
+        let outer_chain = self.chain;
+        while self.match_token(Token::PipeForward) {
+            self.extend_chain()?;
+            let function = self.parse_binary(COMPOSITION_PRECEDENCE)?;
+            // The argument was built first but goes last, so its records
+            // move after the function's.
+            let (argument, function_size) = (size(&left), size(&function));
+            left = match function {
+                Expression::Application(mut items) => {
+                    self.built.pop();
+                    self.move_before(function_size - 1, argument);
+                    items.push(left);
+                    Expression::Application(items)
+                }
+                function => {
+                    self.move_before(function_size, argument);
+                    Expression::Application(vec![function, left])
+                }
             };
+            self.record(start);
         }
+        self.chain = outer_chain;
         Ok(left)
     }
 
     //--------------------------------------------------------------------------
-    // ARITHMETIC
+    // UNARY
     //--------------------------------------------------------------------------
     ///
-    /// arithmetic = application { ( "+" | "-" | "*" | "/" ) application }
+    /// unary = ( "!" | "-" ) unary | application
     ///
-    fn parse_arithmetic(&mut self) -> Result<Expression, ParseError> {
-        let mut left = self.parse_application()?;
-
-        while let Some(operator) = match self.current_token() {
-            Some(Token::Plus) => Some(ArithmeticOperator::Add),
-            Some(Token::Minus) => Some(ArithmeticOperator::Subtract),
-            Some(Token::Star) => Some(ArithmeticOperator::Multiply),
-            Some(Token::Slash) => Some(ArithmeticOperator::Divide),
-            _ => None,
-        } {
-            self.advance();
-            let right = self.parse_application()?;
-            left = Expression::Arithmetic {
-                left: Box::new(left),
-                operator,
-                right: Box::new(right),
-            };
-        }
-        Ok(left)
+    /// A prefix `!` or `-` binds looser than application and tighter than
+    /// every binary operator, so `!f x` negates `f x` and `-x * 2` is
+    /// `(-x) * 2`. A `-` is only prefix where an operand is expected: after a
+    /// term it is subtraction, so `f -1` is `f - 1` and `f (-1)` applies `f`.
+    ///
+    fn parse_unary(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current;
+        let operator = if self.match_token(Token::Bang) {
+            UnaryOperator::Not
+        } else if self.match_token(Token::Minus) {
+            UnaryOperator::Negate
+        } else {
+            return self.parse_application();
+        };
+        let operand = self.nested(Self::parse_unary)?;
+        self.record(start);
+        Ok(Expression::Unary {
+            operator,
+            operand: Box::new(operand),
+        })
     }
 
     //--------------------------------------------------------------------------
@@ -332,13 +686,19 @@ impl Parser {
     /// preceding expression, forming an `Application` node if multiple are present.
     ///
     fn parse_application(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current;
         let mut expressions = vec![self.parse_term()?];
 
         while let Some(token) = self.current_token() {
             match token {
+                Token::Identifier(name) if Some(name.as_str()) == self.stop_word => break,
                 Token::Identifier(_)
                 | Token::Number(_)
+                | Token::String(_)
+                | Token::True
+                | Token::False
                 | Token::LeftParen
+                | Token::LeftBracket
                 | Token::Wildcard
                 | Token::Lambda => {
                     let arg = self.parse_term()?;
@@ -348,11 +708,15 @@ impl Parser {
             }
         }
 
-        if expressions.len() > 1 {
-            Ok(Expression::Application(expressions))
+        let expression = if expressions.len() > 1 {
+            self.record(start);
+            Expression::Application(expressions)
         } else {
-            Ok(expressions.pop().unwrap())
-        }
+            expressions.pop().unwrap()
+        };
+        self.check_invariants();
+        check_node(&expression);
+        Ok(expression)
     }
 
     //--------------------------------------------------------------------------
@@ -361,97 +725,309 @@ impl Parser {
     ///
     /// term = identifier
     ///      | number
+    ///      | "true" | "false"
     ///      | "(" expression ")"
+    ///      | "(" expression "," expression { "," expression } [ "," ] ")"
     ///      | "(" expression "." identifier ")"
+    ///      | "[" [ expression { "," expression } [ "," ] ] "]"
+    ///
+    /// A single parenthesized expression is a `GroupedExpression`; with
+    /// commas it is a `Tuple`. Both tuples and lists allow one trailing comma,
+    /// as in `(a, b,)`.
     ///
-    /// This function also integrates logic for optionally parsing a **member access**
-    /// of the form `( expr . ident )` by first parsing an expression *without composition*,
-    /// then looking ahead for `. identifier )`. If not found, it’s just a grouped expression.
+    /// Parenthesized terms go to `parse_parenthesized`, which also handles a
+    /// **member access** of the form `( expr . ident )` by first parsing an
+    /// expression *without composition*, then looking ahead for
+    /// `. identifier )`. If not found, it’s just a grouped expression.
     ///
     fn parse_term(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current;
         match self.current_token() {
             // Identifiers
             Some(Token::Identifier(name)) => {
                 let name_clone = name.clone();
                 self.advance();
+                self.record(start);
                 Ok(Expression::Term(Term::Identifier(name_clone)))
             }
             // Numbers
             Some(Token::Number(value)) => {
                 let val = *value;
                 self.advance();
+                self.record(start);
                 Ok(Expression::Term(Term::Number(val)))
             }
-            // Parentheses, possibly member access
-            Some(Token::LeftParen) => {
-                // consume '('
+            // Strings
+            Some(Token::String(text)) => {
+                let text = text.clone();
                 self.advance();
-                let expr = self.parse_expression_no_composition()?;
-
-                // Look for `( expr . identifier )`
-                if self.current_token() == Some(&Token::Dot) {
-                    if let Some(Token::Identifier(_)) = self.peek_next_token() {
-                        if self.peek_two_tokens_ahead() == Some(&Token::RightParen) {
-                            // parse member access
-                            // consume '.'
-                            self.advance();
-                            let member_name = match self.current_token() {
-                                Some(Token::Identifier(s)) => {
-                                    let temp = s.clone();
-                                    self.advance();
-                                    temp
-                                }
-                                Some(t) => {
-                                    return Err(ParseError::UnexpectedToken {
-                                        expected: "identifier".into(),
-                                        found: format!("{:?}", t),
-                                        message: "Expected identifier after '.' in member access"
-                                            .into(),
-                                    });
-                                }
-                                None => return Err(ParseError::UnexpectedEOF),
-                            };
-
-                            self.consume_token(
-                                Token::RightParen,
-                                "Expected ')' after member access",
-                            )?;
-
-                            return Ok(Expression::Term(Term::MemberAccess {
-                                expression: Box::new(expr),
-                                member: member_name,
-                            }));
-                        }
-                    }
-                }
-
-                // Otherwise, it’s a grouped expression: ( expr )
-                self.consume_token(Token::RightParen, "Expected ')' after expression")?;
-                Ok(Expression::Term(Term::GroupedExpression(Box::new(expr))))
+                self.record(start);
+                Ok(Expression::Term(Term::String(text)))
             }
+            // Booleans
+            Some(Token::True) | Some(Token::False) => {
+                let value = self.current_token() == Some(&Token::True);
+                self.advance();
+                self.record(start);
+                Ok(Expression::Term(Term::Boolean(value)))
+            }
+            // Parentheses, possibly member access
+            Some(Token::LeftParen) => self.ending_at(None, Self::parse_parenthesized),
+            // List literals
+            Some(Token::LeftBracket) => self.ending_at(None, Self::parse_list),
             // Lambda can appear as a term
             Some(Token::Lambda) => self.parse_lambda(),
 
             // Wildcard as a special identifier
             Some(Token::Wildcard) => {
                 self.advance();
+                self.record(start);
                 Ok(Expression::Term(Term::Identifier("_".into())))
             }
 
             // Otherwise, error
             Some(t) => Err(ParseError::UnexpectedToken {
                 expected: "term".to_string(),
-                found: format!("{:?}", t),
-                message: "Unexpected token while parsing a term.".into(),
+                found: t.to_string(),
+                message: "Unexpected token while parsing a term".into(),
+                position: self.position(),
             }),
             None => Err(ParseError::UnexpectedEOF),
         }
     }
 
+    /// Parses a term in parentheses: a grouped expression, a tuple, or a
+    /// member access. A contextual keyword ending the enclosing expression is
+    /// an ordinary name in here.
+    fn parse_parenthesized(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current;
+        // consume '('
+        self.advance();
+        let expr = self.parse_expression_no_composition()?;
+
+        // Look for `( expr . identifier )`
+        if self.current_token() == Some(&Token::Dot) {
+            if let Some(Token::Identifier(_)) = self.peek_next_token() {
+                if self.peek_two_tokens_ahead() == Some(&Token::RightParen) {
+                    // parse member access
+                    // consume '.'
+                    self.advance();
+                    let member_name = match self.current_token() {
+                        Some(Token::Identifier(s)) => {
+                            let temp = s.clone();
+                            self.advance();
+                            temp
+                        }
+                        Some(t) => {
+                            return Err(ParseError::UnexpectedToken {
+                                expected: "identifier".into(),
+                                found: t.to_string(),
+                                message: "Expected identifier after '.' in member access".into(),
+                                position: self.position(),
+                            });
+                        }
+                        None => return Err(ParseError::UnexpectedEOF),
+                    };
+
+                    self.consume_token(Token::RightParen, "Expected ')' after member access")?;
+                    self.record(start);
+
+                    return Ok(Expression::Term(Term::MemberAccess {
+                        expression: Box::new(expr),
+                        member: member_name,
+                    }));
+                }
+            }
+        }
+
+        // Otherwise, it’s a grouped expression: ( expr ), where expr
+        // may continue with compositions and pipelines, or a tuple.
+        let expr = self.parse_composition(expr)?;
+        let expr = self.parse_pipeline(expr)?;
+        if self.match_token(Token::Comma) {
+            return self.parse_tuple(start, expr);
+        }
+        self.consume_token(Token::RightParen, "Expected ')' after expression")?;
+        self.record(start);
+        Ok(Expression::Term(Term::GroupedExpression(Box::new(expr))))
+    }
+
+    /// Parses the rest of a tuple that opened at token `start` and whose first
+    /// element is `first`, from after the comma that follows it through the
+    /// closing `)`.
+    fn parse_tuple(&mut self, start: usize, first: Expression) -> Result<Expression, ParseError> {
+        let mut elements = vec![first];
+        elements.extend(self.parse_comma_separated(&Token::RightParen, Self::parse_expression)?);
+        self.check_tuple_length(elements.len(), "expression")?;
+        self.consume_token(Token::RightParen, "Expected ')' after tuple elements")?;
+        self.record(start);
+        Ok(Expression::Term(Term::Tuple(elements)))
+    }
+
+    /// Parses a list literal, from its `[` through the closing `]`. A missing
+    /// `]` is reported at the opening `[`, which is easier to match up with
+    /// than wherever the elements happened to stop.
+    fn parse_list(&mut self) -> Result<Expression, ParseError> {
+        let open = self.current;
+        self.consume_token(Token::LeftBracket, "Expected '['")?;
+        let elements = self.parse_comma_separated(&Token::RightBracket, Self::parse_expression)?;
+        if !self.match_token(Token::RightBracket) {
+            return Err(ParseError::UnexpectedToken {
+                expected: Token::RightBracket.to_string(),
+                found: self.current_token().unwrap_or(&Token::Eof).to_string(),
+                message: "Expected ']' after list elements".to_string(),
+                position: self.position_at(open),
+            });
+        }
+        self.record(open);
+        Ok(Expression::Term(Term::List(elements)))
+    }
+
+    /// Parses elements separated by commas up to, but not including, `close`,
+    /// as in the elements of tuples and lists. A single trailing comma before
+    /// `close` is allowed; a comma where an element should start, as in
+    /// `[1,, 2]`, is reported at that comma. Stops at the first element not
+    /// followed by a comma, leaving the caller to expect `close` there.
+    fn parse_comma_separated<T>(
+        &mut self,
+        close: &Token,
+        mut parse_element: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut elements = Vec::new();
+        while self.current_token() != Some(close) {
+            if self.current_token() == Some(&Token::Comma) {
+                return Err(ParseError::UnexpectedToken {
+                    expected: "element".to_string(),
+                    found: Token::Comma.to_string(),
+                    message: "Expected an element before ','; remove the extra ','".to_string(),
+                    position: self.position(),
+                });
+            }
+            elements.push(parse_element(self)?);
+            if !self.match_token(Token::Comma) {
+                break;
+            }
+        }
+        Ok(elements)
+    }
+
+    /// Fails unless a tuple of `length` elements has at least two, so that
+    /// `(a,)` isn't taken for `(a)`. `element` names what the tuple holds.
+    fn check_tuple_length(&self, length: usize, element: &str) -> Result<(), ParseError> {
+        if length >= 2 {
+            return Ok(());
+        }
+        Err(ParseError::UnexpectedToken {
+            expected: element.to_string(),
+            found: self.current_token().unwrap_or(&Token::Eof).to_string(),
+            message: "A tuple has at least two elements; remove the ',' or add another element"
+                .to_string(),
+            position: self.position(),
+        })
+    }
+
     //--------------------------------------------------------------------------
     // PATTERN
     //--------------------------------------------------------------------------
+    ///
+    /// Parses the pattern of a match arm, its guard, its `with` bindings, and
+    /// the `->` after them. If that fails but the arm starts with an
+    /// expression followed by `->` or `when` (e.g. `| f x -> ...`), the error
+    /// names the expression that is not a pattern instead of the first token
+    /// the pattern grammar rejects.
+    ///
+    fn parse_arm_pattern(
+        &mut self,
+    ) -> Result<(Pattern, Option<Expression>, Vec<ArmBinding>), ParseError> {
+        let (start, depth, chain, built) = (self.current, self.depth, self.chain, self.built.len());
+        let err = match self.parse_pattern().and_then(|pattern| {
+            let guard = if self.eat_contextual("when") {
+                Some(self.parse_guard()?)
+            } else {
+                None
+            };
+            let mut bindings = Vec::new();
+            while self.match_token(Token::With) {
+                bindings.push(self.parse_arm_binding()?);
+            }
+            self.consume_token(Token::Arrow, "Expected '->' in match arm")?;
+            Ok((pattern, guard, bindings))
+        }) {
+            Ok(arm) => return Ok(arm),
+            Err(err) => err,
+        };
+
+        self.current = start;
+        self.depth = depth;
+        self.chain = chain;
+        self.built.truncate(built);
+        match self.ending_at(Some("when"), Self::parse_expression) {
+            Ok(expression)
+                if self.current_token() == Some(&Token::Arrow) || self.check_contextual("when") =>
+            {
+                match Pattern::try_from(&expression) {
+                    Err(conversion) => Err(ParseError::UnexpectedToken {
+                        expected: "pattern".to_string(),
+                        found: conversion.kind().to_string(),
+                        message: "Expressions are not allowed in pattern position".to_string(),
+                        position: self.position_at(start),
+                    }),
+                    Ok(_) => Err(err),
+                }
+            }
+            _ => Err(err),
+        }
+    }
+
+    /// Parses the condition after an arm's `when`. An arm that goes straight
+    /// on to its `->`, `with`, or the end of input has left the guard out.
+    fn parse_guard(&mut self) -> Result<Expression, ParseError> {
+        match self.current_token() {
+            None | Some(Token::Arrow | Token::With | Token::Eof) => {
+                Err(ParseError::UnexpectedToken {
+                    expected: "expression".to_string(),
+                    found: self.current_token().unwrap_or(&Token::Eof).to_string(),
+                    message: "Expected a guard condition after 'when'".to_string(),
+                    position: self.position(),
+                })
+            }
+            Some(_) => self.parse_expression(),
+        }
+    }
+
+    /// Parses `identifier [ ":" type_annotation ] "=" expression` after an
+    /// arm's `with`.
+    fn parse_arm_binding(&mut self) -> Result<ArmBinding, ParseError> {
+        let identifier = self.parse_identifier()?;
+        let type_annotation = if self.match_token(Token::Colon) {
+            Some(self.parse_type_annotation()?)
+        } else {
+            None
+        };
+
+        self.consume_token(Token::Assign, "Expected '=' in match arm binding")?;
+        let value = self.parse_expression()?;
+
+        Ok(ArmBinding {
+            identifier,
+            type_annotation,
+            value,
+        })
+    }
+
+    /// pattern = atomic_pattern [ "::" pattern ]
+    ///
+    /// `::` is right-associative, so `x :: y :: rest` is `x :: (y :: rest)`.
     fn parse_pattern(&mut self) -> Result<Pattern, ParseError> {
+        let head = self.parse_atomic_pattern()?;
+        if !self.match_token(Token::Cons) {
+            return Ok(head);
+        }
+        let tail = self.nested(Self::parse_pattern)?;
+        Ok(Pattern::Cons(Box::new(head), Box::new(tail)))
+    }
+
+    fn parse_atomic_pattern(&mut self) -> Result<Pattern, ParseError> {
         match self.current_token() {
             Some(Token::Identifier(s)) => {
                 let name = s.clone();
@@ -463,16 +1039,27 @@ impl Parser {
                 self.advance();
                 Ok(Pattern::Number(val))
             }
+            Some(Token::True) | Some(Token::False) => {
+                let value = self.current_token() == Some(&Token::True);
+                self.advance();
+                Ok(Pattern::Boolean(value))
+            }
+            // Wildcard as a special identifier
+            Some(Token::Wildcard) => {
+                self.advance();
+                Ok(Pattern::Identifier("_".into()))
+            }
             Some(Token::LeftParen) => {
                 self.advance();
-                let inner = self.parse_pattern()?;
+                let inner = self.nested(Self::parse_pattern)?;
                 self.consume_token(Token::RightParen, "Expected ')' after pattern")?;
                 Ok(Pattern::Grouped(Box::new(inner)))
             }
             Some(token) => Err(ParseError::UnexpectedToken {
                 expected: "pattern".to_string(),
-                found: format!("{:?}", token),
-                message: "Unexpected token while parsing a pattern.".into(),
+                found: token.to_string(),
+                message: "Unexpected token while parsing a pattern".into(),
+                position: self.position(),
             }),
             None => Err(ParseError::UnexpectedEOF),
         }
@@ -481,50 +1068,192 @@ impl Parser {
     //--------------------------------------------------------------------------
     // TYPE ANNOTATION
     //--------------------------------------------------------------------------
+    ///
+    /// type_annotation = type_operand [ "->" type_annotation ]
+    /// type_operand    = base_type | "[" type_annotation "]"
+    ///                 | "(" type_annotation { "," type_annotation } ")"
+    /// base_type       = "Int" | "Bool" | "String" | "Float" | identifier
+    ///
+    /// Function types group to the right, so `Int -> Int -> Int` is
+    /// `Int -> (Int -> Int)`, and parentheses without a comma only group,
+    /// however deeply nested: `((Int -> Bool))` is `Int -> Bool`.
+    ///
     fn parse_type_annotation(&mut self) -> Result<TypeAnnotation, ParseError> {
+        let from_type = self.parse_type_operand()?;
+        if !self.match_token(Token::Arrow) {
+            return Ok(from_type);
+        }
+        let to_type = self.nested(Self::parse_type_annotation)?;
+        Ok(TypeAnnotation::Function(
+            Box::new(from_type),
+            Box::new(to_type),
+        ))
+    }
+
+    /// Parses a base type, a list type, or a parenthesized type or tuple
+    /// type: the operands of `->`. A comma is what makes parentheses a tuple
+    /// type, so `(Int)` is just `Int` and `(Int -> Bool)` a function type.
+    fn parse_type_operand(&mut self) -> Result<TypeAnnotation, ParseError> {
         match self.current_token() {
-            Some(Token::Identifier(name)) => {
-                let tname = name.clone();
+            Some(Token::Identifier(_)) => self.parse_base_type(),
+            Some(Token::LeftBracket) => {
                 self.advance();
-                match tname.as_str() {
-                    "Int" => Ok(TypeAnnotation::Int),
-                    "Bool" => Ok(TypeAnnotation::Bool),
-                    "String" => Ok(TypeAnnotation::String),
-                    "Float" => Ok(TypeAnnotation::Float),
-                    "(" => {
-                        self.consume_token(Token::LeftParen, "Expected '(' in function type")?;
-                        let from_type = self.parse_type_annotation()?;
-                        self.consume_token(Token::Arrow, "Expected '->' in function type")?;
-                        let to_type = self.parse_type_annotation()?;
-                        self.consume_token(Token::RightParen, "Expected ')' in function type")?;
-                        Ok(TypeAnnotation::Function(
-                            Box::new(from_type),
-                            Box::new(to_type),
-                        ))
-                    }
-                    _ => Err(ParseError::InvalidIdentifier(tname)),
-                }
+                let element = self.nested(Self::parse_type_annotation)?;
+                self.consume_token(Token::RightBracket, "Expected '->' or ']' after type")?;
+                Ok(TypeAnnotation::List(Box::new(element)))
             }
             Some(Token::LeftParen) => {
                 self.advance();
-                let from_type = self.parse_type_annotation()?;
-                self.consume_token(Token::Arrow, "Expected '->' in function type")?;
-                let to_type = self.parse_type_annotation()?;
-                self.consume_token(Token::RightParen, "Expected ')' in function type")?;
-                Ok(TypeAnnotation::Function(
-                    Box::new(from_type),
-                    Box::new(to_type),
-                ))
+                let first = self.nested(Self::parse_type_annotation)?;
+                if self.current_token() != Some(&Token::Comma) {
+                    self.consume_token(Token::RightParen, "Expected '->', ',' or ')' after type")?;
+                    return Ok(first);
+                }
+                self.advance();
+                let mut elements = vec![first];
+                elements.extend(self.parse_comma_separated(&Token::RightParen, |parser| {
+                    parser.nested(Self::parse_type_annotation)
+                })?);
+                self.check_tuple_length(elements.len(), "type annotation")?;
+                self.consume_token(Token::RightParen, "Expected '->', ',' or ')' after type")?;
+                Ok(TypeAnnotation::Tuple(elements))
             }
             Some(tok) => Err(ParseError::UnexpectedToken {
                 expected: "type annotation".into(),
-                found: format!("{:?}", tok),
+                found: tok.to_string(),
                 message: "Expected a type annotation".into(),
+                position: self.position(),
             }),
             None => Err(ParseError::UnexpectedEOF),
         }
     }
 
+    /// Parses a type name: a built-in type, a user-defined type when it is
+    /// capitalized, or else a type variable.
+    fn parse_base_type(&mut self) -> Result<TypeAnnotation, ParseError> {
+        let name = self.parse_identifier()?;
+        match name.as_str() {
+            "Int" => Ok(TypeAnnotation::Int),
+            "Bool" => Ok(TypeAnnotation::Bool),
+            "String" => Ok(TypeAnnotation::String),
+            "Float" => Ok(TypeAnnotation::Float),
+            _ if name.starts_with(char::is_uppercase) => Ok(TypeAnnotation::Named(name)),
+            _ => Ok(TypeAnnotation::Var(name)),
+        }
+    }
+
+    //--------------------------------------------------------------------------
+    // INVARIANTS
+    //--------------------------------------------------------------------------
+
+    /// Creates a parser whose cursor starts at `current`, which may be out of
+    /// range. Only meant for testing [`Parser::check_invariants`].
+    #[cfg(all(test, debug_assertions))]
+    fn with_cursor(tokens: Vec<Token>, current: usize) -> Self {
+        Self {
+            current,
+            ..Self::new(tokens)
+        }
+    }
+
+    /// Panics, in debug builds only, if the parser's cursor has moved past
+    /// the end of its tokens. Called at the exit of key rules; a violation is
+    /// a bug in the parser.
+    pub(crate) fn check_invariants(&self) {
+        if cfg!(debug_assertions) && self.current > self.tokens.len() {
+            panic!(
+                "parser invariant violated: cursor is past the end of the tokens\n  \
+                 cursor: {} of {} tokens\n  tokens: {:?}",
+                self.current,
+                self.tokens.len(),
+                self.tokens
+            );
+        }
+    }
+
+    //--------------------------------------------------------------------------
+    // NESTING
+    //--------------------------------------------------------------------------
+
+    /// Goes one level deeper, failing if that exceeds the limit.
+    fn enter(&mut self) -> Result<(), ParseError> {
+        if self.depth >= self.max_depth {
+            return Err(ParseError::NestingTooDeep {
+                limit: self.max_depth,
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Counts one more operator of the chains enclosing the current point,
+    /// failing if they hold more than [`Parser::CHAIN_FACTOR`] times the
+    /// nesting limit.
+    fn extend_chain(&mut self) -> Result<(), ParseError> {
+        let limit = self.max_depth * Self::CHAIN_FACTOR;
+        if self.chain >= limit {
+            return Err(ParseError::NestingTooDeep { limit });
+        }
+        self.chain += 1;
+        Ok(())
+    }
+
+    /// Records that the expression just built came from the tokens from
+    /// `start` up to the current one.
+    fn record(&mut self, start: usize) {
+        self.built.push(Built {
+            start,
+            end: self.current,
+            sugar: false,
+        });
+    }
+
+    /// Records that the expression just built is sugar within the construct
+    /// that started at token `start`.
+    fn record_sugar(&mut self, start: usize) {
+        self.built.push(Built {
+            start,
+            end: self.current,
+            sugar: true,
+        });
+    }
+
+    /// The token where the expression built last starts.
+    fn last_start(&self) -> usize {
+        self.built.last().map_or(self.current, |built| built.start)
+    }
+
+    /// Moves the last `after` records before the `before` records preceding
+    /// them.
+    fn move_before(&mut self, after: usize, before: usize) {
+        let from = self.built.len() - after - before;
+        self.built[from..].rotate_left(before);
+    }
+
+    /// Runs `parse` one level deeper.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        self.enter()?;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Runs `parse` with `stop_word` as the contextual keyword that ends the
+    /// expression, or with none.
+    fn ending_at<T>(
+        &mut self,
+        stop_word: Option<&'static str>,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        let outer = std::mem::replace(&mut self.stop_word, stop_word);
+        let result = parse(self);
+        self.stop_word = outer;
+        result
+    }
+
     //--------------------------------------------------------------------------
     // TOKEN UTILITY
     //--------------------------------------------------------------------------
@@ -534,9 +1263,10 @@ impl Parser {
             Ok(())
         } else {
             Err(ParseError::UnexpectedToken {
-                expected: format!("{:?}", expected),
-                found: format!("{:?}", self.current_token().cloned().unwrap_or(Token::Eof)),
+                expected: expected.to_string(),
+                found: self.current_token().unwrap_or(&Token::Eof).to_string(),
                 message: error_message.to_string(),
+                position: self.position(),
             })
         }
     }
@@ -549,16 +1279,29 @@ impl Parser {
         } else {
             Err(ParseError::UnexpectedToken {
                 expected: "identifier".to_string(),
-                found: self
-                    .current_token()
-                    .cloned()
-                    .map(|t| format!("{:?}", t))
-                    .unwrap_or_else(|| "EOF".to_string()),
+                found: self.current_token().unwrap_or(&Token::Eof).to_string(),
                 message: "Expected an identifier".to_string(),
+                position: self.position(),
             })
         }
     }
 
+    /// Whether the current token is the identifier `keyword`. Contextual
+    /// keywords are lexed as identifiers and recognized only where their
+    /// construct can occur, so they stay usable as names everywhere else.
+    fn check_contextual(&self, keyword: &str) -> bool {
+        matches!(self.current_token(), Some(Token::Identifier(name)) if name == keyword)
+    }
+
+    /// Consumes the contextual keyword `keyword` if it is the current token.
+    fn eat_contextual(&mut self, keyword: &str) -> bool {
+        let found = self.check_contextual(keyword);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
     fn match_token(&mut self, expected: Token) -> bool {
         if self.current_token() == Some(&expected) {
             self.advance();
@@ -576,12 +1319,23 @@ impl Parser {
         if self.current < self.tokens.len() {
             let token = self.tokens[self.current].clone();
             self.current += 1;
+            self.consumed += 1;
             Some(token)
         } else {
             None
         }
     }
 
+    /// Where the current token starts, if known.
+    fn position(&self) -> Option<Position> {
+        self.position_at(self.current)
+    }
+
+    /// Where the token at `index` starts, if known.
+    fn position_at(&self, index: usize) -> Option<Position> {
+        self.positions.get(index).copied()
+    }
+
     fn peek_next_token(&self) -> Option<&Token> {
         self.tokens.get(self.current + 1)
     }
@@ -590,3 +1344,99 @@ impl Parser {
         self.tokens.get(self.current + 2)
     }
 }
+
+/*******************************************************************************
+ *                             TREE INVARIANTS
+ *-------------------------------------------------------------------------------
+ * Shapes the parser never produces, checked in debug builds so a bug surfaces
+ * where the bad node is built rather than in whatever consumes it.
+ ******************************************************************************/
+
+impl Program {
+    /// Panics if any expression has a shape the parser never produces: an
+    /// application of fewer than two expressions, a match without arms, or a
+    /// comparison or logic operation without a right-hand side.
+    #[cfg(debug_assertions)]
+    pub(crate) fn check_invariants(&self) {
+        self.expression.walk(&mut check_node);
+    }
+}
+
+/// Number of expressions in `expression`, itself included.
+fn size(expression: &Expression) -> usize {
+    let mut size = 0;
+    expression.walk(&mut |_| size += 1);
+    size
+}
+
+/// Panics, in debug builds only, if `expression` itself (not its children)
+/// has a shape the parser never produces.
+fn check_node(expression: &Expression) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    let violation = match expression {
+        Expression::Application(items) if items.len() < 2 => {
+            "application of fewer than two expressions"
+        }
+        Expression::PatternMatch { arms, .. } if arms.is_empty() => "match without arms",
+        Expression::Comparison { right: None, .. } | Expression::Logic { right: None, .. } => {
+            "binary operation without a right-hand side"
+        }
+        _ => return,
+    };
+    panic!(
+        "parser invariant violated: {}\n  node: {:?}",
+        violation, expression
+    );
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::*;
+
+    /// Tests that a parser cursor past the end of its tokens trips the
+    /// invariant check.
+    #[test]
+    #[should_panic(expected = "parser invariant violated")]
+    fn test_invariant_cursor_past_end() {
+        // Arrange
+        let parser = Parser::with_cursor(vec![Token::Eof], 2);
+
+        // Act
+        parser.check_invariants();
+    }
+
+    /// Tests that an application of a single expression trips the tree
+    /// invariant check.
+    #[test]
+    #[should_panic(expected = "application of fewer than two expressions")]
+    fn test_invariant_single_item_application() {
+        // Arrange
+        let program = Program {
+            expression: Expression::Application(vec![Expression::Term(Term::Identifier(
+                "f".to_string(),
+            ))]),
+        };
+
+        // Act
+        program.check_invariants();
+    }
+
+    /// Tests that a match without arms trips the tree invariant check.
+    #[test]
+    #[should_panic(expected = "match without arms")]
+    fn test_invariant_match_without_arms() {
+        // Arrange
+        let program = Program {
+            expression: Expression::PatternMatch {
+                expression: Box::new(Expression::Term(Term::Identifier("x".to_string()))),
+                arms: Vec::new(),
+            },
+        };
+
+        // Act
+        program.check_invariants();
+    }
+}
@@ -0,0 +1,804 @@
+//! src/printer.rs
+
+/*******************************************************************************
+ *                              PRINTER MODULE
+ *-------------------------------------------------------------------------------
+ * Renders AST nodes back to source text. The output is fully parenthesized:
+ * every operand, argument, scrutinee, guard, and arm body that is not atomic is
+ * wrapped in parentheses, so the text re-parses to the same tree regardless
+ * of precedence, up to the `GroupedExpression` nodes the parentheses add.
+ * `let`s at the top of a match arm are printed as the arm's `with` bindings,
+ * which parse to the same tree without nesting the arm body.
+ *
+ * This is the "maximal parentheses" style; it favors being unambiguous over
+ * being pretty.
+ *
+ * The same text can be laid out over several lines in a `PrintStyle`: how far
+ * to indent, which constructs always break, the width lines should fit in,
+ * and whether to annotate each line with the grammar rules starting on it.
+ * Breaking is greedy: a construct stays on the current line if it fits, and
+ * otherwise breaks at its own seams, with each part again kept whole when it
+ * fits. Applications fill each line with as many arguments as fit. Only
+ * whitespace and trailing comments are added, so every style re-parses to
+ * the same tree.
+ ******************************************************************************/
+
+use crate::{
+    index::child_expressions, Expression, FunctionComposition, Pattern, Term, TypeAnnotation,
+};
+
+/// Renders `expression` as fully parenthesized source on a single line; this
+/// is the [`Compact`] style.
+pub fn print_expression(expression: &Expression) -> String {
+    match expression {
+        Expression::LetExpr {
+            recursive,
+            identifier,
+            type_annotation,
+            value,
+            body,
+        } => format!(
+            "let {}{}{} = {} in {}",
+            if *recursive { "rec " } else { "" },
+            identifier,
+            annotation(type_annotation),
+            print_expression(value),
+            print_expression(body)
+        ),
+        Expression::LetAnd {
+            recursive,
+            bindings,
+            body,
+        } => {
+            let bindings: Vec<String> = bindings
+                .iter()
+                .map(|binding| {
+                    format!(
+                        "{}{} = {}",
+                        binding.identifier,
+                        annotation(&binding.type_annotation),
+                        print_expression(&binding.value)
+                    )
+                })
+                .collect();
+            format!(
+                "let {}{} in {}",
+                if *recursive { "rec " } else { "" },
+                bindings.join(" and "),
+                print_expression(body)
+            )
+        }
+        Expression::IfExpr {
+            condition,
+            then_branch,
+            else_branch,
+        } => format!(
+            "if {} then {} else {}",
+            print_expression(condition),
+            print_expression(then_branch),
+            print_expression(else_branch)
+        ),
+        Expression::Lambda {
+            parameter,
+            type_annotation,
+            body,
+        } => format!(
+            "\\{}{} -> {}",
+            parameter,
+            annotation(type_annotation),
+            print_expression(body)
+        ),
+        Expression::PatternMatch { expression, arms } => {
+            let arms: Vec<String> = arms
+                .iter()
+                .map(|arm| {
+                    let (bindings, body) = arm_bindings(&arm.expression);
+                    format!(
+                        "| {}{}{} -> {}",
+                        print_pattern(&arm.pattern),
+                        guard(&arm.guard),
+                        bindings,
+                        operand(body)
+                    )
+                })
+                .collect();
+            format!("match {} with {}", operand(expression), arms.join(" "))
+        }
+        Expression::Comparison {
+            left, right: None, ..
+        }
+        | Expression::Logic {
+            left, right: None, ..
+        } => print_expression(left),
+        Expression::Comparison { .. }
+        | Expression::Logic { .. }
+        | Expression::Arithmetic { .. }
+        | Expression::List { .. }
+        | Expression::FunctionComposition(FunctionComposition { .. }) => {
+            // A chain like `1 + 2 + 3` nests to the left as deep as it is
+            // long, so walk down its left operands rather than recursing.
+            let mut chain = Vec::new();
+            let mut left = expression;
+            while let Some((operator, inner, right)) = left.as_binary() {
+                chain.push((operator, right));
+                left = inner;
+            }
+            let mut text = operand(left);
+            for (i, (operator, right)) in chain.iter().enumerate().rev() {
+                text = format!("{} {} {}", text, operator.symbol(), operand(right));
+                if i > 0 {
+                    text = format!("({})", text);
+                }
+            }
+            text
+        }
+        Expression::Unary {
+            operator,
+            operand: inner,
+        } => format!("{}{}", operator.symbol(), operand(inner)),
+        Expression::Application(items) => items.iter().map(operand).collect::<Vec<_>>().join(" "),
+        Expression::Term(Term::Identifier(name)) => name.clone(),
+        Expression::Term(Term::Number(value)) => value.to_string(),
+        Expression::Term(Term::String(text)) => format!("\"{}\"", escaped(text)),
+        Expression::Term(Term::Boolean(value)) => value.to_string(),
+        Expression::Term(Term::GroupedExpression(inner)) => {
+            format!("({})", print_expression(inner))
+        }
+        Expression::Term(Term::MemberAccess { expression, member }) => {
+            format!("({} . {})", operand(expression), member)
+        }
+        Expression::Term(Term::Tuple(elements)) => format!(
+            "({})",
+            elements
+                .iter()
+                .map(print_expression)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Expression::Term(Term::List(elements)) => format!(
+            "[{}]",
+            elements
+                .iter()
+                .map(print_expression)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Renders `pattern` as source.
+pub fn print_pattern(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Identifier(name) => name.clone(),
+        Pattern::Number(value) => value.to_string(),
+        Pattern::Boolean(value) => value.to_string(),
+        Pattern::Grouped(inner) => format!("({})", print_pattern(inner)),
+        Pattern::Cons(head, tail) => match head.as_ref() {
+            Pattern::Cons(..) => format!("({}) :: {}", print_pattern(head), print_pattern(tail)),
+            _ => format!("{} :: {}", print_pattern(head), print_pattern(tail)),
+        },
+    }
+}
+
+/// Renders `annotation` as source, e.g. `(Int -> Bool)` or `[(Int, Bool)]`.
+pub fn print_type(annotation: &TypeAnnotation) -> String {
+    match annotation {
+        TypeAnnotation::Function(from, to) => {
+            format!("({} -> {})", print_type(from), print_type(to))
+        }
+        TypeAnnotation::Int => "Int".to_string(),
+        TypeAnnotation::Bool => "Bool".to_string(),
+        TypeAnnotation::String => "String".to_string(),
+        TypeAnnotation::Float => "Float".to_string(),
+        TypeAnnotation::Named(name) | TypeAnnotation::Var(name) => name.clone(),
+        TypeAnnotation::List(element) => format!("[{}]", print_type(element)),
+        TypeAnnotation::Tuple(elements) => {
+            let elements: Vec<String> = elements.iter().map(print_type).collect();
+            format!("({})", elements.join(", "))
+        }
+    }
+}
+
+/// Renders `expression`, parenthesized unless it is atomic.
+pub(crate) fn operand(expression: &Expression) -> String {
+    match expression {
+        Expression::Term(_) if !negative(expression) => print_expression(expression),
+        _ => format!("({})", print_expression(expression)),
+    }
+}
+
+/// Whether `expression` is a negative number, which prints with a leading
+/// `-` and so needs parentheses as an operand, as in `f (-1)`.
+fn negative(expression: &Expression) -> bool {
+    matches!(expression, Expression::Term(Term::Number(value)) if value.is_sign_negative())
+}
+
+/// Escapes `text` for a string literal, so it prints on one line and lexes
+/// back to itself.
+pub(crate) fn escaped(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders a match arm's guard, if any, as ` when <condition>`.
+fn guard(guard: &Option<Box<Expression>>) -> String {
+    match guard {
+        Some(condition) => format!(" when {}", operand(condition)),
+        None => String::new(),
+    }
+}
+
+/// Renders the `let`s at the top of a match arm's body as `with` bindings,
+/// returning them and the body inside them. `| p -> let x = e in b` becomes
+/// `| p with x = e -> b`, which parses to the same tree without the nesting.
+fn arm_bindings(mut expression: &Expression) -> (String, &Expression) {
+    let mut bindings = String::new();
+    while let Expression::LetExpr {
+        recursive: false,
+        identifier,
+        type_annotation,
+        value,
+        body,
+    } = expression
+    {
+        bindings.push_str(&format!(
+            " with {}{} = {}",
+            identifier,
+            annotation(type_annotation),
+            operand(value)
+        ));
+        expression = body;
+    }
+    (bindings, expression)
+}
+
+fn annotation(type_annotation: &Option<TypeAnnotation>) -> String {
+    match type_annotation {
+        Some(annotation) => format!(": {}", print_type(annotation)),
+        None => String::new(),
+    }
+}
+
+/*******************************************************************************
+ *                                 STYLES
+ ******************************************************************************/
+
+/// A construct that a style can treat specially.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Construct {
+    Let,
+    If,
+    Lambda,
+    Match,
+    MatchArm,
+    Comparison,
+    Logic,
+    Arithmetic,
+    List,
+    Unary,
+    Composition,
+    Application,
+    MemberAccess,
+    Tuple,
+    ListLiteral,
+}
+
+impl Construct {
+    /// The construct `expression` is, or `None` for atoms and parentheses.
+    pub fn of(expression: &Expression) -> Option<Construct> {
+        match expression {
+            Expression::LetExpr { .. } | Expression::LetAnd { .. } => Some(Construct::Let),
+            Expression::IfExpr { .. } => Some(Construct::If),
+            Expression::Lambda { .. } => Some(Construct::Lambda),
+            Expression::PatternMatch { .. } => Some(Construct::Match),
+            Expression::Comparison { right: Some(_), .. } => Some(Construct::Comparison),
+            Expression::Logic { right: Some(_), .. } => Some(Construct::Logic),
+            Expression::Arithmetic { .. } => Some(Construct::Arithmetic),
+            Expression::List { .. } => Some(Construct::List),
+            Expression::Unary { .. } => Some(Construct::Unary),
+            Expression::FunctionComposition(_) => Some(Construct::Composition),
+            Expression::Application(_) => Some(Construct::Application),
+            Expression::Term(Term::MemberAccess { .. }) => Some(Construct::MemberAccess),
+            Expression::Term(Term::Tuple(_)) => Some(Construct::Tuple),
+            Expression::Term(Term::List(_)) => Some(Construct::ListLiteral),
+            _ => None,
+        }
+    }
+
+    /// The name of the grammar rule for the construct, as the teaching style
+    /// prints it.
+    pub fn rule(self) -> &'static str {
+        match self {
+            Construct::Let => "let-expr",
+            Construct::If => "if-expr",
+            Construct::Lambda => "lambda",
+            Construct::Match => "pattern-match",
+            Construct::MatchArm => "match-arm",
+            Construct::Comparison => "comparison",
+            Construct::Logic => "logic",
+            Construct::Arithmetic => "arithmetic",
+            Construct::List => "list",
+            Construct::Unary => "unary",
+            Construct::Composition => "composition",
+            Construct::Application => "application",
+            Construct::MemberAccess => "member-access",
+            Construct::Tuple => "tuple",
+            Construct::ListLiteral => "list-literal",
+        }
+    }
+}
+
+/// How [`print_styled`] lays out source over lines.
+pub trait PrintStyle {
+    /// Columns each nested level is indented by.
+    fn indent(&self) -> usize;
+
+    /// The width lines should fit in, or `None` to never break for width.
+    fn max_width(&self) -> Option<usize>;
+
+    /// Whether `construct` is broken over lines even when it would fit.
+    fn always_breaks(&self, construct: Construct) -> bool;
+
+    /// Whether each line ends with a comment naming the grammar rules of
+    /// the constructs that start on it.
+    fn annotates(&self) -> bool;
+}
+
+/// Everything on one line, as [`print_expression`] prints it. Meant for
+/// diagnostics and other one-line snippets.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Compact;
+
+impl PrintStyle for Compact {
+    fn indent(&self) -> usize {
+        0
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        None
+    }
+
+    fn always_breaks(&self, _: Construct) -> bool {
+        false
+    }
+
+    fn annotates(&self) -> bool {
+        false
+    }
+}
+
+/// The standard multi-line layout: every `let` and every match arm on a line
+/// of its own, and anything else broken only to fit in `width` columns.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Expanded {
+    pub width: usize,
+}
+
+impl Default for Expanded {
+    fn default() -> Self {
+        Expanded { width: 80 }
+    }
+}
+
+impl PrintStyle for Expanded {
+    fn indent(&self) -> usize {
+        2
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        Some(self.width)
+    }
+
+    fn always_breaks(&self, construct: Construct) -> bool {
+        matches!(construct, Construct::Let | Construct::Match)
+    }
+
+    fn annotates(&self) -> bool {
+        false
+    }
+}
+
+/// For learning the grammar: every binding construct broken over lines, and
+/// each line ending in a comment naming the rules that start on it, such as
+/// `-- if-expr`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Teaching {
+    pub width: usize,
+}
+
+impl Default for Teaching {
+    fn default() -> Self {
+        Teaching { width: 60 }
+    }
+}
+
+impl PrintStyle for Teaching {
+    fn indent(&self) -> usize {
+        2
+    }
+
+    fn max_width(&self) -> Option<usize> {
+        Some(self.width)
+    }
+
+    fn always_breaks(&self, construct: Construct) -> bool {
+        matches!(
+            construct,
+            Construct::Let | Construct::If | Construct::Lambda | Construct::Match
+        )
+    }
+
+    fn annotates(&self) -> bool {
+        true
+    }
+}
+
+/// The style called `name`: `compact`, `expanded`, or `teaching`.
+pub fn style_named(name: &str) -> Option<Box<dyn PrintStyle>> {
+    match name {
+        "compact" => Some(Box::new(Compact)),
+        "expanded" => Some(Box::new(Expanded::default())),
+        "teaching" => Some(Box::new(Teaching::default())),
+        _ => None,
+    }
+}
+
+/// Renders `expression` as fully parenthesized source laid out in `style`.
+/// Lines have no trailing whitespace, and there is no final newline.
+pub fn print_styled(expression: &Expression, style: &dyn PrintStyle) -> String {
+    let mut printer = Printer {
+        style,
+        lines: Vec::new(),
+        line: String::new(),
+        notes: Vec::new(),
+        indent: 0,
+    };
+    printer.print(expression);
+    printer.flush();
+    printer.lines.join("\n")
+}
+
+/// Lays out source over lines for `print_styled`.
+struct Printer<'a> {
+    style: &'a dyn PrintStyle,
+    /// Finished lines.
+    lines: Vec<String>,
+    /// The line being written.
+    line: String,
+    /// The rules of the constructs starting on the current line.
+    notes: Vec<&'static str>,
+    /// The indentation continuation lines start at.
+    indent: usize,
+}
+
+impl Printer<'_> {
+    /// Prints `expression` at the end of the current line, breaking it over
+    /// lines if it doesn't fit.
+    fn print(&mut self, expression: &Expression) {
+        if self.flat(expression, &print_expression(expression)) {
+            return;
+        }
+        let construct = Construct::of(expression);
+        if let Some(construct) = construct {
+            self.note(construct);
+        }
+        match expression {
+            Expression::LetExpr {
+                recursive,
+                identifier,
+                type_annotation,
+                value,
+                body,
+            } => {
+                let indent = self.indent;
+                self.line.push_str(&format!(
+                    "let {}{}{} = ",
+                    if *recursive { "rec " } else { "" },
+                    identifier,
+                    annotation(type_annotation)
+                ));
+                if !self.flat(value, &format!("{} in", print_expression(value))) {
+                    self.nested(|printer| printer.print(value));
+                    self.newline(indent);
+                    self.line.push_str("in");
+                }
+                self.newline(indent);
+                self.print(body);
+            }
+            Expression::LetAnd {
+                recursive,
+                bindings,
+                body,
+            } => {
+                let indent = self.indent;
+                self.line
+                    .push_str(if *recursive { "let rec " } else { "let " });
+                for (i, binding) in bindings.iter().enumerate() {
+                    if i > 0 {
+                        self.newline(indent);
+                        self.line.push_str("and ");
+                    }
+                    self.line.push_str(&format!(
+                        "{}{} = ",
+                        binding.identifier,
+                        annotation(&binding.type_annotation)
+                    ));
+                    let last = i + 1 == bindings.len();
+                    let value = print_expression(&binding.value);
+                    let text = if last { format!("{} in", value) } else { value };
+                    if !self.flat(&binding.value, &text) {
+                        self.nested(|printer| printer.print(&binding.value));
+                        if last {
+                            self.newline(indent);
+                            self.line.push_str("in");
+                        }
+                    }
+                }
+                self.newline(indent);
+                self.print(body);
+            }
+            Expression::IfExpr {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let indent = self.indent;
+                for (keyword, part) in [
+                    ("if ", condition),
+                    ("then ", then_branch),
+                    ("else ", else_branch),
+                ] {
+                    if keyword != "if " {
+                        self.newline(indent);
+                    }
+                    self.line.push_str(keyword);
+                    self.indented(|printer| printer.print(part));
+                }
+            }
+            Expression::Lambda {
+                parameter,
+                type_annotation,
+                body,
+            } => {
+                self.line.push_str(&format!(
+                    "\\{}{} -> ",
+                    parameter,
+                    annotation(type_annotation)
+                ));
+                if !self.flat(body, &print_expression(body)) {
+                    self.nested(|printer| printer.print(body));
+                }
+            }
+            Expression::PatternMatch { expression, arms } => {
+                self.line.push_str("match ");
+                self.indented(|printer| printer.print_operand(expression));
+                self.line.push_str(" with");
+                self.indented(|printer| {
+                    for arm in arms {
+                        let (bindings, body) = arm_bindings(&arm.expression);
+                        printer.newline(printer.indent);
+                        printer.note(Construct::MatchArm);
+                        printer.line.push_str(&format!(
+                            "| {}{}{} -> ",
+                            print_pattern(&arm.pattern),
+                            guard(&arm.guard),
+                            bindings
+                        ));
+                        if !printer.flat(body, &operand(body)) {
+                            printer.nested(|printer| printer.print_operand(body));
+                        }
+                    }
+                });
+            }
+            Expression::Unary { operator, operand } => {
+                self.line.push_str(operator.symbol());
+                self.print_operand(operand);
+            }
+            Expression::Application(items) => {
+                self.print_operand(&items[0]);
+                self.indented(|printer| {
+                    for item in &items[1..] {
+                        if printer.flat(item, &format!(" {}", operand(item))) {
+                            continue;
+                        }
+                        // Only an argument too wide for this line moves to the
+                        // next; one the style breaks anyway starts here.
+                        if printer.always_breaks(item) {
+                            printer.line.push(' ');
+                        } else {
+                            printer.newline(printer.indent);
+                        }
+                        printer.print_operand(item);
+                    }
+                });
+            }
+            Expression::Term(Term::GroupedExpression(inner)) => {
+                self.line.push('(');
+                self.indented(|printer| printer.print(inner));
+                self.line.push(')');
+            }
+            Expression::Term(Term::MemberAccess { expression, member }) => {
+                self.line.push('(');
+                self.indented(|printer| printer.print_operand(expression));
+                self.line.push_str(&format!(" . {})", member));
+            }
+            Expression::Term(Term::Tuple(elements)) => self.print_elements(('(', ')'), elements),
+            Expression::Term(Term::List(elements)) => self.print_elements(('[', ']'), elements),
+            Expression::Term(_) => self.line.push_str(&print_expression(expression)),
+            _ => match expression.as_binary() {
+                Some((operator, left, right)) => {
+                    // Walk down the left operands of a chain like `1 + 2 + 3`
+                    // as `print_operand` would, opening each one that breaks,
+                    // then print the right operands on the way back out.
+                    let indent = self.indent;
+                    let mut chain = vec![(operator, right, indent, false)];
+                    let mut left = left;
+                    loop {
+                        let Some((operator, inner, right)) = left.as_binary() else {
+                            self.print_operand(left);
+                            break;
+                        };
+                        if self.flat(left, &operand(left)) {
+                            break;
+                        }
+                        self.line.push('(');
+                        self.indent += self.style.indent();
+                        if self.flat(left, &print_expression(left)) {
+                            self.line.push(')');
+                            break;
+                        }
+                        if let Some(construct) = Construct::of(left) {
+                            self.note(construct);
+                        }
+                        chain.push((operator, right, self.indent, true));
+                        left = inner;
+                    }
+                    for (operator, right, indent, parenthesized) in chain.into_iter().rev() {
+                        self.indent = indent;
+                        let rest = format!(" {} {}", operator.symbol(), operand(right));
+                        if !self.flat(right, &rest) {
+                            self.indented(|printer| {
+                                printer.newline(printer.indent);
+                                printer.line.push_str(&format!("{} ", operator.symbol()));
+                                printer.print_operand(right);
+                            });
+                        }
+                        if parenthesized {
+                            self.line.push(')');
+                        }
+                    }
+                    self.indent = indent;
+                }
+                // A comparison or logic node without a right operand prints
+                // as its left operand.
+                None => {
+                    if let Expression::Comparison { left, .. } | Expression::Logic { left, .. } =
+                        expression
+                    {
+                        self.print(left);
+                    }
+                }
+            },
+        }
+    }
+
+    /// Prints `elements` separated by commas between the `delimiters`.
+    fn print_elements(&mut self, (open, close): (char, char), elements: &[Expression]) {
+        self.line.push(open);
+        self.indented(|printer| {
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    printer.line.push_str(", ");
+                }
+                printer.print(element);
+            }
+        });
+        self.line.push(close);
+    }
+
+    /// Prints `expression`, parenthesized unless it is atomic.
+    fn print_operand(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Term(_) if !negative(expression) => self.print(expression),
+            _ => {
+                if self.flat(expression, &operand(expression)) {
+                    return;
+                }
+                self.line.push('(');
+                self.indented(|printer| printer.print(expression));
+                self.line.push(')');
+            }
+        }
+    }
+
+    /// Appends `text`, the single-line rendering of `expression`, if the
+    /// style lets everything in it stay on one line and it fits.
+    fn flat(&mut self, expression: &Expression, text: &str) -> bool {
+        let breaks = self.always_breaks(expression);
+        let fits = self
+            .style
+            .max_width()
+            .is_none_or(|width| self.line.chars().count() + text.chars().count() <= width);
+        if breaks || !fits {
+            return false;
+        }
+        self.annotate(expression);
+        self.line.push_str(text);
+        true
+    }
+
+    /// Whether `expression` has a construct the style always breaks.
+    fn always_breaks(&self, expression: &Expression) -> bool {
+        Construct::of(expression).is_some_and(|construct| self.style.always_breaks(construct))
+            || child_expressions(expression)
+                .into_iter()
+                .any(|(_, child)| self.always_breaks(child))
+    }
+
+    /// Notes every construct in `expression` as starting on the current line.
+    fn annotate(&mut self, expression: &Expression) {
+        if !self.style.annotates() {
+            return;
+        }
+        if let Some(construct) = Construct::of(expression) {
+            self.note(construct);
+        }
+        if let Expression::PatternMatch { .. } = expression {
+            self.note(Construct::MatchArm);
+        }
+        for (_, child) in child_expressions(expression) {
+            self.annotate(child);
+        }
+    }
+
+    /// Notes `construct` as starting on the current line.
+    fn note(&mut self, construct: Construct) {
+        if self.style.annotates() && !self.notes.contains(&construct.rule()) {
+            self.notes.push(construct.rule());
+        }
+    }
+
+    /// Runs `print` one level further indented.
+    fn indented(&mut self, print: impl FnOnce(&mut Self)) {
+        let indent = self.indent;
+        self.indent += self.style.indent();
+        print(self);
+        self.indent = indent;
+    }
+
+    /// Runs `print` on a new line, one level further indented.
+    fn nested(&mut self, print: impl FnOnce(&mut Self)) {
+        self.indented(|printer| {
+            printer.newline(printer.indent);
+            print(printer);
+        });
+    }
+
+    /// Ends the current line and starts one indented by `indent` columns.
+    fn newline(&mut self, indent: usize) {
+        self.flush();
+        self.line = " ".repeat(indent);
+    }
+
+    /// Moves the current line, and its annotation, to the finished lines.
+    fn flush(&mut self) {
+        let mut line = self.line.trim_end().to_string();
+        if !self.notes.is_empty() {
+            line.push_str(&format!("  -- {}", self.notes.join(", ")));
+            self.notes.clear();
+        }
+        self.lines.push(line);
+        self.line.clear();
+    }
+}
@@ -0,0 +1,217 @@
+//! src/repl.rs
+
+/*******************************************************************************
+ *                                REPL MODULE
+ *-------------------------------------------------------------------------------
+ * Building blocks of the interactive loop (`rdp --repl`): deciding whether an
+ * entry is complete or continues on the next line, answering an entry, and a
+ * history of entries persisted across sessions in an append-only file.
+ *
+ * An entry is either a program, answered with its AST in full, or a command
+ * starting with `:`. Commands:
+ *   - `:ast <program>` answers with the AST on one line.
+ *
+ * Line editing lives in `editor`, which is pure buffer manipulation driven by
+ * key events. `terminal`, behind the `line-editor` feature on Unix, feeds it
+ * keys from a terminal in raw mode; without it, the loop reads plain lines.
+ ******************************************************************************/
+
+pub mod editor;
+#[cfg(all(feature = "line-editor", unix))]
+pub mod terminal;
+
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{validate, Diagnostic, Lexer, ParseError, Parser, Token, ValidateConfig};
+
+/// Environment variable overriding where the history is kept.
+pub const HISTORY_VAR: &str = "RDP_HISTORY";
+
+/// The commands an entry can start with.
+pub const COMMANDS: [&str; 1] = [":ast"];
+
+/// What the REPL shows in response to one entry.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Reply {
+    /// What to print on standard output, if anything.
+    pub output: Option<String>,
+    /// What to report on standard error, before the output.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Answers `entry`. Blank entries get an empty reply.
+pub fn respond(entry: &str, config: &ValidateConfig) -> Reply {
+    let (command, source) = split_command(entry);
+    if source.trim().is_empty() && command.is_none() {
+        return Reply::default();
+    }
+    let compact = match command {
+        None => false,
+        Some(":ast") => true,
+        Some(unknown) => {
+            let message = format!(
+                "unknown command '{}'; expected one of: {}",
+                unknown,
+                COMMANDS.join(", ")
+            );
+            return Reply {
+                output: None,
+                diagnostics: vec![Diagnostic::error("unknown-command", message)],
+            };
+        }
+    };
+    let report = validate(source, config);
+    Reply {
+        output: report.program.map(|program| {
+            if compact {
+                format!("{:?}", program)
+            } else {
+                format!("{:#?}", program)
+            }
+        }),
+        diagnostics: report.diagnostics,
+    }
+}
+
+/// Splits the leading `:command`, if any, from the rest of `entry`.
+fn split_command(entry: &str) -> (Option<&str>, &str) {
+    let trimmed = entry.trim_start();
+    if !trimmed.starts_with(':') {
+        return (None, entry);
+    }
+    let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+    (Some(&trimmed[..end]), &trimmed[end..])
+}
+
+/// Whether `source` is the start of a valid program that ends too early, so
+/// the entry should continue on another line. Blank input is complete, and
+/// so is a command with no program after it.
+pub fn is_incomplete(source: &str) -> bool {
+    let source = split_command(source).1;
+    if source.trim().is_empty() {
+        return false;
+    }
+    let Ok(tokens) = Lexer::tokenize_str(source) else {
+        return false;
+    };
+    match Parser::new(tokens).parse_program() {
+        Err(ParseError::UnexpectedToken { found, .. }) => found == Token::Eof.to_string(),
+        Err(ParseError::UnexpectedEOF | ParseError::MissingPatternMatchArm) => true,
+        _ => false,
+    }
+}
+
+/// Where the history is kept by default: `$RDP_HISTORY` if set, otherwise a
+/// per-user state directory (`$XDG_STATE_HOME/rdp/history`, falling back to
+/// `~/.local/state/rdp/history`, or `%APPDATA%\rdp\history` on Windows).
+pub fn default_history_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(HISTORY_VAR) {
+        return Some(PathBuf::from(path));
+    }
+    let state = if cfg!(windows) {
+        PathBuf::from(env::var_os("APPDATA")?)
+    } else if let Some(state) = env::var_os("XDG_STATE_HOME") {
+        PathBuf::from(state)
+    } else {
+        PathBuf::from(env::var_os("HOME")?)
+            .join(".local")
+            .join("state")
+    };
+    Some(state.join("rdp").join("history"))
+}
+
+/// Entries from earlier sessions, oldest first, backed by an append-only
+/// file. A multi-line entry is one item, stored on one line with its line
+/// breaks escaped.
+#[derive(Debug, Default)]
+pub struct History {
+    path: Option<PathBuf>,
+    entries: Vec<String>,
+}
+
+impl History {
+    /// A history that is not saved anywhere.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Loads the history kept at `path`, which may not exist yet. New entries
+    /// are appended to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but can't be read.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let entries = match fs::read_to_string(&path) {
+            Ok(content) => content.lines().map(unescape).collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(Self {
+            path: Some(path),
+            entries,
+        })
+    }
+
+    /// Every entry, oldest first.
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Records `entry`, unless it is blank or repeats the latest entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry can't be appended to the history file;
+    /// it is still recorded in memory.
+    pub fn push(&mut self, entry: &str) -> io::Result<()> {
+        if entry.trim().is_empty() || self.entries.last().is_some_and(|last| last == entry) {
+            return Ok(());
+        }
+        self.entries.push(entry.to_string());
+        match &self.path {
+            Some(path) => append_line(path, &escape(entry)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn append_line(path: &Path, line: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+/// Escapes `\` and line breaks so an entry fits on one line of the file.
+fn escape(entry: &str) -> String {
+    entry
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(line: &str) -> String {
+    let mut entry = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            entry.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => entry.push('\n'),
+            Some('r') => entry.push('\r'),
+            Some(other) => entry.push(other),
+            None => entry.push('\\'),
+        }
+    }
+    entry
+}
@@ -0,0 +1,200 @@
+//! src/repl/editor.rs
+
+/*******************************************************************************
+ *                               EDITOR MODULE
+ *-------------------------------------------------------------------------------
+ * The line editor behind the REPL prompt, as a state machine over key events.
+ * It knows nothing about terminals: it edits a buffer that may span several
+ * lines, and decides on Enter whether to submit the entry or open a new line,
+ * so it can be driven by synthetic keys in tests.
+ *
+ * Up and Down move between the lines of the entry and, past its first or last
+ * line, between history entries. The entry being typed is kept as a draft
+ * while browsing and comes back when moving past the newest entry.
+ ******************************************************************************/
+
+use crate::LineIndex;
+
+/// A key press, as far as the editor is concerned.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Key {
+    Char(char),
+    Left,
+    Right,
+    Home,
+    End,
+    Backspace,
+    Up,
+    Down,
+    Enter,
+    CtrlC,
+    CtrlD,
+}
+
+/// What a key press did to the entry.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Event {
+    /// The entry is still being edited.
+    Pending,
+    /// The entry is complete and was submitted; the buffer is now empty.
+    Submit(String),
+    /// The entry was abandoned with Ctrl-C; the buffer is now empty.
+    Cancelled,
+    /// Ctrl-D on an empty entry: the session should end.
+    Exit,
+}
+
+/// A multi-line entry being edited, with history to recall from.
+pub struct Editor {
+    buffer: Vec<char>,
+    cursor: usize,
+    history: Vec<String>,
+    /// The history entry being shown, if browsing.
+    recalled: Option<usize>,
+    /// The entry being typed before browsing started.
+    draft: String,
+    is_complete: fn(&str) -> bool,
+}
+
+impl Editor {
+    /// Creates an editor recalling from `history`, oldest first. Enter submits
+    /// the entry if `is_complete` holds for it, and opens a new line otherwise.
+    pub fn new(history: Vec<String>, is_complete: fn(&str) -> bool) -> Self {
+        Self {
+            buffer: Vec::new(),
+            cursor: 0,
+            history,
+            recalled: None,
+            draft: String::new(),
+            is_complete,
+        }
+    }
+
+    /// The entry as typed so far.
+    pub fn buffer(&self) -> String {
+        self.buffer.iter().collect()
+    }
+
+    /// The cursor position, in characters from the start of the entry.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The 0-based line and column of the cursor within the entry.
+    pub fn cursor_line_col(&self) -> (usize, usize) {
+        let (line, column) = LineIndex::new(&self.buffer()).offset_to_line_col(self.cursor);
+        (line - 1, column - 1)
+    }
+
+    /// Makes `entry` recallable, e.g. after it was submitted.
+    pub fn remember(&mut self, entry: &str) {
+        if self.history.last().map(String::as_str) != Some(entry) {
+            self.history.push(entry.to_string());
+        }
+    }
+
+    /// Applies `key` to the entry.
+    pub fn handle(&mut self, key: Key) -> Event {
+        match key {
+            Key::Char(c) => self.insert(c),
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(self.buffer.len()),
+            Key::Home => self.cursor = self.line_start(self.cursor),
+            Key::End => self.cursor = self.line_end(self.cursor),
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.buffer.remove(self.cursor);
+            }
+            Key::Backspace => {}
+            Key::Up => self.up(),
+            Key::Down => self.down(),
+            Key::Enter => {
+                let entry = self.buffer();
+                if (self.is_complete)(&entry) {
+                    self.reset();
+                    return Event::Submit(entry);
+                }
+                self.insert('\n');
+            }
+            Key::CtrlC => {
+                self.reset();
+                return Event::Cancelled;
+            }
+            Key::CtrlD if self.buffer.is_empty() => return Event::Exit,
+            Key::CtrlD => {}
+        }
+        Event::Pending
+    }
+
+    fn insert(&mut self, c: char) {
+        self.buffer.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    fn reset(&mut self) {
+        self.buffer.clear();
+        self.cursor = 0;
+        self.recalled = None;
+        self.draft.clear();
+    }
+
+    fn up(&mut self) {
+        let start = self.line_start(self.cursor);
+        if start > 0 {
+            let column = self.cursor - start;
+            let previous = self.line_start(start - 1);
+            self.cursor = (previous + column).min(start - 1);
+            return;
+        }
+        let index = match self.recalled {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.buffer();
+                self.history.len() - 1
+            }
+        };
+        self.recall(Some(index));
+    }
+
+    fn down(&mut self) {
+        let end = self.line_end(self.cursor);
+        if end < self.buffer.len() {
+            let column = self.cursor - self.line_start(self.cursor);
+            self.cursor = (end + 1 + column).min(self.line_end(end + 1));
+            return;
+        }
+        match self.recalled {
+            Some(index) if index + 1 < self.history.len() => self.recall(Some(index + 1)),
+            Some(_) => self.recall(None),
+            None => {}
+        }
+    }
+
+    /// Shows history entry `index`, or the draft for `None`, with the cursor
+    /// at its end.
+    fn recall(&mut self, index: Option<usize>) {
+        let entry = match index {
+            Some(index) => &self.history[index],
+            None => &self.draft,
+        };
+        self.buffer = entry.chars().collect();
+        self.cursor = self.buffer.len();
+        self.recalled = index;
+    }
+
+    fn line_start(&self, position: usize) -> usize {
+        self.buffer[..position]
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |newline| newline + 1)
+    }
+
+    fn line_end(&self, position: usize) -> usize {
+        self.buffer[position..]
+            .iter()
+            .position(|&c| c == '\n')
+            .map_or(self.buffer.len(), |newline| position + newline)
+    }
+}
@@ -0,0 +1,205 @@
+//! src/repl/terminal.rs
+
+/*******************************************************************************
+ *                              TERMINAL MODULE
+ *-------------------------------------------------------------------------------
+ * Drives an `Editor` from a Unix terminal. The terminal is put in raw mode
+ * with `stty` for the duration of one entry, so keys arrive one at a time and
+ * unechoed; escape sequences are decoded into keys, and after every key the
+ * whole entry is redrawn below the prompt.
+ *
+ * Only the common ANSI sequences are understood; anything else is ignored.
+ ******************************************************************************/
+
+use std::{
+    io::{self, Read, Write},
+    process::{Command, Stdio},
+};
+
+use super::editor::{Editor, Event, Key};
+use crate::LineIndex;
+
+/// Prompt shown before the first line of an entry.
+pub const PROMPT: &str = "> ";
+/// Prompt shown before every continuation line of an entry.
+pub const CONTINUATION: &str = ". ";
+
+/// Reads one entry from the terminal into `editor`, returning how it ended:
+/// submitted, cancelled, or the session ending.
+///
+/// # Errors
+///
+/// Returns an error if the terminal can't be switched to raw mode or read.
+pub fn read_entry(editor: &mut Editor) -> io::Result<Event> {
+    let _raw = RawMode::enable()?;
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    let mut drawn = Drawn::default();
+    drawn.redraw(&mut stdout, editor)?;
+    loop {
+        let Some(key) = read_key(&mut stdin)? else {
+            if editor.buffer().is_empty() {
+                return Ok(Event::Exit);
+            }
+            continue;
+        };
+        let event = editor.handle(key);
+        if event == Event::Pending {
+            drawn.redraw(&mut stdout, editor)?;
+            continue;
+        }
+        if let Event::Submit(entry) = &event {
+            drawn.show(&mut stdout, entry, entry.chars().count())?;
+        }
+        write!(stdout, "\r\n")?;
+        stdout.flush()?;
+        return Ok(event);
+    }
+}
+
+/// Restores the terminal settings it replaced when dropped.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        Ok(Self {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed; is stdin a terminal?"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Reads one key, or `None` at end of input or for an unknown sequence.
+fn read_key(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let Some(byte) = read_byte(input)? else {
+        return Ok(None);
+    };
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        0x03 => Key::CtrlC,
+        0x04 => Key::CtrlD,
+        0x01 => Key::Home,
+        0x05 => Key::End,
+        0x7f | 0x08 => Key::Backspace,
+        0x1b => return read_escape(input),
+        byte if byte < 0x20 => return Ok(None),
+        byte => return read_char(input, byte),
+    };
+    Ok(Some(key))
+}
+
+/// Decodes the rest of an escape sequence, `ESC [ X` or `ESC O X`.
+fn read_escape(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let Some(b'[' | b'O') = read_byte(input)? else {
+        return Ok(None);
+    };
+    let mut last = read_byte(input)?;
+    // Sequences like `ESC [ 1 ~` carry a number before the final byte.
+    let mut number = None;
+    while let Some(digit @ b'0'..=b'9') = last {
+        number = Some(digit);
+        last = read_byte(input)?;
+    }
+    let key = match (last, number) {
+        (Some(b'A'), _) => Key::Up,
+        (Some(b'B'), _) => Key::Down,
+        (Some(b'C'), _) => Key::Right,
+        (Some(b'D'), _) => Key::Left,
+        (Some(b'H'), _) | (Some(b'~'), Some(b'1' | b'7')) => Key::Home,
+        (Some(b'F'), _) | (Some(b'~'), Some(b'4' | b'8')) => Key::End,
+        _ => return Ok(None),
+    };
+    Ok(Some(key))
+}
+
+/// Decodes a UTF-8 character starting with `first`.
+fn read_char(input: &mut impl Read, first: u8) -> io::Result<Option<Key>> {
+    let width = match first {
+        0xf0.. => 4,
+        0xe0.. => 3,
+        0xc0.. => 2,
+        _ => 1,
+    };
+    let mut bytes = vec![first];
+    for _ in 1..width {
+        match read_byte(input)? {
+            Some(byte) => bytes.push(byte),
+            None => return Ok(None),
+        }
+    }
+    Ok(std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|text| text.chars().next())
+        .map(Key::Char))
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    match input.read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+/// Where the cursor was left by the last redraw.
+#[derive(Default)]
+struct Drawn {
+    /// Line of the entry the cursor is on, counted from the prompt line.
+    cursor_line: usize,
+}
+
+impl Drawn {
+    fn redraw(&mut self, out: &mut impl Write, editor: &Editor) -> io::Result<()> {
+        self.show(out, &editor.buffer(), editor.cursor())
+    }
+
+    /// Replaces what was drawn with `entry`, cursor at char offset `cursor`.
+    fn show(&mut self, out: &mut impl Write, entry: &str, cursor: usize) -> io::Result<()> {
+        if self.cursor_line > 0 {
+            write!(out, "\x1b[{}A", self.cursor_line)?;
+        }
+        write!(out, "\r\x1b[J")?;
+
+        let index = LineIndex::new(entry);
+        for line in 1..=index.line_count() {
+            let prompt = if line == 1 { PROMPT } else { CONTINUATION };
+            if line > 1 {
+                write!(out, "\r\n")?;
+            }
+            let text = index.line_text(entry, line).unwrap_or_default();
+            write!(out, "{}{}", prompt, text)?;
+        }
+
+        let (line, column) = index.offset_to_line_col(cursor);
+        let (line, column) = (line - 1, column - 1 + PROMPT.len());
+        if index.line_count() - 1 > line {
+            write!(out, "\x1b[{}A", index.line_count() - 1 - line)?;
+        }
+        write!(out, "\r")?;
+        if column > 0 {
+            write!(out, "\x1b[{}C", column)?;
+        }
+        self.cursor_line = line;
+        out.flush()
+    }
+}
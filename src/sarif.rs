@@ -0,0 +1,126 @@
+//! src/sarif.rs
+
+/*******************************************************************************
+ *                               SARIF MODULE
+ *-------------------------------------------------------------------------------
+ * Serializes diagnostics as a minimal SARIF 2.1.0 log, the format CI systems
+ * use for inline pull request annotations. One log holds a single run of
+ * `rdp`, with one rule per diagnostic code and one result per diagnostic.
+ *
+ * Diagnostics do not carry source positions yet, so each result points at
+ * its file as a whole.
+ ******************************************************************************/
+
+use std::{collections::BTreeSet, path::Path};
+
+use crate::{json::Json, Diagnostic, Severity};
+
+/// Renders the diagnostics of a single file as a SARIF log.
+pub fn diagnostics_to_sarif(diagnostics: &[Diagnostic], file: &Path) -> String {
+    let mut log = SarifLog::new();
+    log.add_file(file, diagnostics);
+    log.to_json()
+}
+
+/// Accumulates the diagnostics of several files into one SARIF log.
+#[derive(Debug, Default)]
+pub struct SarifLog {
+    results: Vec<(String, Diagnostic)>,
+}
+
+impl SarifLog {
+    /// Creates an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds every diagnostic reported for `file`.
+    pub fn add_file(&mut self, file: &Path, diagnostics: &[Diagnostic]) {
+        let mut uri = file.to_string_lossy().replace('\\', "/");
+        if file.is_absolute() {
+            uri = format!("file://{}", uri);
+        }
+        for diagnostic in diagnostics {
+            self.results.push((uri.clone(), diagnostic.clone()));
+        }
+    }
+
+    /// Renders the log as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        // Rules are listed once per code, sorted so the output is stable.
+        let rules: Vec<&str> = self
+            .results
+            .iter()
+            .map(|(_, diagnostic)| diagnostic.code)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let results = self
+            .results
+            .iter()
+            .map(|(uri, diagnostic)| {
+                let rule_index = rules.iter().position(|r| *r == diagnostic.code).unwrap();
+                Json::Object(vec![
+                    ("ruleId", Json::string(diagnostic.code)),
+                    ("ruleIndex", Json::Number(rule_index)),
+                    ("level", Json::string(level(diagnostic.severity))),
+                    (
+                        "message",
+                        Json::Object(vec![("text", Json::string(&diagnostic.message))]),
+                    ),
+                    (
+                        "locations",
+                        Json::Array(vec![Json::Object(vec![(
+                            "physicalLocation",
+                            Json::Object(vec![(
+                                "artifactLocation",
+                                Json::Object(vec![("uri", Json::string(uri))]),
+                            )]),
+                        )])]),
+                    ),
+                ])
+            })
+            .collect();
+
+        let driver = Json::Object(vec![
+            ("name", Json::string(env!("CARGO_PKG_NAME"))),
+            ("version", Json::string(env!("CARGO_PKG_VERSION"))),
+            ("informationUri", Json::string(env!("CARGO_PKG_REPOSITORY"))),
+            (
+                "rules",
+                Json::Array(
+                    rules
+                        .iter()
+                        .map(|code| Json::Object(vec![("id", Json::string(code))]))
+                        .collect(),
+                ),
+            ),
+        ]);
+
+        let log = Json::Object(vec![
+            (
+                "$schema",
+                Json::string("https://json.schemastore.org/sarif-2.1.0.json"),
+            ),
+            ("version", Json::string("2.1.0")),
+            (
+                "runs",
+                Json::Array(vec![Json::Object(vec![
+                    ("tool", Json::Object(vec![("driver", driver)])),
+                    ("results", Json::Array(results)),
+                ])]),
+            ),
+        ]);
+
+        log.to_pretty_string()
+    }
+}
+
+/// Maps a severity onto the SARIF `level` property.
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
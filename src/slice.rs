@@ -0,0 +1,128 @@
+//! src/slice.rs
+
+/*******************************************************************************
+ *                               SLICE MODULE
+ *-------------------------------------------------------------------------------
+ * Program slicing: cutting a program down to one expression and the bindings
+ * it transitively depends on, for debugging and for extracting examples.
+ *
+ * The slice is rebuilt along the path from the target up to the root. Each
+ * enclosing binder is kept only if the slice so far uses its name, and
+ * everything else on the way (other branches, unrelated `let`s) is dropped.
+ * Because kept binders stay in their original nesting order, every name in
+ * the slice refers to the same binding it did in the program.
+ *
+ * A `let` is kept as a `let`, with its whole value, and of a `let ... and`
+ * group only the bindings the slice uses are kept. Lambda parameters and
+ * match-arm pattern variables have no value to keep, so they become lambda
+ * parameters of the slice: it then evaluates to a function of those inputs,
+ * outermost binder first, rather than to the target's value directly.
+ ******************************************************************************/
+
+use crate::{binders_for, free_variables, Expression, LetBinding, NodeId, Program};
+
+/// Cuts `program` down to the expression `target` and the bindings around it
+/// that it transitively uses.
+///
+/// # Panics
+///
+/// Panics if `target` is not a node of `program`'s index.
+pub fn slice(program: &Program, target: NodeId) -> Program {
+    let index = program.build_index();
+    let mut sliced = index.node(target).clone();
+    let mut needed = free_variables(&sliced);
+
+    let mut child = target;
+    while let (Some(parent), Some(step)) = (index.parent(child), index.step(child)) {
+        match index.node(parent) {
+            Expression::LetExpr {
+                recursive,
+                identifier,
+                type_annotation,
+                value,
+                ..
+            } if needed.remove(identifier) => {
+                // `let` is recursive: its value may use its own name.
+                let mut uses = free_variables(value);
+                uses.remove(identifier);
+                needed.extend(uses);
+                sliced = Expression::LetExpr {
+                    recursive: *recursive,
+                    identifier: identifier.clone(),
+                    type_annotation: type_annotation.clone(),
+                    value: value.clone(),
+                    body: Box::new(sliced),
+                };
+            }
+            Expression::LetAnd {
+                recursive,
+                bindings,
+                ..
+            } if bindings
+                .iter()
+                .any(|binding| needed.contains(&binding.identifier)) =>
+            {
+                // Keep the bindings of the group the slice uses, directly or
+                // through one another.
+                let mut kept = vec![false; bindings.len()];
+                while let Some(i) = (0..bindings.len())
+                    .find(|&i| !kept[i] && needed.contains(&bindings[i].identifier))
+                {
+                    kept[i] = true;
+                    needed.extend(free_variables(&bindings[i].value));
+                }
+                for binding in bindings {
+                    needed.remove(&binding.identifier);
+                }
+                let mut kept: Vec<LetBinding> = bindings
+                    .iter()
+                    .zip(kept)
+                    .filter(|(_, kept)| *kept)
+                    .map(|(binding, _)| binding.clone())
+                    .collect();
+                sliced = if kept.len() == 1 {
+                    let binding = kept.remove(0);
+                    Expression::LetExpr {
+                        recursive: *recursive,
+                        identifier: binding.identifier,
+                        type_annotation: binding.type_annotation,
+                        value: binding.value,
+                        body: Box::new(sliced),
+                    }
+                } else {
+                    Expression::LetAnd {
+                        recursive: *recursive,
+                        bindings: kept,
+                        body: Box::new(sliced),
+                    }
+                };
+            }
+            Expression::Lambda {
+                parameter,
+                type_annotation,
+                ..
+            } if needed.remove(parameter) => {
+                sliced = Expression::Lambda {
+                    parameter: parameter.clone(),
+                    type_annotation: type_annotation.clone(),
+                    body: Box::new(sliced),
+                };
+            }
+            node @ Expression::PatternMatch { .. } => {
+                for name in binders_for(node, step).into_iter().rev() {
+                    if needed.remove(&name) {
+                        sliced = Expression::Lambda {
+                            parameter: name,
+                            type_annotation: None,
+                            body: Box::new(sliced),
+                        };
+                    }
+                }
+            }
+            _ => {}
+        }
+        child = parent;
+    }
+
+    Program { expression: sliced }
+}
@@ -0,0 +1,168 @@
+//! src/source.rs
+
+/*******************************************************************************
+ *                               SOURCE MODULE
+ *-------------------------------------------------------------------------------
+ * Source files and the registry that owns them. Tools that handle several
+ * files at once add each to a `SourceMap`, which hands out a `FileId`, and
+ * refer to files by id from then on. A diagnostic can carry a `Location`, an
+ * id plus an optional span, so a report covering many files names the file
+ * every finding belongs to, and the line and column when it has a span.
+ *
+ * Each file builds its `LineIndex` on first use, so files whose positions are
+ * never asked for never pay for the scan.
+ ******************************************************************************/
+
+use std::{fs, io, path::Path, sync::OnceLock};
+
+use crate::{
+    validate::parse_and_lint, Diagnostic, LineIndex, ParseError, Span, ValidateConfig,
+    ValidationReport,
+};
+
+/// Identifies a file within the `SourceMap` that assigned it.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct FileId(usize);
+
+/// Where a diagnostic points: a file, and the characters within it when known.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Location {
+    pub file: FileId,
+    pub span: Option<Span>,
+}
+
+/// One source text and the name it is reported under.
+#[derive(Debug)]
+pub struct SourceFile {
+    id: FileId,
+    name: String,
+    text: String,
+    line_index: OnceLock<LineIndex>,
+}
+
+impl SourceFile {
+    pub fn id(&self) -> FileId {
+        self.id
+    }
+
+    /// The name shown in reports, such as the path the file was read from.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The file's line index, built on first use.
+    pub fn line_index(&self) -> &LineIndex {
+        self.line_index.get_or_init(|| LineIndex::new(&self.text))
+    }
+
+    /// Lexes, parses, and lints the file as `config` says. Every diagnostic
+    /// is located in this file, with a span when the error carries one.
+    pub fn validate(&self, config: &ValidateConfig) -> ValidationReport {
+        match parse_and_lint(&self.text, config) {
+            Ok((program, diagnostics)) => ValidationReport {
+                program: Some(program),
+                diagnostics: diagnostics
+                    .into_iter()
+                    .map(|mut diagnostic| {
+                        diagnostic.location = Some(self.location(None));
+                        diagnostic
+                    })
+                    .collect(),
+            },
+            Err(err) => ValidationReport {
+                program: None,
+                diagnostics: vec![self.diagnose(&err)],
+            },
+        }
+    }
+
+    /// Reports `err`, raised while lexing or parsing this file, as a
+    /// diagnostic located in it.
+    pub fn diagnose(&self, err: &ParseError) -> Diagnostic {
+        Diagnostic {
+            location: Some(self.location(err.span(self.line_index()))),
+            ..Diagnostic::from(err)
+        }
+    }
+
+    fn location(&self, span: Option<Span>) -> Location {
+        Location {
+            file: self.id,
+            span,
+        }
+    }
+}
+
+/// Every source file of one run, by id.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `text` under `name` and returns its id.
+    pub fn add(&mut self, name: impl Into<String>, text: impl Into<String>) -> FileId {
+        let id = FileId(self.files.len());
+        self.files.push(SourceFile {
+            id,
+            name: name.into(),
+            text: text.into(),
+            line_index: OnceLock::new(),
+        });
+        id
+    }
+
+    /// Reads the file at `path` and adds it under its path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read as UTF-8 text.
+    pub fn load(&mut self, path: &Path) -> io::Result<FileId> {
+        let text = fs::read_to_string(path)?;
+        Ok(self.add(path.display().to_string(), text))
+    }
+
+    /// The file with id `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` was assigned by another map.
+    pub fn get(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0]
+    }
+
+    /// Every file, in the order they were added.
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    /// The file name and, if the location has a span, the 1-based line and
+    /// column where it starts.
+    pub fn resolve(&self, location: Location) -> (&str, Option<(usize, usize)>) {
+        let file = self.get(location.file);
+        let line_col = location
+            .span
+            .map(|span| file.line_index().offset_to_line_col(span.start));
+        (file.name(), line_col)
+    }
+
+    /// Renders `diagnostic` prefixed by where it points, as
+    /// `name:line:col: warning[code]: message`, or `name: ...` without a span.
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        match diagnostic.location.map(|location| self.resolve(location)) {
+            Some((name, Some((line, column)))) => {
+                format!("{}:{}:{}: {}", name, line, column, diagnostic)
+            }
+            Some((name, None)) => format!("{}: {}", name, diagnostic),
+            None => diagnostic.to_string(),
+        }
+    }
+}
@@ -0,0 +1,329 @@
+//! src/specialize.rs
+
+/*******************************************************************************
+ *                             SPECIALIZE MODULE
+ *-------------------------------------------------------------------------------
+ * Partial evaluation of a program against bindings known ahead of time, such
+ * as the parameters of a formula that are fixed at deployment. Known names
+ * are replaced by their values, and whatever then becomes constant is folded:
+ *
+ *  - arithmetic on numbers, including negation, is computed, except
+ *    division or remainder by zero;
+ *  - an `if` whose condition is a Boolean literal or compares constants keeps
+ *    only the branch taken;
+ *  - a `match` on a number keeps only the arm it selects, with the pattern's
+ *    variable, if any, bound to that number, unless a guarded arm could be
+ *    taken first;
+ *  - a `let` whose value becomes a number is substituted into its body.
+ *
+ * The result is a smaller program that, evaluated with the remaining
+ * bindings, gives the same result as the original evaluated with all of
+ * them. The language has no side effects, so dropping code that can no longer
+ * run is always safe. Only numbers can be known ahead of time.
+ ******************************************************************************/
+
+use std::collections::HashMap;
+
+use crate::{
+    compile_match, ArithmeticOperator, ComparisonOperator, Expression, FunctionComposition,
+    LetBinding, LogicOperator, MatchArm, Pattern, Program, Term, UnaryOperator,
+};
+
+/// Specializes `program` for the free names in `known` having those values.
+pub fn specialize(program: &Program, known: &HashMap<String, f64>) -> Program {
+    Program {
+        expression: specialize_expression(&program.expression, known),
+    }
+}
+
+fn specialize_expression(expression: &Expression, known: &HashMap<String, f64>) -> Expression {
+    let recurse = |expression: &Expression| Box::new(specialize_expression(expression, known));
+    match expression {
+        Expression::Term(Term::Identifier(name)) => match known.get(name) {
+            Some(&value) => Expression::Term(Term::Number(value)),
+            None => expression.clone(),
+        },
+        Expression::Term(Term::Number(_) | Term::String(_) | Term::Boolean(_)) => {
+            expression.clone()
+        }
+        Expression::Term(Term::GroupedExpression(inner)) => match *recurse(inner) {
+            number @ Expression::Term(Term::Number(_)) => number,
+            inner => Expression::Term(Term::GroupedExpression(Box::new(inner))),
+        },
+        Expression::Term(Term::MemberAccess { expression, member }) => {
+            Expression::Term(Term::MemberAccess {
+                expression: recurse(expression),
+                member: member.clone(),
+            })
+        }
+        Expression::Term(Term::Tuple(elements)) => Expression::Term(Term::Tuple(
+            elements
+                .iter()
+                .map(|element| specialize_expression(element, known))
+                .collect(),
+        )),
+        Expression::Term(Term::List(elements)) => Expression::Term(Term::List(
+            elements
+                .iter()
+                .map(|element| specialize_expression(element, known))
+                .collect(),
+        )),
+        Expression::LetExpr {
+            recursive,
+            identifier,
+            type_annotation,
+            value,
+            body,
+        } => {
+            // `let` is recursive, so the name is shadowed in its value too.
+            let mut inner = without(known, [identifier]);
+            let value = specialize_expression(value, &inner);
+            if let Some(number) = number(&value) {
+                inner.insert(identifier.clone(), number);
+                return specialize_expression(body, &inner);
+            }
+            Expression::LetExpr {
+                recursive: *recursive,
+                identifier: identifier.clone(),
+                type_annotation: type_annotation.clone(),
+                value: Box::new(value),
+                body: Box::new(specialize_expression(body, &inner)),
+            }
+        }
+        Expression::LetAnd {
+            recursive,
+            bindings,
+            body,
+        } => {
+            // Every name of the group is in scope in every value.
+            let inner = without(known, bindings.iter().map(|binding| &binding.identifier));
+            Expression::LetAnd {
+                recursive: *recursive,
+                bindings: bindings
+                    .iter()
+                    .map(|binding| LetBinding {
+                        identifier: binding.identifier.clone(),
+                        type_annotation: binding.type_annotation.clone(),
+                        value: Box::new(specialize_expression(&binding.value, &inner)),
+                    })
+                    .collect(),
+                body: Box::new(specialize_expression(body, &inner)),
+            }
+        }
+        Expression::Lambda {
+            parameter,
+            type_annotation,
+            body,
+        } => Expression::Lambda {
+            parameter: parameter.clone(),
+            type_annotation: type_annotation.clone(),
+            body: Box::new(specialize_expression(body, &without(known, [parameter]))),
+        },
+        Expression::IfExpr {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            let condition = specialize_expression(condition, known);
+            match truth(&condition) {
+                Some(true) => specialize_expression(then_branch, known),
+                Some(false) => specialize_expression(else_branch, known),
+                None => Expression::IfExpr {
+                    condition: Box::new(condition),
+                    then_branch: recurse(then_branch),
+                    else_branch: recurse(else_branch),
+                },
+            }
+        }
+        Expression::PatternMatch { expression, arms } => {
+            let scrutinee = specialize_expression(expression, known);
+            if let Some(value) = number(&scrutinee) {
+                let selection = compile_match(arms).select(value);
+                if let (Some(arm), false) = (selection.arm, selection.undecided) {
+                    let mut inner = known.clone();
+                    if let Some(name) = binder(&arms[arm].pattern) {
+                        inner.insert(name.to_string(), value);
+                    }
+                    return specialize_expression(&arms[arm].expression, &inner);
+                }
+            }
+            Expression::PatternMatch {
+                expression: Box::new(scrutinee),
+                arms: arms
+                    .iter()
+                    .map(|arm| {
+                        let inner = without(known, bound_names(&arm.pattern));
+                        MatchArm {
+                            pattern: arm.pattern.clone(),
+                            guard: arm
+                                .guard
+                                .as_ref()
+                                .map(|guard| Box::new(specialize_expression(guard, &inner))),
+                            expression: Box::new(specialize_expression(&arm.expression, &inner)),
+                        }
+                    })
+                    .collect(),
+            }
+        }
+        Expression::Arithmetic {
+            left,
+            operator,
+            right,
+        } => {
+            let (left, right) = (recurse(left), recurse(right));
+            match (number(&left), number(&right)) {
+                (Some(a), Some(b))
+                    if !(matches!(
+                        operator,
+                        ArithmeticOperator::Divide | ArithmeticOperator::Modulo
+                    ) && b == 0.0) =>
+                {
+                    Expression::Term(Term::Number(match operator {
+                        ArithmeticOperator::Add => a + b,
+                        ArithmeticOperator::Subtract => a - b,
+                        ArithmeticOperator::Multiply => a * b,
+                        ArithmeticOperator::Divide => a / b,
+                        ArithmeticOperator::Modulo => a % b,
+                        ArithmeticOperator::Power => a.powf(b),
+                    }))
+                }
+                _ => Expression::Arithmetic {
+                    left,
+                    operator: operator.clone(),
+                    right,
+                },
+            }
+        }
+        Expression::Comparison {
+            left,
+            operator,
+            right,
+        } => Expression::Comparison {
+            left: recurse(left),
+            operator: operator.clone(),
+            right: right.as_deref().map(recurse),
+        },
+        Expression::Logic {
+            left,
+            operator,
+            right,
+        } => Expression::Logic {
+            left: recurse(left),
+            operator: operator.clone(),
+            right: right.as_deref().map(recurse),
+        },
+        Expression::List {
+            left,
+            operator,
+            right,
+        } => Expression::List {
+            left: recurse(left),
+            operator: operator.clone(),
+            right: recurse(right),
+        },
+        Expression::Unary { operator, operand } => {
+            let operand = recurse(operand);
+            match (operator, number(&operand)) {
+                (UnaryOperator::Negate, Some(value)) => Expression::Term(Term::Number(-value)),
+                _ => Expression::Unary {
+                    operator: operator.clone(),
+                    operand,
+                },
+            }
+        }
+        Expression::Application(items) => Expression::Application(
+            items
+                .iter()
+                .map(|item| specialize_expression(item, known))
+                .collect(),
+        ),
+        Expression::FunctionComposition(FunctionComposition { f, g }) => {
+            Expression::FunctionComposition(FunctionComposition {
+                f: recurse(f),
+                g: recurse(g),
+            })
+        }
+    }
+}
+
+/// `known` without the names in `shadowed`.
+fn without<'a>(
+    known: &HashMap<String, f64>,
+    shadowed: impl IntoIterator<Item = &'a String>,
+) -> HashMap<String, f64> {
+    let mut known = known.clone();
+    for name in shadowed {
+        known.remove(name);
+    }
+    known
+}
+
+/// The value of `expression` if it is a number literal.
+fn number(expression: &Expression) -> Option<f64> {
+    match expression {
+        Expression::Term(Term::Number(value)) => Some(*value),
+        Expression::Term(Term::GroupedExpression(inner)) => number(inner),
+        _ => None,
+    }
+}
+
+/// The value of `condition` if it is a Boolean literal or only compares
+/// constants.
+fn truth(condition: &Expression) -> Option<bool> {
+    match condition {
+        Expression::Term(Term::Boolean(value)) => Some(*value),
+        Expression::Comparison {
+            left,
+            operator,
+            right: Some(right),
+        } => {
+            let (a, b) = (number(left)?, number(right)?);
+            Some(match operator {
+                ComparisonOperator::Equal => a == b,
+                ComparisonOperator::NotEqual => a != b,
+                ComparisonOperator::LessThan => a < b,
+                ComparisonOperator::GreaterThan => a > b,
+                ComparisonOperator::LessEqual => a <= b,
+                ComparisonOperator::GreaterEqual => a >= b,
+            })
+        }
+        Expression::Logic {
+            left,
+            operator,
+            right: Some(right),
+        } => {
+            let (a, b) = (truth(left)?, truth(right)?);
+            Some(match operator {
+                LogicOperator::And => a && b,
+                LogicOperator::Or => a || b,
+            })
+        }
+        Expression::Comparison { left, .. } | Expression::Logic { left, .. } => truth(left),
+        Expression::Term(Term::GroupedExpression(inner)) => truth(inner),
+        _ => None,
+    }
+}
+
+/// The name `pattern` binds the whole scrutinee to, if any.
+fn binder(pattern: &Pattern) -> Option<&String> {
+    match pattern {
+        Pattern::Identifier(name) if name != "_" => Some(name),
+        Pattern::Identifier(_) | Pattern::Number(_) | Pattern::Boolean(_) | Pattern::Cons(..) => {
+            None
+        }
+        Pattern::Grouped(inner) => binder(inner),
+    }
+}
+
+/// Every name `pattern` binds, including those inside a cons.
+fn bound_names(pattern: &Pattern) -> Vec<&String> {
+    match pattern {
+        Pattern::Cons(head, tail) => {
+            let mut names = bound_names(head);
+            names.extend(bound_names(tail));
+            names
+        }
+        Pattern::Grouped(inner) => bound_names(inner),
+        _ => binder(pattern).into_iter().collect(),
+    }
+}
@@ -0,0 +1,202 @@
+//! src/stats.rs
+
+/*******************************************************************************
+ *                               STATS MODULE
+ *-------------------------------------------------------------------------------
+ * Usage statistics over a corpus of programs: how often each token kind,
+ * operator, construct, and identifier appears. Meant to inform language
+ * changes (precedence, new keywords) with data from real code.
+ *
+ * Token counts come from token streams, everything else from ASTs, so callers
+ * feed both via `record_tokens` and `record_program`. `collect_stats` covers
+ * the common AST-only case, and `merge` combines per-file results.
+ ******************************************************************************/
+
+use std::{collections::BTreeMap, fmt};
+
+use crate::{json::Json, Expression, Program, Term, Token};
+
+/// Aggregated usage counts. Every map is keyed by name and ordered, so output
+/// is deterministic.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Stats {
+    /// Number of programs recorded.
+    pub programs: usize,
+    /// Token kind (e.g. `Let`, `Identifier`) to occurrences, excluding `Eof`.
+    pub tokens: BTreeMap<String, usize>,
+    /// Operator symbol (e.g. `+`, `==`, `.`) to occurrences.
+    pub operators: BTreeMap<String, usize>,
+    /// Construct (e.g. `let`, `match-arm`, `application`) to occurrences.
+    pub constructs: BTreeMap<String, usize>,
+    /// Identifier to occurrences, counting both bindings and references.
+    pub identifiers: BTreeMap<String, usize>,
+}
+
+/// Collects operator, construct, and identifier counts for `programs`.
+pub fn collect_stats(programs: &[Program]) -> Stats {
+    let mut stats = Stats::default();
+    for program in programs {
+        stats.record_program(program);
+    }
+    stats
+}
+
+impl Stats {
+    /// Counts the tokens of one program by kind.
+    pub fn record_tokens(&mut self, tokens: &[Token]) {
+        for token in tokens.iter().filter(|token| **token != Token::Eof) {
+            bump(&mut self.tokens, token_kind(token));
+        }
+    }
+
+    /// Counts the operators, constructs, and identifiers of one program.
+    pub fn record_program(&mut self, program: &Program) {
+        self.programs += 1;
+        program
+            .expression
+            .walk(&mut |expression| self.record(expression));
+    }
+
+    /// Adds every count of `other` to this one.
+    pub fn merge(&mut self, other: &Stats) {
+        self.programs += other.programs;
+        for (mine, theirs) in [
+            (&mut self.tokens, &other.tokens),
+            (&mut self.operators, &other.operators),
+            (&mut self.constructs, &other.constructs),
+            (&mut self.identifiers, &other.identifiers),
+        ] {
+            for (name, count) in theirs {
+                *mine.entry(name.clone()).or_default() += count;
+            }
+        }
+    }
+
+    /// Returns the `n` most frequent identifiers, most frequent first. Ties are
+    /// ordered by name.
+    pub fn top_identifiers(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut identifiers = rows(&self.identifiers);
+        identifiers.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        identifiers.truncate(n);
+        identifiers
+    }
+
+    /// Renders the statistics as a pretty-printed JSON document. Identifiers
+    /// are listed in full, most frequent first.
+    pub fn to_json(&self) -> String {
+        let counts = |entries: Vec<(&str, usize)>| {
+            Json::Array(
+                entries
+                    .into_iter()
+                    .map(|(name, count)| {
+                        Json::Object(vec![
+                            ("name", Json::string(name)),
+                            ("count", Json::Number(count)),
+                        ])
+                    })
+                    .collect(),
+            )
+        };
+        Json::Object(vec![
+            ("programs", Json::Number(self.programs)),
+            ("tokens", counts(rows(&self.tokens))),
+            ("operators", counts(rows(&self.operators))),
+            ("constructs", counts(rows(&self.constructs))),
+            (
+                "identifiers",
+                counts(self.top_identifiers(self.identifiers.len())),
+            ),
+        ])
+        .to_pretty_string()
+    }
+
+    fn record(&mut self, expression: &Expression) {
+        match expression {
+            Expression::LetExpr { identifier, .. } => {
+                bump(&mut self.constructs, "let");
+                bump(&mut self.identifiers, identifier);
+            }
+            Expression::LetAnd { bindings, .. } => {
+                bump(&mut self.constructs, "let");
+                for binding in bindings {
+                    bump(&mut self.identifiers, &binding.identifier);
+                }
+            }
+            Expression::IfExpr { .. } => bump(&mut self.constructs, "if"),
+            Expression::Lambda { parameter, .. } => {
+                bump(&mut self.constructs, "lambda");
+                bump(&mut self.identifiers, parameter);
+            }
+            Expression::PatternMatch { arms, .. } => {
+                bump(&mut self.constructs, "match");
+                *self.constructs.entry("match-arm".to_string()).or_default() += arms.len();
+                for _ in arms.iter().filter(|arm| arm.guard.is_some()) {
+                    bump(&mut self.constructs, "match-guard");
+                }
+            }
+            Expression::Unary { operator, .. } => bump(&mut self.operators, operator.symbol()),
+            Expression::Application(_) => bump(&mut self.constructs, "application"),
+            Expression::Term(Term::Identifier(name)) => bump(&mut self.identifiers, name),
+            Expression::Term(Term::GroupedExpression(_)) => bump(&mut self.constructs, "grouped"),
+            Expression::Term(Term::MemberAccess { .. }) => {
+                bump(&mut self.constructs, "member-access")
+            }
+            Expression::Term(Term::Tuple(_)) => bump(&mut self.constructs, "tuple"),
+            Expression::Term(Term::List(_)) => bump(&mut self.constructs, "list-literal"),
+            Expression::Term(Term::Number(_) | Term::String(_) | Term::Boolean(_)) => {}
+            Expression::Comparison { .. }
+            | Expression::Logic { .. }
+            | Expression::Arithmetic { .. }
+            | Expression::List { .. }
+            | Expression::FunctionComposition(_) => {
+                if let Some((operator, _, _)) = expression.as_binary() {
+                    bump(&mut self.operators, operator.symbol());
+                }
+            }
+        }
+    }
+}
+
+/// Renders the statistics as plain-text tables, listing the ten most frequent
+/// identifiers.
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "programs: {}", self.programs)?;
+        let sections: [(&str, Vec<(&str, usize)>); 4] = [
+            ("tokens", rows(&self.tokens)),
+            ("operators", rows(&self.operators)),
+            ("constructs", rows(&self.constructs)),
+            ("identifiers (top 10)", self.top_identifiers(10)),
+        ];
+        for (title, rows) in sections {
+            writeln!(f, "\n{}:", title)?;
+            let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+            for (name, count) in rows {
+                writeln!(f, "  {:<width$}  {:>6}", name, count, width = width)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Names a token by its kind, dropping any payload: `Identifier("x")` becomes
+/// `Identifier`.
+fn token_kind(token: &Token) -> String {
+    let debug = format!("{:?}", token);
+    match debug.split_once('(') {
+        Some((kind, _)) => kind.to_string(),
+        None => debug,
+    }
+}
+
+/// Lists the entries of `counts` in name order.
+fn rows(counts: &BTreeMap<String, usize>) -> Vec<(&str, usize)> {
+    counts
+        .iter()
+        .map(|(name, count)| (name.as_str(), *count))
+        .collect()
+}
+
+fn bump(counts: &mut BTreeMap<String, usize>, name: impl Into<String>) {
+    *counts.entry(name.into()).or_default() += 1;
+}
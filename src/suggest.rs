@@ -0,0 +1,87 @@
+//! src/suggest.rs
+
+/*******************************************************************************
+ *                              SUGGEST MODULE
+ *-------------------------------------------------------------------------------
+ * "Did you mean ...?" support for diagnostics. Given a misspelled name and the
+ * names that would have been valid in its place, `suggest` picks the closest
+ * candidate by Damerau-Levenshtein distance (optimal string alignment variant:
+ * insertions, deletions, substitutions, and adjacent transpositions).
+ *
+ * The distance is capped relative to the length of the name, so short names
+ * only tolerate a single typo and unrelated names never produce a suggestion.
+ ******************************************************************************/
+
+/// Returns the candidate closest to `name`, if any is close enough to be a
+/// plausible typo.
+///
+/// Candidates identical to `name` are ignored. Ties are broken by picking the
+/// lexicographically smallest candidate, so the result does not depend on the
+/// order of `candidates`.
+pub fn suggest<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let cap = max_distance(name);
+
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .filter_map(|candidate| {
+            bounded_distance(name, candidate, cap).map(|distance| (distance, candidate))
+        })
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Largest edit distance still considered a typo of `name`: one edit per three
+/// characters, and at least one.
+fn max_distance(name: &str) -> usize {
+    (name.chars().count() / 3).max(1)
+}
+
+/// Computes the optimal string alignment distance between `a` and `b`, giving
+/// up with `None` as soon as it is certain to exceed `cap`.
+fn bounded_distance(a: &str, b: &str, cap: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // The length difference alone is a lower bound on the distance.
+    if a.len().abs_diff(b.len()) > cap {
+        return None;
+    }
+
+    // Three rolling rows: two rows back (for transpositions), previous, current.
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        let mut row_min = current[0];
+
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(before[j - 2] + 1);
+            }
+
+            current[j] = distance;
+            row_min = row_min.min(distance);
+        }
+
+        // Every later row is at least this row's minimum.
+        if row_min > cap {
+            return None;
+        }
+
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    Some(previous[b.len()]).filter(|distance| *distance <= cap)
+}
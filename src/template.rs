@@ -0,0 +1,1055 @@
+//! src/template.rs
+
+/*******************************************************************************
+ *                              TEMPLATE MODULE
+ *-------------------------------------------------------------------------------
+ * Structural search over ASTs. A template is an expression in which `$name`
+ * metavariables stand for any sub-expression, e.g. `if $c then $x else $x`.
+ * A metavariable used more than once must match alpha-equivalent sub-trees
+ * each time: trees that are identical up to the names of their binders.
+ *
+ * Matching is structural and ignores redundant parentheses. Names bound
+ * inside a template (`\x -> x`) match any consistently renamed binder, and a
+ * name the template leaves free matches only the same free name.
+ *
+ * A second template can replace every match. Rewrites that would change what
+ * a name refers to are refused, match by match, with a warning. The others
+ * come back as edits to the source text over the spans of the matches, so
+ * the formatting around them is kept. A match with no span of its own, like
+ * the inner lambda of `\x y -> e`, is rewritten by reprinting the nearest
+ * expression around it that has one.
+ *
+ * A template can also be instantiated directly, each metavariable filled with
+ * an expression the caller provides. Splicing is hygienic: a binder of the
+ * template that would capture a free name of a splice is renamed, with
+ * primes added, so every name in a splice keeps referring outside the
+ * template.
+ ******************************************************************************/
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::{error, fmt};
+
+use crate::{
+    index::child_expressions, print_expression, printer::operand, AstIndex, Diagnostic, Expression,
+    FunctionComposition, LetBinding, Lexer, MatchArm, NodeId, ParseError, Parser, Pattern, Program,
+    Span, Step, Term, TextEdit,
+};
+
+/// A parsed search template.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Template {
+    expression: Expression,
+}
+
+/// One place where a template matched.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TemplateMatch<'a> {
+    /// The matched expression's id in the program's `AstIndex`.
+    pub node: NodeId,
+    /// The matched expression.
+    pub expression: &'a Expression,
+    /// What each metavariable, keyed with its `$`, matched.
+    pub bindings: BTreeMap<String, &'a Expression>,
+}
+
+/// The outcome of [`Template::rewrite`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rewrite {
+    /// Edits to the source replacing every accepted match, none overlapping.
+    /// Apply them with [`crate::apply_edits`].
+    pub edits: Vec<TextEdit>,
+    /// Number of matches replaced.
+    pub rewritten: usize,
+    /// One warning per match left unchanged, explaining why.
+    pub refused: Vec<Diagnostic>,
+}
+
+/// Why [`Template::instantiate`] could not fill a template.
+#[derive(Debug, PartialEq, Clone)]
+pub enum InstantiateError {
+    /// A metavariable of the template, named with its `$`, has no binding.
+    MissingBinding(String),
+
+    /// A binding names no metavariable of the template.
+    ExtraBinding(String),
+}
+
+impl fmt::Display for InstantiateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstantiateError::MissingBinding(name) => {
+                write!(f, "no expression was given for `{}`", name)
+            }
+            InstantiateError::ExtraBinding(name) => {
+                write!(f, "`{}` is not a metavariable of the template", name)
+            }
+        }
+    }
+}
+
+impl error::Error for InstantiateError {}
+
+/// Parses `source` as a template, accepting `$name` metavariables wherever
+/// an identifier may appear.
+///
+/// # Errors
+/// Returns a `ParseError` if `source` is not a valid template.
+pub fn parse_template(source: &str) -> Result<Template, ParseError> {
+    let tokens = Lexer::template(source).tokenize()?;
+    let program = Parser::new(tokens).parse_program()?;
+    Ok(Template {
+        expression: program.expression,
+    })
+}
+
+impl Template {
+    /// The template as an expression; metavariables are identifiers starting
+    /// with `$`.
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    /// Finds every expression of `program` the template matches, in pre-order.
+    /// Matches may nest. A parenthesized expression is reported once, as the
+    /// expression inside the parentheses.
+    pub fn find_matches<'a>(&self, program: &'a Program) -> Vec<TemplateMatch<'a>> {
+        self.matches(program)
+            .into_iter()
+            .map(|(found, _)| found)
+            .collect()
+    }
+
+    /// Replaces every outermost match of this template in `program` with
+    /// `replacement`, its metavariables substituted by what they matched.
+    ///
+    /// A match is left unchanged, with a warning, if the replacement uses a
+    /// metavariable this template doesn't bind, if a binder of the replacement
+    /// would capture a free name of a substituted sub-tree, or if a free name
+    /// of the replacement would refer to a binding around the match.
+    ///
+    /// `source` is the text `program` was parsed from; if it parses to another
+    /// tree, the edits replace all of it.
+    pub fn rewrite(&self, program: &Program, replacement: &Template, source: &str) -> Rewrite {
+        let index = program.build_index();
+        let mut spans = Lexer::new(source)
+            .tokenize_spanned()
+            .ok()
+            .and_then(|tokens| Parser::from_spanned(tokens).parse_program_spanned().ok())
+            .filter(|(parsed, _)| parsed == program)
+            .map_or_else(|| vec![None; index.len()], |(_, spans)| spans);
+        spans[index.root()].get_or_insert(Span {
+            start: 0,
+            end: source.chars().count(),
+        });
+        let template_free = free_variables(&self.expression);
+        let mut accepted: Vec<NodeId> = Vec::new();
+        let mut replacements = HashMap::new();
+        let mut refused = Vec::new();
+
+        for (found, scopes) in self.matches(program) {
+            let ancestors = index.path_to_root(found.node);
+            if ancestors.iter().any(|node| accepted.contains(node)) {
+                continue;
+            }
+
+            let site = binders_at(&index, found.node);
+            match substitute(replacement, &found.bindings, &scopes, &template_free, &site) {
+                Ok(expression) => {
+                    accepted.push(found.node);
+                    replacements.insert(found.expression as *const Expression, expression);
+                }
+                Err(reason) => refused.push(Diagnostic::warning("rewrite-refused", reason)),
+            }
+        }
+
+        // Each match is rewritten in the nearest expression with a span: the
+        // match itself, unless it is sugar. Those inside another are left to it.
+        let spanned = |node: NodeId| {
+            index
+                .path_to_root(node)
+                .into_iter()
+                .find_map(|node| spans[node].map(|span| (node, span)))
+                .expect("the root has a span")
+        };
+        let targets: BTreeMap<NodeId, Span> = accepted.iter().map(|&node| spanned(node)).collect();
+        let edits = targets
+            .iter()
+            .filter(|(&node, _)| {
+                !index.path_to_root(node)[1..]
+                    .iter()
+                    .any(|ancestor| targets.contains_key(ancestor))
+            })
+            .map(|(&node, span)| {
+                let rewritten = replace_nodes(index.node(node), &mut |node| {
+                    replacements.get(&(node as *const Expression)).cloned()
+                });
+                let grouped = matches!(
+                    index.parent(node).map(|parent| index.node(parent)),
+                    None | Some(Expression::Term(Term::GroupedExpression(_)))
+                );
+                TextEdit {
+                    range: span.start..span.end,
+                    replacement: if grouped {
+                        print_expression(&rewritten)
+                    } else {
+                        operand(&rewritten)
+                    },
+                }
+            })
+            .collect();
+
+        Rewrite {
+            edits,
+            rewritten: accepted.len(),
+            refused,
+        }
+    }
+
+    /// Builds the template's expression with each metavariable replaced by
+    /// its entry in `bindings`, keyed with its `$` like
+    /// [`TemplateMatch::bindings`].
+    ///
+    /// A binder of the template is renamed if it would capture a free name
+    /// of a splice in its scope, and a binder named like a metavariable, as
+    /// in `\$x -> ...`, always loses its `$`.
+    ///
+    /// # Errors
+    /// Returns an `InstantiateError` if a metavariable has no binding, or a
+    /// binding is for no metavariable.
+    pub fn instantiate(
+        &self,
+        bindings: &HashMap<&str, Expression>,
+    ) -> Result<Expression, InstantiateError> {
+        let metavariables: BTreeSet<String> = free_variables(&self.expression)
+            .into_iter()
+            .filter(|name| name.starts_with('$'))
+            .collect();
+        if let Some(missing) = metavariables
+            .iter()
+            .find(|name| !bindings.contains_key(name.as_str()))
+        {
+            return Err(InstantiateError::MissingBinding(missing.clone()));
+        }
+        let mut extra: Vec<&str> = bindings
+            .keys()
+            .copied()
+            .filter(|name| !metavariables.contains(*name))
+            .collect();
+        extra.sort_unstable();
+        if let Some(extra) = extra.first() {
+            return Err(InstantiateError::ExtraBinding(extra.to_string()));
+        }
+
+        let mut taken = names(&self.expression);
+        for splice in bindings.values() {
+            taken.extend(free_variables(splice));
+        }
+        let mut splicer = Splicer {
+            bindings,
+            taken,
+            renamed: Vec::new(),
+        };
+        Ok(splicer.expression(&self.expression))
+    }
+
+    /// Matches the template against every expression of `program`, returning
+    /// each match along with, for each metavariable, the names the matched
+    /// expression itself binds around what the metavariable matched.
+    fn matches<'a>(&self, program: &'a Program) -> Vec<(TemplateMatch<'a>, Scopes)> {
+        let index = program.build_index();
+        (0..index.len())
+            .filter_map(|node| {
+                let expression = index.node(node);
+                if matches!(expression, Expression::Term(Term::GroupedExpression(_))) {
+                    return None;
+                }
+                let mut matcher = Matcher::new(true);
+                matcher.expression(&self.expression, expression).then(|| {
+                    let found = TemplateMatch {
+                        node,
+                        expression,
+                        bindings: matcher.bindings,
+                    };
+                    (found, matcher.scopes)
+                })
+            })
+            .collect()
+    }
+}
+
+/// The names `expression` uses without binding them.
+pub fn free_variables(expression: &Expression) -> BTreeSet<String> {
+    let mut free = BTreeSet::new();
+    walk_scoped(expression, &mut Vec::new(), &mut |node, bound| {
+        if let Expression::Term(Term::Identifier(name)) = node {
+            if !bound.contains(name) {
+                free.insert(name.clone());
+            }
+        }
+    });
+    free
+}
+
+/// Whether `a` and `b` are identical up to the names of their binders.
+pub fn alpha_equivalent(a: &Expression, b: &Expression) -> bool {
+    Matcher::new(false).expression(a, b)
+}
+
+/*******************************************************************************
+ *                                 MATCHER
+ ******************************************************************************/
+
+/// Compares a template against a subject, tracking binders on both sides.
+struct Matcher<'a> {
+    /// Whether `$name` identifiers are metavariables, or ordinary names.
+    metavariables: bool,
+    /// Binders in scope, innermost last, as (template name, subject name).
+    binders: Vec<(String, String)>,
+    /// What each metavariable matched so far.
+    bindings: BTreeMap<String, &'a Expression>,
+    /// The subject's binders in scope where each metavariable first matched.
+    scopes: Scopes,
+}
+
+/// For each metavariable, names bound around it within a match.
+type Scopes = BTreeMap<String, Vec<String>>;
+
+impl<'a> Matcher<'a> {
+    fn new(metavariables: bool) -> Self {
+        Self {
+            metavariables,
+            binders: Vec::new(),
+            bindings: BTreeMap::new(),
+            scopes: BTreeMap::new(),
+        }
+    }
+
+    fn expression(&mut self, template: &Expression, subject: &'a Expression) -> bool {
+        let (template, subject) = (ungrouped(template), ungrouped(subject));
+        match (template, subject) {
+            (Expression::Term(Term::Identifier(name)), _) if self.is_metavariable(name) => {
+                match self.bindings.get(name) {
+                    Some(bound) => alpha_equivalent(bound, subject),
+                    None => {
+                        self.bindings.insert(name.clone(), subject);
+                        let scope = self.binders.iter().map(|(_, bound)| bound.clone());
+                        self.scopes.insert(name.clone(), scope.collect());
+                        true
+                    }
+                }
+            }
+            (Expression::Term(Term::Identifier(a)), Expression::Term(Term::Identifier(b))) => {
+                self.same_variable(a, b)
+            }
+            (Expression::Term(Term::Number(a)), Expression::Term(Term::Number(b))) => a == b,
+            (Expression::Term(Term::String(a)), Expression::Term(Term::String(b))) => a == b,
+            (Expression::Term(Term::Boolean(a)), Expression::Term(Term::Boolean(b))) => a == b,
+            (
+                Expression::Term(Term::MemberAccess {
+                    expression: a,
+                    member: a_member,
+                }),
+                Expression::Term(Term::MemberAccess {
+                    expression: b,
+                    member: b_member,
+                }),
+            ) => a_member == b_member && self.expression(a, b),
+            (
+                Expression::LetExpr {
+                    recursive: a_recursive,
+                    identifier: a_name,
+                    type_annotation: a_type,
+                    value: a_value,
+                    body: a_body,
+                },
+                Expression::LetExpr {
+                    recursive: b_recursive,
+                    identifier: b_name,
+                    type_annotation: b_type,
+                    value: b_value,
+                    body: b_body,
+                },
+            ) => {
+                // `let` is recursive: the name is in scope in its own value.
+                a_recursive == b_recursive
+                    && a_type == b_type
+                    && self.scoped(vec![(a_name.clone(), b_name.clone())], |matcher| {
+                        matcher.expression(a_value, b_value) && matcher.expression(a_body, b_body)
+                    })
+            }
+            (
+                Expression::LetAnd {
+                    recursive: a_recursive,
+                    bindings: a_bindings,
+                    body: a_body,
+                },
+                Expression::LetAnd {
+                    recursive: b_recursive,
+                    bindings: b_bindings,
+                    body: b_body,
+                },
+            ) => {
+                // Every name of the group is in scope in every value.
+                let names = a_bindings
+                    .iter()
+                    .zip(b_bindings)
+                    .map(|(a, b)| (a.identifier.clone(), b.identifier.clone()))
+                    .collect();
+                a_recursive == b_recursive
+                    && a_bindings.len() == b_bindings.len()
+                    && a_bindings
+                        .iter()
+                        .zip(b_bindings)
+                        .all(|(a, b)| a.type_annotation == b.type_annotation)
+                    && self.scoped(names, |matcher| {
+                        a_bindings
+                            .iter()
+                            .zip(b_bindings)
+                            .all(|(a, b)| matcher.expression(&a.value, &b.value))
+                            && matcher.expression(a_body, b_body)
+                    })
+            }
+            (
+                Expression::IfExpr {
+                    condition: a_condition,
+                    then_branch: a_then,
+                    else_branch: a_else,
+                },
+                Expression::IfExpr {
+                    condition: b_condition,
+                    then_branch: b_then,
+                    else_branch: b_else,
+                },
+            ) => {
+                self.expression(a_condition, b_condition)
+                    && self.expression(a_then, b_then)
+                    && self.expression(a_else, b_else)
+            }
+            (
+                Expression::Lambda {
+                    parameter: a_parameter,
+                    type_annotation: a_type,
+                    body: a_body,
+                },
+                Expression::Lambda {
+                    parameter: b_parameter,
+                    type_annotation: b_type,
+                    body: b_body,
+                },
+            ) => {
+                a_type == b_type
+                    && self.scoped(
+                        vec![(a_parameter.clone(), b_parameter.clone())],
+                        |matcher| matcher.expression(a_body, b_body),
+                    )
+            }
+            (
+                Expression::PatternMatch {
+                    expression: a,
+                    arms: a_arms,
+                },
+                Expression::PatternMatch {
+                    expression: b,
+                    arms: b_arms,
+                },
+            ) => {
+                self.expression(a, b)
+                    && a_arms.len() == b_arms.len()
+                    && a_arms.iter().zip(b_arms).all(|(a_arm, b_arm)| {
+                        let mut binders = Vec::new();
+                        pattern(&a_arm.pattern, &b_arm.pattern, &mut binders)
+                            && self.scoped(binders, |matcher| {
+                                let guards = match (&a_arm.guard, &b_arm.guard) {
+                                    (None, None) => true,
+                                    (Some(a), Some(b)) => matcher.expression(a, b),
+                                    _ => false,
+                                };
+                                guards && matcher.expression(&a_arm.expression, &b_arm.expression)
+                            })
+                    })
+            }
+            (
+                Expression::Unary {
+                    operator: a_operator,
+                    operand: a,
+                },
+                Expression::Unary {
+                    operator: b_operator,
+                    operand: b,
+                },
+            ) => a_operator == b_operator && self.expression(a, b),
+            (Expression::Application(a_items), Expression::Application(b_items))
+            | (Expression::Term(Term::Tuple(a_items)), Expression::Term(Term::Tuple(b_items)))
+            | (Expression::Term(Term::List(a_items)), Expression::Term(Term::List(b_items))) => {
+                a_items.len() == b_items.len()
+                    && a_items
+                        .iter()
+                        .zip(b_items)
+                        .all(|(a, b)| self.expression(a, b))
+            }
+            _ => match (template.as_binary(), subject.as_binary()) {
+                (Some((a_operator, a_left, a_right)), Some((b_operator, b_left, b_right))) => {
+                    a_operator == b_operator
+                        && self.expression(a_left, b_left)
+                        && self.expression(a_right, b_right)
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// A `$name` the template does not bind itself is a metavariable.
+    fn is_metavariable(&self, name: &str) -> bool {
+        self.metavariables
+            && name.starts_with('$')
+            && !self.binders.iter().any(|(bound, _)| bound == name)
+    }
+
+    /// Whether `a` in the template and `b` in the subject refer to the same
+    /// variable: the same binder, or the same free name.
+    fn same_variable(&self, a: &str, b: &str) -> bool {
+        let a_binder = self.binders.iter().rposition(|(name, _)| name == a);
+        let b_binder = self.binders.iter().rposition(|(_, name)| name == b);
+        match (a_binder, b_binder) {
+            (None, None) => a == b,
+            (a_binder, b_binder) => a_binder == b_binder,
+        }
+    }
+
+    /// Runs `check` with `binders` in scope.
+    fn scoped(
+        &mut self,
+        binders: Vec<(String, String)>,
+        check: impl FnOnce(&mut Self) -> bool,
+    ) -> bool {
+        let depth = self.binders.len();
+        self.binders.extend(binders);
+        let matched = check(self);
+        self.binders.truncate(depth);
+        matched
+    }
+}
+
+/// Compares two arm patterns, collecting the names they bind as pairs.
+fn pattern(a: &Pattern, b: &Pattern, binders: &mut Vec<(String, String)>) -> bool {
+    match (a, b) {
+        (Pattern::Grouped(a), _) => pattern(a, b, binders),
+        (_, Pattern::Grouped(b)) => pattern(a, b, binders),
+        (Pattern::Identifier(a), Pattern::Identifier(b)) if a == "_" || b == "_" => a == b,
+        (Pattern::Identifier(a), Pattern::Identifier(b)) => {
+            binders.push((a.clone(), b.clone()));
+            true
+        }
+        (Pattern::Number(a), Pattern::Number(b)) => a == b,
+        (Pattern::Boolean(a), Pattern::Boolean(b)) => a == b,
+        (Pattern::Cons(a_head, a_tail), Pattern::Cons(b_head, b_tail)) => {
+            pattern(a_head, b_head, binders) && pattern(a_tail, b_tail, binders)
+        }
+        _ => false,
+    }
+}
+
+/// Strips any parentheses around `expression`.
+pub(crate) fn ungrouped(mut expression: &Expression) -> &Expression {
+    while let Expression::Term(Term::GroupedExpression(inner)) = expression {
+        expression = inner;
+    }
+    expression
+}
+
+/*******************************************************************************
+ *                                 REWRITING
+ ******************************************************************************/
+
+/// Instantiates `replacement` with `bindings` for a match whose surroundings
+/// bind `site`, or explains why doing so would change what a name refers to.
+fn substitute(
+    replacement: &Template,
+    bindings: &BTreeMap<String, &Expression>,
+    scopes: &Scopes,
+    template_free: &BTreeSet<String>,
+    site: &[String],
+) -> Result<Expression, String> {
+    let mut problem = None;
+    walk_scoped(
+        &replacement.expression,
+        &mut Vec::new(),
+        &mut |node, bound| {
+            if let (None, Expression::Term(Term::Identifier(name))) = (&problem, node) {
+                if !bound.contains(name) {
+                    problem = name_problem(name, bound, bindings, scopes, template_free, site);
+                }
+            }
+        },
+    );
+    if let Some(problem) = problem {
+        return Err(problem);
+    }
+
+    Ok(replace_nodes(
+        &replacement.expression,
+        &mut |node| match node {
+            Expression::Term(Term::Identifier(name)) => {
+                bindings.get(name).map(|bound| (*bound).clone())
+            }
+            _ => None,
+        },
+    ))
+}
+
+/// Checks one name the replacement doesn't bind itself, under the
+/// replacement's own binders `bound`.
+fn name_problem(
+    name: &str,
+    bound: &[String],
+    bindings: &BTreeMap<String, &Expression>,
+    scopes: &Scopes,
+    template_free: &BTreeSet<String>,
+    site: &[String],
+) -> Option<String> {
+    if !name.starts_with('$') {
+        // A name the search template used as well refers to what it matched.
+        let shadowed = site.iter().any(|bound| bound == name) && !template_free.contains(name);
+        return shadowed.then(|| {
+            format!(
+                "`{}` in the replacement would refer to the `{}` bound around the match",
+                name, name
+            )
+        });
+    }
+
+    let Some(substituted) = bindings.get(name) else {
+        return Some(format!("`{}` is not bound by the search template", name));
+    };
+    let inner = &scopes[name];
+    free_variables(substituted).into_iter().find_map(|free| {
+        let rebound = bound.contains(&free);
+        if inner.contains(&free) && !rebound {
+            Some(format!(
+                "substituting `{}` would move `{}` out of its binder",
+                name, free
+            ))
+        } else if !inner.contains(&free) && rebound {
+            Some(format!(
+                "substituting `{}` would capture `{}` under a binder of the replacement",
+                name, free
+            ))
+        } else {
+            None
+        }
+    })
+}
+
+/*******************************************************************************
+ *                                 SPLICING
+ ******************************************************************************/
+
+/// Fills a template's metavariables for [`Template::instantiate`].
+struct Splicer<'a, 'b> {
+    bindings: &'a HashMap<&'b str, Expression>,
+    /// Every name in the template or free in a splice, which fresh names
+    /// must avoid.
+    taken: BTreeSet<String>,
+    /// The template's binders in scope, innermost last, with the name each
+    /// is given in the output.
+    renamed: Vec<(String, String)>,
+}
+
+impl Splicer<'_, '_> {
+    fn expression(&mut self, expression: &Expression) -> Expression {
+        replace_nodes(expression, &mut |node| match node {
+            Expression::Term(Term::Identifier(name)) => {
+                let bound = self.renamed.iter().rev().find(|(old, _)| old == name);
+                Some(match (bound, self.bindings.get(name.as_str())) {
+                    (Some((_, new)), _) => Expression::Term(Term::Identifier(new.clone())),
+                    (None, Some(splice)) => splice.clone(),
+                    (None, None) => node.clone(),
+                })
+            }
+            Expression::LetExpr {
+                recursive,
+                identifier,
+                type_annotation,
+                value,
+                body,
+            } => {
+                let name = self.binder(identifier, &[value, body]);
+                Some(
+                    self.scoped(vec![(identifier.clone(), name.clone())], |splicer| {
+                        Expression::LetExpr {
+                            recursive: *recursive,
+                            identifier: name,
+                            type_annotation: type_annotation.clone(),
+                            value: Box::new(splicer.expression(value)),
+                            body: Box::new(splicer.expression(body)),
+                        }
+                    }),
+                )
+            }
+            Expression::LetAnd {
+                recursive,
+                bindings,
+                body,
+            } => {
+                let mut scope: Vec<&Expression> =
+                    bindings.iter().map(|binding| &*binding.value).collect();
+                scope.push(body);
+                let names: Vec<(String, String)> = bindings
+                    .iter()
+                    .map(|binding| {
+                        let name = self.binder(&binding.identifier, &scope);
+                        (binding.identifier.clone(), name)
+                    })
+                    .collect();
+                Some(self.scoped(names.clone(), |splicer| {
+                    Expression::LetAnd {
+                        recursive: *recursive,
+                        bindings: bindings
+                            .iter()
+                            .zip(names)
+                            .map(|(binding, (_, name))| LetBinding {
+                                identifier: name,
+                                type_annotation: binding.type_annotation.clone(),
+                                value: Box::new(splicer.expression(&binding.value)),
+                            })
+                            .collect(),
+                        body: Box::new(splicer.expression(body)),
+                    }
+                }))
+            }
+            Expression::Lambda {
+                parameter,
+                type_annotation,
+                body,
+            } => {
+                let name = self.binder(parameter, &[body]);
+                Some(
+                    self.scoped(vec![(parameter.clone(), name.clone())], |splicer| {
+                        Expression::Lambda {
+                            parameter: name,
+                            type_annotation: type_annotation.clone(),
+                            body: Box::new(splicer.expression(body)),
+                        }
+                    }),
+                )
+            }
+            Expression::PatternMatch { expression, arms } => {
+                Some(Expression::PatternMatch {
+                    expression: Box::new(self.expression(expression)),
+                    arms: arms
+                        .iter()
+                        .map(|arm| {
+                            let mut names = Vec::new();
+                            pattern_names(&arm.pattern, &mut names);
+                            let scope: Vec<&Expression> = arm
+                                .guard
+                                .iter()
+                                .map(|guard| &**guard)
+                                .chain([&*arm.expression])
+                                .collect();
+                            let renames: Vec<(String, String)> = names
+                                .into_iter()
+                                .map(|name| {
+                                    let new = self.binder(&name, &scope);
+                                    (name, new)
+                                })
+                                .collect();
+                            MatchArm {
+                                pattern: renamed_pattern(&arm.pattern, &renames),
+                                guard: arm.guard.as_ref().map(|guard| {
+                                    Box::new(self.scoped(renames.clone(), |splicer| {
+                                        splicer.expression(guard)
+                                    }))
+                                }),
+                                expression: Box::new(self.scoped(renames, |splicer| {
+                                    splicer.expression(&arm.expression)
+                                })),
+                            }
+                        })
+                        .collect(),
+                })
+            }
+            _ => None,
+        })
+    }
+
+    /// The output name for the template binder `name` scoping over `scope`:
+    /// a fresh one if a splice in `scope` has `name` free, or if `name` is
+    /// a metavariable, and `name` itself otherwise.
+    fn binder(&mut self, name: &str, scope: &[&Expression]) -> String {
+        let captures = scope.iter().any(|expression| {
+            free_variables(expression).iter().any(|free| {
+                !self.renamed.iter().any(|(bound, _)| bound == free)
+                    && self
+                        .bindings
+                        .get(free.as_str())
+                        .is_some_and(|splice| free_variables(splice).contains(name))
+            })
+        });
+        if !captures && !name.starts_with('$') {
+            return name.to_string();
+        }
+        let mut fresh = name.trim_start_matches('$').to_string();
+        while self.taken.contains(&fresh) {
+            fresh.push('\'');
+        }
+        self.taken.insert(fresh.clone());
+        fresh
+    }
+
+    /// Runs `splice` with `binders` in scope.
+    fn scoped<T>(
+        &mut self,
+        binders: Vec<(String, String)>,
+        splice: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let depth = self.renamed.len();
+        self.renamed.extend(binders);
+        let result = splice(self);
+        self.renamed.truncate(depth);
+        result
+    }
+}
+
+/// `pattern` with its names renamed as `renames` says.
+fn renamed_pattern(pattern: &Pattern, renames: &[(String, String)]) -> Pattern {
+    match pattern {
+        Pattern::Identifier(name) => Pattern::Identifier(
+            renames
+                .iter()
+                .find(|(old, _)| old == name)
+                .map_or_else(|| name.clone(), |(_, new)| new.clone()),
+        ),
+        Pattern::Grouped(inner) => Pattern::Grouped(Box::new(renamed_pattern(inner, renames))),
+        Pattern::Cons(head, tail) => Pattern::Cons(
+            Box::new(renamed_pattern(head, renames)),
+            Box::new(renamed_pattern(tail, renames)),
+        ),
+        Pattern::Number(_) | Pattern::Boolean(_) => pattern.clone(),
+    }
+}
+
+/// Every name `expression` uses or binds.
+fn names(expression: &Expression) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    expression.walk(&mut |node| match node {
+        Expression::Term(Term::Identifier(name))
+        | Expression::LetExpr {
+            identifier: name, ..
+        }
+        | Expression::Lambda {
+            parameter: name, ..
+        } => {
+            names.insert(name.clone());
+        }
+        Expression::LetAnd { bindings, .. } => {
+            names.extend(bindings.iter().map(|binding| binding.identifier.clone()));
+        }
+        Expression::PatternMatch { arms, .. } => {
+            let mut bound = Vec::new();
+            for arm in arms {
+                pattern_names(&arm.pattern, &mut bound);
+            }
+            names.extend(bound);
+        }
+        _ => {}
+    });
+    names
+}
+
+/// The names bound around the node `id`, outermost first.
+fn binders_at(index: &AstIndex<'_>, id: NodeId) -> Vec<String> {
+    let mut names = Vec::new();
+    for node in index.path_to_root(id) {
+        if let (Some(parent), Some(step)) = (index.parent(node), index.step(node)) {
+            names.extend(binders_for(index.node(parent), step));
+        }
+    }
+    names.reverse();
+    names
+}
+
+/// The names `parent` binds around its child at `step`.
+pub(crate) fn binders_for(parent: &Expression, step: Step) -> Vec<String> {
+    match (parent, step.index) {
+        // `let` is recursive, so it binds in both its value and its body.
+        (Expression::LetExpr { identifier, .. }, _) => vec![identifier.clone()],
+        (Expression::LetAnd { bindings, .. }, _) => bindings
+            .iter()
+            .map(|binding| binding.identifier.clone())
+            .collect(),
+        (Expression::Lambda { parameter, .. }, _) => vec![parameter.clone()],
+        // An arm's pattern binds in both its guard and its body.
+        (Expression::PatternMatch { arms, .. }, Some(arm)) => {
+            let mut names = Vec::new();
+            pattern_names(&arms[arm].pattern, &mut names);
+            names
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn pattern_names(pattern: &Pattern, names: &mut Vec<String>) {
+    match pattern {
+        Pattern::Identifier(name) if name != "_" => names.push(name.clone()),
+        Pattern::Grouped(inner) => pattern_names(inner, names),
+        Pattern::Cons(head, tail) => {
+            pattern_names(head, names);
+            pattern_names(tail, names);
+        }
+        _ => {}
+    }
+}
+
+/// Calls `visit` on `expression` and every sub-expression, with the names
+/// bound around each, innermost last.
+fn walk_scoped(
+    expression: &Expression,
+    bound: &mut Vec<String>,
+    visit: &mut impl FnMut(&Expression, &[String]),
+) {
+    visit(expression, bound);
+    for (step, child) in child_expressions(expression) {
+        let depth = bound.len();
+        bound.extend(binders_for(expression, step));
+        walk_scoped(child, bound, visit);
+        bound.truncate(depth);
+    }
+}
+
+/// Copies `expression`, except that any node for which `replace` returns an
+/// expression is swapped for it instead of being copied.
+fn replace_nodes(
+    expression: &Expression,
+    replace: &mut impl FnMut(&Expression) -> Option<Expression>,
+) -> Expression {
+    if let Some(replaced) = replace(expression) {
+        return replaced;
+    }
+    let mut copy = |expression: &Expression| Box::new(replace_nodes(expression, replace));
+    match expression {
+        Expression::LetExpr {
+            recursive,
+            identifier,
+            type_annotation,
+            value,
+            body,
+        } => Expression::LetExpr {
+            recursive: *recursive,
+            identifier: identifier.clone(),
+            type_annotation: type_annotation.clone(),
+            value: copy(value),
+            body: copy(body),
+        },
+        Expression::LetAnd {
+            recursive,
+            bindings,
+            body,
+        } => Expression::LetAnd {
+            recursive: *recursive,
+            bindings: bindings
+                .iter()
+                .map(|binding| LetBinding {
+                    identifier: binding.identifier.clone(),
+                    type_annotation: binding.type_annotation.clone(),
+                    value: copy(&binding.value),
+                })
+                .collect(),
+            body: copy(body),
+        },
+        Expression::IfExpr {
+            condition,
+            then_branch,
+            else_branch,
+        } => Expression::IfExpr {
+            condition: copy(condition),
+            then_branch: copy(then_branch),
+            else_branch: copy(else_branch),
+        },
+        Expression::Lambda {
+            parameter,
+            type_annotation,
+            body,
+        } => Expression::Lambda {
+            parameter: parameter.clone(),
+            type_annotation: type_annotation.clone(),
+            body: copy(body),
+        },
+        Expression::PatternMatch { expression, arms } => Expression::PatternMatch {
+            expression: copy(expression),
+            arms: arms
+                .iter()
+                .map(|arm| MatchArm {
+                    pattern: arm.pattern.clone(),
+                    guard: arm.guard.as_ref().map(|guard| copy(guard)),
+                    expression: copy(&arm.expression),
+                })
+                .collect(),
+        },
+        Expression::Comparison {
+            left,
+            operator,
+            right,
+        } => Expression::Comparison {
+            left: copy(left),
+            operator: operator.clone(),
+            right: right.as_deref().map(&mut copy),
+        },
+        Expression::Logic {
+            left,
+            operator,
+            right,
+        } => Expression::Logic {
+            left: copy(left),
+            operator: operator.clone(),
+            right: right.as_deref().map(&mut copy),
+        },
+        Expression::Arithmetic {
+            left,
+            operator,
+            right,
+        } => Expression::Arithmetic {
+            left: copy(left),
+            operator: operator.clone(),
+            right: copy(right),
+        },
+        Expression::List {
+            left,
+            operator,
+            right,
+        } => Expression::List {
+            left: copy(left),
+            operator: operator.clone(),
+            right: copy(right),
+        },
+        Expression::Unary { operator, operand } => Expression::Unary {
+            operator: operator.clone(),
+            operand: copy(operand),
+        },
+        Expression::Application(items) => {
+            Expression::Application(items.iter().map(|item| *copy(item)).collect())
+        }
+        Expression::Term(Term::GroupedExpression(inner)) => {
+            Expression::Term(Term::GroupedExpression(copy(inner)))
+        }
+        Expression::Term(Term::MemberAccess { expression, member }) => {
+            Expression::Term(Term::MemberAccess {
+                expression: copy(expression),
+                member: member.clone(),
+            })
+        }
+        Expression::Term(Term::Tuple(elements)) => Expression::Term(Term::Tuple(
+            elements.iter().map(|element| *copy(element)).collect(),
+        )),
+        Expression::Term(Term::List(elements)) => Expression::Term(Term::List(
+            elements.iter().map(|element| *copy(element)).collect(),
+        )),
+        Expression::Term(term) => Expression::Term(term.clone()),
+        Expression::FunctionComposition(FunctionComposition { f, g }) => {
+            Expression::FunctionComposition(FunctionComposition {
+                f: copy(f),
+                g: copy(g),
+            })
+        }
+    }
+}
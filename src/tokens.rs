@@ -12,6 +12,10 @@
  * symbols.
  ********************************************************************************/
 
+use std::fmt;
+
+use crate::printer::escaped;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     //--------------------------------------------------------------------------
@@ -38,6 +42,12 @@ pub enum Token {
     /// Represents the `with` keyword, used with match-expressions.
     With,
 
+    /// Represents the `true` boolean literal.
+    True,
+
+    /// Represents the `false` boolean literal.
+    False,
+
     /// Represents the `\` symbol for lambda abstractions.
     Lambda,
 
@@ -47,18 +57,30 @@ pub enum Token {
     /// Equality operator (`==`).
     Equal,
 
+    /// Inequality operator (`!=`).
+    NotEqual,
+
     /// Less-than operator (`<`).
     LessThan,
 
     /// Greater-than operator (`>`).
     GreaterThan,
 
+    /// Less-than-or-equal operator (`<=`).
+    LessEqual,
+
+    /// Greater-than-or-equal operator (`>=`).
+    GreaterEqual,
+
     /// Logical AND operator (`&&`).
     And,
 
     /// Logical OR operator (`||`).
     Or,
 
+    /// Logical NOT operator (`!`).
+    Bang,
+
     /// Plus operator (`+`).
     Plus,
 
@@ -71,15 +93,30 @@ pub enum Token {
     /// Division operator (`/`).
     Slash,
 
+    /// Remainder operator (`%`).
+    Percent,
+
+    /// Power operator (`^`).
+    Caret,
+
     /// Arrow operator (`->`), used in function types and lambdas.
     Arrow,
 
+    /// Cons operator (`::`), prepending an element to a list.
+    Cons,
+
+    /// Concatenation operator (`++`), joining two lists or strings.
+    PlusPlus,
+
     /// Dot operator (`.`), relevant for composition or member access.
     Dot,
 
     /// Pipe symbol (`|`), often used in pattern matching arms.
     Pipe,
 
+    /// Pipe-forward operator (`|>`), passing a value to a function.
+    PipeForward,
+
     //--------------------------------------------------------------------------
     // Literals
     //--------------------------------------------------------------------------
@@ -89,6 +126,9 @@ pub enum Token {
     /// Numeric literal, storing a floating-point value for both int and float.
     Number(f64),
 
+    /// String literal, storing the characters between the quotes.
+    String(String),
+
     //--------------------------------------------------------------------------
     // Delimiters
     //--------------------------------------------------------------------------
@@ -98,9 +138,24 @@ pub enum Token {
     /// Right parenthesis (`)`).
     RightParen,
 
+    /// Left square bracket (`[`), reserved for list syntax.
+    LeftBracket,
+
+    /// Right square bracket (`]`), reserved for list syntax.
+    RightBracket,
+
+    /// Comma (`,`), reserved for separating list elements.
+    Comma,
+
     /// Colon (`:`), often used for type annotations.
     Colon,
 
+    /// Semicolon (`;`), reserved for sequencing.
+    Semicolon,
+
+    /// Double semicolon (`;;`), terminating a top-level program.
+    DoubleSemicolon,
+
     /// Assignment operator (`=`), used in bindings (`let x = expr`).
     Assign,
 
@@ -110,9 +165,145 @@ pub enum Token {
     /// Wildcard identifier (`_`), commonly used in patterns.
     Wildcard,
 
+    //--------------------------------------------------------------------------
+    // Trivia
+    //--------------------------------------------------------------------------
+    /// A run of whitespace, kept only by [`crate::Lexer::tokenize_with_trivia`].
+    Whitespace(String),
+
+    /// A `--` comment without its line break, or the `#!` line, kept only by
+    /// [`crate::Lexer::tokenize_with_trivia`].
+    Comment(String),
+
     //--------------------------------------------------------------------------
     // End of File
     //--------------------------------------------------------------------------
     /// End-of-file marker. Indicates no more tokens are available.
     Eof,
 }
+
+impl Token {
+    /// Whether this is a whitespace or comment token, which carries no
+    /// meaning for the parser.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Token::Whitespace(_) | Token::Comment(_))
+    }
+}
+
+/// Renders a token as it appears in source. Integral numbers lose their
+/// `.0`, strings are quoted with their escapes restored, and `Eof` reads
+/// `end of input`.
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Token::Let => "let",
+            Token::In => "in",
+            Token::If => "if",
+            Token::Then => "then",
+            Token::Else => "else",
+            Token::Match => "match",
+            Token::With => "with",
+            Token::True => "true",
+            Token::False => "false",
+            Token::Lambda => "\\",
+            Token::Equal => "==",
+            Token::NotEqual => "!=",
+            Token::LessThan => "<",
+            Token::GreaterThan => ">",
+            Token::LessEqual => "<=",
+            Token::GreaterEqual => ">=",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Bang => "!",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::Caret => "^",
+            Token::Arrow => "->",
+            Token::Cons => "::",
+            Token::PlusPlus => "++",
+            Token::Dot => ".",
+            Token::Pipe => "|",
+            Token::PipeForward => "|>",
+            Token::Identifier(name) | Token::Whitespace(name) | Token::Comment(name) => {
+                return f.write_str(name)
+            }
+            Token::Number(value) => return write!(f, "{}", value),
+            Token::String(text) => return write!(f, "\"{}\"", escaped(text)),
+            Token::LeftParen => "(",
+            Token::RightParen => ")",
+            Token::LeftBracket => "[",
+            Token::RightBracket => "]",
+            Token::Comma => ",",
+            Token::Colon => ":",
+            Token::Semicolon => ";",
+            Token::DoubleSemicolon => ";;",
+            Token::Assign => "=",
+            Token::Wildcard => "_",
+            Token::Eof => "end of input",
+        };
+        f.write_str(text)
+    }
+}
+
+/*-----------------------------------------------------------------------------
+ *                              SPANS
+ *-----------------------------------------------------------------------------
+ * Positions of tokens in the input, for tools that map tokens back to text.
+ *---------------------------------------------------------------------------*/
+
+/// A half-open range `start..end` of character offsets into the input.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A 1-based line and column in the input, counting characters.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A token, where it starts, and the characters it covers, as produced by
+/// [`crate::Lexer::tokenize_spanned`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub position: Position,
+    pub span: Span,
+}
+
+/// One piece of the input as produced by [`crate::Lexer::tokenize_lossy`].
+/// The spans of a lossy token stream cover the input exactly, in order.
+#[derive(Debug, PartialEq, Clone)]
+pub enum LossyToken {
+    /// A token that lexed normally.
+    Token { token: Token, span: Span },
+
+    /// Whitespace and comments, or the `#!` line that opened the input.
+    Trivia { span: Span },
+
+    /// A maximal run of characters that could not be lexed.
+    Error { span: Span },
+}
+
+impl LossyToken {
+    /// The part of the input this piece covers.
+    pub fn span(&self) -> Span {
+        match self {
+            LossyToken::Token { span, .. }
+            | LossyToken::Trivia { span }
+            | LossyToken::Error { span } => *span,
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! src/validate.rs
+
+/*******************************************************************************
+ *                              VALIDATE MODULE
+ *-------------------------------------------------------------------------------
+ * One call that runs the whole front end over a source text, for embedders
+ * that just want to know whether a program is acceptable and why not:
+ * lexing and parsing, the default lints, any opt-in checks, the complexity
+ * budget, and the configured lint levels. Everything found comes back as
+ * `Diagnostic`s, in the order the phases ran, with the parsed program when
+ * there is one.
+ *
+ * The parser stops at the first error, so a program that fails to parse
+ * gets that one error and no lints.
+ ******************************************************************************/
+
+use crate::{
+    enforce_budget, lint, Budget, Diagnostic, DiagnosticsConfig, Lexer, LexerOptions, ParseError,
+    Parser, Program, Severity, Span,
+};
+
+/// An opt-in check over a parsed program, such as `suggest_parens`, given the
+/// span of each node as `Parser::parse_program_spanned` returns them.
+pub type Check = fn(&Program, &[Option<Span>]) -> Vec<Diagnostic>;
+
+/// What `validate` runs and how it reports findings.
+#[derive(Debug, Clone, Default)]
+pub struct ValidateConfig {
+    /// Limits applied while lexing.
+    pub options: LexerOptions,
+    /// Checks run after the default lints.
+    pub checks: Vec<Check>,
+    /// Levels applied to lint findings.
+    pub diagnostics: DiagnosticsConfig,
+    /// Complexity limits; every violation is an error.
+    pub budget: Budget,
+}
+
+/// Everything `validate` found out about a source text.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ValidationReport {
+    /// The parsed program, or `None` if the source failed to lex or parse.
+    pub program: Option<Program>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Lexes, parses, and lints `source` as `config` says.
+pub fn validate(source: &str, config: &ValidateConfig) -> ValidationReport {
+    match parse_and_lint(source, config) {
+        Ok((program, diagnostics)) => ValidationReport {
+            program: Some(program),
+            diagnostics,
+        },
+        Err(err) => ValidationReport {
+            program: None,
+            diagnostics: vec![Diagnostic::from(&err)],
+        },
+    }
+}
+
+/// Like `validate`, but keeps the error if the source failed to parse.
+pub(crate) fn parse_and_lint(
+    source: &str,
+    config: &ValidateConfig,
+) -> Result<(Program, Vec<Diagnostic>), ParseError> {
+    let (program, spans) = Lexer::with_options(source, config.options)
+        .tokenize_spanned()
+        .and_then(|tokens| Parser::from_spanned(tokens).parse_program_spanned())?;
+    let diagnostics = config.lint(&program, &spans);
+    Ok((program, diagnostics))
+}
+
+impl ValidateConfig {
+    /// Runs the default lints, the opt-in checks, and the budget over
+    /// `program`, with the configured levels applied to what they find.
+    /// `spans` are the spans of its nodes, or empty if they aren't known.
+    pub fn lint(&self, program: &Program, spans: &[Option<Span>]) -> Vec<Diagnostic> {
+        let mut diagnostics = lint(program);
+        for check in &self.checks {
+            diagnostics.extend(check(program, spans));
+        }
+        if let Err(violations) = enforce_budget(program, &self.budget) {
+            diagnostics.extend(violations);
+        }
+        self.diagnostics.apply(diagnostics)
+    }
+}
+
+impl ValidationReport {
+    /// Whether the program is rejected: it failed to parse, or a finding
+    /// is an error.
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
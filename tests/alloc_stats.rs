@@ -0,0 +1,51 @@
+//! tests/alloc_stats.rs
+//!
+//! Allocation ratchets for the lexer and parser. Run with
+//! `cargo test --features dev-metrics`; the bounds are generous on purpose
+//! and should be tightened as allocation-saving changes land.
+
+#![cfg(feature = "dev-metrics")]
+
+use rdp::{AllocStats, CountingAllocator, Lexer, Parser};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const FACTORIAL: &str = include_str!("../examples/factorial.pfl");
+
+/// Tests that allocations are counted, with their sizes.
+#[test]
+fn test_measure_counts_allocations() {
+    // Act
+    let (buffer, stats) = AllocStats::measure(|| Vec::<u8>::with_capacity(1024));
+
+    // Assert
+    assert_eq!(buffer.capacity(), 1024);
+    assert!(stats.allocations >= 1, "{}", stats);
+    assert!(stats.bytes >= 1024, "{}", stats);
+}
+
+/// Tests that lexing a small program stays under its allocation budget.
+#[test]
+fn test_lexing_allocation_ratchet() {
+    // Act
+    let (tokens, stats) = AllocStats::measure(|| Lexer::tokenize_str(FACTORIAL));
+
+    // Assert
+    assert!(tokens.is_ok());
+    assert!(stats.allocations < 200, "lexing made {}", stats);
+}
+
+/// Tests that parsing a small program stays under its allocation budget.
+#[test]
+fn test_parsing_allocation_ratchet() {
+    // Arrange
+    let tokens = Lexer::tokenize_str(FACTORIAL).unwrap();
+
+    // Act
+    let (program, stats) = AllocStats::measure(|| Parser::new(tokens).parse_program());
+
+    // Assert
+    assert!(program.is_ok());
+    assert!(stats.allocations < 400, "parsing made {}", stats);
+}
@@ -0,0 +1,90 @@
+//! tests/ast.rs
+
+use rdp::{Expression, Lexer, Parser, Pattern, PatternConversionError, Term};
+
+fn parse_expression(input: &str) -> Expression {
+    let tokens = Lexer::tokenize_str(input).expect("Failed to tokenize input");
+    Parser::new(tokens)
+        .parse_program()
+        .expect("Failed to parse input")
+        .expression
+}
+
+/// Tests that identifiers, numbers, wildcards, and grouping convert both ways.
+#[test]
+fn test_pattern_shaped_expressions_round_trip() {
+    for (input, pattern) in [
+        ("x", Pattern::Identifier("x".to_string())),
+        ("_", Pattern::Identifier("_".to_string())),
+        ("42", Pattern::Number(42.0)),
+        (
+            "((n))",
+            Pattern::Grouped(Box::new(Pattern::Grouped(Box::new(Pattern::Identifier(
+                "n".to_string(),
+            ))))),
+        ),
+    ] {
+        // Arrange
+        let expression = parse_expression(input);
+
+        // Act
+        let converted = Pattern::try_from(&expression);
+        let back = Expression::from(&pattern);
+
+        // Assert
+        assert_eq!(converted, Ok(pattern), "'{}'", input);
+        assert_eq!(back, expression, "'{}'", input);
+    }
+}
+
+/// Tests that each non-pattern expression is rejected, naming its kind.
+#[test]
+fn test_non_pattern_expressions_are_rejected() {
+    for (input, kind) in [
+        ("let x = 1 in x", "let expression"),
+        ("if a then b else c", "if expression"),
+        ("\\x -> x", "lambda"),
+        ("match x with | _ -> 1", "match expression"),
+        ("a == b", "comparison"),
+        ("a && b", "logic expression"),
+        ("a + b", "arithmetic expression"),
+        ("f x", "application"),
+        ("(r . name)", "member access"),
+        ("f . g", "function composition"),
+    ] {
+        // Arrange
+        let expression = parse_expression(input);
+
+        // Act
+        let err = Pattern::try_from(&expression).unwrap_err();
+
+        // Assert
+        assert_eq!(err.kind(), kind, "'{}'", input);
+        assert_eq!(err.expression, expression, "'{}'", input);
+    }
+}
+
+/// Tests that the error points at the offending sub-expression inside grouping.
+#[test]
+fn test_rejection_names_nested_sub_expression() {
+    // Arrange
+    let expression = parse_expression("((f x))");
+
+    // Act
+    let err = Pattern::try_from(&expression).unwrap_err();
+
+    // Assert
+    assert_eq!(
+        err,
+        PatternConversionError {
+            expression: Expression::Application(vec![
+                Expression::Term(Term::Identifier("f".to_string())),
+                Expression::Term(Term::Identifier("x".to_string())),
+            ]),
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "An application is not allowed in a pattern."
+    );
+}
@@ -0,0 +1,132 @@
+//! tests/budget.rs
+
+mod support;
+
+use rdp::{enforce_budget, validate, Budget, ValidateConfig, OVER_BUDGET};
+
+/// The messages of the violations of `budget` in `input`.
+fn violations(input: &str, budget: Budget) -> Vec<String> {
+    match enforce_budget(&support::parse_program(input), &budget) {
+        Ok(()) => Vec::new(),
+        Err(diagnostics) => diagnostics
+            .into_iter()
+            .inspect(|diagnostic| assert_eq!(diagnostic.code, OVER_BUDGET))
+            .map(|diagnostic| diagnostic.message)
+            .collect(),
+    }
+}
+
+const PROGRAM: &str = "let a = 1 in let b = f a b c in match b with | 0 -> (a + 1) | _ -> b";
+
+/// Tests that a program within every limit passes.
+#[test]
+fn test_compliant_program_passes() {
+    // Arrange
+    let budget: Budget = "depth=6,arms=2,application=4,lets=2,nodes=15"
+        .parse()
+        .unwrap();
+
+    // Act
+    let result = enforce_budget(&support::parse_program(PROGRAM), &budget);
+
+    // Assert
+    assert_eq!(result, Ok(()));
+    assert_eq!(
+        enforce_budget(&support::parse_program(PROGRAM), &Budget::default()),
+        Ok(())
+    );
+}
+
+/// Tests that each limit is enforced on its own, pointing at the offending
+/// expression.
+#[test]
+fn test_each_limit_is_enforced() {
+    // Arrange
+    let cases = [
+        (
+            "depth=5",
+            "expression depth is 6, over the budget of 5, at body.body.arms[0].expression.left",
+        ),
+        (
+            "arms=1",
+            "match arm count is 2, over the budget of 1, at body.body",
+        ),
+        (
+            "application=3",
+            "application length is 4, over the budget of 3, at body.value",
+        ),
+        ("lets=1", "let depth is 2, over the budget of 1, at body"),
+        (
+            "nodes=14",
+            "node count is 15, over the budget of 14, at root",
+        ),
+    ];
+
+    for (limits, expected) in cases {
+        // Act
+        let found = violations(PROGRAM, limits.parse().unwrap());
+
+        // Assert
+        assert_eq!(found, vec![expected.to_string()], "{}", limits);
+    }
+}
+
+/// Tests that every construct over a per-construct limit is reported.
+#[test]
+fn test_every_long_application_is_reported() {
+    // Act
+    let found = violations(
+        "f a b + g c d",
+        Budget {
+            max_application_length: Some(2),
+            ..Budget::default()
+        },
+    );
+
+    // Assert
+    assert_eq!(
+        found,
+        vec![
+            "application length is 3, over the budget of 2, at left".to_string(),
+            "application length is 3, over the budget of 2, at right".to_string(),
+        ]
+    );
+}
+
+/// Tests parsing budgets from their command-line form.
+#[test]
+fn test_parse_budget() {
+    // Act
+    let budget = "depth=32,nodes=5000".parse::<Budget>();
+    let unknown = "width=3".parse::<Budget>();
+    let invalid = "depth=many".parse::<Budget>();
+
+    // Assert
+    assert_eq!(
+        budget,
+        Ok(Budget {
+            max_depth: Some(32),
+            max_nodes: Some(5000),
+            ..Budget::default()
+        })
+    );
+    assert!(unknown.unwrap_err().starts_with("unknown limit 'width'"));
+    assert_eq!(invalid, Err("invalid limit 'many' for 'depth'".to_string()));
+}
+
+/// Tests that validation enforces the configured budget as errors.
+#[test]
+fn test_validate_enforces_budget() {
+    // Arrange
+    let config = ValidateConfig {
+        budget: "nodes=3".parse().unwrap(),
+        ..ValidateConfig::default()
+    };
+
+    // Act
+    let report = validate("1 + 2 + 3", &config);
+
+    // Assert
+    assert!(report.program.is_some());
+    assert!(report.has_errors());
+}
@@ -0,0 +1,122 @@
+//! tests/callgraph.rs
+
+mod support;
+
+use rdp::{call_graph, CallGraph, Program};
+
+/// Builds the call graph of the program in `source`.
+fn graph(source: &str) -> CallGraph {
+    call_graph(&Program {
+        expression: support::parse(source).unwrap(),
+    })
+}
+
+/// A program with nested bindings, a shadowed name, mutual and direct
+/// recursion, a forward reference, and a builtin.
+const FIXTURE: &str = "
+let helper = \\x -> x + 1 in
+let outer = \\n ->
+    let inner = \\m -> helper (print m) in
+    let helper = \\k -> k in
+    inner (helper n)
+in
+let even = \\n -> if n == 0 then 1 else odd (n - 1) in
+let odd = \\n -> if n == 0 then 0 else even (n - 1) in
+let rec spin = \\f -> spin (f 1) in
+let unused = later . helper in
+let later = \\y -> outer y in
+outer (even 4)
+";
+
+/// Tests that each call reaches the binding in scope: `outer`'s own
+/// `helper` shadows the top-level one after it is bound, not before.
+#[test]
+fn test_call_graph_edges() {
+    // Act
+    let graph = graph(FIXTURE);
+
+    // Assert
+    assert_eq!(
+        graph.edges(),
+        vec![
+            ("even", "odd"),
+            ("later", "outer"),
+            ("odd", "even"),
+            ("outer", "outer.helper"),
+            ("outer", "outer.inner"),
+            ("outer.inner", "helper"),
+            ("outer.inner", "print"),
+            ("spin", "spin"),
+            ("unused", "helper"),
+            ("unused", "later"),
+        ]
+    );
+}
+
+/// Tests that names bound nowhere become external nodes, and parameters
+/// don't become nodes at all.
+#[test]
+fn test_call_graph_nodes() {
+    // Act
+    let graph = graph(FIXTURE);
+
+    // Assert
+    let nodes: Vec<(&str, bool)> = graph
+        .nodes()
+        .iter()
+        .map(|node| (node.name.as_str(), node.external))
+        .collect();
+    assert_eq!(
+        nodes,
+        vec![
+            ("helper", false),
+            ("outer", false),
+            ("even", false),
+            ("odd", false),
+            ("spin", false),
+            ("unused", false),
+            ("later", false),
+            ("outer.inner", false),
+            ("print", true),
+            ("outer.helper", false),
+        ]
+    );
+}
+
+/// Tests entry points, reachability, and cycles.
+#[test]
+fn test_call_graph_queries() {
+    // Act
+    let graph = graph(FIXTURE);
+
+    // Assert
+    assert_eq!(graph.roots(), vec!["spin", "unused"]);
+    assert_eq!(
+        graph.unreachable_from(&["outer", "even"]),
+        vec!["spin", "unused", "later"]
+    );
+    assert_eq!(graph.cycles(), vec![vec!["even", "odd"], vec!["spin"]]);
+}
+
+/// Tests that a name bound twice in the same place gets a numbered node.
+#[test]
+fn test_call_graph_repeated_names() {
+    // Act
+    let graph = graph("let f = \\x -> x in let g = \\y -> f y in let f = \\z -> g z in f 1");
+
+    // Assert
+    assert_eq!(graph.edges(), vec![("f#2", "g"), ("g", "f")]);
+}
+
+/// Tests the Graphviz rendering.
+#[test]
+fn test_call_graph_to_dot() {
+    // Act
+    let dot = graph("let main = \\x -> print (main x) in main 1").to_dot();
+
+    // Assert
+    assert_eq!(
+        dot,
+        "digraph calls {\n  \"main\";\n  \"print\" [style=dashed];\n  \"main\" -> \"main\";\n  \"main\" -> \"print\";\n}\n"
+    );
+}
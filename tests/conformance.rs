@@ -0,0 +1,71 @@
+//! tests/conformance.rs
+
+use rdp::conformance;
+
+/// Runs the bundled conformance suite against this implementation.
+///
+/// Set `RDP_BLESS=1` to regenerate the expectation files after an intentional
+/// change in behavior, then review the resulting diff.
+#[test]
+fn test_conformance_suite() {
+    // Arrange
+    if std::env::var_os("RDP_BLESS").is_some() {
+        conformance::bless_dir(conformance::SUITE_DIR.as_ref()).expect("Failed to bless suite");
+    }
+
+    // Act
+    let report = conformance::run_all().expect("Failed to read conformance suite");
+
+    // Assert
+    assert!(report.is_success(), "{:#?}", report.failures);
+    assert!(report.passed >= 60);
+}
+
+/// Tests that a missing expectation file means the artifact must not exist.
+#[test]
+fn test_outcome_of_lex_error_has_no_tokens() {
+    // Arrange
+    let input = "let x = @";
+
+    // Act
+    let outcome = conformance::Outcome::of(input);
+
+    // Assert
+    assert_eq!(outcome.tokens, None);
+    assert_eq!(outcome.ast, None);
+    assert_eq!(
+        outcome.error,
+        Some(
+            "lex E0001 1:9-1:10: Unexpected character: expected 'valid token', but found '@'."
+                .to_string()
+        )
+    );
+}
+
+/// Tests that the AST is rendered as an s-expression, with annotations,
+/// guards, and long lists broken after their head.
+#[test]
+fn test_outcome_ast_is_an_s_expression() {
+    // Arrange
+    let short = "let f: Int -> Int = \\x -> -x in f 1";
+    let long = "match xs with | x :: rest when x > limit -> rest | _ -> [first, second, third]";
+
+    // Act
+    let short_ast = conformance::Outcome::of(short).ast;
+    let long_ast = conformance::Outcome::of(long).ast;
+
+    // Assert
+    assert_eq!(
+        short_ast.as_deref(),
+        Some("(let f (: (-> Int Int)) (lambda x (negate x)) (apply f 1))")
+    );
+    assert_eq!(
+        long_ast.as_deref(),
+        Some(
+            "(match\n  \
+             xs\n  \
+             (arm (:: x rest) (when (> x limit)) rest)\n  \
+             (arm _ (list first second third)))"
+        )
+    );
+}
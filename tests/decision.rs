@@ -0,0 +1,137 @@
+//! tests/decision.rs
+
+mod support;
+
+use rdp::{compile_match, testrand, Expression, MatchArm};
+
+/// Parses `source`, a `match`, and returns its arms.
+fn arms(source: &str) -> Vec<MatchArm> {
+    match support::parse(source).unwrap() {
+        Expression::PatternMatch { arms, .. } => arms,
+        other => panic!("not a match: {:?}", other),
+    }
+}
+
+/// Tests that each literal selects its arm and anything else the default.
+#[test]
+fn test_select_literal_and_default() {
+    // Arrange
+    let tree = compile_match(&arms("match n with | 3 -> a | 1 -> b | (2) -> c | x -> d"));
+
+    // Act
+    let selected: Vec<Option<usize>> = [1.0, 2.0, 3.0, 4.0, f64::NAN]
+        .into_iter()
+        .map(|value| tree.select(value).arm)
+        .collect();
+
+    // Assert
+    assert_eq!(selected, [Some(1), Some(2), Some(0), Some(3), Some(3)]);
+}
+
+/// Tests that a match without an irrefutable arm selects nothing for an
+/// uncovered value.
+#[test]
+fn test_select_without_default() {
+    // Arrange
+    let tree = compile_match(&arms("match n with | 0 -> a | 1 -> b"));
+
+    // Act
+    let selection = tree.select(5.0);
+
+    // Assert
+    assert_eq!(selection.arm, None);
+}
+
+/// Tests that repeated literals and arms after a catch-all are dropped as
+/// unreachable, keeping first-match order.
+#[test]
+fn test_unreachable_arms_are_dropped() {
+    // Arrange
+    let tree = compile_match(&arms(
+        "match n with | 1 -> a | 2 -> b | 1 -> c | _ -> d | 3 -> e | y -> f",
+    ));
+
+    // Act
+    let reachable = tree.reachable_arms();
+
+    // Assert
+    assert_eq!(reachable, [0, 1, 3]);
+    assert_eq!(tree.select(1.0).arm, Some(0));
+    assert_eq!(tree.select(3.0).arm, Some(3));
+}
+
+/// Tests that the tree stops at the first guarded arm a number can match,
+/// leaving values that reach it undecided, and that every arm from there on
+/// stays reachable.
+#[test]
+fn test_guarded_arm_stops_the_tree() {
+    // Arrange
+    let tree = compile_match(&arms(
+        "match n with | 1 -> a | true when p -> b | 2 when p -> c | 3 -> d | _ -> e | 4 -> f",
+    ));
+
+    // Act
+    let literal = tree.select(1.0);
+    let guarded = tree.select(3.0);
+
+    // Assert
+    assert_eq!((literal.arm, literal.undecided), (Some(0), false));
+    assert_eq!((guarded.arm, guarded.undecided), (Some(2), true));
+    assert_eq!(tree.reachable_arms(), [0, 2, 3, 4, 5]);
+}
+
+/// Tests that a 200-arm numeric match selects every arm in a handful of
+/// comparisons, where testing arms in order takes up to 200.
+#[test]
+fn test_large_match_needs_few_comparisons() {
+    // Arrange
+    let source: String = (0..200)
+        .map(|n| format!(" | {} -> {}", n, n))
+        .chain([" | _ -> 0".to_string()])
+        .collect();
+    let tree = compile_match(&arms(&format!("match n with{}", source)));
+
+    // Act
+    let selections: Vec<_> = (0..210).map(|n| tree.select(n as f64)).collect();
+
+    // Assert
+    for (n, selection) in selections.iter().enumerate() {
+        assert_eq!(selection.arm, Some(n.min(200)));
+        assert!(selection.comparisons <= 8, "{} took {:?}", n, selection);
+    }
+}
+
+/// Tests that the tree selects the same arm as trying the arms in order,
+/// for random matches and scrutinees.
+#[test]
+fn test_tree_agrees_with_first_match() {
+    testrand::check(
+        testrand::seed(),
+        500,
+        |rng| {
+            let arms: String = (0..1 + rng.below(12))
+                .map(|_| match rng.below(6) {
+                    0 => " | _ -> 0".to_string(),
+                    1 => " | (x) -> 0".to_string(),
+                    _ => format!(" | {} -> 0", rng.below(8)),
+                })
+                .collect();
+            format!("match n with{}", arms)
+        },
+        |source| {
+            let arms = arms(source);
+            let tree = compile_match(&arms);
+            for value in 0..10 {
+                let value = value as f64;
+                let first = arms
+                    .iter()
+                    .position(|arm| support::matches_number(&arm.pattern, value));
+                let selected = tree.select(value).arm;
+                if selected != first {
+                    return Err(format!("{} selects {:?}, not {:?}", value, selected, first));
+                }
+            }
+            Ok(())
+        },
+    );
+}
@@ -0,0 +1,110 @@
+//! tests/declarations.rs
+
+mod support;
+
+use rdp::{print_expression, Program, Severity};
+
+fn program(source: &str) -> Program {
+    Program {
+        expression: support::parse(source).unwrap(),
+    }
+}
+
+/// Tests that a declaration using one defined below it is moved after it,
+/// so every name is defined before it is used.
+#[test]
+fn test_forward_reference_is_reordered() {
+    // Arrange
+    let program = program("let main = helper 1 in let helper = \\x -> x + 1 in main");
+
+    // Act
+    let sorted = program.topo_sorted().unwrap();
+
+    // Assert
+    assert_eq!(
+        print_expression(&sorted.expression),
+        "let helper = \\x -> x + 1 in let main = helper 1 in main"
+    );
+}
+
+/// Tests that declarations already in order, or independent of each
+/// other, keep their source order.
+#[test]
+fn test_sorting_is_stable() {
+    // Arrange
+    let source = "let c = 3 in let a = 1 in let b = a + c in let f = \\n -> f n in b";
+    let program = program(source);
+
+    // Act
+    let sorted = program.topo_sorted().unwrap();
+
+    // Assert
+    assert_eq!(sorted, program);
+}
+
+/// Tests that two declarations using each other are reported as a cycle
+/// naming both.
+#[test]
+fn test_two_declaration_cycle() {
+    // Arrange
+    let program = program("let a = b + 1 in let b = a + 1 in let c = 0 in a");
+
+    // Act
+    let errors = program.topo_sorted().unwrap_err();
+
+    // Assert
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].severity, Severity::Error);
+    assert_eq!(errors[0].code, "declaration-cycle");
+    assert_eq!(
+        errors[0].message,
+        "top-level declarations depend on each other in a cycle: 'a', 'b'"
+    );
+}
+
+/// Tests that a name bound inside a declaration's value is not a use of the
+/// top-level declaration with the same name.
+#[test]
+fn test_inner_bindings_are_not_uses() {
+    // Arrange
+    let program = program("let a = \\b -> b in let b = a 1 in b");
+
+    // Act
+    let sorted = program.topo_sorted().unwrap();
+
+    // Assert
+    assert_eq!(sorted, program);
+}
+
+/// Tests that a name declared twice is rejected rather than guessed at.
+#[test]
+fn test_duplicate_declaration() {
+    // Arrange
+    let program = program("let x = 1 in let x = x + 1 in x");
+
+    // Act
+    let errors = program.topo_sorted().unwrap_err();
+
+    // Assert
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, "duplicate-declaration");
+}
+
+/// Tests listing the declarations a program starts with.
+#[test]
+fn test_declarations() {
+    // Arrange
+    let program = program("let a : Int = 1 in let b = 2 in (let c = 3 in c)");
+
+    // Act
+    let (declarations, body) = program.declarations();
+
+    // Assert
+    let names: Vec<&str> = declarations
+        .iter()
+        .map(|declaration| declaration.identifier)
+        .collect();
+    assert_eq!(names, ["a", "b"]);
+    assert!(declarations[0].type_annotation.is_some());
+    assert_eq!(print_expression(body), "(let c = 3 in c)");
+}
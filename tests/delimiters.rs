@@ -0,0 +1,117 @@
+//! tests/delimiters.rs
+
+use rdp::{matching_delimiter, Span};
+
+/// The partner of the delimiter starting at the `nth` occurrence of `needle`
+/// in `src`, as the text it spans and the occurrence that is.
+fn partner(src: &str, needle: &str, nth: usize) -> Option<(String, usize)> {
+    let offset = occurrences(src, needle)[nth];
+    matching_delimiter(src, offset).map(|span| {
+        let text: String = src
+            .chars()
+            .skip(span.start)
+            .take(span.end - span.start)
+            .collect();
+        let nth = occurrences(src, &text)
+            .iter()
+            .position(|&start| start == span.start)
+            .expect("a partner is a whole token");
+        (text, nth)
+    })
+}
+
+/// Char offsets where `needle` starts in `src`.
+fn occurrences(src: &str, needle: &str) -> Vec<usize> {
+    let chars: Vec<char> = src.chars().collect();
+    let needle: Vec<char> = needle.chars().collect();
+    (0..chars.len())
+        .filter(|&start| chars[start..].starts_with(&needle))
+        .collect()
+}
+
+fn some(text: &str, nth: usize) -> Option<(String, usize)> {
+    Some((text.to_string(), nth))
+}
+
+/// Tests that nested parentheses pair up innermost first, from either side.
+#[test]
+fn test_nested_parens() {
+    // Arrange
+    let src = "f ((a b) (c (d)))";
+
+    // Act & Assert
+    assert_eq!(partner(src, "(", 0), some(")", 3));
+    assert_eq!(partner(src, "(", 1), some(")", 0));
+    assert_eq!(partner(src, "(", 2), some(")", 2));
+    assert_eq!(partner(src, "(", 3), some(")", 1));
+    assert_eq!(partner(src, ")", 2), some("(", 2));
+    assert_eq!(partner(src, ")", 3), some("(", 0));
+}
+
+/// Tests that keywords pair up through lambdas, and that `if`, `then`, and
+/// `else` lead to each other in turn.
+#[test]
+fn test_keywords_nested_in_lambdas() {
+    // Arrange
+    let src = "let f = \\x -> let g = \\y -> if y then x else y in g in f";
+
+    // Act & Assert
+    assert_eq!(partner(src, "let", 0), some("in", 1));
+    assert_eq!(partner(src, "let", 1), some("in", 0));
+    assert_eq!(partner(src, "in", 1), some("let", 0));
+    assert_eq!(partner(src, "if", 0), some("then", 0));
+    assert_eq!(partner(src, "then", 0), some("else", 0));
+    assert_eq!(partner(src, "else", 0), some("if", 0));
+}
+
+/// Tests that `match` pairs with its `with`, but not with the `with` of an
+/// arm's binding, which has no partner.
+#[test]
+fn test_match_with() {
+    // Arrange
+    let src = "match xs with | (h :: t) with n = h -> match t with | _ -> n";
+
+    // Act & Assert
+    assert_eq!(partner(src, "match", 0), some("with", 0));
+    assert_eq!(partner(src, "with", 0), some("match", 0));
+    assert_eq!(partner(src, "with", 1), None);
+    assert_eq!(partner(src, "match", 1), some("with", 2));
+}
+
+/// Tests that any offset inside a delimiter finds its partner, and that an
+/// offset on anything else finds nothing.
+#[test]
+fn test_offsets() {
+    // Arrange
+    let src = "let x = 1 in x";
+
+    // Act & Assert
+    assert_eq!(
+        matching_delimiter(src, 2),
+        Some(Span { start: 10, end: 12 })
+    );
+    assert_eq!(matching_delimiter(src, 11), Some(Span { start: 0, end: 3 }));
+    assert_eq!(matching_delimiter(src, 3), None);
+    assert_eq!(matching_delimiter(src, 4), None);
+    assert_eq!(matching_delimiter(src, 99), None);
+}
+
+/// Tests that unbalanced delimiters have no partner, and that no keyword
+/// after a mismatched one is paired, while parentheses still are, even in
+/// code that doesn't lex.
+#[test]
+fn test_unbalanced() {
+    // Arrange
+    let src = "let a = (b (c) in if d then let e = 1 in (e) # ) ( let";
+
+    // Act & Assert
+    assert_eq!(partner(src, "(", 0), some(")", 2));
+    assert_eq!(partner(src, "(", 1), some(")", 0));
+    assert_eq!(partner(src, "let", 0), None);
+    assert_eq!(partner(src, "in", 0), None);
+    assert_eq!(partner(src, "if", 0), None);
+    assert_eq!(partner(src, "then", 0), None);
+    assert_eq!(partner(src, "let", 1), None);
+    assert_eq!(partner(src, "(", 2), some(")", 1));
+    assert_eq!(partner(src, "(", 3), None);
+}
@@ -0,0 +1,111 @@
+//! tests/diff.rs
+
+mod support;
+
+use rdp::{semantic_diff, Change, Program};
+
+/// Lists the changes from the program in `old` to the one in `new`.
+fn diff(old: &str, new: &str) -> Vec<Change> {
+    let parse = |source| Program {
+        expression: support::parse(source).unwrap(),
+    };
+    semantic_diff(&parse(old), &parse(new))
+}
+
+/// The program the tests start from.
+const ORIGINAL: &str = "let base = 10 in\nlet scale = \\x -> x * base + 1 in\nscale 2";
+
+/// Tests that changing only whitespace and comments reports nothing.
+#[test]
+fn test_pure_reformat_reports_nothing() {
+    // Arrange
+    let reformatted = "-- scaling\nlet base = 10 in let scale = \\x -> x * base + 1 in scale 2";
+
+    // Act
+    let changes = diff(ORIGINAL, reformatted);
+
+    // Assert
+    assert_eq!(changes, vec![]);
+}
+
+/// Tests that redundant parentheses and renamed inner binders are
+/// formatting-only.
+#[test]
+fn test_parentheses_and_inner_binders_are_formatting_only() {
+    // Arrange
+    let reformatted = "let base = (10) in\nlet scale = \\y -> (y * base) + 1 in\nscale 2";
+
+    // Act
+    let changes = diff(ORIGINAL, reformatted);
+
+    // Assert
+    assert_eq!(changes, vec![Change::FormattingOnly]);
+}
+
+/// Tests that a binding renamed along with its uses is a rename, and nothing
+/// else.
+#[test]
+fn test_renamed_binding() {
+    // Arrange
+    let renamed = "let base = 10 in\nlet times = \\x -> x * base + 1 in\ntimes 2";
+
+    // Act
+    let changes = diff(ORIGINAL, renamed);
+
+    // Assert
+    assert_eq!(
+        changes,
+        vec![Change::Renamed {
+            from: "scale".to_string(),
+            to: "times".to_string()
+        }]
+    );
+}
+
+/// Tests that a changed constant is reported at the constant, with both
+/// versions of it.
+#[test]
+fn test_changed_arithmetic_constant() {
+    // Arrange
+    let changed = "let base = 10 in\nlet scale = \\x -> x * base + 2 in\nscale 2";
+
+    // Act
+    let changes = diff(ORIGINAL, changed);
+
+    // Assert
+    assert_eq!(
+        changes,
+        vec![Change::Modified {
+            path: "body.value.body.right".to_string(),
+            old: "1".to_string(),
+            new: "2".to_string()
+        }]
+    );
+    assert_eq!(
+        changes[0].to_string(),
+        "modified body.value.body.right: `1` -> `2`"
+    );
+}
+
+/// Tests that bindings with no counterpart are added or removed.
+#[test]
+fn test_added_and_removed_bindings() {
+    // Arrange
+    let changed = "let base = 10 in\nlet offset = \\x -> x + 1 in\nscale 2";
+
+    // Act
+    let changes = diff(ORIGINAL, changed);
+
+    // Assert
+    assert_eq!(
+        changes,
+        vec![
+            Change::Removed {
+                name: "scale".to_string()
+            },
+            Change::Added {
+                name: "offset".to_string()
+            },
+        ]
+    );
+}
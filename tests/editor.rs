@@ -0,0 +1,247 @@
+//! tests/editor.rs
+
+use std::{env, fs, process};
+
+use rdp::{
+    repl::{
+        editor::{Editor, Event, Key},
+        is_incomplete, respond, History,
+    },
+    Lexer, Parser, Severity, ValidateConfig,
+};
+
+fn editor(history: &[&str]) -> Editor {
+    let history = history.iter().map(|entry| entry.to_string()).collect();
+    Editor::new(history, |source| !is_incomplete(source))
+}
+
+fn type_text(editor: &mut Editor, text: &str) {
+    for c in text.chars() {
+        assert_eq!(editor.handle(Key::Char(c)), Event::Pending);
+    }
+}
+
+/// Tests that entries ending too early are incomplete, while complete and
+/// invalid ones are not.
+#[test]
+fn test_is_incomplete() {
+    // Arrange
+    let incomplete = [
+        "let x = 1 in",
+        "if x then",
+        "match x with",
+        "(1 + 2",
+        "x +",
+        ":ast let x = 1 in",
+    ];
+    let complete = [
+        "",
+        "   ",
+        "let x = 1 in x",
+        "1 + 2",
+        "1 + )",
+        ":ast",
+        ":ast x",
+    ];
+
+    // Act & Assert
+    for source in incomplete {
+        assert!(is_incomplete(source), "{:?} should be incomplete", source);
+    }
+    for source in complete {
+        assert!(!is_incomplete(source), "{:?} should be complete", source);
+    }
+}
+
+/// Tests that a program is answered with its full AST and `:ast` with the
+/// same AST on one line.
+#[test]
+fn test_respond_prints_the_ast() {
+    // Arrange
+    let config = ValidateConfig::default();
+    let tokens = Lexer::tokenize_str("let id = \\x -> x in id 1").unwrap();
+    let program = Parser::new(tokens).parse_program().unwrap();
+
+    // Act
+    let full = respond("let id = \\x -> x in id 1", &config);
+    let compact = respond(":ast let id = \\x -> x\nin id 1", &config);
+
+    // Assert
+    assert_eq!(full.output, Some(format!("{:#?}", program)));
+    assert_eq!(compact.output, Some(format!("{:?}", program)));
+    assert!(full.diagnostics.is_empty() && compact.diagnostics.is_empty());
+}
+
+/// Tests that invalid entries and unknown commands are answered with
+/// diagnostics only, and blank entries with nothing.
+#[test]
+fn test_respond_reports_diagnostics() {
+    // Arrange
+    let config = ValidateConfig::default();
+
+    // Act
+    let invalid = respond(":ast let = 1", &config);
+    let unknown = respond(":type 1", &config);
+    let blank = respond("  ", &config);
+
+    // Assert
+    assert_eq!(invalid.output, None);
+    assert_eq!(invalid.diagnostics[0].code, "E0001");
+    assert_eq!(unknown.output, None);
+    assert_eq!(unknown.diagnostics[0].severity, Severity::Error);
+    assert_eq!(
+        unknown.diagnostics[0].message,
+        "unknown command ':type'; expected one of: :ast"
+    );
+    assert_eq!(blank, Default::default());
+}
+
+/// Tests that Enter opens a new line until the entry is complete, then
+/// submits all of its lines as one entry.
+#[test]
+fn test_enter_continues_incomplete_entries() {
+    // Arrange
+    let mut editor = editor(&[]);
+    type_text(&mut editor, "let x = 1 in");
+
+    // Act
+    let first = editor.handle(Key::Enter);
+    type_text(&mut editor, "x");
+    let second = editor.handle(Key::Enter);
+
+    // Assert
+    assert_eq!(first, Event::Pending);
+    assert_eq!(second, Event::Submit("let x = 1 in\nx".to_string()));
+    assert_eq!(editor.buffer(), "");
+}
+
+/// Tests cursor movement and editing within the current line.
+#[test]
+fn test_cursor_editing() {
+    // Arrange
+    let mut editor = editor(&[]);
+    type_text(&mut editor, "1 + 3");
+
+    // Act
+    editor.handle(Key::Backspace);
+    type_text(&mut editor, "2");
+    editor.handle(Key::Home);
+    type_text(&mut editor, "0 + ");
+    editor.handle(Key::End);
+    editor.handle(Key::Left);
+    editor.handle(Key::Backspace);
+
+    // Assert
+    assert_eq!(editor.buffer(), "0 + 1 +2");
+    assert_eq!(editor.cursor(), 7);
+}
+
+/// Tests that Up and Down move between the lines of a multi-line entry
+/// before reaching into the history.
+#[test]
+fn test_up_down_move_between_lines() {
+    // Arrange
+    let mut editor = editor(&["older"]);
+    type_text(&mut editor, "let x = 1 in");
+    editor.handle(Key::Enter);
+    type_text(&mut editor, "x");
+
+    // Act
+    editor.handle(Key::Up);
+    let on_first_line = editor.cursor_line_col();
+    editor.handle(Key::Down);
+    let on_second_line = editor.cursor_line_col();
+
+    // Assert
+    assert_eq!(on_first_line, (0, 1));
+    assert_eq!(on_second_line, (1, 1));
+    assert_eq!(editor.buffer(), "let x = 1 in\nx");
+}
+
+/// Tests browsing the history and coming back to the entry being typed.
+#[test]
+fn test_history_navigation_keeps_the_draft() {
+    // Arrange
+    let mut editor = editor(&["1 + 1", "2 + 2"]);
+    type_text(&mut editor, "dra");
+
+    // Act
+    editor.handle(Key::Up);
+    let newest = editor.buffer();
+    editor.handle(Key::Up);
+    editor.handle(Key::Up);
+    let oldest = editor.buffer();
+    editor.handle(Key::Down);
+    editor.handle(Key::Down);
+    let draft = editor.buffer();
+
+    // Assert
+    assert_eq!(newest, "2 + 2");
+    assert_eq!(oldest, "1 + 1");
+    assert_eq!(draft, "dra");
+}
+
+/// Tests that a multi-line entry is recalled as one item and can be
+/// resubmitted as it was.
+#[test]
+fn test_multi_line_entry_recalled_whole() {
+    // Arrange
+    let mut editor = editor(&[]);
+    type_text(&mut editor, "if x then");
+    editor.handle(Key::Enter);
+    type_text(&mut editor, "1 else 2");
+    let Event::Submit(entry) = editor.handle(Key::Enter) else {
+        panic!("entry should be complete");
+    };
+    editor.remember(&entry);
+
+    // Act
+    editor.handle(Key::Up);
+    let recalled = editor.buffer();
+    let resubmitted = editor.handle(Key::Enter);
+
+    // Assert
+    assert_eq!(recalled, "if x then\n1 else 2");
+    assert_eq!(resubmitted, Event::Submit(recalled));
+}
+
+/// Tests that Ctrl-C abandons the entry and Ctrl-D only exits when the
+/// entry is empty.
+#[test]
+fn test_ctrl_c_and_ctrl_d() {
+    // Arrange
+    let mut editor = editor(&[]);
+    type_text(&mut editor, "let x");
+
+    // Act
+    let with_text = editor.handle(Key::CtrlD);
+    let cancelled = editor.handle(Key::CtrlC);
+    let empty = editor.handle(Key::CtrlD);
+
+    // Assert
+    assert_eq!(with_text, Event::Pending);
+    assert_eq!(cancelled, Event::Cancelled);
+    assert_eq!(empty, Event::Exit);
+}
+
+/// Tests that the history survives reopening, with multi-line entries and
+/// backslashes intact, and without blank or repeated entries.
+#[test]
+fn test_history_file_round_trip() {
+    // Arrange
+    let dir = env::temp_dir().join(format!("rdp-history-{}", process::id()));
+    let path = dir.join("nested").join("history");
+    let _ = fs::remove_dir_all(&dir);
+
+    // Act
+    let mut history = History::open(&path).unwrap();
+    for entry in ["1 + 1", "1 + 1", "  ", "let x = 1 in\nx", "\\x -> x"] {
+        history.push(entry).unwrap();
+    }
+    let reopened = History::open(&path).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+
+    // Assert
+    assert_eq!(reopened.entries(), ["1 + 1", "let x = 1 in\nx", "\\x -> x"]);
+    assert_eq!(reopened.entries(), history.entries());
+}
@@ -0,0 +1,200 @@
+//! tests/error_catalog.rs
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use rdp::{Lexer, LexerOptions, ParseError, Parser};
+
+/// Codes that no source program can currently trigger, each with the reason.
+/// Each entry must leave this list as soon as the language gains a construct
+/// that produces it.
+const UNREACHABLE_CODES: [(&str, &str); 3] = [
+    (
+        "E0002",
+        "The lexer always terminates the token stream with `Eof`.",
+    ),
+    (
+        "E0004",
+        "Any name in type position is a named type or a type variable.",
+    ),
+    ("E0007", "Nothing constructs the catch-all variant."),
+];
+
+fn catalog_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/errors")
+}
+
+fn catalog_entries() -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(catalog_dir())
+        .expect("Failed to read error catalog")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pfl"))
+        .collect();
+    entries.sort();
+    entries
+}
+
+/// Extra CLI options for a catalog entry, read from the `.args` file next to
+/// it: one option per line.
+fn entry_args(entry: &Path) -> Vec<String> {
+    fs::read_to_string(entry.with_extension("args"))
+        .map(|args| args.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Mirrors the CLI's handling of the lexer options an entry may use.
+fn entry_options(entry: &Path) -> LexerOptions {
+    let mut options = LexerOptions::default();
+    for arg in entry_args(entry) {
+        match arg.split_once('=') {
+            Some(("--max-identifier-length", n)) => {
+                options.max_identifier_length = Some(n.parse().unwrap())
+            }
+            Some(("--max-line-length", n)) => options.max_line_length = Some(n.parse().unwrap()),
+            _ => panic!("Unsupported catalog option '{}'", arg),
+        }
+    }
+    options
+}
+
+fn parse_file(path: &Path) -> Result<(), ParseError> {
+    let source = fs::read_to_string(path).unwrap();
+    let tokens = Lexer::with_options(&source, entry_options(path)).tokenize()?;
+    Parser::new(tokens).parse_program().map(|_| ())
+}
+
+/// Runs the CLI on every catalog program and compares its diagnostic with the
+/// curated `.stderr` file next to it. Options listed in a `.args` file are
+/// passed to the CLI before the program, which is named relative to the
+/// catalog so diagnostics read the same on every machine.
+///
+/// Set `RDP_BLESS=1` to rewrite the `.stderr` files from the current output,
+/// then review the resulting diff.
+#[test]
+fn test_error_catalog_messages() {
+    // Arrange
+    let bless = std::env::var_os("RDP_BLESS").is_some();
+    let entries = catalog_entries();
+    assert!(!entries.is_empty());
+
+    for entry in entries {
+        // Act
+        let output = Command::new(env!("CARGO_BIN_EXE_rdp"))
+            .current_dir(catalog_dir())
+            .args(entry_args(&entry))
+            .arg(entry.file_name().unwrap())
+            .output()
+            .expect("Failed to run rdp");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+
+        // Assert
+        assert!(!output.status.success(), "{} parsed", entry.display());
+        assert!(
+            output.stdout.is_empty(),
+            "{} printed output",
+            entry.display()
+        );
+
+        let expected_path = entry.with_extension("stderr");
+        if bless {
+            fs::write(&expected_path, &stderr).unwrap();
+        }
+        let expected = fs::read_to_string(&expected_path)
+            .unwrap_or_else(|_| panic!("Missing {}", expected_path.display()));
+        assert_eq!(stderr, expected, "{}", entry.display());
+    }
+}
+
+/// Tests that the subcommands reading a single file report a program that
+/// fails just as the plain CLI does, with the entry's catalog diagnostic.
+#[test]
+fn test_subcommands_report_errors_alike() {
+    // Arrange
+    let entry = "if-missing-then.pfl";
+    let expected = fs::read_to_string(catalog_dir().join("if-missing-then.stderr")).unwrap();
+
+    for (args, status) in [
+        (vec!["--callgraph", entry], 1),
+        (vec!["--slice=root", entry], 1),
+        (vec!["--search=$x", "--replace=$x", entry], 1),
+        (vec!["--diff", entry, entry], 2),
+    ] {
+        // Act
+        let output = Command::new(env!("CARGO_BIN_EXE_rdp"))
+            .current_dir(catalog_dir())
+            .args(&args)
+            .output()
+            .expect("Failed to run rdp");
+
+        // Assert
+        assert_eq!(output.status.code(), Some(status), "{:?}", args);
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            expected,
+            "{:?}",
+            args
+        );
+    }
+}
+
+/// Tests that every reachable error code has at least one catalog entry, so a
+/// new variant can't ship without a curated message.
+#[test]
+fn test_error_catalog_covers_every_code() {
+    // Arrange
+    let covered: BTreeSet<&str> = catalog_entries()
+        .iter()
+        .map(|entry| {
+            parse_file(entry)
+                .expect_err("Catalog programs must fail")
+                .code()
+        })
+        .collect();
+
+    let unreachable: BTreeSet<&str> = UNREACHABLE_CODES.map(|(code, _)| code).into();
+
+    // Act
+    let missing: Vec<&str> = ParseError::CODES
+        .into_iter()
+        .filter(|code| !covered.contains(code) && !unreachable.contains(code))
+        .collect();
+    let stale: Vec<&str> = unreachable
+        .into_iter()
+        .filter(|code| covered.contains(code))
+        .collect();
+
+    // Assert
+    assert!(missing.is_empty(), "No catalog entry for {:?}", missing);
+    assert!(
+        stale.is_empty(),
+        "Reachable now, remove from list: {:?}",
+        stale
+    );
+}
+
+/// Tests that no rendered message ends its own sentence before the
+/// `: expected ..., but found ...` part, as in `term.: expected`.
+#[test]
+fn test_messages_do_not_end_before_details() {
+    // Arrange
+    let conformance = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("conformance");
+    let mut rendered: Vec<PathBuf> = catalog_entries()
+        .iter()
+        .map(|entry| entry.with_extension("stderr"))
+        .collect();
+    for case in fs::read_dir(conformance).expect("Failed to read conformance corpus") {
+        rendered.push(case.unwrap().path().join("error.txt"));
+    }
+
+    for path in rendered.iter().filter(|path| path.exists()) {
+        // Act
+        let text = fs::read_to_string(path).unwrap();
+
+        // Assert
+        assert!(!text.contains(".:"), "{}: {}", path.display(), text);
+    }
+}
@@ -0,0 +1,84 @@
+//! tests/features.rs
+
+use std::path::Path;
+
+use rdp::{
+    collect_stats, diagnostics_to_sarif, features, lint, parse_template, supports, Feature, Lexer,
+    LexerOptions, ParseError, Parser, Program, Token,
+};
+
+fn parse(input: &str) -> Result<Program, ParseError> {
+    let tokens = Lexer::tokenize_str(input)?;
+    Parser::new(tokens).parse_program()
+}
+
+/// Exercises `feature`, returning whether it actually works in this build.
+fn probe(feature: Feature) -> bool {
+    match feature {
+        Feature::DigitSeparators => {
+            Lexer::tokenize_str("1_000") == Ok(vec![Token::Number(1000.0), Token::Eof])
+        }
+        Feature::Shebang => parse("#!/usr/bin/env rdp\n1").is_ok(),
+        Feature::LexerLimits => {
+            let options = LexerOptions {
+                max_identifier_length: Some(1),
+                ..LexerOptions::default()
+            };
+            matches!(
+                Lexer::with_options("ab", options).tokenize(),
+                Err(ParseError::IdentifierTooLong { .. })
+            )
+        }
+        Feature::Lints => parse("match x with | 1 -> 2")
+            .map(|program| !lint(&program).is_empty())
+            .unwrap_or(false),
+        Feature::Sarif => diagnostics_to_sarif(&[], Path::new("a.pfl")).contains("2.1.0"),
+        Feature::Stats => parse("a + b")
+            .map(|program| collect_stats(&[program]).operators.contains_key("+"))
+            .unwrap_or(false),
+        Feature::StructuralSearch => match (parse_template("$x + $x"), parse("a + a")) {
+            (Ok(template), Ok(program)) => template.find_matches(&program).len() == 1,
+            _ => false,
+        },
+        Feature::Strings => parse("\"a\"").is_ok(),
+        Feature::Booleans => Lexer::tokenize_str("true") == Ok(vec![Token::True, Token::Eof]),
+        Feature::Tuples => parse("(a, b)").is_ok(),
+        Feature::Lists => parse("[a]").is_ok(),
+        _ => panic!("No probe for feature '{}'", feature),
+    }
+}
+
+/// Tests that the advertised features are exactly the ones that work, so the
+/// hand-maintained list can't drift from the implementation.
+#[test]
+fn test_features_match_implementation() {
+    for &feature in Feature::all() {
+        // Act
+        let works = probe(feature);
+
+        // Assert
+        assert_eq!(
+            supports(feature),
+            works,
+            "'{}' is advertised incorrectly",
+            feature
+        );
+    }
+}
+
+/// Tests that every supported feature is known and listed once.
+#[test]
+fn test_features_are_known_and_unique() {
+    // Arrange
+    let supported = features();
+
+    // Assert
+    for (i, feature) in supported.iter().enumerate() {
+        assert!(Feature::all().contains(feature));
+        assert!(
+            !supported[i + 1..].contains(feature),
+            "'{}' listed twice",
+            feature
+        );
+    }
+}
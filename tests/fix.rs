@@ -0,0 +1,112 @@
+//! tests/fix.rs
+
+use rdp::{
+    apply_fixes, fix_until_stable, suggest_parens, validate, Applicability, Diagnostic, Fix,
+    TextEdit, ValidateConfig, MAX_FIX_ROUNDS,
+};
+
+/// A warning whose machine-applicable fix replaces `range` by `replacement`.
+fn fixable(range: std::ops::Range<usize>, replacement: &str) -> Diagnostic {
+    Diagnostic::warning("test", "fixable").with_fix(Fix {
+        applicability: Applicability::MachineApplicable,
+        edits: vec![TextEdit {
+            range,
+            replacement: replacement.to_string(),
+        }],
+    })
+}
+
+/// Tests that independent fixes are all applied, wherever they are in the
+/// source and in whatever order they are reported.
+#[test]
+fn test_apply_independent_fixes() {
+    // Arrange
+    let source = "let x = f a + b in g";
+    let diagnostics = [
+        fixable(19..20, "h"),
+        fixable(8..11, "(f a)"),
+        fixable(4..5, "y"),
+    ];
+
+    // Act
+    let outcome = apply_fixes(source, &diagnostics);
+
+    // Assert
+    assert_eq!(outcome.source, "let y = (f a) + b in h");
+    assert_eq!(outcome.applied, 3);
+    assert!(outcome.skipped.is_empty());
+    assert!(outcome.converged);
+}
+
+/// Tests that a fix overlapping one applied earlier is skipped, and that
+/// fixes that are not machine-applicable are never applied.
+#[test]
+fn test_overlapping_fix_is_skipped() {
+    // Arrange
+    let source = "f a + b";
+    let review = Diagnostic::warning("test", "review").with_fix(Fix {
+        applicability: Applicability::MaybeIncorrect,
+        edits: vec![TextEdit {
+            range: 6..7,
+            replacement: "c".to_string(),
+        }],
+    });
+    let diagnostics = [fixable(0..3, "(f a)"), fixable(2..7, "(a + b)"), review];
+
+    // Act
+    let outcome = apply_fixes(source, &diagnostics);
+
+    // Assert
+    assert_eq!(outcome.source, "(f a) + b");
+    assert_eq!(outcome.applied, 1);
+    assert_eq!(outcome.skipped, vec![diagnostics[1].clone()]);
+    assert!(!outcome.converged);
+}
+
+/// Tests that fixing repeats until nothing is left to fix, and stops at the
+/// round limit when fixes keep coming.
+#[test]
+fn test_fix_until_stable() {
+    // Arrange
+    // Wraps the source in parentheses until it is 4 levels deep.
+    let shallow = |source: &str| match source.chars().take_while(|&c| c == '(').count() {
+        depth if depth < 4 => vec![fixable(0..0, "("), fixable(source.len()..source.len(), ")")],
+        _ => Vec::new(),
+    };
+    let endless = |source: &str| vec![fixable(0..0, "("), fixable(source.len()..source.len(), ")")];
+
+    // Act
+    let stable = fix_until_stable("x", MAX_FIX_ROUNDS, shallow);
+    let capped = fix_until_stable("x", 3, endless);
+
+    // Assert
+    assert_eq!(stable.source, "((((x))))");
+    assert_eq!(stable.applied, 8);
+    assert!(stable.converged);
+    assert_eq!(capped.source, "(((x)))");
+    assert_eq!(capped.applied, 6);
+    assert!(!capped.converged);
+}
+
+/// Tests that the located fixes of `suggest_parens`, applied until stable
+/// over the validate pipeline, leave nothing to suggest and keep the rest of
+/// the source as written.
+#[test]
+fn test_fix_suggest_parens_until_stable() {
+    // Arrange
+    let config = ValidateConfig {
+        checks: vec![suggest_parens],
+        ..ValidateConfig::default()
+    };
+    let source = "-- sum\nf x + g y + h z -- all\n";
+
+    // Act
+    let outcome = fix_until_stable(source, MAX_FIX_ROUNDS, |source| {
+        validate(source, &config).diagnostics
+    });
+
+    // Assert
+    assert_eq!(outcome.source, "-- sum\n(f x) + (g y) + (h z) -- all\n");
+    assert_eq!(outcome.applied, 3);
+    assert!(outcome.converged);
+}
@@ -0,0 +1,109 @@
+//! tests/folding.rs
+
+use std::fs;
+
+use rdp::{folding_ranges, FoldKind, FoldRange};
+
+fn range(start_line: usize, end_line: usize, kind: FoldKind) -> FoldRange {
+    FoldRange {
+        start_line,
+        end_line,
+        kind,
+    }
+}
+
+/// Tests the exact ranges of a formatted program: single-line constructs
+/// have none, and a value and its parentheses fold once.
+#[test]
+fn test_folding_ranges() {
+    // Arrange
+    let src = "\
+let classify =
+  \\n ->
+    match n with
+    | 0 -> \"zero\"
+    | k when k < 0 ->
+      \"negative\"
+    | _ ->
+      let half =
+        (n
+          / 2)
+      in
+      show half
+in
+classify 4
+";
+
+    // Act
+    let ranges = folding_ranges(src);
+
+    // Assert
+    assert_eq!(
+        ranges,
+        vec![
+            range(2, 12, FoldKind::LetValue),
+            range(3, 12, FoldKind::LambdaBody),
+            range(5, 6, FoldKind::MatchArm),
+            range(7, 12, FoldKind::MatchArm),
+            range(9, 10, FoldKind::LetValue),
+        ]
+    );
+}
+
+/// Tests that a fold ending on the line where the next one starts stops a
+/// line earlier, and is dropped if that leaves it on one line.
+#[test]
+fn test_folds_sharing_a_line() {
+    // Arrange
+    let src = "let x = f (a,\n  b) in g (c,\n  d)\n";
+
+    // Act
+    let ranges = folding_ranges(src);
+
+    // Assert
+    assert_eq!(ranges, vec![range(2, 3, FoldKind::LetBody)]);
+}
+
+/// Tests that code that doesn't parse still folds the parentheses that pair
+/// up.
+#[test]
+fn test_folding_broken_code() {
+    // Arrange
+    let src = "let x = (a\n  b) in (c\n  d";
+
+    // Act
+    let ranges = folding_ranges(src);
+
+    // Assert
+    assert_eq!(ranges, vec![range(1, 2, FoldKind::Grouped)]);
+}
+
+/// Tests that the ranges of every example are multi-line, ordered, and
+/// either nested or apart.
+#[test]
+fn test_example_folds_nest() {
+    for entry in fs::read_dir("examples").expect("examples directory") {
+        // Arrange
+        let path = entry.expect("examples entry").path();
+        let src = fs::read_to_string(&path).expect("readable example");
+
+        // Act
+        let ranges = folding_ranges(&src);
+
+        // Assert
+        for (index, a) in ranges.iter().enumerate() {
+            assert!(a.start_line < a.end_line, "{}: {:?}", path.display(), a);
+            for b in &ranges[index + 1..] {
+                let nested = b.end_line <= a.end_line;
+                let apart = b.start_line > a.end_line;
+                assert!(
+                    a.start_line <= b.start_line && (nested || apart),
+                    "{}: {:?} and {:?}",
+                    path.display(),
+                    a,
+                    b
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! tests/hostile_input.rs
+//!
+//! The graceful degradation contract: on any input, `tokenize`, `parse`, and
+//! `validate` return, without panicking or overflowing the stack, after work
+//! roughly linear in the size of the input. Each family below is a known
+//! worst case, generated at several sizes; work is measured by the parser's
+//! token count rather than wall-clock time, so the checks are stable in CI.
+//!
+//! Tests run on the default 2 MiB test thread stack, which is smaller than a
+//! main thread's, so passing here leaves headroom for the command line.
+
+use rdp::{
+    print_expression, print_styled, testrand, validate, Lexer, ParseError, Parser, Teaching,
+    ValidateConfig,
+};
+
+/// Input sizes, in bytes, each family is generated at.
+const SIZES: [usize; 4] = [4_096, 8_192, 16_384, 32_768];
+
+/// Repeats `unit` to fill about `size` bytes, between `prefix` and the
+/// matching number of `suffix`es.
+fn fill(size: usize, prefix: &str, unit: &str, middle: &str, suffix: &str) -> String {
+    let count = size / (unit.len() + suffix.len()).max(1);
+    format!(
+        "{}{}{}{}",
+        prefix,
+        unit.repeat(count),
+        middle,
+        suffix.repeat(count)
+    )
+}
+
+/// An operand nested as deeply as the parser allows, followed by a chain of
+/// `length` more operands; the tree is deeper than either alone.
+fn deepest_operand(length: usize) -> String {
+    let depth = Parser::DEFAULT_MAX_DEPTH - 2;
+    format!(
+        "{}1{}{}",
+        "(".repeat(depth),
+        ")".repeat(depth),
+        " + 1".repeat(length)
+    )
+}
+
+/// The worst cases, by name, each generating an input of about `size` bytes.
+#[allow(clippy::type_complexity)]
+const FAMILIES: [(&str, fn(usize) -> String); 13] = [
+    ("deep parentheses", |n| fill(n, "", "(", "1", ")")),
+    ("deep lets", |n| fill(n, "", "let x = ", "1", " in x")),
+    ("deep lambdas", |n| fill(n, "", "\\x -> ", "x", "")),
+    ("deep ifs", |n| fill(n, "", "if 1 then ", "1", " else 1")),
+    ("deep patterns", |n| {
+        fill(n, "match x with | ", "(", "1", ")") + " -> 1"
+    }),
+    ("deep types", |n| {
+        fill(n, "let f: ", "(Int -> ", "Int", ")") + " = 1 in f"
+    }),
+    ("operator chain", |n| fill(n, "", "1 + ", "1", "")),
+    ("composition chain", |n| fill(n, "", "f . ", "g", "")),
+    ("deep operand of a chain", |n| deepest_operand(n / 4)),
+    ("long identifier", |n| "a".repeat(n)),
+    ("wide application", |n| fill(n, "f", " x", "", "")),
+    ("huge match", |n| {
+        fill(n, "match x with", " | 1 -> 1", " | _ -> 0", "")
+    }),
+    ("expression patterns", |n| {
+        fill(n, "match x with | ", "(", "f x", ")") + " -> 1"
+    }),
+];
+
+/// Lexes, parses, and validates `input`, returning the tokens the parser
+/// consumed. Any panic or stack overflow fails the test.
+fn run(input: &str) -> usize {
+    let consumed = match Lexer::tokenize_str(input) {
+        Ok(tokens) => {
+            let mut parser = Parser::new(tokens);
+            let _ = parser.parse_program();
+            parser.tokens_consumed()
+        }
+        Err(_) => 0,
+    };
+    let _ = validate(input, &ValidateConfig::default());
+    consumed
+}
+
+/// Tests that every worst case is handled, with work growing at most
+/// linearly as the input doubles.
+#[test]
+fn test_worst_cases_scale_linearly() {
+    for (name, generate) in FAMILIES {
+        // Act
+        let work: Vec<usize> = SIZES.iter().map(|&size| run(&generate(size))).collect();
+
+        // Assert
+        for (pair, sizes) in work.windows(2).zip(SIZES.windows(2)) {
+            let ratio = sizes[1] / sizes[0];
+            assert!(
+                pair[1] <= pair[0] * ratio + 64,
+                "{}: {} tokens at {} bytes, but {} at {} bytes",
+                name,
+                pair[0],
+                sizes[0],
+                pair[1],
+                sizes[1]
+            );
+        }
+    }
+}
+
+/// Tests that nesting past the limit fails with `NestingTooDeep`, however it
+/// is spelled, instead of exhausting the stack. Operator chains only fail
+/// past their own, larger budget.
+#[test]
+fn test_deep_nesting_is_rejected() {
+    for (i, (name, generate)) in FAMILIES[..8].iter().enumerate() {
+        // Arrange
+        let tokens = Lexer::tokenize_str(&generate(100_000)).unwrap();
+        let limit = if i < 6 {
+            Parser::DEFAULT_MAX_DEPTH
+        } else {
+            Parser::DEFAULT_MAX_DEPTH * Parser::CHAIN_FACTOR
+        };
+
+        // Act
+        let result = Parser::new(tokens).parse_program();
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(ParseError::NestingTooDeep { limit }),
+            "{}",
+            name
+        );
+    }
+}
+
+/// Tests that nesting up to the limit is accepted, and that the limit can be
+/// lowered.
+#[test]
+fn test_nesting_limit() {
+    // Arrange
+    let parse = |source: &str, max_depth: usize| {
+        Parser::new(Lexer::tokenize_str(source).unwrap())
+            .with_max_depth(max_depth)
+            .parse_program()
+    };
+    let chain = format!("1{}", " + 1".repeat(2 * Parser::CHAIN_FACTOR));
+
+    // Act
+    let within = parse("let a = ((1)) in a", 4);
+    let beyond = parse("let a = ((1)) in a", 3);
+    let chain_within = parse(&chain, 2);
+    let chain_beyond = parse(&chain, 1);
+
+    // Assert
+    assert!(within.is_ok());
+    assert_eq!(beyond, Err(ParseError::NestingTooDeep { limit: 3 }));
+    assert!(chain_within.is_ok());
+    assert_eq!(
+        chain_beyond,
+        Err(ParseError::NestingTooDeep {
+            limit: Parser::CHAIN_FACTOR
+        })
+    );
+}
+
+/// Tests that a long flat chain parses, since it nests the tree without
+/// nesting the parser.
+#[test]
+fn test_flat_sum_is_not_nesting() {
+    // Arrange
+    let source = format!("1{}", " + 1".repeat(999));
+
+    // Act
+    let result = Parser::new(Lexer::tokenize_str(&source).unwrap()).parse_program();
+
+    // Assert
+    let program = result.expect("a 1,000-term sum parses");
+    assert_eq!(
+        print_expression(&program.expression).matches('+').count(),
+        999
+    );
+}
+
+/// Tests that the deepest trees the parser accepts can be validated and
+/// printed, in any style.
+#[test]
+fn test_deepest_accepted_tree() {
+    // Arrange
+    let source = deepest_operand(Parser::DEFAULT_MAX_DEPTH * Parser::CHAIN_FACTOR);
+
+    // Act
+    let report = validate(&source, &ValidateConfig::default());
+
+    // Assert
+    let program = report.program.expect("the deepest tree parses");
+    assert!(!print_expression(&program.expression).is_empty());
+    assert!(!print_styled(&program.expression, &Teaching { width: 20 }).is_empty());
+    assert!(program.build_index().len() > 2 * Parser::DEFAULT_MAX_DEPTH);
+}
+
+/// Tests that arbitrary text never panics.
+#[test]
+fn test_noise_is_handled() {
+    testrand::check(testrand::seed(), 500, testrand::noise, |input| {
+        run(input);
+        Ok(())
+    });
+}
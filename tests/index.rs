@@ -0,0 +1,89 @@
+//! tests/index.rs
+
+mod support;
+
+use rdp::{Expression, Step, Term};
+
+fn field(field: &'static str) -> Step {
+    Step { field, index: None }
+}
+
+/// Tests parent links, child lists, and steps on a nested fixture.
+#[test]
+fn test_parent_links_on_nested_fixture() {
+    // Arrange
+    let program =
+        support::parse_program("let f = \\x -> x + 1 in match f 2 with | 0 -> a | n -> (n)");
+
+    // Act
+    let index = program.build_index();
+
+    // Assert
+    // 0 let, 1 lambda, 2 x + 1, 3 x, 4 1, 5 match, 6 f 2, 7 f, 8 2, 9 a, 10 (n), 11 n
+    assert_eq!(index.len(), 12);
+    assert_eq!(index.parent(index.root()), None);
+    assert_eq!(index.children(0), &[1, 5]);
+    assert_eq!(index.children(5), &[6, 9, 10]);
+    assert_eq!(index.parent(3), Some(2));
+    assert_eq!(index.parent(11), Some(10));
+    assert_eq!(
+        index.step(10),
+        Some(Step {
+            field: "arms",
+            index: Some(1),
+        })
+    );
+    assert_eq!(
+        *index.node(11),
+        Expression::Term(Term::Identifier("n".to_string()))
+    );
+}
+
+/// Tests that ids follow the pre-order of `Expression::walk`.
+#[test]
+fn test_ids_follow_walk_order() {
+    // Arrange
+    let program = support::parse_program("if a < b then f . g else (r . name)");
+    let index = program.build_index();
+    let mut walked = Vec::new();
+
+    // Act
+    program.expression.walk(&mut |node| walked.push(node));
+
+    // Assert
+    assert_eq!(walked.len(), index.len());
+    for (id, node) in walked.into_iter().enumerate() {
+        assert!(std::ptr::eq(node, index.node(id)));
+        assert_eq!(index.id_of(node), Some(id));
+    }
+}
+
+/// Tests paths up to the root and back down by steps.
+#[test]
+fn test_paths_round_trip() {
+    // Arrange
+    let program =
+        support::parse_program("let f = \\x -> x + 1 in match f 2 with | 0 -> a | n -> (n)");
+    let index = program.build_index();
+
+    // Act
+    let to_root = index.path_to_root(11);
+    let steps = index.path(11);
+
+    // Assert
+    assert_eq!(to_root, vec![11, 10, 5, 0]);
+    assert_eq!(
+        steps,
+        vec![
+            field("body"),
+            Step {
+                field: "arms",
+                index: Some(1),
+            },
+            field("expression"),
+        ]
+    );
+    assert_eq!(index.node_at_path(&steps), Some(11));
+    assert_eq!(index.node_at_path(&[]), Some(index.root()));
+    assert_eq!(index.node_at_path(&[field("condition")]), None);
+}
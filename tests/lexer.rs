@@ -1,6 +1,9 @@
 //! tests/lexer.rs
 
-use rdp::{Lexer, ParseError, Token};
+use rdp::{
+    Lexer, LexerOptions, LineIndex, LossyToken, ParseError, Parser, Position, Span, SpannedToken,
+    Token,
+};
 
 /// Tests the lexing of a simple `let` expression.
 #[test]
@@ -29,6 +32,353 @@ fn test_let_expression() {
     assert_eq!(tokens, expected);
 }
 
+/// Tests that `<=` and `>=` lex as one token, with or without spaces
+/// around them, while `<` and `>` alone stay `LessThan` and `GreaterThan`.
+#[test]
+fn test_comparison_operators() {
+    // Arrange
+    let input = "x <=1 >= y < z > 2";
+    let expected = vec![
+        Token::Identifier("x".to_string()),
+        Token::LessEqual,
+        Token::Number(1.0),
+        Token::GreaterEqual,
+        Token::Identifier("y".to_string()),
+        Token::LessThan,
+        Token::Identifier("z".to_string()),
+        Token::GreaterThan,
+        Token::Number(2.0),
+        Token::Eof,
+    ];
+
+    // Act
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.tokenize().unwrap();
+
+    // Assert
+    assert_eq!(tokens, expected);
+}
+
+/// Tests that `!=` lexes as one token.
+#[test]
+fn test_not_equal_operator() {
+    // Arrange
+    let input = "x != 42";
+    let expected = vec![
+        Token::Identifier("x".to_string()),
+        Token::NotEqual,
+        Token::Number(42.0),
+        Token::Eof,
+    ];
+
+    // Act
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.tokenize().unwrap();
+
+    // Assert
+    assert_eq!(tokens, expected);
+}
+
+/// Tests that `%` and `^` lex as operators, with or without spaces.
+#[test]
+fn test_percent_and_caret_operators() {
+    // Arrange
+    let input = "x % 2%y ^ 3^z";
+    let expected = vec![
+        Token::Identifier("x".to_string()),
+        Token::Percent,
+        Token::Number(2.0),
+        Token::Percent,
+        Token::Identifier("y".to_string()),
+        Token::Caret,
+        Token::Number(3.0),
+        Token::Caret,
+        Token::Identifier("z".to_string()),
+        Token::Eof,
+    ];
+
+    // Act
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.tokenize().unwrap();
+
+    // Assert
+    assert_eq!(tokens, expected);
+}
+
+/// Tests that `::` lexes as one token, with or without spaces, while a
+/// single `:` stays a `Colon` for type annotations.
+#[test]
+fn test_cons_and_colon() {
+    // Arrange
+    let input = "x::xs : List x: Int :::";
+    let expected = vec![
+        Token::Identifier("x".to_string()),
+        Token::Cons,
+        Token::Identifier("xs".to_string()),
+        Token::Colon,
+        Token::Identifier("List".to_string()),
+        Token::Identifier("x".to_string()),
+        Token::Colon,
+        Token::Identifier("Int".to_string()),
+        Token::Cons,
+        Token::Colon,
+        Token::Eof,
+    ];
+
+    // Act
+    let tokens = Lexer::tokenize_str(input).unwrap();
+
+    // Assert
+    assert_eq!(tokens, expected);
+}
+
+/// Tests that `++` with nothing between the pluses always lexes as one
+/// token, while pluses separated by whitespace stay apart.
+#[test]
+fn test_plus_plus() {
+    // Arrange
+    let input = "xs++ys + +z +++";
+    let expected = vec![
+        Token::Identifier("xs".to_string()),
+        Token::PlusPlus,
+        Token::Identifier("ys".to_string()),
+        Token::Plus,
+        Token::Plus,
+        Token::Identifier("z".to_string()),
+        Token::PlusPlus,
+        Token::Plus,
+        Token::Eof,
+    ];
+
+    // Act
+    let tokens = Lexer::tokenize_str(input).unwrap();
+
+    // Assert
+    assert_eq!(tokens, expected);
+}
+
+/// Tests that `|` tells a match arm's `Pipe`, `||`, and `|>` apart.
+#[test]
+fn test_pipe_forward() {
+    // Arrange
+    let input = "| a||b |> f|>g | |";
+    let expected = vec![
+        Token::Pipe,
+        Token::Identifier("a".to_string()),
+        Token::Or,
+        Token::Identifier("b".to_string()),
+        Token::PipeForward,
+        Token::Identifier("f".to_string()),
+        Token::PipeForward,
+        Token::Identifier("g".to_string()),
+        Token::Pipe,
+        Token::Pipe,
+        Token::Eof,
+    ];
+
+    // Act
+    let tokens = Lexer::tokenize_str(input).unwrap();
+
+    // Assert
+    assert_eq!(tokens, expected);
+}
+
+/// Tests that `;;` is matched before `;`, and that `; ;` is two semicolons.
+#[test]
+fn test_semicolons() {
+    // Arrange
+    let input = "x;; ; ; ;;;";
+    let expected = vec![
+        Token::Identifier("x".to_string()),
+        Token::DoubleSemicolon,
+        Token::Semicolon,
+        Token::Semicolon,
+        Token::DoubleSemicolon,
+        Token::Semicolon,
+        Token::Eof,
+    ];
+
+    // Act
+    let tokens = Lexer::tokenize_str(input).unwrap();
+
+    // Assert
+    assert_eq!(tokens, expected);
+}
+
+/// Tests that `!=` is matched before a lone `!`, and that `! =` is two
+/// tokens.
+#[test]
+fn test_bang_and_not_equal() {
+    // Arrange
+    let input = "!x != !!y ! =";
+    let expected = vec![
+        Token::Bang,
+        Token::Identifier("x".to_string()),
+        Token::NotEqual,
+        Token::Bang,
+        Token::Bang,
+        Token::Identifier("y".to_string()),
+        Token::Bang,
+        Token::Assign,
+        Token::Eof,
+    ];
+
+    // Act
+    let tokens = Lexer::tokenize_str(input).unwrap();
+
+    // Assert
+    assert_eq!(tokens, expected);
+}
+
+/// Tests that spanned tokens carry the 1-based line and column where they
+/// start, across lines, comments, and multi-character tokens.
+#[test]
+fn test_tokenize_spanned_positions() {
+    // Arrange
+    let input = "let x = 1 in -- one\n  x >= \"é\"\n\tx";
+    let at = |line, column| Position { line, column };
+    let expected = vec![
+        (Token::Let, at(1, 1)),
+        (Token::Identifier("x".to_string()), at(1, 5)),
+        (Token::Assign, at(1, 7)),
+        (Token::Number(1.0), at(1, 9)),
+        (Token::In, at(1, 11)),
+        (Token::Identifier("x".to_string()), at(2, 3)),
+        (Token::GreaterEqual, at(2, 5)),
+        (Token::String("é".to_string()), at(2, 8)),
+        (Token::Identifier("x".to_string()), at(3, 2)),
+        (Token::Eof, at(3, 3)),
+    ];
+
+    // Act
+    let tokens = Lexer::new(input).tokenize_spanned().unwrap();
+
+    // Assert
+    let actual: Vec<(Token, Position)> = tokens
+        .into_iter()
+        .map(
+            |SpannedToken {
+                 token, position, ..
+             }| (token, position),
+        )
+        .collect();
+    assert_eq!(actual, expected);
+}
+
+/// Tests that words merely starting with a keyword lex as identifiers.
+#[test]
+fn test_keyword_prefixes_are_identifiers() {
+    for word in [
+        "letter",
+        "iffy",
+        "thenceforth",
+        "elsewhere",
+        "matching",
+        "without",
+        "android",
+        "whenever",
+    ] {
+        // Act
+        let tokens = Lexer::tokenize_str(word).unwrap();
+
+        // Assert
+        assert_eq!(
+            tokens,
+            vec![Token::Identifier(word.to_string()), Token::Eof],
+            "{}",
+            word
+        );
+    }
+}
+
+/// Tests that underscores and primes continue an identifier, including one
+/// that starts with a keyword.
+#[test]
+fn test_identifiers_with_underscores_and_primes() {
+    for word in [
+        "my_var",
+        "snake_case_name",
+        "x'",
+        "my_list'",
+        "f''",
+        "let_",
+        "in'",
+    ] {
+        // Act
+        let tokens = Lexer::tokenize_str(word).unwrap();
+
+        // Assert
+        assert_eq!(
+            tokens,
+            vec![Token::Identifier(word.to_string()), Token::Eof],
+            "{}",
+            word
+        );
+    }
+}
+
+/// Tests that identifiers may use letters and digits from any script, and
+/// that positions after them count characters, not bytes.
+#[test]
+fn test_unicode_identifiers() {
+    // Arrange
+    let input = "let résumé = 1 in résumé\nλ x₁ Straße' @";
+
+    // Act
+    let tokens = Lexer::tokenize_str("let résumé = 1 in résumé").unwrap();
+    let spanned = Lexer::new("λ x₁ Straße' + 1").tokenize_spanned().unwrap();
+    let error = Lexer::tokenize_str(input);
+
+    // Assert
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Let,
+            Token::Identifier("résumé".to_string()),
+            Token::Assign,
+            Token::Number(1.0),
+            Token::In,
+            Token::Identifier("résumé".to_string()),
+            Token::Eof,
+        ]
+    );
+    let columns: Vec<usize> = spanned.iter().map(|token| token.position.column).collect();
+    assert_eq!(columns, vec![1, 3, 6, 14, 16, 17]);
+    assert_eq!(spanned[2].token, Token::Identifier("Straße'".to_string()));
+    match error {
+        Err(ParseError::UnexpectedToken { position, .. }) => {
+            assert_eq!(
+                position,
+                Some(Position {
+                    line: 2,
+                    column: 14
+                })
+            )
+        }
+        other => panic!("Expected an unexpected '@', got {:?}", other),
+    }
+}
+
+/// Tests that an underscore on its own, or before a name, is still a
+/// wildcard.
+#[test]
+fn test_lone_underscore_is_wildcard() {
+    // Act
+    let alone = Lexer::tokenize_str("_").unwrap();
+    let leading = Lexer::tokenize_str("_x").unwrap();
+
+    // Assert
+    assert_eq!(alone, vec![Token::Wildcard, Token::Eof]);
+    assert_eq!(
+        leading,
+        vec![
+            Token::Wildcard,
+            Token::Identifier("x".to_string()),
+            Token::Eof
+        ]
+    );
+}
+
 /// Tests the lexing of an `if` expression.
 #[test]
 fn test_if_expression() {
@@ -95,11 +445,11 @@ fn test_match_expression() {
         Token::Pipe,
         Token::Number(1.0),
         Token::Arrow,
-        Token::Identifier("true".to_string()),
+        Token::True,
         Token::Pipe,
         Token::Wildcard,
         Token::Arrow,
-        Token::Identifier("false".to_string()),
+        Token::False,
         Token::Eof,
     ];
 
@@ -132,7 +482,7 @@ fn test_complex_expression() {
         Token::LeftParen,
         Token::Identifier("z".to_string()),
         Token::And,
-        Token::Identifier("true".to_string()),
+        Token::True,
         Token::RightParen,
         Token::Eof,
     ];
@@ -162,11 +512,955 @@ fn test_invalid_token() {
             expected,
             found,
             message,
+            position,
         } => {
             assert_eq!(expected, "valid token");
             assert_eq!(found, "@");
             assert_eq!(message, "Unexpected character");
+            assert_eq!(position, Some(Position { line: 1, column: 9 }));
         }
         _ => panic!("Unexpected error type"),
     }
 }
+
+/// Tests that `_` separators group digits without changing the value.
+#[test]
+fn test_number_digit_separators() {
+    // Arrange
+    let input = "1_000_000 0.000_1 12_3.4_5";
+    let expected = vec![
+        Token::Number(1_000_000.0),
+        Token::Number(0.0001),
+        Token::Number(123.45),
+        Token::Eof,
+    ];
+
+    // Act
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.tokenize().unwrap();
+
+    // Assert
+    assert_eq!(tokens, expected);
+}
+
+/// Tests separators in both parts of one number, and that a leading
+/// underscore is a wildcard rather than part of the number after it.
+#[test]
+fn test_number_separators_in_fraction_and_leading_underscore() {
+    // Arrange
+    let input = "1_000.5_5 _100";
+    let expected = vec![
+        Token::Number(1000.55),
+        Token::Wildcard,
+        Token::Number(100.0),
+        Token::Eof,
+    ];
+
+    // Act
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.tokenize().unwrap();
+
+    // Assert
+    assert_eq!(tokens, expected);
+}
+
+/// Tests that misplaced separators are rejected with the exact lexeme.
+#[test]
+fn test_number_invalid_separators() {
+    // Arrange
+    let inputs = [
+        ("1_", "1_"),
+        ("1__0", "1__0"),
+        ("1_.5", "1_.5"),
+        ("1._5", "1._5"),
+        ("1.5_ + 2", "1.5_"),
+    ];
+
+    for (input, lexeme) in inputs {
+        // Act
+        let mut lexer = Lexer::new(input);
+        let result = lexer.tokenize();
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(ParseError::InvalidNumberFormat {
+                lexeme: lexeme.to_string(),
+                offset: 0,
+            })
+        );
+    }
+}
+
+/// Tests that a decimal comma is rejected with a hint, pointing at the whole
+/// number.
+#[test]
+fn test_number_decimal_comma() {
+    // Arrange
+    let input = "let x = 1,5 in x + 12_0,25";
+
+    // Act
+    let result = Lexer::tokenize_str(input);
+
+    // Assert
+    let err = result.unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::CommaDecimal {
+            lexeme: "1,5".to_string(),
+            offset: 8,
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "Comma is not a decimal separator; use '1.5' instead of '1,5'."
+    );
+    assert_eq!(
+        err.span(&LineIndex::new(input)),
+        Some(Span { start: 8, end: 11 })
+    );
+}
+
+/// Tests that commas which may be meant as separators, spaced or inside
+/// parentheses or brackets, are not taken for decimal commas.
+#[test]
+fn test_separator_commas_are_not_decimal_commas() {
+    // Arrange
+    let inputs = [
+        ("1, 5", 2),
+        ("1 ,5", 3),
+        ("(1,5)", 3),
+        ("f (g 1,5)", 7),
+        ("[1,5]", 3),
+        ("1,x", 2),
+    ];
+
+    for (input, column) in inputs {
+        // Act
+        let tokens = Lexer::new(input).tokenize_spanned().unwrap();
+
+        // Assert
+        let commas: Vec<Position> = tokens
+            .iter()
+            .filter(|spanned| spanned.token == Token::Comma)
+            .map(|spanned| spanned.position)
+            .collect();
+        assert_eq!(commas, vec![Position { line: 1, column }], "{}", input);
+    }
+}
+
+/// Tests that brackets and commas lex as tokens.
+#[test]
+fn test_brackets_and_commas() {
+    // Arrange
+    let input = "[1, 2, 3]";
+
+    // Act
+    let tokens = Lexer::tokenize_str(input).unwrap();
+
+    // Assert
+    assert_eq!(
+        tokens,
+        vec![
+            Token::LeftBracket,
+            Token::Number(1.0),
+            Token::Comma,
+            Token::Number(2.0),
+            Token::Comma,
+            Token::Number(3.0),
+            Token::RightBracket,
+            Token::Eof,
+        ]
+    );
+}
+
+/// Tests that numbers take an exponent with either sign.
+#[test]
+fn test_number_exponents() {
+    // Arrange
+    let input = "1e10 2.5e-3 6.02E23 1e+2 1_000e1_0";
+    let expected = vec![
+        Token::Number(1e10),
+        Token::Number(2.5e-3),
+        Token::Number(6.02e23),
+        Token::Number(100.0),
+        Token::Number(1e13),
+        Token::Eof,
+    ];
+
+    // Act
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.tokenize().unwrap();
+
+    // Assert
+    assert_eq!(tokens, expected);
+}
+
+/// Tests that an exponent without digits or with an identifier attached, or
+/// too large a literal, is rejected with the whole lexeme.
+#[test]
+fn test_number_invalid_exponents() {
+    // Arrange
+    let inputs = [
+        ("1e", "1e"),
+        ("1e+", "1e+"),
+        ("2.5E- 3", "2.5E-"),
+        ("1ex", "1ex"),
+        ("1e5e", "1e5e"),
+        ("2.5e-3x1 + y", "2.5e-3x1"),
+        ("1e_5", "1e_5"),
+        ("1e999", "1e999"),
+    ];
+
+    for (input, lexeme) in inputs {
+        // Act
+        let mut lexer = Lexer::new(input);
+        let result = lexer.tokenize();
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(ParseError::InvalidNumberFormat {
+                lexeme: lexeme.to_string(),
+                offset: 0,
+            }),
+            "{}",
+            input
+        );
+    }
+}
+
+/// Tests that a separator followed by letters is a number error, not a
+/// number followed by an identifier.
+#[test]
+fn test_number_separator_before_identifier() {
+    // Arrange
+    let input = "1_x";
+
+    // Act
+    let mut lexer = Lexer::new(input);
+    let result = lexer.tokenize();
+
+    // Assert
+    assert_eq!(
+        result,
+        Err(ParseError::InvalidNumberFormat {
+            lexeme: "1_x".to_string(),
+            offset: 0,
+        })
+    );
+}
+
+/// Tests that a malformed number carries where it starts, and spans the
+/// literal as written.
+#[test]
+fn test_invalid_number_span() {
+    // Arrange
+    let input = "x + 1__0";
+
+    // Act
+    let err = Lexer::new(input).tokenize().unwrap_err();
+
+    // Assert
+    assert_eq!(
+        err,
+        ParseError::InvalidNumberFormat {
+            lexeme: "1__0".to_string(),
+            offset: 4,
+        }
+    );
+    assert_eq!(
+        err.span(&LineIndex::new(input)),
+        Some(Span { start: 4, end: 8 })
+    );
+}
+
+/// Tests that a leading `#!` line is set aside instead of tokenized.
+#[test]
+fn test_shebang_line() {
+    // Arrange
+    let input = "#!/usr/bin/env rdp\nlet x = 1 in x";
+    let expected = vec![
+        Token::Let,
+        Token::Identifier("x".to_string()),
+        Token::Assign,
+        Token::Number(1.0),
+        Token::In,
+        Token::Identifier("x".to_string()),
+        Token::Eof,
+    ];
+
+    // Act
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.tokenize().unwrap();
+
+    // Assert
+    assert_eq!(tokens, expected);
+    assert_eq!(lexer.shebang(), Some("#!/usr/bin/env rdp"));
+}
+
+/// Tests that `#` is still an error anywhere but the start of the input.
+#[test]
+fn test_shebang_only_on_first_line() {
+    // Arrange
+    let inputs = [
+        "\n#!/usr/bin/env rdp\n1",
+        " #!/usr/bin/env rdp\n1",
+        "1 #! 2",
+    ];
+
+    for input in inputs {
+        // Act
+        let mut lexer = Lexer::new(input);
+        let result = lexer.tokenize();
+
+        // Assert
+        assert_eq!(lexer.shebang(), None);
+        assert!(matches!(
+            result,
+            Err(ParseError::UnexpectedToken { ref found, .. }) if found == "#"
+        ));
+    }
+}
+
+/// Tests that a huge identifier is rejected at its start without being kept
+/// in the error.
+#[test]
+fn test_identifier_length_limit() {
+    // Arrange
+    let input = format!("let {} = 1 in x", "a".repeat(100_000));
+    let options = LexerOptions {
+        max_identifier_length: Some(255),
+        ..LexerOptions::default()
+    };
+
+    // Act
+    let mut lexer = Lexer::with_options(&input, options);
+    let err = lexer.tokenize().unwrap_err();
+
+    // Assert
+    assert_eq!(
+        err,
+        ParseError::IdentifierTooLong {
+            offset: 4,
+            limit: 255
+        }
+    );
+    assert!(err.to_string().len() < 200);
+}
+
+/// Tests that a huge line is reported by number without being kept in the
+/// error.
+#[test]
+fn test_line_length_limit() {
+    // Arrange
+    let input = format!("let x = 1 in\n{}\n", "x + ".repeat(250_000) + "x");
+    let options = LexerOptions {
+        max_line_length: Some(120),
+        ..LexerOptions::default()
+    };
+
+    // Act
+    let mut lexer = Lexer::with_options(&input, options);
+    let err = lexer.tokenize().unwrap_err();
+
+    // Assert
+    assert_eq!(
+        err,
+        ParseError::LineTooLong {
+            line: 2,
+            limit: 120
+        }
+    );
+    assert!(err.to_string().len() < 200);
+}
+
+/// Tests that input within the limits, and any input without limits, lexes
+/// as usual.
+#[test]
+fn test_limits_within_bounds() {
+    // Arrange
+    let input = "let abc = 1 in\nabc";
+    let options = LexerOptions {
+        max_identifier_length: Some(3),
+        max_line_length: Some(14),
+    };
+
+    // Act
+    let limited = Lexer::with_options(input, options).tokenize();
+    let unlimited = Lexer::tokenize_str(input);
+
+    // Assert
+    assert!(limited.is_ok());
+    assert_eq!(limited, unlimited);
+}
+
+/// Tests that the identifier limit counts characters, not bytes.
+#[test]
+fn test_identifier_limit_counts_characters() {
+    // Arrange
+    let options = LexerOptions {
+        max_identifier_length: Some(3),
+        ..LexerOptions::default()
+    };
+
+    // Act
+    let within = Lexer::with_options("λμν", options).tokenize();
+    let beyond = Lexer::with_options("x + λμνξ", options).tokenize();
+
+    // Assert
+    assert!(within.is_ok());
+    assert_eq!(
+        beyond,
+        Err(ParseError::IdentifierTooLong {
+            offset: 4,
+            limit: 3
+        })
+    );
+}
+
+/// Tests string literals: empty, and with spaces, operators, and keywords
+/// taken as plain text.
+#[test]
+fn test_string_literals() {
+    // Arrange
+    let input = r#"f "" "a + b" "let x = \\y -> y""#;
+
+    // Act
+    let tokens = Lexer::tokenize_str(input).unwrap();
+
+    // Assert
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("f".to_string()),
+            Token::String(String::new()),
+            Token::String("a + b".to_string()),
+            Token::String("let x = \\y -> y".to_string()),
+            Token::Eof,
+        ]
+    );
+}
+
+/// Tests that escape sequences are decoded, including a Unicode escape
+/// outside the Basic Multilingual Plane and one just before the closing quote.
+#[test]
+fn test_string_escapes() {
+    // Arrange
+    let input = r#""say \"hi\"\n" "a\\b\tc\r" "\u{1F600}\u{e9}" "end\"""#;
+
+    // Act
+    let tokens = Lexer::tokenize_str(input).unwrap();
+
+    // Assert
+    assert_eq!(
+        tokens,
+        vec![
+            Token::String("say \"hi\"\n".to_string()),
+            Token::String("a\\b\tc\r".to_string()),
+            Token::String("😀é".to_string()),
+            Token::String("end\"".to_string()),
+            Token::Eof,
+        ]
+    );
+}
+
+/// Tests that unknown and malformed escapes are rejected, naming the escape
+/// and where its backslash is.
+#[test]
+fn test_invalid_string_escapes() {
+    // Arrange
+    let inputs = [
+        (r#"f "a\qb""#, r"\q", 4),
+        (r#""\u{}""#, r"\u{}", 1),
+        (r#""\u{D800}""#, r"\u{D800}", 1),
+        (r#""\u{1000000}""#, r"\u{1000000}", 1),
+        (r#""\u{12g}""#, r"\u{12", 1),
+        (r#""\u1234""#, r"\u", 1),
+    ];
+
+    for (input, escape, offset) in inputs {
+        // Act
+        let result = Lexer::tokenize_str(input);
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(ParseError::InvalidEscape {
+                escape: escape.to_string(),
+                offset,
+            }),
+            "{}",
+            input
+        );
+    }
+    assert_eq!(
+        Lexer::tokenize_str(r#""a\q""#).unwrap_err().to_string(),
+        r"Invalid escape sequence '\q' in string literal."
+    );
+    assert_eq!(
+        Lexer::tokenize_str(r#""a\"#),
+        Err(ParseError::UnterminatedString)
+    );
+}
+
+/// Tests that a string missing its closing quote is rejected.
+#[test]
+fn test_unterminated_string() {
+    // Arrange
+    let input = "let s = \"abc in s";
+
+    // Act
+    let result = Lexer::tokenize_str(input);
+
+    // Assert
+    assert_eq!(result, Err(ParseError::UnterminatedString));
+}
+
+/// Tests that `--` comments are skipped to the end of the line, while `-`
+/// and `->` still lex as operators.
+#[test]
+fn test_line_comments() {
+    // Arrange
+    let input = "x -- comment -> 1\n+ 1 - y -- last";
+
+    // Act
+    let tokens = Lexer::tokenize_str(input).unwrap();
+    let arrow = Lexer::tokenize_str("\\x -> x--").unwrap();
+
+    // Assert
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("x".to_string()),
+            Token::Plus,
+            Token::Number(1.0),
+            Token::Minus,
+            Token::Identifier("y".to_string()),
+            Token::Eof,
+        ]
+    );
+    assert_eq!(
+        arrow,
+        vec![
+            Token::Lambda,
+            Token::Identifier("x".to_string()),
+            Token::Arrow,
+            Token::Identifier("x".to_string()),
+            Token::Eof,
+        ]
+    );
+}
+
+/// Tests that a comment on the last line of a match doesn't swallow the
+/// arms before it, and that lossy lexing reports comments as trivia.
+#[test]
+fn test_comment_after_match() {
+    // Arrange
+    let input = "match n with\n| 0 -> 1\n| _ -> n -- fallback";
+
+    // Act
+    let tokens = Lexer::tokenize_str(input).unwrap();
+    let pieces = Lexer::new(input).tokenize_lossy();
+
+    // Assert
+    assert_eq!(tokens.len(), 12);
+    assert_eq!(tokens[10], Token::Identifier("n".to_string()));
+    assert_eq!(
+        pieces.last(),
+        Some(&LossyToken::Trivia {
+            span: Span { start: 30, end: 42 }
+        })
+    );
+}
+
+/// Tests that trailing whitespace does not produce a second `Eof`.
+#[test]
+fn test_trailing_whitespace_single_eof() {
+    // Arrange
+    let input = "x \n\n";
+
+    // Act
+    let tokens = Lexer::tokenize_str(input).unwrap();
+
+    // Assert
+    assert_eq!(tokens, vec![Token::Identifier("x".to_string()), Token::Eof]);
+}
+
+/// Tests that lossy lexing keeps the tokens around characters that don't lex,
+/// merging adjacent bad characters into one error.
+#[test]
+fn test_tokenize_lossy() {
+    // Arrange
+    let input = "#!rdp\nx @# 1__0)";
+
+    // Act
+    let pieces = Lexer::new(input).tokenize_lossy();
+
+    // Assert
+    let span = |start, end| Span { start, end };
+    assert_eq!(
+        pieces,
+        vec![
+            LossyToken::Trivia { span: span(0, 5) },
+            LossyToken::Trivia { span: span(5, 6) },
+            LossyToken::Token {
+                token: Token::Identifier("x".to_string()),
+                span: span(6, 7)
+            },
+            LossyToken::Trivia { span: span(7, 8) },
+            LossyToken::Error { span: span(8, 10) },
+            LossyToken::Trivia { span: span(10, 11) },
+            LossyToken::Error { span: span(11, 15) },
+            LossyToken::Token {
+                token: Token::RightParen,
+                span: span(15, 16)
+            },
+        ]
+    );
+}
+
+/// Tests that a string with a bad escape is one error through its closing
+/// quote, and that the tokens after it survive.
+#[test]
+fn test_tokenize_lossy_after_invalid_escape() {
+    // Arrange
+    let input = "x \"a\\q\" y";
+
+    // Act
+    let pieces = Lexer::new(input).tokenize_lossy();
+
+    // Assert
+    let span = |start, end| Span { start, end };
+    assert_eq!(
+        pieces,
+        vec![
+            LossyToken::Token {
+                token: Token::Identifier("x".to_string()),
+                span: span(0, 1)
+            },
+            LossyToken::Trivia { span: span(1, 2) },
+            LossyToken::Error { span: span(2, 7) },
+            LossyToken::Trivia { span: span(7, 8) },
+            LossyToken::Token {
+                token: Token::Identifier("y".to_string()),
+                span: span(8, 9)
+            },
+        ]
+    );
+}
+
+/// Tests that lossy lexing ignores the lexer's limits.
+#[test]
+fn test_tokenize_lossy_ignores_limits() {
+    // Arrange
+    let options = LexerOptions {
+        max_identifier_length: Some(2),
+        max_line_length: Some(2),
+    };
+
+    // Act
+    let pieces = Lexer::with_options("abcdef", options).tokenize_lossy();
+
+    // Assert
+    assert_eq!(
+        pieces,
+        vec![LossyToken::Token {
+            token: Token::Identifier("abcdef".to_string()),
+            span: Span { start: 0, end: 6 }
+        }]
+    );
+}
+
+/// Tests that every token displays as it appears in source.
+#[test]
+fn test_token_display() {
+    // Arrange
+    let cases = [
+        (Token::Let, "let"),
+        (Token::In, "in"),
+        (Token::If, "if"),
+        (Token::Then, "then"),
+        (Token::Else, "else"),
+        (Token::Match, "match"),
+        (Token::With, "with"),
+        (Token::True, "true"),
+        (Token::False, "false"),
+        (Token::Lambda, "\\"),
+        (Token::Equal, "=="),
+        (Token::NotEqual, "!="),
+        (Token::LessThan, "<"),
+        (Token::GreaterThan, ">"),
+        (Token::LessEqual, "<="),
+        (Token::GreaterEqual, ">="),
+        (Token::And, "&&"),
+        (Token::Or, "||"),
+        (Token::Bang, "!"),
+        (Token::Plus, "+"),
+        (Token::Minus, "-"),
+        (Token::Star, "*"),
+        (Token::Slash, "/"),
+        (Token::Percent, "%"),
+        (Token::Caret, "^"),
+        (Token::Arrow, "->"),
+        (Token::Cons, "::"),
+        (Token::PlusPlus, "++"),
+        (Token::Dot, "."),
+        (Token::Pipe, "|"),
+        (Token::PipeForward, "|>"),
+        (Token::Identifier("café'".to_string()), "café'"),
+        (Token::Number(42.0), "42"),
+        (Token::Number(1.5), "1.5"),
+        (Token::String("a \"b\"\n".to_string()), "\"a \\\"b\\\"\\n\""),
+        (Token::LeftParen, "("),
+        (Token::RightParen, ")"),
+        (Token::LeftBracket, "["),
+        (Token::RightBracket, "]"),
+        (Token::Comma, ","),
+        (Token::Colon, ":"),
+        (Token::Semicolon, ";"),
+        (Token::DoubleSemicolon, ";;"),
+        (Token::Assign, "="),
+        (Token::Wildcard, "_"),
+        (Token::Whitespace(" \t\n".to_string()), " \t\n"),
+        (Token::Comment("-- note".to_string()), "-- note"),
+        (Token::Eof, "end of input"),
+    ];
+
+    // Act
+    let rendered: Vec<String> = cases.iter().map(|(token, _)| token.to_string()).collect();
+
+    // Assert
+    let expected: Vec<&str> = cases.iter().map(|&(_, text)| text).collect();
+    assert_eq!(rendered, expected);
+}
+
+/// Tests that iterating a lexer yields the same tokens as `tokenize`, ending
+/// with a single `Eof`, and that it stays finished afterwards.
+#[test]
+fn test_iterator_matches_tokenize() {
+    // Arrange
+    let source = "let f = \\x -> x ^ 2 in\n-- square\nf 1_000 + \"ok\"";
+    let mut lexer = Lexer::new(source);
+
+    // Act
+    let streamed: Result<Vec<Token>, ParseError> = lexer.by_ref().collect();
+
+    // Assert
+    assert_eq!(streamed, Lexer::tokenize_str(source));
+    assert_eq!(streamed.unwrap().last(), Some(&Token::Eof));
+    assert_eq!(lexer.next(), None);
+    assert_eq!(lexer.next(), None);
+}
+
+/// Tests that the iterator yields tokens lazily up to the first error, then
+/// stops.
+#[test]
+fn test_iterator_stops_after_first_error() {
+    // Arrange
+    let mut lexer = Lexer::new("let x = @ in # 1");
+
+    // Act
+    let items: Vec<Result<Token, ParseError>> = lexer.by_ref().collect();
+
+    // Assert
+    assert_eq!(
+        items[..3],
+        [
+            Ok(Token::Let),
+            Ok(Token::Identifier("x".to_string())),
+            Ok(Token::Assign)
+        ]
+    );
+    assert_eq!(items.len(), 4);
+    assert!(matches!(items[3], Err(ParseError::UnexpectedToken { .. })));
+    assert_eq!(lexer.next(), None);
+}
+
+/// Tests that a synthetic program of over a megabyte, full of multi-byte
+/// characters, lexes completely, with positions and spans still counted in
+/// characters.
+#[test]
+fn test_large_input() {
+    // Arrange
+    let line = "let café = \"naïve λ\" ^ 2.5e1 -- ≥ comment\n";
+    let lines = (1 << 20) / line.len() + 1;
+    let source = line.repeat(lines);
+
+    // Act
+    let tokens = Lexer::tokenize_str(&source).unwrap();
+    let spanned = Lexer::new(&source).tokenize_spanned().unwrap();
+    let lossy = Lexer::new(&source).tokenize_lossy();
+
+    // Assert
+    assert!(source.len() > 1 << 20);
+    assert_eq!(tokens.len(), lines * 6 + 1);
+    assert_eq!(
+        tokens[..6],
+        [
+            Token::Let,
+            Token::Identifier("café".to_string()),
+            Token::Assign,
+            Token::String("naïve λ".to_string()),
+            Token::Caret,
+            Token::Number(25.0),
+        ]
+    );
+    assert_eq!(tokens[tokens.len() - 7..tokens.len() - 1], tokens[..6]);
+    assert_eq!(
+        spanned[spanned.len() - 2].position,
+        Position {
+            line: lines,
+            column: 24,
+        }
+    );
+    assert_eq!(
+        lossy.last(),
+        Some(&LossyToken::Trivia {
+            span: Span {
+                start: source.chars().count() - 14,
+                end: source.chars().count(),
+            }
+        })
+    );
+}
+
+/// Tests that recovering lexing reports every bad character, in order, and
+/// keeps the tokens around them.
+#[test]
+fn test_tokenize_recovering_reports_every_error() {
+    // Arrange
+    let source = "let x = @1 in\n#x + ~2";
+
+    // Act
+    let (tokens, errors) = Lexer::new(source).tokenize_recovering();
+
+    // Assert
+    let found: Vec<String> = errors
+        .iter()
+        .map(|err| match err {
+            ParseError::UnexpectedToken { found, .. } => found.clone(),
+            other => panic!("unexpected error {:?}", other),
+        })
+        .collect();
+    assert_eq!(found, vec!["@", "#", "~"]);
+    assert_eq!(tokens, Lexer::tokenize_str("let x = 1 in x + 2").unwrap());
+    assert!(Parser::new(tokens).parse_program().is_ok());
+}
+
+/// Tests that recovering lexing reports a bad escape once and resumes after
+/// the string holding it, even past an escaped quote.
+#[test]
+fn test_tokenize_recovering_after_invalid_escape() {
+    // Arrange
+    let source = "x \"a\\q\\\"b\" y";
+
+    // Act
+    let (tokens, errors) = Lexer::new(source).tokenize_recovering();
+
+    // Assert
+    assert!(
+        matches!(errors.as_slice(), [ParseError::InvalidEscape { .. }]),
+        "{:?}",
+        errors
+    );
+    assert_eq!(tokens, Lexer::tokenize_str("x y").unwrap());
+}
+
+/// Tests that recovering lexing of valid input matches `tokenize`.
+#[test]
+fn test_tokenize_recovering_without_errors() {
+    // Arrange
+    let source = "-- square\nlet f = \\x -> x ^ 2 in f 1_000 + \"ok\"";
+
+    // Act
+    let (tokens, errors) = Lexer::new(source).tokenize_recovering();
+
+    // Assert
+    assert!(errors.is_empty());
+    assert_eq!(tokens, Lexer::tokenize_str(source).unwrap());
+}
+
+/// Tests that the trivia-bearing stream displays back to the exact source,
+/// and that without its trivia it is the stream of `tokenize`.
+#[test]
+fn test_tokenize_with_trivia_round_trips() {
+    // Arrange
+    let source = "#!/usr/bin/env rdp\r\n-- square\r\nlet f = \\x -> x ^ 2 in\t f 42 -- done\n";
+
+    // Act
+    let tokens = Lexer::new(source).tokenize_with_trivia().unwrap();
+
+    // Assert
+    let rebuilt: String = tokens
+        .iter()
+        .filter(|token| **token != Token::Eof)
+        .map(Token::to_string)
+        .collect();
+    assert_eq!(rebuilt, source);
+    assert_eq!(
+        tokens[0],
+        Token::Comment("#!/usr/bin/env rdp\r".to_string())
+    );
+    assert_eq!(tokens[1], Token::Whitespace("\n".to_string()));
+    assert_eq!(tokens[2], Token::Comment("-- square\r".to_string()));
+    let meaningful: Vec<Token> = tokens
+        .into_iter()
+        .filter(|token| !token.is_trivia())
+        .collect();
+    assert_eq!(meaningful, Lexer::tokenize_str(source).unwrap());
+}
+
+/// Tests that the parser ignores the trivia of a trivia-bearing stream.
+#[test]
+fn test_parser_skips_trivia() {
+    // Arrange
+    let source = "-- add\nx +  -- one\n  1";
+    let tokens = Lexer::new(source).tokenize_with_trivia().unwrap();
+
+    // Act
+    let program = Parser::new(tokens).parse_program();
+
+    // Assert
+    let expected = Parser::new(Lexer::tokenize_str(source).unwrap()).parse_program();
+    assert_eq!(program, expected);
+    assert!(program.is_ok());
+}
+
+/// Tests that a lexer reset after an error lexes a second input from its
+/// start, keeping its options.
+#[test]
+fn test_reset_after_error() {
+    // Arrange
+    let options = LexerOptions {
+        max_identifier_length: Some(3),
+        ..LexerOptions::default()
+    };
+    let mut lexer = Lexer::with_options("x @ y", options);
+    let first = lexer.tokenize();
+
+    // Act
+    lexer.reset("\nab");
+    let second = lexer.tokenize_spanned();
+    lexer.reset("abcd");
+    let third = lexer.tokenize();
+
+    // Assert
+    assert!(first.is_err());
+    assert_eq!(
+        second,
+        Ok(vec![
+            SpannedToken {
+                token: Token::Identifier("ab".to_string()),
+                position: Position { line: 2, column: 1 },
+                span: Span { start: 1, end: 3 },
+            },
+            SpannedToken {
+                token: Token::Eof,
+                position: Position { line: 2, column: 3 },
+                span: Span { start: 3, end: 3 },
+            },
+        ])
+    );
+    assert!(third.is_err());
+}
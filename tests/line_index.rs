@@ -0,0 +1,196 @@
+//! tests/line_index.rs
+
+use rdp::{
+    testrand::{check, seed, Rng},
+    LineIndex, TextEdit,
+};
+
+/// Tests mapping offsets to lines and columns, including at line breaks and
+/// past the end of the text.
+#[test]
+fn test_offset_to_line_col() {
+    // Arrange
+    let index = LineIndex::new("let x = 1 in\nx + 1\n");
+
+    // Act
+    let positions: Vec<(usize, usize)> = [0, 4, 12, 13, 17, 19, 100]
+        .into_iter()
+        .map(|offset| index.offset_to_line_col(offset))
+        .collect();
+
+    // Assert
+    assert_eq!(
+        positions,
+        vec![(1, 1), (1, 5), (1, 13), (2, 1), (2, 5), (3, 1), (3, 1)]
+    );
+    assert_eq!(index.line_count(), 3);
+}
+
+/// Tests that offsets count characters, not bytes.
+#[test]
+fn test_offsets_count_characters() {
+    // Arrange
+    let source = "é\nλx";
+
+    // Act
+    let index = LineIndex::new(source);
+
+    // Assert
+    assert_eq!(index.offset_to_line_col(3), (2, 2));
+    assert_eq!(index.line_text(source, 2), Some("λx"));
+}
+
+/// Tests that `\r\n` and `\n` breaks give the same positions at line
+/// starts, line ends, and the last character of a file without a trailing
+/// line break.
+#[test]
+fn test_crlf_positions_match_lf() {
+    // Arrange
+    let lf = LineIndex::new("ab\ncd");
+    let crlf = LineIndex::new("ab\r\ncd");
+
+    // Act
+    let lf_positions: Vec<(usize, usize)> = [0, 2, 3, 4, 5]
+        .into_iter()
+        .map(|offset| lf.offset_to_line_col(offset))
+        .collect();
+    let crlf_positions: Vec<(usize, usize)> = [0, 2, 4, 5, 6]
+        .into_iter()
+        .map(|offset| crlf.offset_to_line_col(offset))
+        .collect();
+
+    // Assert
+    let expected = vec![(1, 1), (1, 3), (2, 1), (2, 2), (2, 3)];
+    assert_eq!(lf_positions, expected);
+    assert_eq!(crlf_positions, expected);
+    assert_eq!(lf.line_count(), 2);
+    assert_eq!(crlf.line_count(), 2);
+}
+
+/// Tests fetching the text of each line, without its line break.
+#[test]
+fn test_line_text() {
+    // Arrange
+    let source = "a\r\nbb\n\nccc";
+
+    // Act
+    let index = LineIndex::new(source);
+
+    // Assert
+    assert_eq!(index.line_text(source, 1), Some("a"));
+    assert_eq!(index.line_text(source, 2), Some("bb"));
+    assert_eq!(index.line_text(source, 3), Some(""));
+    assert_eq!(index.line_text(source, 4), Some("ccc"));
+    assert_eq!(index.line_text(source, 0), None);
+    assert_eq!(index.line_text(source, 5), None);
+}
+
+/// Tests that line and column map back to the offset they came from, and
+/// that positions past the end of a line are rejected.
+#[test]
+fn test_line_col_to_offset() {
+    // Arrange
+    let index = LineIndex::new("ab\r\ncd");
+
+    // Act
+    let round_trips: Vec<usize> = (0..=6)
+        .map(|offset| {
+            let (line, column) = index.offset_to_line_col(offset);
+            index.line_col_to_offset(line, column).unwrap()
+        })
+        .collect();
+
+    // Assert
+    assert_eq!(round_trips, vec![0, 1, 2, 3, 4, 5, 6]);
+    assert_eq!(index.line_col_to_offset(1, 4), Some(3));
+    assert_eq!(index.line_col_to_offset(1, 5), None);
+    assert_eq!(index.line_col_to_offset(2, 3), Some(6));
+    assert_eq!(index.line_col_to_offset(2, 4), None);
+    assert_eq!(index.line_col_to_offset(3, 1), None);
+    assert_eq!(index.line_col_to_offset(1, 0), None);
+}
+
+/// Tests that UTF-16 columns count two units for characters outside the
+/// Basic Multilingual Plane and one for the rest.
+#[test]
+fn test_utf16_columns() {
+    // Arrange
+    let index = LineIndex::new("é😀x\n😀😀");
+
+    // Act
+    let columns: Vec<(usize, usize)> = [0, 1, 2, 3, 4, 5, 6]
+        .into_iter()
+        .map(|offset| index.offset_to_line_col_utf16(offset))
+        .collect();
+
+    // Assert
+    assert_eq!(
+        columns,
+        vec![(1, 1), (1, 2), (1, 4), (1, 5), (2, 1), (2, 3), (2, 5)]
+    );
+    assert_eq!(index.offset_to_line_col(2), (1, 3));
+    assert_eq!(index.line_col_utf16_to_offset(1, 4), Some(2));
+    assert_eq!(index.line_col_utf16_to_offset(1, 3), None);
+    assert_eq!(index.line_col_utf16_to_offset(2, 5), Some(6));
+    assert_eq!(index.line_col_utf16_to_offset(2, 6), None);
+}
+
+/// Tests that an index updated by an edit equals one built from the edited
+/// text, for edits within a line, across lines, and at the end.
+#[test]
+fn test_apply_edit_matches_rebuild() {
+    // Arrange
+    let source = "let x = 1 in\r\nλ😀 + x\nx";
+    let edits = [
+        (4..5, "long"),
+        (6..6, "\n😀\n"),
+        (10..17, ""),
+        (13..15, "é\r\n"),
+        (0..22, "z"),
+        (22..22, "\n"),
+        (30..40, "tail"),
+    ];
+
+    for (range, replacement) in edits {
+        let edit = TextEdit {
+            range,
+            replacement: replacement.to_string(),
+        };
+        let mut index = LineIndex::new(source);
+
+        // Act
+        index.apply_edit(source, &edit);
+
+        // Assert
+        let edited = edit.apply(source);
+        assert_eq!(index, LineIndex::new(&edited), "{:?}", edit);
+    }
+}
+
+/// Tests random edits of random text against a rebuild.
+#[test]
+fn test_apply_edit_property() {
+    const PIECES: [&str; 7] = ["a", "bc", "\n", "\r\n", "é", "😀", ""];
+    let text =
+        |rng: &mut Rng| -> String { (0..rng.below(8)).map(|_| *rng.pick(&PIECES)).collect() };
+
+    check(seed(), 500, text, |source| {
+        let mut rng = Rng::new(source.len() as u64);
+        let mut source = source.to_string();
+        let mut index = LineIndex::new(&source);
+        for _ in 0..4 {
+            let length = source.chars().count();
+            let start = rng.below(length + 1);
+            let edit = TextEdit {
+                range: start..start + rng.below(length - start + 1),
+                replacement: text(&mut rng),
+            };
+            index.apply_edit(&source, &edit);
+            source = edit.apply(&source);
+            if index != LineIndex::new(&source) {
+                return Err(format!("index differs after {:?}", edit));
+            }
+        }
+        Ok(())
+    });
+}
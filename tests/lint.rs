@@ -0,0 +1,370 @@
+//! tests/lint.rs
+
+mod support;
+
+use rdp::{
+    apply_edits, check_arity, check_exhaustiveness, lint, suggest_parens, suggest_parens_with,
+    Confusion, Diagnostic, DiagnosticsConfig, Level, ParseError, Severity, LINT_CODES,
+};
+
+/// Tests that a number match without a catch-all arm is reported.
+#[test]
+fn test_number_match_without_catch_all() {
+    // Arrange
+    let program = support::parse_program("match x with | 0 -> a | 1 -> b");
+
+    // Act
+    let diagnostics = check_exhaustiveness(&program);
+
+    // Assert
+    assert_eq!(
+        diagnostics,
+        vec![Diagnostic {
+            severity: Severity::Warning,
+            code: "non-exhaustive-match",
+            message: "match is not exhaustive: only '0', '1' covered; add an identifier or '_' arm"
+                .to_string(),
+            fixes: Vec::new(),
+            location: None,
+        }]
+    );
+}
+
+/// Tests that an identifier arm, even parenthesized, makes a match exhaustive.
+#[test]
+fn test_match_with_catch_all_is_exhaustive() {
+    // Arrange
+    let plain = support::parse_program("match x with | 0 -> a | n -> b");
+    let grouped = support::parse_program("match x with | 0 -> a | (n) -> b");
+
+    // Act
+    let plain_diagnostics = check_exhaustiveness(&plain);
+    let grouped_diagnostics = check_exhaustiveness(&grouped);
+
+    // Assert
+    assert!(plain_diagnostics.is_empty());
+    assert!(grouped_diagnostics.is_empty());
+}
+
+/// Tests that arms for both Booleans make a match exhaustive, and one alone
+/// does not.
+#[test]
+fn test_boolean_match_exhaustiveness() {
+    // Arrange
+    let both = support::parse_program("match p with | true -> a | (false) -> b");
+    let one = support::parse_program("match p with | true -> a | true -> b");
+
+    // Act
+    let both_diagnostics = check_exhaustiveness(&both);
+    let one_diagnostics = check_exhaustiveness(&one);
+
+    // Assert
+    assert!(both_diagnostics.is_empty());
+    assert_eq!(one_diagnostics.len(), 1);
+}
+
+/// Tests that a match whose arms are all one Boolean names the other as
+/// missing, rather than asking for a catch-all.
+#[test]
+fn test_boolean_match_names_missing_case() {
+    // Arrange
+    let program = support::parse_program("match b with | true -> a | (true) when c -> b");
+
+    // Act
+    let diagnostics = check_exhaustiveness(&program);
+
+    // Assert
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].message.contains("false"));
+    assert_eq!(
+        diagnostics[0].message,
+        "match is not exhaustive: missing 'false'; add a 'false' arm"
+    );
+}
+
+/// Tests that a guarded arm covers nothing, even when its pattern is a
+/// catch-all.
+#[test]
+fn test_guarded_arms_are_not_exhaustive() {
+    // Arrange
+    let partly = support::parse_program("match x with | 0 -> a | n when n > 0 -> b");
+    let entirely = support::parse_program("match x with | n when n > 0 -> a | _ when true -> b");
+    let fallback = support::parse_program("match x with | n when n > 0 -> a | _ -> b");
+
+    // Act
+    let partly_diagnostics = check_exhaustiveness(&partly);
+    let entirely_diagnostics = check_exhaustiveness(&entirely);
+    let fallback_diagnostics = check_exhaustiveness(&fallback);
+
+    // Assert
+    let messages = |diagnostics: Vec<Diagnostic>| -> Vec<String> {
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    };
+    assert_eq!(
+        messages(partly_diagnostics),
+        ["match is not exhaustive: only '0' covered; add an identifier or '_' arm"]
+    );
+    assert_eq!(
+        messages(entirely_diagnostics),
+        ["match is not exhaustive: every arm has a guard; add an identifier or '_' arm without one"]
+    );
+    assert!(fallback_diagnostics.is_empty());
+}
+
+/// Tests that matches nested anywhere in the program are checked.
+#[test]
+fn test_nested_matches_are_checked() {
+    // Arrange
+    let program = support::parse_program(
+        "let f = \\x -> match x with | 1 -> match y with | 2 -> a | n -> b in f (match z with | 3 -> c)",
+    );
+
+    // Act
+    let diagnostics = lint(&program);
+
+    // Assert
+    let messages: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "warning[non-exhaustive-match]: match is not exhaustive: only '1' covered; add an identifier or '_' arm",
+            "warning[non-exhaustive-match]: match is not exhaustive: only '3' covered; add an identifier or '_' arm",
+        ]
+    );
+}
+
+/// Tests each default confusion and the fix offered for it, which wraps the
+/// confusing operand where it stands in the source.
+#[test]
+fn test_suggest_parens_reports_confusions() {
+    for (input, fixed) in [
+        ("f x + y", "(f x) + y"),
+        ("a || b  &&  c", "a || (b  &&  c)"),
+        ("a < b && c -- why", "a < (b && c) -- why"),
+    ] {
+        // Arrange
+        let (program, spans) = support::parse_spanned(input).expect("Failed to parse input");
+
+        // Act
+        let diagnostics = suggest_parens(&program, &spans);
+
+        // Assert
+        assert_eq!(diagnostics.len(), 1, "'{}'", input);
+        assert_eq!(diagnostics[0].code, "suggest-parens");
+        assert_eq!(diagnostics[0].fixes.len(), 1, "'{}'", input);
+        assert_eq!(
+            apply_edits(input, &diagnostics[0].fixes[0].edits),
+            fixed,
+            "'{}'",
+            input
+        );
+    }
+}
+
+/// Tests that applying any fix leaves a program that parses to the same tree.
+#[test]
+fn test_suggest_parens_fixes_reparse_to_equal_ast() {
+    // Arrange
+    let source = "let y = g a * 2 in if a || b && c then f x - y else a > b || c";
+    let (program, spans) = support::parse_spanned(source).expect("Failed to parse input");
+
+    // Act
+    let diagnostics = suggest_parens(&program, &spans);
+
+    // Assert
+    assert_eq!(diagnostics.len(), 4);
+    for diagnostic in diagnostics {
+        let fixed = apply_edits(source, &diagnostic.fixes[0].edits);
+        let reparsed = support::parse(&fixed).expect("fix must parse");
+        assert_eq!(
+            support::ungroup(&reparsed),
+            support::ungroup(&program.expression),
+            "'{}'",
+            fixed
+        );
+    }
+}
+
+/// Tests that a finding still comes without spans, but with no fix.
+#[test]
+fn test_suggest_parens_without_spans() {
+    // Arrange
+    let program = support::parse_program("f x + y");
+
+    // Act
+    let diagnostics = suggest_parens(&program, &[]);
+
+    // Assert
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].fixes.is_empty());
+}
+
+/// Tests that explicit parentheses silence the suggestions.
+#[test]
+fn test_suggest_parens_accepts_explicit_grouping() {
+    // Arrange
+    let program = support::parse_program("(f x) + y + (a || (b && c))");
+
+    // Act
+    let diagnostics = suggest_parens(&program, &[]);
+
+    // Assert
+    assert!(diagnostics.is_empty());
+}
+
+/// Tests that the confusion table is configurable and the check is opt-in.
+#[test]
+fn test_suggest_parens_is_configurable_and_opt_in() {
+    // Arrange
+    let program = support::parse_program("f x + y");
+
+    // Act
+    let only_logic = suggest_parens_with(&program, &[], &[Confusion::MixedLogic]);
+    let default_lints = lint(&program);
+
+    // Assert
+    assert!(only_logic.is_empty());
+    assert!(default_lints.is_empty());
+}
+
+/// Tests that applying a let-bound lambda chain to too many arguments is
+/// reported, including through a parenthesized partial application.
+#[test]
+fn test_arity_over_application() {
+    // Arrange
+    let program = support::parse_program(
+        "let add = \\x -> \\y -> x + y in let inc = \\n -> n + 1 in add 1 2 3 + (inc 1) 2",
+    );
+
+    // Act
+    let messages: Vec<String> = check_arity(&program)
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect();
+
+    // Assert
+    assert_eq!(
+        messages,
+        vec![
+            "'add' takes 2 arguments but is applied to 3",
+            "'inc' takes 1 argument but is applied to 2",
+        ]
+    );
+}
+
+/// Tests that full and partial applications of a curried function are fine,
+/// as is a use of a shadowing binding of the same name.
+#[test]
+fn test_arity_correct_curried_use() {
+    // Arrange
+    let program = support::parse_program(
+        "let add = \\x -> \\y -> x + y in add 1 2 + add 1 + (\\add -> add 1 2 3) f",
+    );
+
+    // Act
+    let diagnostics = check_arity(&program);
+
+    // Assert
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+/// Tests that a function used as a value anywhere is never reported, since it
+/// may be called in ways the check can't see.
+#[test]
+fn test_arity_suppressed_when_function_escapes() {
+    // Arrange
+    let program =
+        support::parse_program("let add = \\x -> \\y -> x + y in let r = apply add in add 1 2 3");
+
+    // Act
+    let diagnostics = check_arity(&program);
+
+    // Assert
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    assert!(lint(&support::parse_program("let f = \\x -> x in f 1 2")).is_empty());
+}
+
+/// Tests that one program's findings are warnings, errors, or silenced
+/// depending on the configured level of their code.
+#[test]
+fn test_diagnostics_config_levels() {
+    // Arrange
+    let program = support::parse_program("match x with | 0 -> (match y with | 1 -> 2)");
+    let mut findings = lint(&program);
+    findings.extend(suggest_parens(&support::parse_program("f x + y"), &[]));
+    let deny = DiagnosticsConfig {
+        overrides: [("non-exhaustive-match".to_string(), Level::Deny)].into(),
+        ..DiagnosticsConfig::default()
+    };
+    let allow = DiagnosticsConfig {
+        default_level: Level::Deny,
+        overrides: [("non-exhaustive-match".to_string(), Level::Allow)].into(),
+    };
+
+    // Act
+    let warned = DiagnosticsConfig::default().apply(findings.clone());
+    let denied = deny.apply(findings.clone());
+    let allowed = allow.apply(findings);
+
+    // Assert
+    let severities = |diagnostics: &[Diagnostic]| -> Vec<(&str, Severity)> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.severity))
+            .collect()
+    };
+    assert_eq!(
+        severities(&warned),
+        [
+            ("non-exhaustive-match", Severity::Warning),
+            ("non-exhaustive-match", Severity::Warning),
+            ("suggest-parens", Severity::Warning),
+        ]
+    );
+    assert_eq!(
+        severities(&denied),
+        [
+            ("non-exhaustive-match", Severity::Error),
+            ("non-exhaustive-match", Severity::Error),
+            ("suggest-parens", Severity::Warning),
+        ]
+    );
+    assert_eq!(severities(&allowed), [("suggest-parens", Severity::Error)]);
+}
+
+/// Tests that errors stay errors even when their code is allowed.
+#[test]
+fn test_diagnostics_config_keeps_errors() {
+    // Arrange
+    let error = Diagnostic::from(&ParseError::UnexpectedEOF);
+    let config = DiagnosticsConfig {
+        default_level: Level::Allow,
+        overrides: [(error.code.to_string(), Level::Allow)].into(),
+    };
+
+    // Act
+    let diagnostics = config.apply(vec![error.clone()]);
+
+    // Assert
+    assert_eq!(diagnostics, [error]);
+}
+
+/// Tests that every lint reports under a code listed in `LINT_CODES`.
+#[test]
+fn test_lint_codes_are_listed() {
+    // Arrange
+    let mut findings = lint(&support::parse_program("match x with | 0 -> 1"));
+    findings.extend(suggest_parens(&support::parse_program("f x + y"), &[]));
+    findings.extend(check_arity(&support::parse_program(
+        "let f = \\x -> x in f 1 2",
+    )));
+
+    // Act
+    let codes: Vec<&str> = findings.iter().map(|diagnostic| diagnostic.code).collect();
+
+    // Assert
+    assert_eq!(codes, LINT_CODES);
+}
@@ -0,0 +1,119 @@
+//! tests/literate.rs
+
+use rdp::{
+    check_docs, extract_blocks, LexerOptions, SourceMap, Span, ValidateConfig,
+    MISSING_EXPECTED_ERROR,
+};
+
+const GUIDE: &str = include_str!("docs/guide.md");
+
+/// Tests that only `pfl` blocks are extracted, with their attributes and
+/// their position in the document.
+#[test]
+fn test_extract_blocks() {
+    // Act
+    let blocks = extract_blocks(GUIDE);
+
+    // Assert
+    let attrs: Vec<&[String]> = blocks.iter().map(|block| &block.attrs[..]).collect();
+    assert_eq!(
+        attrs,
+        vec![
+            &[][..],
+            &["expect-error=E0001".to_string()][..],
+            &["ignore".to_string()][..],
+            &[][..],
+        ]
+    );
+    assert_eq!(blocks[1].source, "let x = in x\n");
+    let Span { start, end } = blocks[1].span_in_markdown;
+    let text: String = GUIDE.chars().skip(start).take(end - start).collect();
+    assert_eq!(text, blocks[1].source);
+}
+
+/// Tests that an unclosed fence runs to the end of the document, and that a
+/// `pfl` fence inside another block is not a block of its own.
+#[test]
+fn test_extract_blocks_nested_and_unclosed() {
+    // Arrange
+    let markdown = "````md\n```pfl\n1\n```\n````\n\n```pfl,expect-error=E0002\n1 +\n";
+
+    // Act
+    let blocks = extract_blocks(markdown);
+
+    // Assert
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].source, "1 +\n");
+    assert_eq!(blocks[0].expected_error(), Some("E0002"));
+}
+
+/// Tests checking the guide: the passing, expected-failure, and ignored
+/// blocks are fine, and the error in the last block is reported at its line
+/// and column in the document.
+#[test]
+fn test_check_docs() {
+    // Arrange
+    let mut sources = SourceMap::new();
+    let id = sources.add("docs/guide.md", GUIDE);
+    let config = ValidateConfig {
+        options: LexerOptions {
+            max_identifier_length: Some(6),
+            ..LexerOptions::default()
+        },
+        ..ValidateConfig::default()
+    };
+
+    // Act
+    let report = check_docs(sources.get(id), &config);
+
+    // Assert
+    assert_eq!((report.checked, report.ignored), (3, 1));
+    assert!(report.has_errors());
+    let rendered: Vec<String> = report
+        .diagnostics
+        .iter()
+        .map(|diagnostic| sources.render(diagnostic))
+        .collect();
+    assert_eq!(
+        rendered,
+        vec![
+            "docs/guide.md:32:5: error[E0008]: Identifier starting at offset 18 exceeds \
+             the maximum length of 6 characters."
+                .to_string()
+        ]
+    );
+}
+
+/// Tests that a block expected to fail is reported at its first line when it
+/// passes or fails differently.
+#[test]
+fn test_missing_expected_error() {
+    // Arrange
+    let mut sources = SourceMap::new();
+    let id = sources.add(
+        "expect.md",
+        "```pfl expect-error=E0002\n1 + 1\n```\n\n```pfl expect-error=E0002\nlet x = in x\n```\n",
+    );
+
+    // Act
+    let report = check_docs(sources.get(id), &ValidateConfig::default());
+
+    // Assert
+    let rendered: Vec<String> = report
+        .diagnostics
+        .iter()
+        .inspect(|diagnostic| assert_eq!(diagnostic.code, MISSING_EXPECTED_ERROR))
+        .map(|diagnostic| sources.render(diagnostic))
+        .collect();
+    assert_eq!(
+        rendered,
+        vec![
+            "expect.md:2:1: error[missing-expected-error]: block was expected to fail \
+             with E0002, but passed"
+                .to_string(),
+            "expect.md:6:1: error[missing-expected-error]: block was expected to fail \
+             with E0002, but failed with E0001"
+                .to_string(),
+        ]
+    );
+}
@@ -0,0 +1,254 @@
+//! tests/operators.rs
+
+mod support;
+
+use rdp::{
+    ArithmeticOperator, Assoc, BinaryOperator, ComparisonOperator, Expression, Lexer,
+    LogicOperator, Parser, Term, Token,
+};
+
+fn identifier(name: &str) -> Expression {
+    Expression::Term(Term::Identifier(name.to_string()))
+}
+
+/// Tests that `a OP b OP c` groups the way each operator's associativity claims.
+#[test]
+fn test_associativity_matches_parser() {
+    for operator in BinaryOperator::all() {
+        // Arrange
+        let symbol = operator.symbol();
+        let input = format!("a {} b {} c", symbol, symbol);
+
+        // Act
+        if operator.associativity() == Assoc::None {
+            let tokens = Lexer::tokenize_str(&input).unwrap();
+            let result = Parser::new(tokens).parse_program();
+
+            // Assert
+            assert!(result.is_err(), "'{}' chained", input);
+            continue;
+        }
+        let program = support::parse_program(&input);
+        let (root, left, right) = program
+            .expression
+            .as_binary()
+            .unwrap_or_else(|| panic!("'{}' did not parse as a binary operation", input));
+
+        // Assert
+        assert_eq!(root, operator, "wrong root for '{}'", input);
+        match operator.associativity() {
+            Assoc::Left => {
+                assert_eq!(left.as_binary().map(|(op, _, _)| op), Some(operator));
+                assert_eq!(*right, identifier("c"));
+            }
+            Assoc::Right => {
+                assert_eq!(*left, identifier("a"));
+                assert_eq!(right.as_binary().map(|(op, _, _)| op), Some(operator));
+            }
+            Assoc::None => unreachable!(),
+        }
+    }
+}
+
+/// Tests that of two operators with different precedence, the one with the
+/// higher precedence binds tighter, in either order.
+#[test]
+fn test_precedence_matches_parser() {
+    for outer in BinaryOperator::all() {
+        for inner in BinaryOperator::all() {
+            if outer.precedence() >= inner.precedence() {
+                continue;
+            }
+
+            // Arrange
+            let inner_first = format!("a {} b {} c", inner.symbol(), outer.symbol());
+            let inner_last = format!("a {} b {} c", outer.symbol(), inner.symbol());
+
+            // Act
+            let first = support::parse_program(&inner_first);
+            let last = support::parse_program(&inner_last);
+            let (first_root, first_left, _) = first.expression.as_binary().unwrap();
+            let (last_root, _, last_right) = last.expression.as_binary().unwrap();
+
+            // Assert
+            assert_eq!(first_root, outer, "wrong root for '{}'", inner_first);
+            assert_eq!(
+                first_left.as_binary().map(|(op, _, _)| op),
+                Some(inner.clone())
+            );
+            assert_eq!(last_root, outer, "wrong root for '{}'", inner_last);
+            assert_eq!(last_right.as_binary().map(|(op, _, _)| op), Some(inner));
+        }
+    }
+}
+
+/// Tests that every operator's symbol lexes to the token it is parsed from.
+#[test]
+fn test_symbol_round_trips_through_token() {
+    for operator in BinaryOperator::all() {
+        // Act
+        let tokens = Lexer::tokenize_str(operator.symbol()).unwrap();
+
+        // Assert
+        assert_eq!(
+            tokens.len(),
+            2,
+            "'{}' is not a single token",
+            operator.symbol()
+        );
+        assert_eq!(BinaryOperator::try_from(&tokens[0]), Ok(operator));
+    }
+}
+
+/// Tests that tokens which are not binary operators are rejected.
+#[test]
+fn test_non_operator_token_is_rejected() {
+    // Act
+    let result = BinaryOperator::try_from(&Token::Arrow);
+
+    // Assert
+    assert!(result.is_err());
+}
+
+/// Tests that the per-kind accessors agree with the unified operator.
+#[test]
+fn test_per_kind_metadata() {
+    // Assert
+    assert_eq!(ArithmeticOperator::Multiply.symbol(), "*");
+    assert_eq!(
+        ArithmeticOperator::Multiply.precedence(),
+        BinaryOperator::from(ArithmeticOperator::Multiply).precedence()
+    );
+    assert_eq!(ComparisonOperator::LessThan.associativity(), Assoc::None);
+    assert_eq!(LogicOperator::Or.symbol(), "||");
+    assert!(LogicOperator::And.precedence() > ComparisonOperator::Equal.precedence());
+}
+
+const README: &str = include_str!("../README.md");
+
+/// The items of the README's operator precedence list, tightest first.
+fn readme_precedence_items() -> Vec<&'static str> {
+    let section = README
+        .split("## Operator Precedence")
+        .nth(1)
+        .and_then(|rest| rest.split("\n## ").next())
+        .expect("README has no operator precedence section");
+    section
+        .lines()
+        .filter_map(|line| {
+            let (number, item) = line.split_once(". ")?;
+            number.parse::<usize>().ok().map(|_| item)
+        })
+        .collect()
+}
+
+/// The code spans of `text`, each with the text that follows it up to the
+/// next code span.
+fn code_spans(text: &str) -> Vec<(&str, &str)> {
+    let parts: Vec<&str> = text.split('`').collect();
+    (1..parts.len())
+        .step_by(2)
+        .map(|i| (parts[i], parts.get(i + 1).copied().unwrap_or("")))
+        .collect()
+}
+
+/// The text of the parenthesis after an item's title, up to its closing
+/// `)` outside any code span.
+fn title_parenthesis(item: &str) -> Option<&str> {
+    let (_, rest) = item.split_once("** (")?;
+    let mut in_code = false;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '`' => in_code = !in_code,
+            ')' if !in_code => return Some(&rest[..i]),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Tests that the README lists the binary operators in the order of the
+/// operator table, one level per item, with every operator listed once.
+#[test]
+fn test_readme_precedence_matches_table() {
+    // Arrange
+    let mut levels = Vec::new();
+    for item in readme_precedence_items() {
+        // An item names its operators in the parenthesis after its title;
+        // items naming anything else, like prefix `-`, are not binary levels.
+        let Some(parenthesis) = title_parenthesis(item) else {
+            continue;
+        };
+        let found: Vec<Option<BinaryOperator>> = code_spans(parenthesis)
+            .into_iter()
+            .map(|(code, _)| {
+                BinaryOperator::all()
+                    .into_iter()
+                    .find(|operator| operator.symbol() == code)
+            })
+            .collect();
+        let operators: Vec<BinaryOperator> = found.iter().flatten().cloned().collect();
+        if !operators.is_empty() && operators.len() == found.len() {
+            levels.push((item, operators));
+        }
+    }
+
+    // Act
+    let listed: usize = levels.iter().map(|(_, operators)| operators.len()).sum();
+
+    // Assert
+    assert_eq!(listed, BinaryOperator::all().len(), "{:?}", levels);
+    for (item, operators) in &levels {
+        let precedence = operators[0].precedence();
+        assert!(
+            operators
+                .iter()
+                .all(|operator| operator.precedence() == precedence),
+            "mixed levels in '{}'",
+            item
+        );
+    }
+    for pair in levels.windows(2) {
+        assert!(
+            pair[0].1[0].precedence() > pair[1].1[0].precedence(),
+            "'{}' is not tighter than '{}'",
+            pair[0].0,
+            pair[1].0
+        );
+    }
+}
+
+/// Tests that each example in the README's precedence list, written
+/// "`a` is `b`", parses to the same tree as its parenthesized form.
+#[test]
+fn test_readme_precedence_examples() {
+    // Arrange
+    let examples: Vec<(&str, &str)> = readme_precedence_items()
+        .into_iter()
+        .flat_map(|item| {
+            let spans = code_spans(item);
+            spans
+                .windows(2)
+                .filter(|pair| pair[0].1 == " is ")
+                .map(|pair| (pair[0].0, pair[1].0))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    // Assert
+    assert!(examples.len() >= 12, "{:?}", examples);
+    for (example, grouped) in examples {
+        // Act
+        let parsed = support::parse(example).expect("Failed to parse example");
+        let expected = support::parse(grouped).expect("Failed to parse grouping");
+
+        // Assert
+        assert_eq!(
+            support::ungroup(&parsed),
+            support::ungroup(&expected),
+            "'{}' is not '{}'",
+            example,
+            grouped
+        );
+    }
+}
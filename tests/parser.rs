@@ -1,8 +1,11 @@
 //! tests/parser.rs
 
+mod support;
+
 use rdp::{
-    ArithmeticOperator, ComparisonOperator, Expression, FunctionComposition, Lexer, LogicOperator,
-    MatchArm, ParseError, Parser, Pattern, Program, Term, Token, TypeAnnotation,
+    ArithmeticOperator, ComparisonOperator, Expression, FunctionComposition, LetBinding, Lexer,
+    ListOperator, LogicOperator, MatchArm, ParseError, Parser, Pattern, Position, Program, Term,
+    Token, TypeAnnotation, UnaryOperator,
 };
 
 /// Tests parsing of a `let` expression.
@@ -32,6 +35,7 @@ fn test_program_parsing_with_let() {
         program,
         Program {
             expression: Expression::LetExpr {
+                recursive: false,
                 identifier: "x".to_string(),
                 type_annotation: Some(TypeAnnotation::Int),
                 value: Box::new(Expression::Term(Term::Number(42.0))),
@@ -41,6 +45,35 @@ fn test_program_parsing_with_let() {
     );
 }
 
+/// Tests that string literals are terms: bound by `let` and passed as
+/// arguments.
+#[test]
+fn test_program_parsing_with_string() {
+    // Arrange
+    let tokens = Lexer::tokenize_str(r#"let greeting = "hi" in greet greeting "there""#).unwrap();
+
+    // Act
+    let program = Parser::new(tokens).parse_program().unwrap();
+
+    // Assert
+    assert_eq!(
+        program,
+        Program {
+            expression: Expression::LetExpr {
+                recursive: false,
+                identifier: "greeting".to_string(),
+                type_annotation: None,
+                value: Box::new(Expression::Term(Term::String("hi".to_string()))),
+                body: Box::new(Expression::Application(vec![
+                    Expression::Term(Term::Identifier("greet".to_string())),
+                    Expression::Term(Term::Identifier("greeting".to_string())),
+                    Expression::Term(Term::String("there".to_string())),
+                ])),
+            }
+        }
+    );
+}
+
 /// Tests parsing of an `if` expression.
 #[test]
 fn test_program_parsing_with_if() {
@@ -119,6 +152,58 @@ fn test_program_parsing_with_lambda() {
     );
 }
 
+/// Tests that a lambda with several parameters parses as nested lambdas, with
+/// parenthesized parameters annotated.
+#[test]
+fn test_program_parsing_with_lambda_parameters() {
+    // Arrange
+    let parse = |source| {
+        Parser::new(Lexer::tokenize_str(source).unwrap())
+            .parse_program()
+            .unwrap()
+            .expression
+    };
+
+    // Act
+    let two = parse("\\x y -> x + y");
+    let three = parse("\\x y z -> x + y + z");
+    let annotated = parse("\\(f: (Int -> Int)) x (n: Int) -> f x + n");
+
+    // Assert
+    assert_eq!(two, parse("\\x -> \\y -> x + y"));
+    assert_eq!(three, parse("\\x -> \\y -> \\z -> x + y + z"));
+    assert_eq!(
+        annotated,
+        parse("\\f: (Int -> Int) -> \\x -> \\n: Int -> f x + n")
+    );
+}
+
+/// Tests that a bare annotation is rejected on a lambda with several
+/// parameters, whichever parameter it follows.
+#[test]
+fn test_lambda_parameters_need_parenthesized_annotations() {
+    for input in ["\\x: Int y -> x", "\\x y: Int -> x"] {
+        // Arrange
+        let tokens = Lexer::tokenize_str(input).unwrap();
+
+        // Act
+        let result = Parser::new(tokens).parse_program();
+
+        // Assert
+        match result {
+            Err(ParseError::UnexpectedToken { message, .. }) => {
+                assert!(
+                    message.starts_with("Parenthesize"),
+                    "{}: {}",
+                    input,
+                    message
+                )
+            }
+            other => panic!("expected an error for {:?}, got {:?}", input, other),
+        }
+    }
+}
+
 /// Tests parsing of a pattern match expression.
 #[test]
 fn test_program_parsing_with_pattern_match() {
@@ -130,11 +215,11 @@ fn test_program_parsing_with_pattern_match() {
         Token::Pipe,
         Token::Number(1.0),
         Token::Arrow,
-        Token::Identifier("true".to_string()),
+        Token::True,
         Token::Pipe,
         Token::Identifier("_".to_string()),
         Token::Arrow,
-        Token::Identifier("false".to_string()),
+        Token::False,
         Token::Eof,
     ];
 
@@ -153,15 +238,13 @@ fn test_program_parsing_with_pattern_match() {
                 arms: vec![
                     MatchArm {
                         pattern: Pattern::Number(1.0),
-                        expression: Box::new(Expression::Term(Term::Identifier(
-                            "true".to_string()
-                        ))),
+                        guard: None,
+                        expression: Box::new(Expression::Term(Term::Boolean(true))),
                     },
                     MatchArm {
                         pattern: Pattern::Identifier("_".to_string()),
-                        expression: Box::new(Expression::Term(Term::Identifier(
-                            "false".to_string()
-                        ))),
+                        guard: None,
+                        expression: Box::new(Expression::Term(Term::Boolean(false))),
                     },
                 ],
             }
@@ -169,32 +252,741 @@ fn test_program_parsing_with_pattern_match() {
     );
 }
 
-/// Tests parsing of a comparison expression.
+/// Tests that `true` and `false` parse as Boolean terms and patterns.
+#[test]
+fn test_program_parsing_with_boolean_literals() {
+    // Arrange
+    let parse = |source| Parser::new(Lexer::tokenize_str(source).unwrap()).parse_program();
+
+    // Act
+    let result = parse("if b then true else false").map(|program| program.expression);
+    let arms = parse("match b with | true -> 1 | (false) -> 0").map(|program| program.expression);
+
+    // Assert
+    assert_eq!(
+        result,
+        Ok(Expression::IfExpr {
+            condition: Box::new(Expression::Term(Term::Identifier("b".to_string()))),
+            then_branch: Box::new(Expression::Term(Term::Boolean(true))),
+            else_branch: Box::new(Expression::Term(Term::Boolean(false))),
+        })
+    );
+    match arms {
+        Ok(Expression::PatternMatch { arms, .. }) => {
+            assert_eq!(arms[0].pattern, Pattern::Boolean(true));
+            assert_eq!(
+                arms[1].pattern,
+                Pattern::Grouped(Box::new(Pattern::Boolean(false)))
+            );
+        }
+        other => panic!("Expected a match, got {:?}", other),
+    }
+}
+
+/// Tests parsing of a comparison expression.
+#[test]
+fn test_program_parsing_with_comparison() {
+    // Arrange
+    let tokens = vec![
+        Token::Identifier("x".to_string()),
+        Token::Equal,
+        Token::Number(42.0),
+        Token::Eof,
+    ];
+
+    // Act
+    let mut parser = Parser::new(tokens);
+    let result = parser.parse_program();
+
+    // Assert
+    assert!(result.is_ok());
+    let program = result.unwrap();
+    assert_eq!(
+        program,
+        Program {
+            expression: Expression::Comparison {
+                left: Box::new(Expression::Term(Term::Identifier("x".to_string()))),
+                operator: ComparisonOperator::Equal,
+                right: Some(Box::new(Expression::Term(Term::Number(42.0)))),
+            }
+        }
+    );
+}
+
+/// Tests parsing of `<=` and `>=` comparisons, binding looser than
+/// arithmetic like the other comparisons.
+#[test]
+fn test_program_parsing_with_inclusive_comparisons() {
+    // Arrange
+    let sources = ["x <= 1 + 1", "x >= 1 + 1"];
+    let operators = [
+        ComparisonOperator::LessEqual,
+        ComparisonOperator::GreaterEqual,
+    ];
+
+    for (source, operator) in sources.into_iter().zip(operators) {
+        // Act
+        let tokens = Lexer::tokenize_str(source).unwrap();
+        let program = Parser::new(tokens).parse_program().unwrap();
+
+        // Assert
+        assert_eq!(
+            program,
+            Program {
+                expression: Expression::Comparison {
+                    left: Box::new(Expression::Term(Term::Identifier("x".to_string()))),
+                    operator,
+                    right: Some(Box::new(Expression::Arithmetic {
+                        left: Box::new(Expression::Term(Term::Number(1.0))),
+                        operator: ArithmeticOperator::Add,
+                        right: Box::new(Expression::Term(Term::Number(1.0))),
+                    })),
+                }
+            }
+        );
+    }
+}
+
+/// Tests a program mixing Unicode identifiers with operators.
+#[test]
+fn test_program_parsing_with_unicode_identifiers() {
+    // Arrange
+    let identifier = |name: &str| Box::new(Expression::Term(Term::Identifier(name.to_string())));
+    let source = "let λ = \\α -> α * 2 in λ café + π";
+
+    // Act
+    let result = Parser::new(Lexer::tokenize_str(source).unwrap()).parse_program();
+
+    // Assert
+    assert_eq!(
+        result.unwrap().expression,
+        Expression::LetExpr {
+            recursive: false,
+            identifier: "λ".to_string(),
+            type_annotation: None,
+            value: Box::new(Expression::Lambda {
+                parameter: "α".to_string(),
+                type_annotation: None,
+                body: Box::new(Expression::Arithmetic {
+                    left: identifier("α"),
+                    operator: ArithmeticOperator::Multiply,
+                    right: Box::new(Expression::Term(Term::Number(2.0))),
+                }),
+            }),
+            body: Box::new(Expression::Arithmetic {
+                left: Box::new(Expression::Application(vec![
+                    *identifier("λ"),
+                    *identifier("café"),
+                ])),
+                operator: ArithmeticOperator::Add,
+                right: identifier("π"),
+            }),
+        }
+    );
+}
+
+/// Tests that `%` binds tighter than `+` and looser than application.
+#[test]
+fn test_program_parsing_with_modulo() {
+    // Arrange
+    let number = |value: f64| Box::new(Expression::Term(Term::Number(value)));
+
+    // Act
+    let result = Parser::new(Lexer::tokenize_str("10 % 3 + 1").unwrap()).parse_program();
+    let applied = Parser::new(Lexer::tokenize_str("f x % 2").unwrap()).parse_program();
+
+    // Assert
+    assert_eq!(
+        result.unwrap().expression,
+        Expression::Arithmetic {
+            left: Box::new(Expression::Arithmetic {
+                left: number(10.0),
+                operator: ArithmeticOperator::Modulo,
+                right: number(3.0),
+            }),
+            operator: ArithmeticOperator::Add,
+            right: number(1.0),
+        }
+    );
+    assert!(matches!(
+        applied.unwrap().expression,
+        Expression::Arithmetic {
+            left,
+            operator: ArithmeticOperator::Modulo,
+            ..
+        } if matches!(*left, Expression::Application(_))
+    ));
+}
+
+/// Tests that `^` groups to the right and binds tighter than `*` and `-`,
+/// while `*` binds tighter than `+`.
+#[test]
+fn test_program_parsing_with_power() {
+    // Arrange
+    let number = |value: f64| Box::new(Expression::Term(Term::Number(value)));
+    let binary = |left, operator, right| {
+        Box::new(Expression::Arithmetic {
+            left,
+            operator,
+            right,
+        })
+    };
+    let parse = |source| {
+        Parser::new(Lexer::tokenize_str(source).unwrap())
+            .parse_program()
+            .unwrap()
+            .expression
+    };
+
+    // Act
+    let tower = parse("2 ^ 3 ^ 2");
+    let product = parse("2 * 3 ^ 2");
+    let difference = parse("2 ^ 3 - 1");
+    let sum = parse("1 + 2 * 3");
+
+    // Assert
+    use ArithmeticOperator::{Add, Multiply, Power, Subtract};
+    assert_eq!(
+        tower,
+        *binary(number(2.0), Power, binary(number(3.0), Power, number(2.0)))
+    );
+    assert_eq!(
+        product,
+        *binary(
+            number(2.0),
+            Multiply,
+            binary(number(3.0), Power, number(2.0))
+        )
+    );
+    assert_eq!(
+        difference,
+        *binary(
+            binary(number(2.0), Power, number(3.0)),
+            Subtract,
+            number(1.0)
+        )
+    );
+    assert_eq!(
+        sum,
+        *binary(number(1.0), Add, binary(number(2.0), Multiply, number(3.0)))
+    );
+}
+
+/// Tests that `*` and `/` bind tighter than `+` and `-`, with both levels
+/// grouping to the left, and that application binds tighter than either.
+#[test]
+fn test_program_parsing_with_mixed_arithmetic() {
+    // Arrange
+    let number = |value: f64| Box::new(Expression::Term(Term::Number(value)));
+    let identifier = |name: &str| Expression::Term(Term::Identifier(name.to_string()));
+    let apply = |f, x| Box::new(Expression::Application(vec![identifier(f), identifier(x)]));
+    let binary = |left, operator, right| {
+        Box::new(Expression::Arithmetic {
+            left,
+            operator,
+            right,
+        })
+    };
+    let parse = |source| {
+        Parser::new(Lexer::tokenize_str(source).unwrap())
+            .parse_program()
+            .unwrap()
+            .expression
+    };
+
+    // Act
+    let quotient = parse("10 - 4 / 2");
+    let products = parse("1 * 2 + 3 * 4");
+    let chained = parse("8 / 4 / 2 - 1 - 1");
+    let applied = parse("f x * g y + 1");
+
+    // Assert
+    use ArithmeticOperator::{Add, Divide, Multiply, Subtract};
+    assert_eq!(
+        quotient,
+        *binary(
+            number(10.0),
+            Subtract,
+            binary(number(4.0), Divide, number(2.0))
+        )
+    );
+    assert_eq!(
+        products,
+        *binary(
+            binary(number(1.0), Multiply, number(2.0)),
+            Add,
+            binary(number(3.0), Multiply, number(4.0))
+        )
+    );
+    assert_eq!(
+        chained,
+        *binary(
+            binary(
+                binary(
+                    binary(number(8.0), Divide, number(4.0)),
+                    Divide,
+                    number(2.0)
+                ),
+                Subtract,
+                number(1.0)
+            ),
+            Subtract,
+            number(1.0)
+        )
+    );
+    assert_eq!(
+        applied,
+        *binary(
+            binary(apply("f", "x"), Multiply, apply("g", "y")),
+            Add,
+            number(1.0)
+        )
+    );
+}
+
+/// Tests that `::` groups to the right, binds looser than application and
+/// tighter than arithmetic, and leaves `:` annotations alone.
+#[test]
+fn test_program_parsing_with_cons() {
+    // Arrange
+    let identifier = |name: &str| Box::new(Expression::Term(Term::Identifier(name.to_string())));
+    let number = |value: f64| Box::new(Expression::Term(Term::Number(value)));
+    let cons = |left, right| {
+        Box::new(Expression::List {
+            left,
+            operator: ListOperator::Cons,
+            right,
+        })
+    };
+    let parse = |source| {
+        Parser::new(Lexer::tokenize_str(source).unwrap())
+            .parse_program()
+            .unwrap()
+            .expression
+    };
+
+    // Act
+    let chain = parse("1 :: 2 :: xs");
+    let applied = parse("f x :: xs");
+    let summed = parse("1 + 2 :: xs");
+    let annotated = parse("let x: Int = 1 in x :: xs");
+
+    // Assert
+    assert_eq!(
+        chain,
+        *cons(number(1.0), cons(number(2.0), identifier("xs")))
+    );
+    assert_eq!(
+        applied,
+        *cons(
+            Box::new(Expression::Application(vec![
+                *identifier("f"),
+                *identifier("x")
+            ])),
+            identifier("xs")
+        )
+    );
+    assert_eq!(
+        summed,
+        Expression::Arithmetic {
+            left: number(1.0),
+            operator: ArithmeticOperator::Add,
+            right: cons(number(2.0), identifier("xs")),
+        }
+    );
+    assert_eq!(
+        annotated,
+        Expression::LetExpr {
+            recursive: false,
+            identifier: "x".to_string(),
+            type_annotation: Some(TypeAnnotation::Int),
+            value: number(1.0),
+            body: cons(identifier("x"), identifier("xs")),
+        }
+    );
+}
+
+/// Tests that `++` groups to the left and binds looser than `+` but tighter
+/// than `&&`, and that spaced pluses are not taken for it.
+#[test]
+fn test_program_parsing_with_concat() {
+    // Arrange
+    let identifier = |name: &str| Box::new(Expression::Term(Term::Identifier(name.to_string())));
+    let concat = |left, right| {
+        Box::new(Expression::List {
+            left,
+            operator: ListOperator::Concat,
+            right,
+        })
+    };
+    let parse = |source| Parser::new(Lexer::tokenize_str(source).unwrap()).parse_program();
+
+    // Act
+    let chain = parse("xs ++ ys ++ zs");
+    let summed = parse("a + b ++ c");
+    let spaced = parse("a + + b");
+
+    // Assert
+    assert_eq!(
+        chain.unwrap().expression,
+        *concat(concat(identifier("xs"), identifier("ys")), identifier("zs"))
+    );
+    assert_eq!(
+        summed.unwrap().expression,
+        *concat(
+            Box::new(Expression::Arithmetic {
+                left: identifier("a"),
+                operator: ArithmeticOperator::Add,
+                right: identifier("b"),
+            }),
+            identifier("c")
+        )
+    );
+    assert!(
+        matches!(spaced, Err(ParseError::UnexpectedToken { ref found, .. }) if found == "+"),
+        "{:?}",
+        spaced
+    );
+}
+
+/// Tests that a pipeline parses as the nested applications it stands for,
+/// after the arithmetic on its left, and inside match arms.
+#[test]
+fn test_program_parsing_with_pipe_forward() {
+    // Arrange
+    let identifier = |name: &str| Expression::Term(Term::Identifier(name.to_string()));
+    let apply =
+        |function: &str, argument| Expression::Application(vec![identifier(function), argument]);
+    let parse = |source| {
+        Parser::new(Lexer::tokenize_str(source).unwrap())
+            .parse_program()
+            .unwrap()
+            .expression
+    };
+
+    // Act
+    let chain = parse("x |> f |> g |> h");
+    let arithmetic = parse("x + 1 |> f");
+    let partial = parse("xs |> map f");
+    let arms = parse("match n with | 0 -> x |> f | _ -> (y |> g)");
+
+    // Assert
+    assert_eq!(chain, apply("h", apply("g", apply("f", identifier("x")))));
+    assert_eq!(arithmetic, apply("f", parse("x + 1")));
+    assert_eq!(partial, parse("map f xs"));
+    assert_eq!(arms, parse("match n with | 0 -> f x | _ -> (g y)"));
+}
+
+/// Tests that `!` binds tighter than `&&` and `||` but looser than
+/// application, and that it nests.
+#[test]
+fn test_program_parsing_with_not() {
+    // Arrange
+    let identifier = |name: &str| Expression::Term(Term::Identifier(name.to_string()));
+    let not = |operand| Expression::Unary {
+        operator: UnaryOperator::Not,
+        operand: Box::new(operand),
+    };
+    let grouped = |expression| Expression::Term(Term::GroupedExpression(Box::new(expression)));
+    let parse = |source| {
+        Parser::new(Lexer::tokenize_str(source).unwrap())
+            .parse_program()
+            .unwrap()
+            .expression
+    };
+
+    // Act
+    let logic = parse("!a && b");
+    let application = parse("!f x");
+    let double = parse("!(!x)");
+    let repeated = parse("!!x");
+    let comparison = parse("!a != b");
+
+    // Assert
+    assert_eq!(
+        logic,
+        Expression::Logic {
+            left: Box::new(not(identifier("a"))),
+            operator: LogicOperator::And,
+            right: Some(Box::new(identifier("b"))),
+        }
+    );
+    assert_eq!(
+        application,
+        not(Expression::Application(vec![
+            identifier("f"),
+            identifier("x")
+        ]))
+    );
+    assert_eq!(double, not(grouped(not(identifier("x")))));
+    assert_eq!(repeated, not(not(identifier("x"))));
+    assert_eq!(
+        comparison,
+        Expression::Comparison {
+            left: Box::new(not(identifier("a"))),
+            operator: ComparisonOperator::NotEqual,
+            right: Some(Box::new(identifier("b"))),
+        }
+    );
+}
+
+/// Tests prefix `-`: it binds tighter than binary operators and looser than
+/// application, and is only prefix where an operand is expected, so `f -1`
+/// stays a subtraction.
+#[test]
+fn test_program_parsing_with_negation() {
+    // Arrange
+    let identifier = |name: &str| Expression::Term(Term::Identifier(name.to_string()));
+    let number = |value: f64| Expression::Term(Term::Number(value));
+    let negate = |operand| Expression::Unary {
+        operator: UnaryOperator::Negate,
+        operand: Box::new(operand),
+    };
+    let arithmetic = |left, operator, right| Expression::Arithmetic {
+        left: Box::new(left),
+        operator,
+        right: Box::new(right),
+    };
+    let grouped = |expression| Expression::Term(Term::GroupedExpression(Box::new(expression)));
+    let parse = |source| {
+        Parser::new(Lexer::tokenize_str(source).unwrap())
+            .parse_program()
+            .unwrap()
+            .expression
+    };
+
+    // Act
+    let literal = parse("-5");
+    let bound = parse("let x = -y in x");
+    let precedence = parse("-x * 2");
+    let subtraction = parse("f -1");
+    let argument = parse("f (-1)");
+    let double = parse("- -x");
+    let operand = parse("1 - -x");
+
+    // Assert
+    assert_eq!(literal, negate(number(5.0)));
+    assert_eq!(
+        bound,
+        Expression::LetExpr {
+            recursive: false,
+            identifier: "x".to_string(),
+            type_annotation: None,
+            value: Box::new(negate(identifier("y"))),
+            body: Box::new(identifier("x")),
+        }
+    );
+    assert_eq!(
+        precedence,
+        arithmetic(
+            negate(identifier("x")),
+            ArithmeticOperator::Multiply,
+            number(2.0)
+        )
+    );
+    assert_eq!(
+        subtraction,
+        arithmetic(identifier("f"), ArithmeticOperator::Subtract, number(1.0))
+    );
+    assert_eq!(
+        argument,
+        Expression::Application(vec![identifier("f"), grouped(negate(number(1.0)))])
+    );
+    assert_eq!(double, negate(negate(identifier("x"))));
+    assert_eq!(
+        operand,
+        arithmetic(
+            number(1.0),
+            ArithmeticOperator::Subtract,
+            negate(identifier("x"))
+        )
+    );
+}
+
+/// Tests that `--x` is a comment, not a double negation, so nothing is left
+/// to parse.
+#[test]
+fn test_double_dash_is_a_comment_not_negation() {
+    // Arrange
+    let tokens = Lexer::tokenize_str("--x").unwrap();
+
+    // Act
+    let result = Parser::new(tokens).parse_program();
+
+    // Assert
+    match result {
+        Err(ParseError::UnexpectedToken {
+            expected, found, ..
+        }) => assert_eq!(
+            (expected.as_str(), found.as_str()),
+            ("term", "end of input")
+        ),
+        other => panic!("expected an unexpected token, got {:?}", other),
+    }
+}
+
+/// Tests that `::` in a match arm parses as a right-associative cons pattern.
+#[test]
+fn test_program_parsing_with_cons_patterns() {
+    // Arrange
+    let name = |name: &str| Box::new(Pattern::Identifier(name.to_string()));
+    let source = "match xs with | x :: y :: rest -> x | (a :: _) :: _ -> a | _ -> 0";
+
+    // Act
+    let program = Parser::new(Lexer::tokenize_str(source).unwrap()).parse_program();
+
+    // Assert
+    let Ok(Program {
+        expression: Expression::PatternMatch { arms, .. },
+    }) = program
+    else {
+        panic!("expected a match, got {:?}", program);
+    };
+    let patterns: Vec<Pattern> = arms.into_iter().map(|arm| arm.pattern).collect();
+    assert_eq!(
+        patterns,
+        vec![
+            Pattern::Cons(name("x"), Box::new(Pattern::Cons(name("y"), name("rest")))),
+            Pattern::Cons(
+                Box::new(Pattern::Grouped(Box::new(Pattern::Cons(
+                    name("a"),
+                    name("_")
+                )))),
+                name("_")
+            ),
+            Pattern::Identifier("_".to_string()),
+        ]
+    );
+}
+
+/// Tests parsing of `!=`, binding looser than function application.
+#[test]
+fn test_program_parsing_with_not_equal() {
+    // Arrange
+    let identifier = |name: &str| Expression::Term(Term::Identifier(name.to_string()));
+
+    // Act
+    let simple = Parser::new(Lexer::tokenize_str("x != 42").unwrap()).parse_program();
+    let applied = Parser::new(Lexer::tokenize_str("f x != g y").unwrap()).parse_program();
+
+    // Assert
+    assert_eq!(
+        simple.unwrap().expression,
+        Expression::Comparison {
+            left: Box::new(identifier("x")),
+            operator: ComparisonOperator::NotEqual,
+            right: Some(Box::new(Expression::Term(Term::Number(42.0)))),
+        }
+    );
+    assert_eq!(
+        applied.unwrap().expression,
+        Expression::Comparison {
+            left: Box::new(Expression::Application(vec![
+                identifier("f"),
+                identifier("x")
+            ])),
+            operator: ComparisonOperator::NotEqual,
+            right: Some(Box::new(Expression::Application(vec![
+                identifier("g"),
+                identifier("y")
+            ]))),
+        }
+    );
+}
+
+/// Tests that errors from spanned tokens carry the line and column of the
+/// offending token in multi-line input.
+#[test]
+fn test_errors_carry_positions() {
+    // Arrange
+    let sources = [
+        ("let x = 1 in\nlet y = in\n  x", 2, 9),
+        ("match x with\n| 0 -> 1\n  | f y -> 2", 3, 5),
+        ("let x = 1 in\n  x )", 2, 5),
+    ];
+
+    for (source, line, column) in sources {
+        // Act
+        let tokens = Lexer::new(source).tokenize_spanned().unwrap();
+        let result = Parser::from_spanned(tokens).parse_program();
+
+        // Assert
+        match result {
+            Err(ParseError::UnexpectedToken { position, .. }) => {
+                assert_eq!(position, Some(Position { line, column }), "{}", source)
+            }
+            other => panic!(
+                "expected an unexpected token in {:?}, got {:?}",
+                source, other
+            ),
+        }
+    }
+}
+
+/// Tests that a name starting with a keyword can be bound and used.
+#[test]
+fn test_program_parsing_with_keyword_prefixed_name() {
+    // Arrange
+    let tokens = Lexer::tokenize_str("let letter = 1 in letter").unwrap();
+
+    // Act
+    let program = Parser::new(tokens).parse_program().unwrap();
+
+    // Assert
+    assert_eq!(
+        program.expression,
+        Expression::LetExpr {
+            recursive: false,
+            identifier: "letter".to_string(),
+            type_annotation: None,
+            value: Box::new(Expression::Term(Term::Number(1.0))),
+            body: Box::new(Expression::Term(Term::Identifier("letter".to_string()))),
+        }
+    );
+}
+
+/// Tests that a name with underscores and a prime can be bound and used.
+#[test]
+fn test_program_parsing_with_underscored_primed_name() {
+    // Arrange
+    let tokens = Lexer::tokenize_str("let my_list' = 1 in my_list'").unwrap();
+
+    // Act
+    let program = Parser::new(tokens).parse_program().unwrap();
+
+    // Assert
+    assert_eq!(
+        program.expression,
+        Expression::LetExpr {
+            recursive: false,
+            identifier: "my_list'".to_string(),
+            type_annotation: None,
+            value: Box::new(Expression::Term(Term::Number(1.0))),
+            body: Box::new(Expression::Term(Term::Identifier("my_list'".to_string()))),
+        }
+    );
+}
+
+/// Tests that a number with an exponent is a single operand.
 #[test]
-fn test_program_parsing_with_comparison() {
+fn test_number_with_exponent_operand() {
     // Arrange
-    let tokens = vec![
-        Token::Identifier("x".to_string()),
-        Token::Equal,
-        Token::Number(42.0),
-        Token::Eof,
-    ];
+    let tokens = Lexer::tokenize_str("\\x -> x * 1e3").unwrap();
 
     // Act
-    let mut parser = Parser::new(tokens);
-    let result = parser.parse_program();
+    let program = Parser::new(tokens).parse_program().unwrap();
 
     // Assert
-    assert!(result.is_ok());
-    let program = result.unwrap();
     assert_eq!(
-        program,
-        Program {
-            expression: Expression::Comparison {
+        program.expression,
+        Expression::Lambda {
+            parameter: "x".to_string(),
+            type_annotation: None,
+            body: Box::new(Expression::Arithmetic {
                 left: Box::new(Expression::Term(Term::Identifier("x".to_string()))),
-                operator: ComparisonOperator::Equal,
-                right: Some(Box::new(Expression::Term(Term::Number(42.0)))),
-            }
+                operator: ArithmeticOperator::Multiply,
+                right: Box::new(Expression::Term(Term::Number(1000.0))),
+            }),
         }
     );
 }
@@ -215,8 +1007,9 @@ fn test_empty_program() {
         result.unwrap_err(),
         ParseError::UnexpectedToken {
             expected: "term".to_string(),
-            found: "Eof".to_string(),
-            message: "Unexpected token while parsing a term.".to_string(),
+            found: "end of input".to_string(),
+            message: "Unexpected token while parsing a term".to_string(),
+            position: None,
         }
     );
 }
@@ -247,18 +1040,12 @@ fn tokenize_input(input: &str) -> Vec<Token> {
     lexer.tokenize().expect("Failed to tokenize input")
 }
 
-fn parse_input(input: &str) -> Program {
-    let tokens = tokenize_input(input);
-    let mut parser = Parser::new(tokens);
-    parser.parse_program().expect("Failed to parse input")
-}
-
 /// Tests parsing of a single identifier.
 #[test]
 fn test_parse_single_application() {
     // Arrange
     let input = "f x";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -277,7 +1064,7 @@ fn test_parse_single_application() {
 fn test_parse_multiple_applications() {
     // Arrange
     let input = "f x y";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -296,7 +1083,7 @@ fn test_parse_multiple_applications() {
 fn test_parse_application_with_nested_functions() {
     // Arrange
     let input = "f (g x) y";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -320,7 +1107,7 @@ fn test_parse_application_with_nested_functions() {
 fn test_parse_application_with_arithmetic() {
     // Arrange
     let input = "f x + y";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -342,7 +1129,7 @@ fn test_parse_application_with_arithmetic() {
 fn test_parse_application_with_lambda() {
     // Arrange
     let input = "f \\x -> x + 1";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -368,7 +1155,7 @@ fn test_parse_application_with_lambda() {
 fn test_parse_single_logical_and() {
     // Arrange
     let input = "a && b";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -389,7 +1176,7 @@ fn test_parse_single_logical_and() {
 fn test_parse_single_logical_or() {
     // Arrange
     let input = "a || b";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -410,7 +1197,7 @@ fn test_parse_single_logical_or() {
 fn test_parse_chained_logical_operators() {
     // Arrange
     let input = "a && b || c";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -433,11 +1220,55 @@ fn test_parse_chained_logical_operators() {
     assert_eq!(program, expected);
 }
 
+/// Tests that `&&` binds tighter than `||`, on either side of it.
+#[test]
+fn test_parse_and_binds_tighter_than_or() {
+    // Arrange
+    let name = |name: &str| Box::new(Expression::Term(Term::Identifier(name.to_string())));
+    let logic = |left, operator, right| {
+        Box::new(Expression::Logic {
+            left,
+            operator,
+            right: Some(right),
+        })
+    };
+    use LogicOperator::{And, Or};
+
+    for (input, expected) in [
+        (
+            "a && b || c && d",
+            logic(
+                logic(name("a"), And, name("b")),
+                Or,
+                logic(name("c"), And, name("d")),
+            ),
+        ),
+        (
+            "a || b && c",
+            logic(name("a"), Or, logic(name("b"), And, name("c"))),
+        ),
+        (
+            "a || b || c && d",
+            logic(
+                logic(name("a"), Or, name("b")),
+                Or,
+                logic(name("c"), And, name("d")),
+            ),
+        ),
+    ] {
+        // Act
+        let program = support::parse_program(input);
+
+        // Assert
+        assert_eq!(program.expression, *expected, "'{}'", input);
+    }
+}
+
 #[test]
 fn test_parse_logical_expression_with_arithmetic() {
     // Arrange
     let input = "a + b && c * d";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -464,7 +1295,7 @@ fn test_parse_logical_expression_with_arithmetic() {
 fn test_parse_nested_logical_expressions() {
     // Arrange
     let input = "a && (b || c)";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -491,7 +1322,7 @@ fn test_parse_nested_logical_expressions() {
 fn test_parse_logical_expression_with_function_application() {
     // Arrange
     let input = "f x && g y";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -516,7 +1347,7 @@ fn test_parse_logical_expression_with_function_application() {
 fn test_parse_complex_logical_expression() {
     // Arrange
     let input = "a + b && f x || c * d";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -554,7 +1385,7 @@ fn test_parse_member_access() {
     // This means we have a grouped expression (x + 1) followed by . foo
     // so it should produce Expression::Term(Term::MemberAccess{...})
     let input = "(x + 1 . foo)";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -579,7 +1410,7 @@ fn test_parse_function_composition() {
     // composition has precedence after comparison, so "f . g" is essentially
     // Expression::FunctionComposition { f, g }
     let input = "f . g";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -600,7 +1431,7 @@ fn test_parse_chained_function_composition() {
     // This should parse left-associative ( (f . g) . h ) unless
     // we specified otherwise.
     let input = "f . g . h";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     // left: (f . g)
@@ -627,7 +1458,7 @@ fn test_parse_composition_with_application() {
     // The parser sees '.' after f, then a comparison-level parse, which
     // proceeds down to parse_application for "g x".
     let input = "f . g x";
-    let program = parse_input(input);
+    let program = support::parse_program(input);
 
     // Act
     let expected = Program {
@@ -643,3 +1474,1009 @@ fn test_parse_composition_with_application() {
     // Assert
     assert_eq!(program, expected);
 }
+
+/// Tests that a `_` arm parses as the wildcard pattern.
+#[test]
+fn test_parse_wildcard_pattern() {
+    // Arrange
+    let input = "match x with | 0 -> a | _ -> b";
+    let program = support::parse_program(input);
+
+    // Act
+    let expected = Program {
+        expression: Expression::PatternMatch {
+            expression: Box::new(Expression::Term(Term::Identifier("x".to_string()))),
+            arms: vec![
+                MatchArm {
+                    pattern: Pattern::Number(0.0),
+                    guard: None,
+                    expression: Box::new(Expression::Term(Term::Identifier("a".to_string()))),
+                },
+                MatchArm {
+                    pattern: Pattern::Identifier("_".to_string()),
+                    guard: None,
+                    expression: Box::new(Expression::Term(Term::Identifier("b".to_string()))),
+                },
+            ],
+        },
+    };
+
+    // Assert
+    assert_eq!(program, expected);
+}
+
+/// Tests that an expression in pattern position is reported as such.
+#[test]
+fn test_expression_in_pattern_position() {
+    // Arrange
+    let tokens = tokenize_input("match x with | 0 -> a | f x -> b");
+
+    // Act
+    let result = Parser::new(tokens).parse_program();
+
+    // Assert
+    assert_eq!(
+        result,
+        Err(ParseError::UnexpectedToken {
+            expected: "pattern".to_string(),
+            found: "application".to_string(),
+            message: "Expressions are not allowed in pattern position".to_string(),
+            position: None,
+        })
+    );
+}
+
+/// Tests that an arm that is not followed by `->` keeps the arrow error.
+#[test]
+fn test_arm_without_arrow_keeps_arrow_error() {
+    // Arrange
+    let tokens = tokenize_input("match x with | a b");
+
+    // Act
+    let result = Parser::new(tokens).parse_program();
+
+    // Assert
+    assert_eq!(
+        result,
+        Err(ParseError::UnexpectedToken {
+            expected: "->".to_string(),
+            found: "b".to_string(),
+            message: "Expected '->' in match arm".to_string(),
+            position: None,
+        })
+    );
+}
+
+/// Tests that composition inside parentheses is a grouped composition.
+#[test]
+fn test_parse_grouped_composition() {
+    // Arrange
+    let input = "(f . g x)";
+    let program = support::parse_program(input);
+
+    // Act
+    let expected = Program {
+        expression: Expression::Term(Term::GroupedExpression(Box::new(
+            Expression::FunctionComposition(FunctionComposition {
+                f: Box::new(Expression::Term(Term::Identifier("f".to_string()))),
+                g: Box::new(Expression::Application(vec![
+                    Expression::Term(Term::Identifier("g".to_string())),
+                    Expression::Term(Term::Identifier("x".to_string())),
+                ])),
+            }),
+        ))),
+    };
+
+    // Assert
+    assert_eq!(program, expected);
+}
+
+/// Tests that tokens left over after a complete expression are an error
+/// rather than silently ignored.
+#[test]
+fn test_trailing_tokens_rejected() {
+    for input in ["x )", "f x in", "a ) < b"] {
+        // Arrange
+        let tokens = Lexer::tokenize_str(input).unwrap();
+
+        // Act
+        let result = Parser::new(tokens).parse_program();
+
+        // Assert
+        match result {
+            Err(ParseError::UnexpectedToken { expected, .. }) => {
+                assert_eq!(expected, "end of input", "input: {}", input)
+            }
+            other => panic!(
+                "expected a trailing-token error for {:?}, got {:?}",
+                input, other
+            ),
+        }
+    }
+}
+
+/// Tests that a comparison following another is rejected with an error
+/// naming both operators, while a parenthesized comparison may be compared.
+#[test]
+fn test_chained_comparisons_rejected() {
+    for (input, first, second, column) in
+        [("a < b < c", "<", "<", 7), ("a == b == c", "==", "==", 8)]
+    {
+        // Arrange
+        let tokens = Lexer::new(input).tokenize_spanned().unwrap();
+
+        // Act
+        let result = Parser::from_spanned(tokens).parse_program();
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(ParseError::ChainedComparison {
+                first: first.to_string(),
+                second: second.to_string(),
+                position: Some(Position { line: 1, column }),
+            }),
+            "'{}'",
+            input
+        );
+    }
+    assert!(matches!(
+        support::parse_program("(a < b) == c").expression,
+        Expression::Comparison {
+            operator: ComparisonOperator::Equal,
+            ..
+        }
+    ));
+}
+
+/// Tests that brackets and commas out of place are reported as unexpected
+/// tokens by name.
+#[test]
+fn test_brackets_and_commas_are_unexpected() {
+    // Arrange
+    let inputs = [
+        ("]", "term", "]"),
+        ("f 1, 2", "end of input", ","),
+        ("(x]", ")", "]"),
+    ];
+
+    for (input, expected_token, found_token) in inputs {
+        let tokens = Lexer::tokenize_str(input).unwrap();
+
+        // Act
+        let result = Parser::new(tokens).parse_program();
+
+        // Assert
+        match result {
+            Err(ParseError::UnexpectedToken {
+                expected, found, ..
+            }) => {
+                assert_eq!(expected, expected_token, "input: {}", input);
+                assert_eq!(found, found_token, "input: {}", input);
+            }
+            other => panic!(
+                "expected an unexpected token in {:?}, got {:?}",
+                input, other
+            ),
+        }
+    }
+}
+
+/// Tests that a program may end with `;;`, but nothing may follow it.
+#[test]
+fn test_program_with_trailing_double_semicolon() {
+    // Arrange
+    let parse = |source| Parser::new(Lexer::tokenize_str(source).unwrap()).parse_program();
+
+    // Act
+    let terminated = parse("x + 1 ;;");
+    let followed = parse("x ;; y");
+    let single = parse("x ;");
+
+    // Assert
+    assert_eq!(terminated, parse("x + 1"));
+    assert!(
+        matches!(&followed, Err(ParseError::UnexpectedToken { found, .. }) if found == "y"),
+        "{:?}",
+        followed
+    );
+    assert!(
+        matches!(&single, Err(ParseError::UnexpectedToken { found, .. }) if found == ";"),
+        "{:?}",
+        single
+    );
+}
+
+/// Tests that `with` bindings on a match arm parse to the same tree as the
+/// explicit `let` version, including where they meet the match's own `with`.
+#[test]
+fn test_arm_bindings_desugar_to_let() {
+    for (sugar, explicit) in [
+        (
+            "match p with | x with s = f x -> s + s",
+            "match p with | x -> let s = f x in s + s",
+        ),
+        (
+            "match p with | (x) with a: Int = f x with b = a * 2 -> a + b | _ -> 0",
+            "match p with | (x) -> let a: Int = f x in let b = a * 2 in a + b | _ -> 0",
+        ),
+        (
+            "match p with | x with s = match x with | 0 -> 1 -> s",
+            "match p with | x -> let s = match x with | 0 -> 1 in s",
+        ),
+        (
+            "match p with | x with h = \\y -> y + 1 -> h x",
+            "match p with | x -> let h = \\y -> y + 1 in h x",
+        ),
+        (
+            "match match q with | y with z = y -> z with | x with s = x -> s",
+            "match match q with | y -> let z = y in z with | x -> let s = x in s",
+        ),
+    ] {
+        // Arrange
+        let sugar_program = support::parse_program(sugar);
+
+        // Act
+        let explicit_program = support::parse_program(explicit);
+
+        // Assert
+        assert_eq!(sugar_program, explicit_program, "'{}'", sugar);
+    }
+}
+
+/// Tests that an arm binding without `=` reports the missing token.
+#[test]
+fn test_arm_binding_without_assign() {
+    // Arrange
+    let tokens = Lexer::tokenize_str("match p with | x with s -> s").unwrap();
+
+    // Act
+    let result = Parser::new(tokens).parse_program();
+
+    // Assert
+    assert_eq!(
+        result,
+        Err(ParseError::UnexpectedToken {
+            expected: "=".to_string(),
+            found: "->".to_string(),
+            message: "Expected '=' in match arm binding".to_string(),
+            position: None,
+        })
+    );
+}
+
+/// Tests that a guard is kept on its own arm, between the pattern and any
+/// `with` bindings, while the other arms of the same match have none.
+#[test]
+fn test_match_guards() {
+    // Arrange
+    let identifier = |name: &str| Box::new(Expression::Term(Term::Identifier(name.to_string())));
+    let input = "match x with | 0 -> zero | n when n > 10 with m = n -> m | _ when ok -> small";
+
+    // Act
+    let program = support::parse_program(input);
+
+    // Assert
+    assert_eq!(
+        program.expression,
+        Expression::PatternMatch {
+            expression: identifier("x"),
+            arms: vec![
+                MatchArm {
+                    pattern: Pattern::Number(0.0),
+                    guard: None,
+                    expression: identifier("zero"),
+                },
+                MatchArm {
+                    pattern: Pattern::Identifier("n".to_string()),
+                    guard: Some(Box::new(Expression::Comparison {
+                        left: identifier("n"),
+                        operator: ComparisonOperator::GreaterThan,
+                        right: Some(Box::new(Expression::Term(Term::Number(10.0)))),
+                    })),
+                    expression: Box::new(Expression::LetExpr {
+                        recursive: false,
+                        identifier: "m".to_string(),
+                        type_annotation: None,
+                        value: identifier("n"),
+                        body: identifier("m"),
+                    }),
+                },
+                MatchArm {
+                    pattern: Pattern::Identifier("_".to_string()),
+                    guard: Some(identifier("ok")),
+                    expression: identifier("small"),
+                },
+            ],
+        }
+    );
+}
+
+/// Tests that a `when` with no condition is reported as a missing guard,
+/// and that an expression before `when` is reported as not a pattern.
+#[test]
+fn test_match_guard_errors() {
+    for (input, expected, found, message, column) in [
+        (
+            "match x with | n when -> n",
+            "expression",
+            "->",
+            "Expected a guard condition after 'when'",
+            23,
+        ),
+        (
+            "match x with | n when",
+            "expression",
+            "end of input",
+            "Expected a guard condition after 'when'",
+            22,
+        ),
+        (
+            "match x with | f n when n > 0 -> n",
+            "pattern",
+            "application",
+            "Expressions are not allowed in pattern position",
+            16,
+        ),
+    ] {
+        // Arrange
+        let tokens = Lexer::new(input).tokenize_spanned().unwrap();
+
+        // Act
+        let result = Parser::from_spanned(tokens).parse_program();
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(ParseError::UnexpectedToken {
+                expected: expected.to_string(),
+                found: found.to_string(),
+                message: message.to_string(),
+                position: Some(Position { line: 1, column }),
+            }),
+            "'{}'",
+            input
+        );
+    }
+}
+
+/// Tests that `let rec` sets `recursive` on the binding, and only there,
+/// and otherwise parses to the same tree as a plain `let`.
+#[test]
+fn test_let_rec_sets_recursive() {
+    for (with_rec, without) in [
+        ("let rec f = \\n -> f n in f 1", "let f = \\n -> f n in f 1"),
+        (
+            "let rec go: (Int -> Int) = \\n -> go n in go",
+            "let go: (Int -> Int) = \\n -> go n in go",
+        ),
+        ("let rec rec = rec in rec", "let rec = rec in rec"),
+    ] {
+        // Arrange
+        let recursive = |program: &Program| match &program.expression {
+            Expression::LetExpr { recursive, .. } => *recursive,
+            other => panic!("expected a let, got {:?}", other),
+        };
+
+        // Act
+        let mut rec_program = support::parse_program(with_rec);
+        let plain_program = support::parse_program(without);
+
+        // Assert
+        assert!(recursive(&rec_program), "'{}'", with_rec);
+        assert!(!recursive(&plain_program), "'{}'", without);
+        if let Expression::LetExpr { recursive, .. } = &mut rec_program.expression {
+            *recursive = false;
+        }
+        assert_eq!(rec_program, plain_program, "'{}'", with_rec);
+    }
+}
+
+/// Tests that `let rec` followed by something other than a name, `:`, or
+/// `=` says a name is missing.
+#[test]
+fn test_let_rec_without_identifier() {
+    for (input, found_token) in [("let rec in x", "in"), ("let rec", "end of input")] {
+        // Arrange
+        let tokens = Lexer::tokenize_str(input).unwrap();
+
+        // Act
+        let result = Parser::new(tokens).parse_program();
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(ParseError::UnexpectedToken {
+                expected: "identifier".to_string(),
+                found: found_token.to_string(),
+                message: "Expected the name to bind after 'let rec'".to_string(),
+                position: None,
+            }),
+            "'{}'",
+            input
+        );
+    }
+}
+
+/// Parses `let f: <annotation> = f in f` and returns the annotation.
+fn parse_annotation(annotation: &str) -> Result<Option<TypeAnnotation>, ParseError> {
+    let source = format!("let f: {} = f in f", annotation);
+    let program = Parser::new(Lexer::tokenize_str(&source)?).parse_program()?;
+    match program.expression {
+        Expression::LetExpr {
+            type_annotation, ..
+        } => Ok(type_annotation),
+        other => panic!("expected a let, got {:?}", other),
+    }
+}
+
+/// Tests that function types group to the right without parentheses, and
+/// that parentheses only group.
+#[test]
+fn test_function_types_are_right_associative() {
+    // Arrange
+    let function = |from, to| TypeAnnotation::Function(Box::new(from), Box::new(to));
+    use TypeAnnotation::{Bool, Int};
+    let cases = [
+        ("Int -> Bool", function(Int, Bool)),
+        ("Int -> Int -> Int", function(Int, function(Int, Int))),
+        (
+            "Int -> Int -> Int -> Bool",
+            function(Int, function(Int, function(Int, Bool))),
+        ),
+        ("(Int -> Bool) -> Int", function(function(Int, Bool), Int)),
+        ("Int -> (Int -> Int)", function(Int, function(Int, Int))),
+        ("(Int -> (Int -> Int))", function(Int, function(Int, Int))),
+    ];
+
+    for (annotation, expected) in cases {
+        // Act
+        let parsed = parse_annotation(annotation);
+
+        // Assert
+        assert_eq!(parsed, Ok(Some(expected)), "'{}'", annotation);
+    }
+}
+
+/// Tests that nested parentheses in annotations only group, wherever they
+/// appear, and that a type name other than a built-in one is a named type.
+#[test]
+fn test_nested_parentheses_in_annotations() {
+    // Arrange
+    let function = |from, to| TypeAnnotation::Function(Box::new(from), Box::new(to));
+    use TypeAnnotation::{Bool, Float, Int, Named};
+    let cases = [
+        ("((Int))", Ok(Some(Int))),
+        ("((Int -> Bool))", Ok(Some(function(Int, Bool)))),
+        ("((Int) -> (Bool))", Ok(Some(function(Int, Bool)))),
+        (
+            "(((Int -> Bool)) -> Float) -> Int",
+            Ok(Some(function(function(function(Int, Bool), Float), Int))),
+        ),
+        (
+            "Int -> ((Bool -> Int))",
+            Ok(Some(function(Int, function(Bool, Int)))),
+        ),
+        (
+            "(Int -> Integer)",
+            Ok(Some(function(Int, Named("Integer".to_string())))),
+        ),
+    ];
+
+    for (annotation, expected) in cases {
+        // Act
+        let parsed = parse_annotation(annotation);
+
+        // Assert
+        assert_eq!(parsed, expected, "'{}'", annotation);
+    }
+}
+
+/// Tests that the `->` after a bare lambda annotation starts the body, so a
+/// function type there must be parenthesized.
+#[test]
+fn test_bare_lambda_annotation_stops_at_arrow() {
+    // Arrange
+    let function =
+        TypeAnnotation::Function(Box::new(TypeAnnotation::Int), Box::new(TypeAnnotation::Int));
+
+    // Act
+    let plain = support::parse_program("\\x: Int -> x");
+    let parenthesized = support::parse_program("\\f: (Int -> Int) -> f");
+
+    // Assert
+    assert!(matches!(
+        plain.expression,
+        Expression::Lambda {
+            type_annotation: Some(TypeAnnotation::Int),
+            ..
+        }
+    ));
+    assert!(matches!(
+        parenthesized.expression,
+        Expression::Lambda {
+            type_annotation: Some(annotation),
+            ..
+        } if annotation == function
+    ));
+}
+
+/// Tests that capitalized names in annotations are named types and
+/// lowercase ones are type variables, in bindings and lambdas alike.
+#[test]
+fn test_named_types_and_type_variables() {
+    // Arrange
+    let function = |from, to| TypeAnnotation::Function(Box::new(from), Box::new(to));
+    let var = |name: &str| TypeAnnotation::Var(name.to_string());
+    let named = |name: &str| TypeAnnotation::Named(name.to_string());
+
+    // Act
+    let identity = parse_annotation("a -> a");
+    let list = parse_annotation("List");
+    let mixed = parse_annotation("Maybe -> a' -> Int");
+    let lambda = support::parse_program("\\f: (a -> b) -> f");
+
+    // Assert
+    assert_eq!(identity, Ok(Some(function(var("a"), var("a")))));
+    assert_eq!(list, Ok(Some(named("List"))));
+    assert_eq!(
+        mixed,
+        Ok(Some(function(
+            named("Maybe"),
+            function(var("a'"), TypeAnnotation::Int)
+        )))
+    );
+    assert!(matches!(
+        lambda.expression,
+        Expression::Lambda {
+            type_annotation: Some(annotation),
+            ..
+        } if annotation == function(var("a"), var("b"))
+    ));
+}
+
+/// Tests list and tuple types, nested in each other and in function types,
+/// and that only a comma makes parentheses a tuple type.
+#[test]
+fn test_list_and_tuple_types() {
+    // Arrange
+    let function = |from, to| TypeAnnotation::Function(Box::new(from), Box::new(to));
+    let list = |element| TypeAnnotation::List(Box::new(element));
+    use TypeAnnotation::{Bool, Int, Tuple};
+    let cases = [
+        ("[Int]", list(Int)),
+        ("[(Int, Bool)]", list(Tuple(vec![Int, Bool]))),
+        ("[[Int]]", list(list(Int))),
+        ("(Int, Bool)", Tuple(vec![Int, Bool])),
+        ("(Int, [Bool], Int)", Tuple(vec![Int, list(Bool), Int])),
+        ("(Int -> Bool)", function(Int, Bool)),
+        ("(Int)", Int),
+        ("((Int, Bool))", Tuple(vec![Int, Bool])),
+        ("(Int -> Bool, Int)", Tuple(vec![function(Int, Bool), Int])),
+        (
+            "[Int -> Bool] -> Int",
+            function(list(function(Int, Bool)), Int),
+        ),
+    ];
+
+    for (annotation, expected) in cases {
+        // Act
+        let parsed = parse_annotation(annotation);
+
+        // Assert
+        assert_eq!(parsed, Ok(Some(expected)), "'{}'", annotation);
+    }
+}
+
+/// Tests that a list type needs its `]`, and a tuple type its `)` and at
+/// least two elements.
+#[test]
+fn test_list_and_tuple_type_errors() {
+    for (annotation, expected, found, message) in [
+        ("[Int", "]", "=", "Expected '->' or ']' after type"),
+        ("[]", "type annotation", "]", "Expected a type annotation"),
+        (
+            "(Int, Bool",
+            ")",
+            "=",
+            "Expected '->', ',' or ')' after type",
+        ),
+        (
+            "(Int,)",
+            "type annotation",
+            ")",
+            "A tuple has at least two elements; remove the ',' or add another element",
+        ),
+    ] {
+        // Act
+        let parsed = parse_annotation(annotation);
+
+        // Assert
+        assert_eq!(
+            parsed,
+            Err(ParseError::UnexpectedToken {
+                expected: expected.to_string(),
+                found: found.to_string(),
+                message: message.to_string(),
+                position: None,
+            }),
+            "'{}'",
+            annotation
+        );
+    }
+}
+
+/// Tests that `let ... and ...` parses to one group holding every binding,
+/// in source order, while a single binding stays a plain `let`.
+#[test]
+fn test_let_and_groups_bindings() {
+    // Arrange
+    let binding = |identifier: &str, type_annotation, value: &str| LetBinding {
+        identifier: identifier.to_string(),
+        type_annotation,
+        value: Box::new(Expression::Term(Term::Identifier(value.to_string()))),
+    };
+    let cases = [
+        (
+            "let a = x and b = y in a",
+            false,
+            "a",
+            vec![binding("a", None, "x"), binding("b", None, "y")],
+        ),
+        (
+            "let rec even: Int = odd and odd = even and z: Bool = z in z",
+            true,
+            "z",
+            vec![
+                binding("even", Some(TypeAnnotation::Int), "odd"),
+                binding("odd", None, "even"),
+                binding("z", Some(TypeAnnotation::Bool), "z"),
+            ],
+        ),
+    ];
+
+    for (input, recursive, body, bindings) in cases {
+        // Act
+        let program = support::parse_program(input);
+
+        // Assert
+        let body = Box::new(Expression::Term(Term::Identifier(body.to_string())));
+        assert_eq!(
+            program.expression,
+            Expression::LetAnd {
+                recursive,
+                bindings,
+                body,
+            },
+            "'{}'",
+            input
+        );
+    }
+    assert!(matches!(
+        support::parse_program("let a = x in a").expression,
+        Expression::LetExpr { .. }
+    ));
+}
+
+/// Tests that a group still needs its `in`, and that every `and` is
+/// followed by a binding.
+#[test]
+fn test_let_and_errors() {
+    for (input, expected, found) in [
+        ("let a = 1 and b = 2", "in", "end of input"),
+        ("let a = 1 and in a", "identifier", "in"),
+    ] {
+        // Arrange
+        let tokens = Lexer::tokenize_str(input).unwrap();
+
+        // Act
+        let result = Parser::new(tokens).parse_program();
+
+        // Assert
+        match result {
+            Err(ParseError::UnexpectedToken {
+                expected: e,
+                found: f,
+                ..
+            }) => assert_eq!((e.as_str(), f.as_str()), (expected, found), "'{}'", input),
+            other => panic!("'{}': expected an error, got {:?}", input, other),
+        }
+    }
+}
+
+/// Tests tuples of two and three elements, nested tuples, and a tuple
+/// holding a lambda, and that a single parenthesized expression still only
+/// groups.
+#[test]
+fn test_tuples() {
+    // Arrange
+    let name = |name: &str| Expression::Term(Term::Identifier(name.to_string()));
+    let number = |value: f64| Expression::Term(Term::Number(value));
+    let tuple = |elements: Vec<Expression>| Expression::Term(Term::Tuple(elements));
+    let cases = [
+        ("(a, b)", tuple(vec![name("a"), name("b")])),
+        (
+            "(1, x + 1, f x)",
+            tuple(vec![
+                number(1.0),
+                Expression::Arithmetic {
+                    left: Box::new(name("x")),
+                    operator: ArithmeticOperator::Add,
+                    right: Box::new(number(1.0)),
+                },
+                Expression::Application(vec![name("f"), name("x")]),
+            ]),
+        ),
+        (
+            "((a, b), (c, d))",
+            tuple(vec![
+                tuple(vec![name("a"), name("b")]),
+                tuple(vec![name("c"), name("d")]),
+            ]),
+        ),
+        (
+            "(\\x -> x, 0)",
+            tuple(vec![
+                Expression::Lambda {
+                    parameter: "x".to_string(),
+                    type_annotation: None,
+                    body: Box::new(name("x")),
+                },
+                number(0.0),
+            ]),
+        ),
+        (
+            "(a)",
+            Expression::Term(Term::GroupedExpression(Box::new(name("a")))),
+        ),
+    ];
+
+    for (input, expected) in cases {
+        // Act
+        let program = support::parse_program(input);
+
+        // Assert
+        assert_eq!(program.expression, expected, "'{}'", input);
+    }
+}
+
+/// Tests list literals: empty, with one element, and with arbitrary
+/// expressions as elements, including nested lists and applications.
+#[test]
+fn test_list_literals() {
+    // Arrange
+    let name = |name: &str| Expression::Term(Term::Identifier(name.to_string()));
+    let number = |value: f64| Expression::Term(Term::Number(value));
+    let list = |elements: Vec<Expression>| Expression::Term(Term::List(elements));
+    let cases = [
+        ("[]", list(vec![])),
+        ("[1]", list(vec![number(1.0)])),
+        (
+            "[1, 2 + 3, f x]",
+            list(vec![
+                number(1.0),
+                Expression::Arithmetic {
+                    left: Box::new(number(2.0)),
+                    operator: ArithmeticOperator::Add,
+                    right: Box::new(number(3.0)),
+                },
+                Expression::Application(vec![name("f"), name("x")]),
+            ]),
+        ),
+        (
+            "f [[a], []]",
+            Expression::Application(vec![
+                name("f"),
+                list(vec![list(vec![name("a")]), list(vec![])]),
+            ]),
+        ),
+    ];
+
+    for (input, expected) in cases {
+        // Act
+        let program = support::parse_program(input);
+
+        // Assert
+        assert_eq!(program.expression, expected, "'{}'", input);
+    }
+}
+
+/// Tests that a list missing its `]` is reported at the opening bracket.
+#[test]
+fn test_list_errors() {
+    for (input, expected, found, message, column) in [
+        (
+            "x + [1, 2",
+            "]",
+            "end of input",
+            "Expected ']' after list elements",
+            5,
+        ),
+        ("[a ; b]", "]", ";", "Expected ']' after list elements", 1),
+    ] {
+        // Arrange
+        let tokens = Lexer::new(input).tokenize_spanned().unwrap();
+
+        // Act
+        let result = Parser::from_spanned(tokens).parse_program();
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(ParseError::UnexpectedToken {
+                expected: expected.to_string(),
+                found: found.to_string(),
+                message: message.to_string(),
+                position: Some(Position { line: 1, column }),
+            }),
+            "'{}'",
+            input
+        );
+    }
+}
+
+/// Tests that tuples, lists, and tuple types all allow a single trailing
+/// comma.
+#[test]
+fn test_trailing_comma_in_every_comma_separated_form() {
+    for (input, expected) in [
+        ("(a, b,)", "(a, b)"),
+        ("[a, b,]", "[a, b]"),
+        ("[a,]", "[a]"),
+        (
+            "let f: (Int, Bool,) = f in f",
+            "let f: (Int, Bool) = f in f",
+        ),
+    ] {
+        // Act
+        let program = support::parse_program(input);
+
+        // Assert
+        assert_eq!(program, support::parse_program(expected), "'{}'", input);
+    }
+}
+
+/// Tests that a missing element between commas is reported at the comma
+/// where it should start, in every comma-separated form.
+#[test]
+fn test_doubled_comma_in_every_comma_separated_form() {
+    for (input, column) in [
+        ("(a,, b)", 4),
+        ("(a, b,,)", 7),
+        ("[1,, 2]", 4),
+        ("[,]", 2),
+        ("let f: (Int,, Bool) = f in f", 13),
+    ] {
+        // Arrange
+        let tokens = Lexer::new(input).tokenize_spanned().unwrap();
+
+        // Act
+        let result = Parser::from_spanned(tokens).parse_program();
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(ParseError::UnexpectedToken {
+                expected: "element".to_string(),
+                found: ",".to_string(),
+                message: "Expected an element before ','; remove the extra ','".to_string(),
+                position: Some(Position { line: 1, column }),
+            }),
+            "'{}'",
+            input
+        );
+    }
+}
+
+/// Tests that a single element with a trailing comma is not a tuple.
+#[test]
+fn test_tuple_of_one_is_rejected() {
+    // Arrange
+    let tokens = Lexer::new("(a,)").tokenize_spanned().unwrap();
+
+    // Act
+    let result = Parser::from_spanned(tokens).parse_program();
+
+    // Assert
+    assert_eq!(
+        result,
+        Err(ParseError::UnexpectedToken {
+            expected: "expression".to_string(),
+            found: ")".to_string(),
+            message: "A tuple has at least two elements; remove the ',' or add another element"
+                .to_string(),
+            position: Some(Position { line: 1, column: 4 }),
+        })
+    );
+}
+
+/// Tests that `rec` is still an ordinary name wherever `let rec` can't
+/// occur.
+#[test]
+fn test_rec_remains_an_identifier() {
+    for source in [
+        "let rec = 5 in rec",
+        "let rec : Int = 5 in rec + 1",
+        "\\rec -> rec rec",
+        "match rec with | rec -> rec",
+        "match p with | x with rec = x -> rec",
+    ] {
+        // Arrange
+        let tokens = tokenize_input(source);
+
+        // Act
+        let result = Parser::new(tokens).parse_program();
+
+        // Assert
+        let program = result.unwrap_or_else(|err| panic!("'{}': {}", source, err));
+        let mut uses_rec = false;
+        program.expression.walk(&mut |node| {
+            uses_rec |= *node == Expression::Term(Term::Identifier("rec".to_string()));
+        });
+        assert!(uses_rec, "'{}' should use the name rec", source);
+    }
+}
+
+/// Tests that `and` ends a let binding's value rather than being applied
+/// to, so the value before it can be an application.
+#[test]
+fn test_and_ends_a_binding_value() {
+    // Arrange
+    let identifier = |name: &str| Expression::Term(Term::Identifier(name.to_string()));
+
+    // Act
+    let program = support::parse_program("let a = f x and b = y in a");
+
+    // Assert
+    assert_eq!(
+        program.expression,
+        Expression::LetAnd {
+            recursive: false,
+            bindings: vec![
+                LetBinding {
+                    identifier: "a".to_string(),
+                    type_annotation: None,
+                    value: Box::new(Expression::Application(vec![
+                        identifier("f"),
+                        identifier("x"),
+                    ])),
+                },
+                LetBinding {
+                    identifier: "b".to_string(),
+                    type_annotation: None,
+                    value: Box::new(identifier("y")),
+                },
+            ],
+            body: Box::new(identifier("a")),
+        }
+    );
+}
+
+/// Tests that `and` and `when` are ordinary names wherever they don't end a
+/// let binding's value or an arm's pattern, including inside brackets there.
+#[test]
+fn test_and_and_when_remain_identifiers() {
+    for (source, name) in [
+        ("let and = 5 in and", "and"),
+        ("f and", "and"),
+        ("let a = (f and) in a", "and"),
+        ("let a = [and] and b = 1 in a", "and"),
+        ("\\when -> when when", "when"),
+        ("match when with | when -> f when", "when"),
+        ("match x with | n when (p when) -> n", "when"),
+    ] {
+        // Arrange
+        let tokens = tokenize_input(source);
+
+        // Act
+        let result = Parser::new(tokens).parse_program();
+
+        // Assert
+        let program = result.unwrap_or_else(|err| panic!("'{}': {}", source, err));
+        let mut uses_name = false;
+        program.expression.walk(&mut |node| {
+            uses_name |= *node == Expression::Term(Term::Identifier(name.to_string()));
+        });
+        assert!(uses_name, "'{}' should use the name {}", source, name);
+    }
+}
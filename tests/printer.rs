@@ -0,0 +1,230 @@
+//! tests/printer.rs
+
+mod support;
+
+use std::{fs, path::Path};
+
+use rdp::{print_expression, print_styled, print_type, style_named, TypeAnnotation};
+
+/// The names of every style.
+const STYLES: [&str; 3] = ["compact", "expanded", "teaching"];
+
+/// Compares `actual` with the golden file `tests/printer/<name>`, rewriting
+/// the file instead when `RDP_BLESS` is set.
+fn assert_golden(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/printer")
+        .join(name);
+    if std::env::var_os("RDP_BLESS").is_some() {
+        fs::write(&path, actual).unwrap();
+    }
+    let expected = fs::read_to_string(&path).expect("Missing golden file");
+    assert_eq!(actual, expected, "{}", name);
+}
+
+/// Tests that operands and arguments that aren't atomic are parenthesized.
+#[test]
+fn test_print_parenthesizes_compound_operands() {
+    for (input, printed) in [
+        ("f x + y * 2", "(f x) + (y * 2)"),
+        ("let x: Int = 1 in \\y -> x", "let x: Int = 1 in \\y -> x"),
+        (
+            "match f x with | (0) -> a | _ -> b c",
+            "match (f x) with | (0) -> a | _ -> (b c)",
+        ),
+        ("(r . name) . g", "(r . name) . g"),
+        ("f (\\x -> x) 2.5", "f (\\x -> x) 2.5"),
+        ("!f x && !!y", "(!(f x)) && (!(!y))"),
+        ("- -x * f (-1)", "(-(-x)) * (f (-1))"),
+        (
+            "match x with | n when n > 0 -> n | _ when ok -> 0",
+            "match x with | n when (n > 0) -> n | _ when ok -> 0",
+        ),
+    ] {
+        // Arrange
+        let expression = support::parse(input).expect("Failed to parse input");
+
+        // Act
+        let result = print_expression(&expression);
+
+        // Assert
+        assert_eq!(result, printed, "'{}'", input);
+    }
+}
+
+/// Tests that `let`s at the top of a match arm print as arm bindings.
+#[test]
+fn test_print_arm_bindings() {
+    // Arrange
+    let input = "match p with | x -> let s: Int = f x in let t = s in s + t | _ -> let u = 1 in u";
+    let expression = support::parse(input).expect("Failed to parse input");
+
+    // Act
+    let result = print_expression(&expression);
+
+    // Assert
+    assert_eq!(
+        result,
+        "match p with | x with s: Int = (f x) with t = s -> (s + t) | _ with u = 1 -> u"
+    );
+    assert_eq!(support::reprints(input), Ok(()));
+}
+
+/// Tests that strings are printed with their special characters escaped, on
+/// one line, and read back to the same value.
+#[test]
+fn test_print_escapes_strings() {
+    // Arrange
+    let input = "f \"say \\\"hi\\\"\nbye\\\\\" \"\\u{1F600}\\u{7}\"";
+
+    // Act
+    let result = print_expression(&support::parse(input).unwrap());
+
+    // Assert
+    assert_eq!(result, r#"f "say \"hi\"\nbye\\" "😀\u{7}""#);
+    assert_eq!(support::reprints(input), Ok(()));
+}
+
+/// Tests printing of nested function types.
+#[test]
+fn test_print_function_type() {
+    // Arrange
+    let annotation = TypeAnnotation::Function(
+        Box::new(TypeAnnotation::Int),
+        Box::new(TypeAnnotation::Function(
+            Box::new(TypeAnnotation::Float),
+            Box::new(TypeAnnotation::Bool),
+        )),
+    );
+
+    // Act
+    let result = print_type(&annotation);
+
+    // Assert
+    assert_eq!(result, "(Int -> (Float -> Bool))");
+}
+
+/// Tests that named types and type variables print, and compare, by name.
+#[test]
+fn test_print_named_types_and_type_variables() {
+    // Arrange
+    let annotation = TypeAnnotation::Function(
+        Box::new(TypeAnnotation::Named("List".to_string())),
+        Box::new(TypeAnnotation::Var("a".to_string())),
+    );
+
+    // Act
+    let printed = print_type(&annotation);
+    let displayed = annotation.to_string();
+    let debugged = format!("{:?}", annotation);
+
+    // Assert
+    assert_eq!(printed, "(List -> a)");
+    assert_eq!(displayed, printed);
+    assert_eq!(debugged, r#"Function(Named("List"), Var("a"))"#);
+    assert_eq!(
+        TypeAnnotation::Named("a".to_string()),
+        TypeAnnotation::Named("a".to_string())
+    );
+    assert_ne!(
+        TypeAnnotation::Named("a".to_string()),
+        TypeAnnotation::Var("a".to_string())
+    );
+}
+
+/// Tests printing of list and tuple types.
+#[test]
+fn test_print_list_and_tuple_types() {
+    // Arrange
+    let annotation = TypeAnnotation::List(Box::new(TypeAnnotation::Tuple(vec![
+        TypeAnnotation::Int,
+        TypeAnnotation::Function(
+            Box::new(TypeAnnotation::Bool),
+            Box::new(TypeAnnotation::Int),
+        ),
+    ])));
+
+    // Act
+    let result = print_type(&annotation);
+
+    // Assert
+    assert_eq!(result, "[(Int, (Bool -> Int))]");
+}
+
+/// Tests that every example program prints back to the same tree.
+#[test]
+fn test_examples_reprint() {
+    for entry in fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/examples")).unwrap() {
+        // Arrange
+        let path = entry.unwrap().path();
+        let source = fs::read_to_string(&path).unwrap();
+
+        // Act
+        let result = support::reprints(&source);
+
+        // Assert
+        assert_eq!(result, Ok(()), "{}", path.display());
+    }
+}
+
+/// Tests the layout of each style for a program with a long application and
+/// a wide match.
+#[test]
+fn test_print_styles() {
+    // Arrange
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/printer/styles.pfl");
+    let expression = support::parse(&fs::read_to_string(path).unwrap()).unwrap();
+
+    for name in STYLES {
+        // Act
+        let printed = print_styled(&expression, style_named(name).unwrap().as_ref());
+
+        // Assert
+        assert_golden(&format!("styles.{}.pfl", name), &format!("{}\n", printed));
+        assert!(
+            printed.lines().all(|line| line == line.trim_end()),
+            "{}",
+            name
+        );
+    }
+}
+
+/// Tests that the compact style is the single-line printer.
+#[test]
+fn test_compact_style_is_single_line() {
+    // Arrange
+    let expression =
+        support::parse("let f = \\x -> match x with | 0 -> a | _ -> b in f 1").unwrap();
+
+    // Act
+    let printed = print_styled(&expression, style_named("compact").unwrap().as_ref());
+
+    // Assert
+    assert_eq!(printed, print_expression(&expression));
+}
+
+/// Tests that every example program prints back to the same tree in every
+/// style, annotations included.
+#[test]
+fn test_examples_reprint_in_every_style() {
+    for entry in fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/examples")).unwrap() {
+        // Arrange
+        let path = entry.unwrap().path();
+        let expression = support::parse(&fs::read_to_string(&path).unwrap()).unwrap();
+
+        for name in STYLES {
+            // Act
+            let printed = print_styled(&expression, style_named(name).unwrap().as_ref());
+            let reparsed = support::parse(&printed);
+
+            // Assert
+            assert_eq!(
+                reparsed.map(|reparsed| support::ungroup(&reparsed)),
+                Ok(support::ungroup(&expression)),
+                "{} in the {} style",
+                path.display(),
+                name
+            );
+        }
+    }
+}
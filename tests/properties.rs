@@ -0,0 +1,78 @@
+//! tests/properties.rs
+
+mod support;
+
+use rdp::testrand;
+
+/// Number of generated cases per property and run.
+const CASES: u64 = 500;
+
+/// Tests that every generated program parses.
+#[test]
+fn test_generated_programs_parse() {
+    testrand::check(testrand::seed(), CASES, testrand::program, support::parses);
+}
+
+/// Tests that arbitrary input never makes the lexer or parser panic.
+#[test]
+fn test_noise_fails_gracefully() {
+    testrand::check(
+        testrand::seed(),
+        CASES,
+        testrand::noise,
+        support::fails_gracefully,
+    );
+}
+
+/// Tests that printing a generated program gives source for the same tree.
+#[test]
+fn test_generated_programs_reprint() {
+    testrand::check(
+        testrand::seed(),
+        CASES,
+        testrand::program,
+        support::reprints,
+    );
+}
+
+/// Tests that laying out a generated program in any style gives source for
+/// the same tree.
+#[test]
+fn test_generated_programs_reprint_in_every_style() {
+    testrand::check(
+        testrand::seed(),
+        CASES,
+        testrand::program,
+        support::reprints_in_every_style,
+    );
+}
+
+/// Tests that lossy lexing covers any input with no gaps or overlaps.
+#[test]
+fn test_lossy_tokens_cover_input() {
+    for generate in [testrand::noise, testrand::bytes] {
+        testrand::check(testrand::seed(), CASES, generate, support::covers_input);
+    }
+}
+
+/// Tests that every expression of a generated program spans its own source.
+#[test]
+fn test_spans_reparse() {
+    testrand::check(
+        testrand::seed(),
+        CASES,
+        testrand::program,
+        support::spans_reparse,
+    );
+}
+
+/// Tests that slicing a generated program at any node is self-contained.
+#[test]
+fn test_slices_are_closed() {
+    testrand::check(
+        testrand::seed(),
+        CASES,
+        testrand::program,
+        support::slices_are_closed,
+    );
+}
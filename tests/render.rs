@@ -0,0 +1,194 @@
+//! tests/render.rs
+
+use rdp::{Diagnostic, Lexer, LexerOptions, LineIndex, ParseError};
+
+/// Tests that an error with a position renders its location, the offending
+/// line, and a caret under the column.
+#[test]
+fn test_render_error_with_position() {
+    // Arrange
+    let source = "let x = 1 in\nlet longname = 2 in x";
+    let options = LexerOptions {
+        max_identifier_length: Some(4),
+        ..LexerOptions::default()
+    };
+    let err = Lexer::with_options(source, options).tokenize().unwrap_err();
+    let index = LineIndex::new(source);
+
+    // Act
+    let mut out = String::new();
+    err.write_rendered(source, &index, &mut out).unwrap();
+
+    // Assert
+    assert_eq!(
+        out,
+        "error[E0008]: Identifier starting at offset 17 exceeds the maximum length of 4 characters.\n\
+         \x20--> 2:9\n\
+         \x20 |\n\
+         2 | let longname = 2 in x\n\
+         \x20 |         ^\n"
+    );
+}
+
+/// Tests that an error without a position renders as its header line, which
+/// matches `Display`.
+#[test]
+fn test_render_error_without_position() {
+    // Arrange
+    let err = ParseError::MissingPatternMatchArm;
+    let index = LineIndex::new("match x with");
+
+    // Act
+    let mut out = String::new();
+    err.write_rendered("match x with", &index, &mut out)
+        .unwrap();
+
+    // Assert
+    assert_eq!(out, format!("error[E0006]: {}\n", err));
+}
+
+/// Tests that one index serves many errors, and that every rendering agrees
+/// with rendering the errors one at a time.
+#[test]
+fn test_render_many_errors_with_one_index() {
+    // Arrange
+    let source = "x\n".repeat(10_000);
+    let index = LineIndex::new(&source);
+    let errors: Vec<ParseError> = (0..10_000)
+        .map(|line| ParseError::LineTooLong {
+            line: line + 1,
+            limit: 0,
+        })
+        .collect();
+
+    // Act
+    let mut out = String::new();
+    for err in &errors {
+        err.write_rendered(&source, &index, &mut out).unwrap();
+    }
+
+    // Assert
+    let mut last = String::new();
+    errors[9_999]
+        .write_rendered(&source, &LineIndex::new(&source), &mut last)
+        .unwrap();
+    assert!(out.ends_with(&last));
+    assert!(last.contains("10000 | x\n"));
+}
+
+/// Tests that rendering an error on a huge line or in a huge identifier
+/// stays small, however much input there is.
+#[test]
+fn test_render_huge_lines_stay_small() {
+    // Arrange
+    let long_line = format!("let x = 1 in\n{}\n", "x + ".repeat(250_000) + "x");
+    let long_identifier = format!("let {} = 1 in x", "a".repeat(100_000));
+    let cases = [
+        (
+            long_line,
+            LexerOptions {
+                max_line_length: Some(100),
+                ..LexerOptions::default()
+            },
+        ),
+        (
+            long_identifier,
+            LexerOptions {
+                max_identifier_length: Some(255),
+                ..LexerOptions::default()
+            },
+        ),
+    ];
+
+    for (source, options) in cases {
+        let err = Lexer::with_options(&source, options)
+            .tokenize()
+            .unwrap_err();
+        let index = LineIndex::new(&source);
+
+        // Act
+        let mut out = String::new();
+        err.write_rendered(&source, &index, &mut out).unwrap();
+
+        // Assert
+        assert!(out.len() < 512, "{} bytes: {}", out.len(), out);
+        assert!(out.contains('…'), "{}", out);
+    }
+}
+
+/// Tests that an identifier too long is shown up to its first character past
+/// the limit, where the caret points, rather than in full.
+#[test]
+fn test_render_identifier_too_long_points_past_limit() {
+    // Arrange
+    let source = format!("let {} = 1 in x", "a".repeat(100_000));
+    let options = LexerOptions {
+        max_identifier_length: Some(255),
+        ..LexerOptions::default()
+    };
+    let err = Lexer::with_options(&source, options)
+        .tokenize()
+        .unwrap_err();
+    let index = LineIndex::new(&source);
+
+    // Act
+    let mut out = String::new();
+    err.write_rendered(&source, &index, &mut out).unwrap();
+
+    // Assert
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[1], " --> 1:260");
+    let shown = lines[3].strip_prefix("1 | ").unwrap();
+    let caret = lines[4].strip_prefix("  | ").unwrap();
+    assert_eq!(shown, format!("…{}…", "a".repeat(80)));
+    assert_eq!(caret.chars().count(), 42);
+}
+
+/// Tests that a long line is cut to a window around the column, with the
+/// caret moved to stay under the same character.
+#[test]
+fn test_render_cuts_long_line_around_caret() {
+    // Arrange
+    let source = format!("{}@{}", "a".repeat(200), "b".repeat(200));
+    let err = ParseError::InvalidEscape {
+        escape: "@".to_string(),
+        offset: 200,
+    };
+    let index = LineIndex::new(&source);
+
+    // Act
+    let mut out = String::new();
+    err.write_rendered(&source, &index, &mut out).unwrap();
+
+    // Assert
+    let lines: Vec<&str> = out.lines().collect();
+    let shown = lines[3].strip_prefix("1 | ").unwrap();
+    let caret = lines[4].strip_prefix("  | ").unwrap();
+    assert!(
+        shown.starts_with("…a") && shown.ends_with("b…"),
+        "{}",
+        shown
+    );
+    assert_eq!(shown.chars().count(), 82);
+    assert_eq!(
+        shown.chars().nth(caret.chars().count() - 1),
+        Some('@'),
+        "{}",
+        out
+    );
+}
+
+/// Tests that a diagnostic writes the same text as its `Display` impl.
+#[test]
+fn test_render_diagnostic() {
+    // Arrange
+    let diagnostic = Diagnostic::warning("non-exhaustive-match", "no catch-all arm");
+
+    // Act
+    let mut out = String::new();
+    diagnostic.write_rendered(&mut out).unwrap();
+
+    // Assert
+    assert_eq!(out, "warning[non-exhaustive-match]: no catch-all arm");
+    assert_eq!(out, diagnostic.to_string());
+}
@@ -0,0 +1,36 @@
+//! tests/repro.rs
+//!
+//! Seeds of property test failures, kept as permanent regression cases. When
+//! a property in `tests/properties.rs` fails, it prints the seed of the
+//! failing case; add a `repro!` line with that seed here once it's fixed.
+
+mod support;
+
+use rdp::testrand;
+
+/// Replays a single generated case: `repro!(name, generator, property, seed)`.
+macro_rules! repro {
+    ($name:ident, $generator:path, $property:path, $seed:expr) => {
+        #[test]
+        fn $name() {
+            testrand::check($seed, 1, $generator, $property);
+        }
+    };
+}
+
+// A `match` on a `match`, with `match` expressions inside its arms.
+repro!(nested_match, testrand::program, support::parses, 11);
+// Keywords glued to identifiers and operators, e.g. `&&elsebbaif_`.
+repro!(
+    glued_keywords,
+    testrand::noise,
+    support::fails_gracefully,
+    9
+);
+// Composition inside parentheses: `((f) . (g))` used to be rejected.
+repro!(
+    composition_inside_parentheses,
+    testrand::program,
+    support::parses,
+    1792170599022349903
+);
@@ -0,0 +1,57 @@
+//! tests/sarif.rs
+
+use std::{fs, path::Path};
+
+use rdp::{diagnostics_to_sarif, lint, Diagnostic, Lexer, Parser, SarifLog};
+
+fn diagnose(input: &str) -> Vec<Diagnostic> {
+    match Lexer::tokenize_str(input).and_then(|tokens| Parser::new(tokens).parse_program()) {
+        Ok(program) => lint(&program),
+        Err(err) => vec![Diagnostic::from(&err)],
+    }
+}
+
+/// Compares `actual` with the golden file `tests/sarif/<name>`, rewriting the
+/// file instead when `RDP_BLESS` is set.
+fn assert_golden(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/sarif")
+        .join(name);
+    if std::env::var_os("RDP_BLESS").is_some() {
+        fs::write(&path, actual).unwrap();
+    }
+    let expected = fs::read_to_string(&path).expect("Missing golden file");
+    assert_eq!(actual, expected);
+}
+
+/// Tests a log aggregating one error from each of two files.
+#[test]
+fn test_sarif_two_errors_two_files() {
+    // Arrange
+    let mut log = SarifLog::new();
+
+    // Act
+    log.add_file(Path::new("src/lexing.pfl"), &diagnose("let x = @"));
+    log.add_file(Path::new("src/parsing.pfl"), &diagnose("let x = 1 in"));
+    let json = log.to_json();
+
+    // Assert
+    assert_golden("two-errors-two-files.sarif", &json);
+}
+
+/// Tests that rules are shared between results with the same code, and that
+/// warnings keep their level.
+#[test]
+fn test_sarif_single_file_rules_and_levels() {
+    // Arrange
+    let diagnostics = diagnose("match x with | 1 -> match y with | 2 -> b");
+
+    // Act
+    let json = diagnostics_to_sarif(&diagnostics, Path::new("nested.pfl"));
+
+    // Assert
+    assert_eq!(json.matches("\"id\": \"non-exhaustive-match\"").count(), 1);
+    assert_eq!(json.matches("\"ruleIndex\": 0").count(), 2);
+    assert_eq!(json.matches("\"level\": \"warning\"").count(), 2);
+    assert!(json.contains("\"uri\": \"nested.pfl\""));
+}
@@ -0,0 +1,101 @@
+//! tests/slice.rs
+
+mod support;
+
+use rdp::{print_expression, slice, Program};
+
+/// Slices the program in `source` at the first expression printing as
+/// `target`, and prints the slice.
+fn slice_at(source: &str, target: &str) -> String {
+    let program = Program {
+        expression: support::parse(source).unwrap(),
+    };
+    let index = program.build_index();
+    let node = (0..index.len())
+        .find(|&id| print_expression(index.node(id)) == target)
+        .unwrap_or_else(|| panic!("no expression prints as `{}`", target));
+    print_expression(&slice(&program, node).expression)
+}
+
+/// Tests that bindings the target doesn't use are dropped, along with the
+/// rest of the program around it.
+#[test]
+fn test_slice_drops_unused_bindings() {
+    // Arrange
+    let source = "let a = 1 in let b = 2 in if a > 0 then b + 1 else a";
+
+    // Act
+    let sliced = slice_at(source, "b + 1");
+
+    // Assert
+    assert_eq!(sliced, "let b = 2 in b + 1");
+}
+
+/// Tests that bindings used only by other kept bindings are kept too.
+#[test]
+fn test_slice_keeps_transitive_dependencies() {
+    // Arrange
+    let source = "let one = 1 in let unused = 7 in let inc = \\x -> x + one in inc 41";
+
+    // Act
+    let sliced = slice_at(source, "inc 41");
+
+    // Assert
+    assert_eq!(sliced, "let one = 1 in let inc = \\x -> x + one in inc 41");
+}
+
+/// Tests that a shadowed binding is not kept for a use of the inner one.
+#[test]
+fn test_slice_respects_shadowing() {
+    // Arrange
+    let source = "let x = 1 in let y = x in let x = 2 in x + 3";
+
+    // Act
+    let sliced = slice_at(source, "x + 3");
+
+    // Assert
+    assert_eq!(sliced, "let x = 2 in x + 3");
+}
+
+/// Tests that lambda parameters and pattern variables the target uses
+/// become parameters of the slice.
+#[test]
+fn test_slice_abstracts_over_parameters() {
+    // Arrange
+    let source = "let k = 2 in \\n -> match n with | 0 -> k | m -> m * k + n";
+
+    // Act
+    let sliced = slice_at(source, "(m * k) + n");
+
+    // Assert
+    assert_eq!(sliced, "let k = 2 in \\n -> \\m -> (m * k) + n");
+}
+
+/// Tests that a target inside a recursive binding keeps that binding.
+#[test]
+fn test_slice_inside_recursive_binding() {
+    // Arrange
+    let source = "let f = \\n -> if n == 0 then 1 else f (n - 1) in let g = 3 in f g";
+
+    // Act
+    let sliced = slice_at(source, "f (n - 1)");
+
+    // Assert
+    assert_eq!(
+        sliced,
+        "let f = \\n -> if n == 0 then 1 else f (n - 1) in \\n -> f (n - 1)"
+    );
+}
+
+/// Tests that slicing at the root keeps the program as it is.
+#[test]
+fn test_slice_at_root() {
+    // Arrange
+    let source = "let a = 1 in a";
+
+    // Act
+    let sliced = slice_at(source, "let a = 1 in a");
+
+    // Assert
+    assert_eq!(sliced, source);
+}
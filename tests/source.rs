@@ -0,0 +1,91 @@
+//! tests/source.rs
+
+use rdp::{LexerOptions, Location, SourceMap, Span, ValidateConfig};
+
+/// Tests that errors in two files are reported under the right file names,
+/// with the line and column when the error carries a position.
+#[test]
+fn test_diagnostics_name_their_file() {
+    // Arrange
+    let mut sources = SourceMap::new();
+    let first = sources.add("src/first.pfl", "let x = in x");
+    let second = sources.add("src/second.pfl", "let ok = 1 in\nlet toolong = 2 in ok");
+    let config = ValidateConfig {
+        options: LexerOptions {
+            max_identifier_length: Some(4),
+            ..LexerOptions::default()
+        },
+        ..ValidateConfig::default()
+    };
+
+    // Act
+    let rendered: Vec<String> = [first, second]
+        .into_iter()
+        .flat_map(|id| sources.get(id).validate(&config).diagnostics)
+        .map(|diagnostic| sources.render(&diagnostic))
+        .collect();
+
+    // Assert
+    assert_eq!(
+        rendered,
+        vec![
+            "src/first.pfl:1:9: error[E0001]: Unexpected token while parsing a term: \
+             expected 'term', but found 'in'."
+                .to_string(),
+            "src/second.pfl:2:5: error[E0008]: Identifier starting at offset 18 exceeds \
+             the maximum length of 4 characters."
+                .to_string(),
+        ]
+    );
+}
+
+/// Tests that lint findings are located in their file, without a span.
+#[test]
+fn test_lint_findings_are_located() {
+    // Arrange
+    let mut sources = SourceMap::new();
+    let id = sources.add("match.pfl", "match x with | 0 -> 1");
+
+    // Act
+    let report = sources.get(id).validate(&ValidateConfig::default());
+
+    // Assert
+    assert!(report.program.is_some());
+    assert_eq!(
+        report.diagnostics[0].location,
+        Some(Location {
+            file: id,
+            span: None
+        })
+    );
+}
+
+/// Tests that locations resolve through the map to the file they were made
+/// for, and that ids index the files in the order they were added.
+#[test]
+fn test_resolve_locations() {
+    // Arrange
+    let mut sources = SourceMap::new();
+    let a = sources.add("a.pfl", "x");
+    let b = sources.add("b.pfl", "1 +\n  2");
+    let at_two = Location {
+        file: b,
+        span: Some(Span { start: 6, end: 7 }),
+    };
+
+    // Act
+    let resolved = sources.resolve(at_two);
+    let whole = sources.resolve(Location {
+        file: a,
+        span: None,
+    });
+
+    // Assert
+    assert_eq!(resolved, ("b.pfl", Some((2, 3))));
+    assert_eq!(whole, ("a.pfl", None));
+    assert_ne!(a, b);
+    assert_eq!(sources.get(b).id(), b);
+    assert_eq!(sources.get(b).text(), "1 +\n  2");
+    let names: Vec<&str> = sources.files().iter().map(|file| file.name()).collect();
+    assert_eq!(names, ["a.pfl", "b.pfl"]);
+}
@@ -0,0 +1,337 @@
+//! tests/specialize.rs
+
+mod support;
+
+use std::collections::HashMap;
+
+use rdp::{
+    print_expression, specialize, testrand, ArithmeticOperator, ComparisonOperator, Expression,
+    LogicOperator, Pattern, Program, Term, UnaryOperator,
+};
+
+fn program(source: &str) -> Program {
+    Program {
+        expression: support::parse(source).unwrap(),
+    }
+}
+
+fn known(bindings: &[(&str, f64)]) -> HashMap<String, f64> {
+    bindings
+        .iter()
+        .map(|&(name, value)| (name.to_string(), value))
+        .collect()
+}
+
+const PRICING: &str = "\
+let base = 10 in
+let discount = match tier with | 1 -> 0 | 2 -> 5 | t -> t * 2 in
+if base - discount > 0 then (base - discount) * quantity else 0";
+
+/// Tests that a known tier collapses the match and everything that only
+/// depended on it.
+#[test]
+fn test_known_tier_collapses_match() {
+    // Act
+    let specialized = specialize(&program(PRICING), &known(&[("tier", 2.0)]));
+
+    // Assert
+    assert_eq!(print_expression(&specialized.expression), "5 * quantity");
+}
+
+/// Tests that a pattern variable is bound to the scrutinee's value.
+#[test]
+fn test_pattern_variable_is_bound() {
+    // Act
+    let specialized = specialize(&program(PRICING), &known(&[("tier", 4.0)]));
+
+    // Assert
+    assert_eq!(print_expression(&specialized.expression), "2 * quantity");
+}
+
+/// Tests that a guarded arm keeps the match, since only its guard can tell
+/// whether it is taken, while arms before it still collapse it.
+#[test]
+fn test_guarded_arm_keeps_match() {
+    // Arrange
+    let program = program("match tier with | 1 -> 0 | t when t > limit -> t | _ -> 5");
+
+    // Act
+    let before = specialize(&program, &known(&[("tier", 1.0)]));
+    let guarded = specialize(&program, &known(&[("tier", 3.0), ("limit", 2.0)]));
+
+    // Assert
+    assert_eq!(print_expression(&before.expression), "0");
+    assert_eq!(
+        print_expression(&guarded.expression),
+        "match 3 with | 1 -> 0 | t when (t > 2) -> t | _ -> 5"
+    );
+}
+
+/// Tests that bindings shadowing a known name are left alone.
+#[test]
+fn test_shadowed_names_are_not_replaced() {
+    // Arrange
+    let program = program("x + (let x = y in x) + (\\x -> x) 1 + (match z with | x -> x)");
+
+    // Act
+    let specialized = specialize(&program, &known(&[("x", 1.0)]));
+
+    // Assert
+    assert_eq!(
+        print_expression(&specialized.expression),
+        "((1 + (let x = y in x)) + ((\\x -> x) 1)) + (match z with | x -> x)"
+    );
+}
+
+/// Tests that code the known bindings don't reach, and division by zero,
+/// are kept as they are.
+#[test]
+fn test_unknown_code_is_kept() {
+    // Arrange
+    let program = program("if a < 1 then b / 0 else match c with | 1 -> 2");
+
+    // Act
+    let specialized = specialize(&program, &known(&[("b", 3.0)]));
+
+    // Assert
+    assert_eq!(
+        print_expression(&specialized.expression),
+        "if a < 1 then 3 / 0 else match c with | 1 -> 2"
+    );
+}
+
+/// Tests that a remainder is folded with the sign of the dividend, except a
+/// remainder by zero.
+#[test]
+fn test_remainder_is_folded() {
+    // Arrange
+    let program = program("(b % 3) + ((0 - b) % 3) + (b % 0)");
+
+    // Act
+    let specialized = specialize(&program, &known(&[("b", 7.0)]));
+
+    // Assert
+    assert_eq!(print_expression(&specialized.expression), "0 + (7 % 0)");
+}
+
+/// Tests that negating a number folds to a negative number, which prints in
+/// parentheses where it is an operand.
+#[test]
+fn test_negation_is_folded() {
+    // Arrange
+    let program = program("-b + -(-c) + f (-b)");
+
+    // Act
+    let specialized = specialize(&program, &known(&[("b", 2.0)]));
+
+    // Assert
+    assert_eq!(
+        print_expression(&specialized.expression),
+        "((-2) + (-(-c))) + (f (-2))"
+    );
+}
+
+/// Tests that evaluating the specialized program with the remaining
+/// bindings gives the same result as the original with all of them.
+#[test]
+fn test_specialization_preserves_results() {
+    const NAMES: [&str; 3] = ["a", "b", "c"];
+    testrand::check(testrand::seed(), 500, formula, |source| {
+        let original = program(source);
+        for seed in 0..8 {
+            let mut rng = testrand::Rng::new(seed);
+            let all: HashMap<String, f64> = NAMES
+                .iter()
+                .map(|name| (name.to_string(), rng.below(5) as f64))
+                .collect();
+            let (known, remaining): (HashMap<_, _>, HashMap<_, _>) =
+                all.clone().into_iter().partition(|_| rng.one_in(2));
+
+            let specialized = specialize(&original, &known);
+            let expected = evaluate(&original.expression, &all);
+            let actual = evaluate(&specialized.expression, &remaining);
+            if expected != actual {
+                return Err(format!(
+                    "with {:?} known, `{}` gives {:?}, not {:?}",
+                    known,
+                    print_expression(&specialized.expression),
+                    actual,
+                    expected
+                ));
+            }
+        }
+        Ok(())
+    });
+}
+
+/// Generates a numeric formula over `a`, `b`, and `c`.
+fn formula(rng: &mut testrand::Rng) -> String {
+    fn number(rng: &mut testrand::Rng, depth: usize) -> String {
+        if depth == 0 || rng.one_in(3) {
+            return match rng.below(3) {
+                0 => rng.below(5).to_string(),
+                _ => rng.pick(&["a", "b", "c", "t"]).to_string(),
+            };
+        }
+        let depth = depth - 1;
+        match rng.below(5) {
+            0 => format!("(let t = {} in {})", number(rng, depth), number(rng, depth)),
+            3 => format!("(-{})", number(rng, depth)),
+            1 => format!(
+                "(if {} then {} else {})",
+                condition(rng, depth),
+                number(rng, depth),
+                number(rng, depth)
+            ),
+            2 => format!(
+                "(match {} with | 1 -> {} | (2) -> {} | t -> {})",
+                number(rng, depth),
+                number(rng, depth),
+                number(rng, depth),
+                number(rng, depth)
+            ),
+            _ => format!(
+                "({} {} {})",
+                number(rng, depth),
+                rng.pick(&["+", "-", "*"]),
+                number(rng, depth)
+            ),
+        }
+    }
+    fn condition(rng: &mut testrand::Rng, depth: usize) -> String {
+        let comparison = |rng: &mut testrand::Rng| {
+            format!(
+                "({} {} {})",
+                number(rng, depth),
+                rng.pick(&["==", "<", ">"]),
+                number(rng, depth)
+            )
+        };
+        match rng.below(3) {
+            0 => format!(
+                "{} {} {}",
+                comparison(rng),
+                rng.pick(&["&&", "||"]),
+                comparison(rng)
+            ),
+            _ => comparison(rng),
+        }
+    }
+    // `t` is bound by the formula itself; unbound uses evaluate to nothing.
+    format!("let t = 0 in {}", number(rng, 4))
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Value {
+    Number(f64),
+    Bool(bool),
+}
+
+/// Evaluates a first-order numeric formula, or returns `None` for anything
+/// else, such as an unbound name or a match without a matching arm.
+fn evaluate(expression: &Expression, env: &HashMap<String, f64>) -> Option<Value> {
+    let number = |expression: &Expression| match evaluate(expression, env)? {
+        Value::Number(value) => Some(value),
+        Value::Bool(_) => None,
+    };
+    let truth = |expression: &Expression| match evaluate(expression, env)? {
+        Value::Bool(value) => Some(value),
+        Value::Number(_) => None,
+    };
+    Some(match expression {
+        Expression::Term(Term::Identifier(name)) => Value::Number(*env.get(name)?),
+        Expression::Term(Term::Number(value)) => Value::Number(*value),
+        Expression::Term(Term::GroupedExpression(inner)) => evaluate(inner, env)?,
+        Expression::LetExpr {
+            identifier,
+            value,
+            body,
+            ..
+        } => {
+            let mut inner = env.clone();
+            inner.remove(identifier);
+            let value = match evaluate(value, &inner)? {
+                Value::Number(value) => value,
+                Value::Bool(_) => return None,
+            };
+            inner.insert(identifier.clone(), value);
+            evaluate(body, &inner)?
+        }
+        Expression::IfExpr {
+            condition,
+            then_branch,
+            else_branch,
+        } => match truth(condition)? {
+            true => evaluate(then_branch, env)?,
+            false => evaluate(else_branch, env)?,
+        },
+        Expression::PatternMatch { expression, arms } => {
+            let value = number(expression)?;
+            let arm = arms
+                .iter()
+                .find(|arm| support::matches_number(&arm.pattern, value))?;
+            let mut inner = env.clone();
+            if let Some(Pattern::Identifier(name)) = ungrouped(&arm.pattern) {
+                inner.insert(name.clone(), value);
+            }
+            evaluate(&arm.expression, &inner)?
+        }
+        Expression::Arithmetic {
+            left,
+            operator,
+            right,
+        } => {
+            let (a, b) = (number(left)?, number(right)?);
+            Value::Number(match operator {
+                ArithmeticOperator::Add => a + b,
+                ArithmeticOperator::Subtract => a - b,
+                ArithmeticOperator::Multiply => a * b,
+                ArithmeticOperator::Divide => a / b,
+                ArithmeticOperator::Modulo => a % b,
+                ArithmeticOperator::Power => a.powf(b),
+            })
+        }
+        Expression::Comparison {
+            left,
+            operator,
+            right: Some(right),
+        } => {
+            let (a, b) = (number(left)?, number(right)?);
+            Value::Bool(match operator {
+                ComparisonOperator::Equal => a == b,
+                ComparisonOperator::NotEqual => a != b,
+                ComparisonOperator::LessThan => a < b,
+                ComparisonOperator::GreaterThan => a > b,
+                ComparisonOperator::LessEqual => a <= b,
+                ComparisonOperator::GreaterEqual => a >= b,
+            })
+        }
+        Expression::Logic {
+            left,
+            operator,
+            right: Some(right),
+        } => {
+            let (a, b) = (truth(left)?, truth(right)?);
+            Value::Bool(match operator {
+                LogicOperator::And => a && b,
+                LogicOperator::Or => a || b,
+            })
+        }
+        Expression::Comparison { left, .. } | Expression::Logic { left, .. } => {
+            evaluate(left, env)?
+        }
+        Expression::Unary {
+            operator: UnaryOperator::Negate,
+            operand,
+        } => Value::Number(-number(operand)?),
+        _ => return None,
+    })
+}
+
+fn ungrouped(pattern: &Pattern) -> Option<&Pattern> {
+    match pattern {
+        Pattern::Grouped(inner) => ungrouped(inner),
+        Pattern::Identifier(name) if name == "_" => None,
+        pattern => Some(pattern),
+    }
+}
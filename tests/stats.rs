@@ -0,0 +1,121 @@
+//! tests/stats.rs
+
+mod support;
+
+use std::collections::BTreeMap;
+
+use rdp::{collect_stats, Lexer, Program, Stats, Token};
+
+const FIXTURES: [&str; 3] = [
+    "let x = 1 in x + x",
+    "\\f -> f . g",
+    "match x with | 0 -> f x | _ -> 1",
+];
+
+fn tokenize_input(input: &str) -> Vec<Token> {
+    Lexer::tokenize_str(input).expect("Failed to tokenize input")
+}
+
+fn counts(entries: &[(&str, usize)]) -> BTreeMap<String, usize> {
+    entries
+        .iter()
+        .map(|(name, count)| (name.to_string(), *count))
+        .collect()
+}
+
+/// Tests operator, construct, and identifier counts over the fixture programs.
+#[test]
+fn test_collect_stats_counts_fixtures() {
+    // Arrange
+    let programs: Vec<Program> = FIXTURES
+        .iter()
+        .map(|input| support::parse_program(input))
+        .collect();
+
+    // Act
+    let stats = collect_stats(&programs);
+
+    // Assert
+    assert_eq!(stats.programs, 3);
+    assert_eq!(stats.operators, counts(&[("+", 1), (".", 1)]));
+    assert_eq!(
+        stats.constructs,
+        counts(&[
+            ("application", 1),
+            ("lambda", 1),
+            ("let", 1),
+            ("match", 1),
+            ("match-arm", 2),
+        ])
+    );
+    assert_eq!(stats.identifiers, counts(&[("f", 3), ("g", 1), ("x", 5)]));
+    assert!(stats.tokens.is_empty());
+}
+
+/// Tests that token kinds are counted without payloads and without `Eof`.
+#[test]
+fn test_record_tokens_counts_kinds() {
+    // Arrange
+    let mut stats = Stats::default();
+
+    // Act
+    for input in FIXTURES {
+        stats.record_tokens(&tokenize_input(input));
+    }
+
+    // Assert
+    assert_eq!(
+        stats.tokens,
+        counts(&[
+            ("Arrow", 3),
+            ("Assign", 1),
+            ("Dot", 1),
+            ("Identifier", 9),
+            ("In", 1),
+            ("Lambda", 1),
+            ("Let", 1),
+            ("Match", 1),
+            ("Number", 3),
+            ("Pipe", 2),
+            ("Plus", 1),
+            ("Wildcard", 1),
+            ("With", 1),
+        ])
+    );
+}
+
+/// Tests that merging per-program stats equals collecting them together.
+#[test]
+fn test_merge_matches_collecting_together() {
+    // Arrange
+    let programs: Vec<Program> = FIXTURES
+        .iter()
+        .map(|input| support::parse_program(input))
+        .collect();
+    let mut merged = Stats::default();
+
+    // Act
+    for program in &programs {
+        merged.merge(&collect_stats(std::slice::from_ref(program)));
+    }
+
+    // Assert
+    assert_eq!(merged, collect_stats(&programs));
+}
+
+/// Tests that top identifiers are ordered by frequency, then by name.
+#[test]
+fn test_top_identifiers() {
+    // Arrange
+    let programs: Vec<Program> = FIXTURES
+        .iter()
+        .map(|input| support::parse_program(input))
+        .collect();
+    let stats = collect_stats(&programs);
+
+    // Act
+    let top = stats.top_identifiers(2);
+
+    // Assert
+    assert_eq!(top, vec![("x", 5), ("f", 3)]);
+}
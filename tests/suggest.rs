@@ -0,0 +1,55 @@
+//! tests/suggest.rs
+
+use rdp::suggest;
+
+/// Tests that a single typo, including a transposition, is suggested.
+#[test]
+fn test_suggest_near_miss() {
+    // Arrange
+    let candidates = ["length", "width", "height"];
+
+    // Act
+    let transposed = suggest("lenght", candidates);
+    let substituted = suggest("widht", candidates);
+    let missing = suggest("heigt", candidates);
+
+    // Assert
+    assert_eq!(transposed, Some("length"));
+    assert_eq!(substituted, Some("width"));
+    assert_eq!(missing, Some("height"));
+}
+
+/// Tests that ties are broken deterministically, regardless of candidate order.
+#[test]
+fn test_suggest_tie_is_deterministic() {
+    // Arrange
+    let forward = ["bat", "cat"];
+    let backward = ["cat", "bat"];
+
+    // Act
+    let first = suggest("at", forward);
+    let second = suggest("at", backward);
+
+    // Assert
+    assert_eq!(first, Some("bat"));
+    assert_eq!(second, Some("bat"));
+}
+
+/// Tests that distant names and exact matches produce no suggestion.
+#[test]
+fn test_suggest_none_when_too_far() {
+    // Arrange
+    let candidates = ["Int", "Bool", "String", "Float"];
+
+    // Act
+    let unrelated = suggest("Integer", candidates);
+    let short = suggest("It", ["Int"]);
+    let exact = suggest("Int", candidates);
+    let empty = suggest("Int", []);
+
+    // Assert
+    assert_eq!(unrelated, None);
+    assert_eq!(short, Some("Int"));
+    assert_eq!(exact, None);
+    assert_eq!(empty, None);
+}
@@ -0,0 +1,292 @@
+//! tests/support/mod.rs
+//!
+//! Properties shared by the randomized tests in `tests/properties.rs` and the
+//! pinned seeds in `tests/repro.rs`, and helpers for comparing ASTs. Each test
+//! binary uses only some of them.
+
+#![allow(dead_code)]
+
+use rdp::{
+    free_variables, print_expression, print_styled, slice, Compact, Expanded, Expression,
+    FunctionComposition, LetBinding, Lexer, MatchArm, ParseError, Parser, Pattern, PrintStyle,
+    Program, Span, Teaching, Term,
+};
+
+/// Lexes and parses `source`, returning the root expression.
+pub fn parse(source: &str) -> Result<Expression, ParseError> {
+    let tokens = Lexer::tokenize_str(source)?;
+    Ok(Parser::new(tokens).parse_program()?.expression)
+}
+
+/// Lexes and parses `source` as a whole program, panicking if it fails.
+pub fn parse_program(source: &str) -> Program {
+    Program {
+        expression: parse(source).expect("Failed to parse input"),
+    }
+}
+
+/// Lexes and parses `source`, returning the program and the span of each of
+/// its nodes.
+pub fn parse_spanned(source: &str) -> Result<(Program, Vec<Option<Span>>), ParseError> {
+    let tokens = Lexer::new(source).tokenize_spanned()?;
+    Parser::from_spanned(tokens).parse_program_spanned()
+}
+
+/// Whether `pattern` matches the number `value`.
+pub fn matches_number(pattern: &Pattern, value: f64) -> bool {
+    match pattern {
+        Pattern::Number(literal) => *literal == value,
+        Pattern::Boolean(_) | Pattern::Cons(..) => false,
+        Pattern::Identifier(_) => true,
+        Pattern::Grouped(inner) => matches_number(inner, value),
+    }
+}
+
+/// Removes every `GroupedExpression` node, so trees that differ only in
+/// redundant parentheses compare equal.
+pub fn ungroup(expression: &Expression) -> Expression {
+    let boxed = |expression: &Expression| Box::new(ungroup(expression));
+    match expression {
+        Expression::LetExpr {
+            recursive,
+            identifier,
+            type_annotation,
+            value,
+            body,
+        } => Expression::LetExpr {
+            recursive: *recursive,
+            identifier: identifier.clone(),
+            type_annotation: type_annotation.clone(),
+            value: boxed(value),
+            body: boxed(body),
+        },
+        Expression::LetAnd {
+            recursive,
+            bindings,
+            body,
+        } => Expression::LetAnd {
+            recursive: *recursive,
+            bindings: bindings
+                .iter()
+                .map(|binding| LetBinding {
+                    identifier: binding.identifier.clone(),
+                    type_annotation: binding.type_annotation.clone(),
+                    value: boxed(&binding.value),
+                })
+                .collect(),
+            body: boxed(body),
+        },
+        Expression::IfExpr {
+            condition,
+            then_branch,
+            else_branch,
+        } => Expression::IfExpr {
+            condition: boxed(condition),
+            then_branch: boxed(then_branch),
+            else_branch: boxed(else_branch),
+        },
+        Expression::Lambda {
+            parameter,
+            type_annotation,
+            body,
+        } => Expression::Lambda {
+            parameter: parameter.clone(),
+            type_annotation: type_annotation.clone(),
+            body: boxed(body),
+        },
+        Expression::PatternMatch { expression, arms } => Expression::PatternMatch {
+            expression: boxed(expression),
+            arms: arms
+                .iter()
+                .map(|arm| MatchArm {
+                    pattern: arm.pattern.clone(),
+                    guard: arm.guard.as_deref().map(boxed),
+                    expression: boxed(&arm.expression),
+                })
+                .collect(),
+        },
+        Expression::Comparison {
+            left,
+            operator,
+            right,
+        } => Expression::Comparison {
+            left: boxed(left),
+            operator: operator.clone(),
+            right: right.as_deref().map(boxed),
+        },
+        Expression::Logic {
+            left,
+            operator,
+            right,
+        } => Expression::Logic {
+            left: boxed(left),
+            operator: operator.clone(),
+            right: right.as_deref().map(boxed),
+        },
+        Expression::Arithmetic {
+            left,
+            operator,
+            right,
+        } => Expression::Arithmetic {
+            left: boxed(left),
+            operator: operator.clone(),
+            right: boxed(right),
+        },
+        Expression::List {
+            left,
+            operator,
+            right,
+        } => Expression::List {
+            left: boxed(left),
+            operator: operator.clone(),
+            right: boxed(right),
+        },
+        Expression::Unary { operator, operand } => Expression::Unary {
+            operator: operator.clone(),
+            operand: boxed(operand),
+        },
+        Expression::Application(items) => {
+            Expression::Application(items.iter().map(ungroup).collect())
+        }
+        Expression::Term(Term::GroupedExpression(inner)) => ungroup(inner),
+        Expression::Term(Term::MemberAccess { expression, member }) => {
+            Expression::Term(Term::MemberAccess {
+                expression: boxed(expression),
+                member: member.clone(),
+            })
+        }
+        Expression::Term(Term::Tuple(elements)) => {
+            Expression::Term(Term::Tuple(elements.iter().map(ungroup).collect()))
+        }
+        Expression::Term(Term::List(elements)) => {
+            Expression::Term(Term::List(elements.iter().map(ungroup).collect()))
+        }
+        Expression::Term(term) => Expression::Term(term.clone()),
+        Expression::FunctionComposition(FunctionComposition { f, g }) => {
+            Expression::FunctionComposition(FunctionComposition {
+                f: boxed(f),
+                g: boxed(g),
+            })
+        }
+    }
+}
+
+/// Printing a parsed program and parsing the result gives back the same tree,
+/// up to grouping.
+pub fn reprints(source: &str) -> Result<(), String> {
+    let original = parse(source).map_err(|err| format!("parsing failed: {}", err))?;
+    let printed = print_expression(&original);
+    let reparsed =
+        parse(&printed).map_err(|err| format!("reparsing `{}` failed: {}", printed, err))?;
+    if ungroup(&reparsed) == ungroup(&original) {
+        Ok(())
+    } else {
+        Err(format!("`{}` parses to a different tree", printed))
+    }
+}
+
+/// Printing in any style, at any width, gives source for the same tree.
+pub fn reprints_in_every_style(source: &str) -> Result<(), String> {
+    let original = parse(source).map_err(|err| format!("parsing failed: {}", err))?;
+    let styles: [&dyn PrintStyle; 5] = [
+        &Compact,
+        &Expanded::default(),
+        &Expanded { width: 20 },
+        &Teaching::default(),
+        &Teaching { width: 20 },
+    ];
+    for style in styles {
+        let printed = print_styled(&original, style);
+        let reparsed =
+            parse(&printed).map_err(|err| format!("reparsing `{}` failed: {}", printed, err))?;
+        if ungroup(&reparsed) != ungroup(&original) {
+            return Err(format!("`{}` parses to a different tree", printed));
+        }
+    }
+    Ok(())
+}
+
+/// Generated programs are valid, so they must lex and parse.
+pub fn parses(source: &str) -> Result<(), String> {
+    let tokens = Lexer::tokenize_str(source).map_err(|err| format!("lexing failed: {}", err))?;
+    Parser::new(tokens)
+        .parse_program()
+        .map(|_| ())
+        .map_err(|err| format!("parsing failed: {}", err))
+}
+
+/// Arbitrary input may be rejected, but only with an error, never a panic.
+pub fn fails_gracefully(source: &str) -> Result<(), String> {
+    if let Ok(tokens) = Lexer::tokenize_str(source) {
+        let _ = Parser::new(tokens).parse_program();
+    }
+    Ok(())
+}
+
+/// Lossy lexing covers every character of the input exactly once, in order.
+pub fn covers_input(source: &str) -> Result<(), String> {
+    let pieces = Lexer::new(source).tokenize_lossy();
+    let mut end = 0;
+    for piece in &pieces {
+        let span = piece.span();
+        if span.start != end || span.end <= span.start {
+            return Err(format!("{:?} does not continue at offset {}", piece, end));
+        }
+        end = span.end;
+    }
+    let length = source.chars().count();
+    if end == length {
+        Ok(())
+    } else {
+        Err(format!("pieces end at {} of {} characters", end, length))
+    }
+}
+
+/// Every expression with a span parses, from just the text it spans, to the
+/// same tree.
+pub fn spans_reparse(source: &str) -> Result<(), String> {
+    let (program, spans) =
+        parse_spanned(source).map_err(|err| format!("parsing failed: {}", err))?;
+    let index = program.build_index();
+    if spans.len() != index.len() {
+        return Err(format!("{} spans for {} nodes", spans.len(), index.len()));
+    }
+    for (id, span) in spans.iter().enumerate() {
+        let Some(span) = span else { continue };
+        let text: String = source
+            .chars()
+            .skip(span.start)
+            .take(span.end - span.start)
+            .collect();
+        match parse(&text) {
+            Ok(reparsed) if reparsed == *index.node(id) => {}
+            _ => return Err(format!("node {} spans `{}`, another tree", id, text)),
+        }
+    }
+    Ok(())
+}
+
+/// Slicing at any node keeps that node and introduces no free names: every
+/// name the slice uses is bound in it or was already free in the program.
+pub fn slices_are_closed(source: &str) -> Result<(), String> {
+    let program = Program {
+        expression: parse(source).map_err(|err| format!("parsing failed: {}", err))?,
+    };
+    let free = free_variables(&program.expression);
+    let index = program.build_index();
+    for target in 0..index.len() {
+        let sliced = slice(&program, target).expression;
+        let mut kept = false;
+        sliced.walk(&mut |node| kept |= node == index.node(target));
+        if !kept {
+            return Err(format!("slice at node {} lost the node", target));
+        }
+        if let Some(name) = free_variables(&sliced).difference(&free).next() {
+            return Err(format!(
+                "slice at node {} leaves '{}' unbound",
+                target, name
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,296 @@
+//! tests/template.rs
+
+mod support;
+
+use std::collections::HashMap;
+
+use rdp::{apply_edits, parse_template, print_expression, InstantiateError, Lexer};
+
+/// Finds `template` in `source`, printing each match.
+fn search(template: &str, source: &str) -> Vec<String> {
+    let template = parse_template(template).expect("Failed to parse template");
+    let program = support::parse_program(source);
+    template
+        .find_matches(&program)
+        .iter()
+        .map(|found| print_expression(found.expression))
+        .collect()
+}
+
+/// Tests that a repeated metavariable finds `if` expressions whose branches
+/// are structurally identical, ignoring redundant parentheses.
+#[test]
+fn test_search_duplicate_branches() {
+    // Arrange
+    let source = "let a = if p then f x else (f x) in if q then 1 else 2";
+
+    // Act
+    let matches = search("if $c then $x else $x", source);
+
+    // Assert
+    assert_eq!(matches, vec!["if p then f x else (f x)"]);
+}
+
+/// Tests a query with two distinct metavariables and what each one binds.
+#[test]
+fn test_search_two_metavariables() {
+    // Arrange
+    let template = parse_template("$f $x + $x").unwrap();
+    let program = support::parse_program("g (a * 2) + (a * 2) - h b");
+
+    // Act
+    let matches = template.find_matches(&program);
+
+    // Assert
+    assert_eq!(matches.len(), 1);
+    let bindings: Vec<(&str, String)> = matches[0]
+        .bindings
+        .iter()
+        .map(|(name, bound)| (name.as_str(), print_expression(bound)))
+        .collect();
+    assert_eq!(
+        bindings,
+        vec![("$f", "g".to_string()), ("$x", "a * 2".to_string())]
+    );
+}
+
+/// Tests that repeated metavariables compare binders up to renaming, but never
+/// confuse a bound name with a free one of the same spelling.
+#[test]
+fn test_search_respects_binders() {
+    // Arrange
+    let source = "if a then \\y -> y else \\z -> z";
+    let captured = "if a then \\y -> x else \\x -> x";
+
+    // Act
+    let renamed = search("if $c then $x else $x", source);
+    let boundary = search("if $c then $x else $x", captured);
+    let template_binder = search("\\v -> v", captured);
+
+    // Assert
+    assert_eq!(renamed.len(), 1);
+    assert!(boundary.is_empty());
+    assert_eq!(template_binder, vec!["\\x -> x"]);
+}
+
+/// Tests that metavariables are only accepted by the template lexer.
+#[test]
+fn test_metavariables_need_template_mode() {
+    // Arrange
+    let source = "$x + 1";
+
+    // Act
+    let program = Lexer::tokenize_str(source);
+    let template = Lexer::template(source).tokenize();
+
+    // Assert
+    assert!(program.is_err());
+    assert!(template.is_ok());
+}
+
+/// Rewrites `source` and applies the edits, returning the rewritten text and
+/// how many matches were refused.
+fn rewrite_text(template: &str, replacement: &str, source: &str) -> (String, usize) {
+    let template = parse_template(template).expect("Failed to parse template");
+    let replacement = parse_template(replacement).expect("Failed to parse replacement");
+    let program = support::parse_program(source);
+
+    let rewrite = template.rewrite(&program, &replacement, source);
+
+    (apply_edits(source, &rewrite.edits), rewrite.refused.len())
+}
+
+/// Rewrites `source`, returning how many matches were refused. The rewritten
+/// text must parse to `expected`.
+fn rewrite(template: &str, replacement: &str, source: &str, expected: &str) -> usize {
+    let (rewritten, refused) = rewrite_text(template, replacement, source);
+    let reparsed = support::parse(&rewritten).expect("Failed to reparse rewrite");
+    let expected = support::parse(expected).expect("Failed to parse expected program");
+    assert_eq!(
+        support::ungroup(&reparsed),
+        support::ungroup(&expected),
+        "'{}'",
+        rewritten
+    );
+    refused
+}
+
+/// Tests the classic boolean simplification, including a match nested in
+/// another one.
+#[test]
+fn test_rewrite_boolean_simplification() {
+    // Arrange
+    let source = "let g = \\b -> if b then true else false in \
+                  if (if x < 1 then true else false) then true else false";
+
+    // Act
+    let refused = rewrite(
+        "if $c then true else false",
+        "$c",
+        source,
+        "let g = \\b -> b in if x < 1 then true else false",
+    );
+
+    // Assert
+    assert_eq!(refused, 0);
+}
+
+/// Tests that a rewrite only edits the matches, keeping the comments and
+/// layout around them.
+#[test]
+fn test_rewrite_preserves_formatting() {
+    // Arrange
+    let source = "-- keep me\nlet ok =\n    if  done  then true else false\nin ok   -- and me\n";
+
+    // Act
+    let (rewritten, refused) = rewrite_text("if $c then true else false", "$c", source);
+
+    // Assert
+    assert_eq!(refused, 0);
+    assert_eq!(
+        rewritten,
+        "-- keep me\nlet ok =\n    done\nin ok   -- and me\n"
+    );
+}
+
+/// Tests that a replacement standing in for an operand is parenthesized
+/// unless it is atomic or already in parentheses.
+#[test]
+fn test_rewrite_parenthesizes_operands() {
+    // Arrange
+    let source = "f (not a) + not b";
+
+    // Act
+    let (rewritten, refused) = rewrite_text("not $x", "$x == false", source);
+
+    // Assert
+    assert_eq!(refused, 0);
+    assert_eq!(rewritten, "f (a == false) + (b == false)");
+}
+
+/// Tests that a match with no span of its own, the inner lambda of a lambda
+/// with several parameters, is rewritten through the lambda around it.
+#[test]
+fn test_rewrite_inside_sugar() {
+    // Arrange
+    let source = "g (\\x y -> y)";
+
+    // Act
+    let refused = rewrite("\\y -> y", "id", source, "g (\\x -> id)");
+
+    // Assert
+    assert_eq!(refused, 0);
+}
+
+/// Tests that a replacement binder that would capture a free name of the
+/// substituted expression is refused, leaving other matches rewritten.
+#[test]
+fn test_rewrite_refuses_capture() {
+    // Arrange
+    let source = "map (g y) ys + map h ys";
+
+    // Act
+    let refused = rewrite(
+        "map $f $xs",
+        "map (\\y -> $f y) $xs",
+        source,
+        "map (g y) ys + map (\\y -> h y) ys",
+    );
+
+    // Assert
+    assert_eq!(refused, 1);
+}
+
+/// Tests that a rewrite that would move a name out of the binder it refers
+/// to, or make a replacement name refer to a binding around the match, is
+/// refused.
+#[test]
+fn test_rewrite_refuses_scope_changes() {
+    // Arrange
+    let escaping = "f (\\x -> x + 1) (\\x -> 1)";
+    let shadowing = "\\not -> if a then false else true";
+
+    // Act
+    let escaped = rewrite("\\x -> $b", "$b", escaping, "f (\\x -> x + 1) 1");
+    let shadowed = rewrite("if $c then false else true", "not $c", shadowing, shadowing);
+
+    // Assert
+    assert_eq!(escaped, 1);
+    assert_eq!(shadowed, 1);
+}
+
+/// Fills `template` with `bindings`, each a metavariable and the source of
+/// its expression, printing the result.
+fn instantiate(template: &str, bindings: &[(&str, &str)]) -> Result<String, InstantiateError> {
+    let template = parse_template(template).expect("Failed to parse template");
+    let bindings: HashMap<&str, _> = bindings
+        .iter()
+        .map(|&(name, source)| {
+            (
+                name,
+                support::parse(source).expect("Failed to parse splice"),
+            )
+        })
+        .collect();
+    template
+        .instantiate(&bindings)
+        .map(|expression| print_expression(&expression))
+}
+
+/// Tests that instantiating a template splices each binding in place of its
+/// metavariable.
+#[test]
+fn test_instantiate_fills_metavariables() {
+    // Act
+    let filled = instantiate(
+        "if $cond then $user else 0",
+        &[("$cond", "x > 1"), ("$user", "f x")],
+    );
+
+    // Assert
+    assert_eq!(filled, Ok("if x > 1 then f x else 0".to_string()));
+}
+
+/// Tests that a metavariable without a binding, and a binding without a
+/// metavariable, are both errors.
+#[test]
+fn test_instantiate_rejects_missing_and_extra_bindings() {
+    // Act
+    let missing = instantiate("if $cond then $user else 0", &[("$cond", "true")]);
+    let extra = instantiate("$x + 1", &[("$x", "y"), ("$y", "z")]);
+
+    // Assert
+    assert_eq!(
+        missing,
+        Err(InstantiateError::MissingBinding("$user".to_string()))
+    );
+    assert_eq!(extra, Err(InstantiateError::ExtraBinding("$y".to_string())));
+}
+
+/// Tests that a binder of the template is renamed rather than capture a
+/// splice's reference to an outer name of the same spelling.
+#[test]
+fn test_instantiate_is_hygienic() {
+    // Act
+    let in_let = instantiate("let x = 1 in $body + x", &[("$body", "x * 2")]);
+    let in_lambda = instantiate("\\y -> $f y", &[("$f", "g y")]);
+    let in_arm = instantiate("match $e with | n -> n + $k", &[("$e", "m"), ("$k", "n")]);
+
+    // Assert
+    assert_eq!(in_let, Ok("let x' = 1 in (x * 2) + x'".to_string()));
+    assert_eq!(in_lambda, Ok("\\y' -> (g y) y'".to_string()));
+    assert_eq!(in_arm, Ok("match m with | n' -> (n' + n)".to_string()));
+}
+
+/// Tests that binders are left alone when no splice refers to their names,
+/// and that a binder named like a metavariable loses its `$`.
+#[test]
+fn test_instantiate_renames_only_when_needed() {
+    // Act
+    let untouched = instantiate("let x = $v in x + $v", &[("$v", "y")]);
+    let binder = instantiate("\\$x -> $x + $k", &[("$k", "1")]);
+
+    // Assert
+    assert_eq!(untouched, Ok("let x = y in x + y".to_string()));
+    assert_eq!(binder, Ok("\\x -> x + 1".to_string()));
+}
@@ -0,0 +1,85 @@
+//! tests/validate.rs
+
+use rdp::{check_arity, suggest_parens, validate, Level, LexerOptions, Severity, ValidateConfig};
+
+/// Tests that a clean program validates with no findings.
+#[test]
+fn test_validate_clean_program() {
+    // Act
+    let report = validate("let x = 1 in x + 1", &ValidateConfig::default());
+
+    // Assert
+    assert!(report.program.is_some());
+    assert!(report.diagnostics.is_empty());
+    assert!(!report.has_errors());
+}
+
+/// Tests that a parse error is reported under its stable code, without a
+/// program.
+#[test]
+fn test_validate_parse_error() {
+    // Act
+    let report = validate("let x = in x", &ValidateConfig::default());
+
+    // Assert
+    assert_eq!(report.program, None);
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].code, "E0001");
+    assert!(report.has_errors());
+}
+
+/// Tests that the default lints, the opt-in checks, and the configured
+/// levels all apply, in that order.
+#[test]
+fn test_validate_runs_every_configured_check() {
+    // Arrange
+    let source = "let add = \\x -> \\y -> x + y in match add 1 2 3 with | 0 -> f x + y";
+    let mut config = ValidateConfig {
+        checks: vec![suggest_parens, |program, _| check_arity(program)],
+        ..ValidateConfig::default()
+    };
+    config
+        .diagnostics
+        .overrides
+        .insert("over-application".to_string(), Level::Deny);
+
+    // Act
+    let report = validate(source, &config);
+
+    // Assert
+    let findings: Vec<(&str, Severity)> = report
+        .diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.code, diagnostic.severity))
+        .collect();
+    assert_eq!(
+        findings,
+        [
+            ("non-exhaustive-match", Severity::Warning),
+            ("suggest-parens", Severity::Warning),
+            ("over-application", Severity::Error),
+        ]
+    );
+    assert!(report.program.is_some());
+    assert!(report.has_errors());
+}
+
+/// Tests that the lexer options in the config are applied.
+#[test]
+fn test_validate_applies_lexer_options() {
+    // Arrange
+    let config = ValidateConfig {
+        options: LexerOptions {
+            max_identifier_length: Some(3),
+            ..LexerOptions::default()
+        },
+        ..ValidateConfig::default()
+    };
+
+    // Act
+    let report = validate("let long = 1 in long", &config);
+
+    // Assert
+    assert_eq!(report.program, None);
+    assert!(report.has_errors());
+}